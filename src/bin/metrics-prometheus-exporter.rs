@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Standalone exporter ingesting metrics events over TCP (see
+//! [`metrics_prometheus::ingest`] module for the line protocol) and serving
+//! them in Prometheus text format on `/metrics` HTTP endpoint.
+//!
+//! ```text
+//! metrics-prometheus-exporter [--ingest <addr>] [--listen <addr>]
+//! ```
+
+use std::{
+    env,
+    io::{self, Read as _, Write as _},
+    net::{TcpListener, TcpStream},
+    process,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use metrics_prometheus::{failure::strategy, ingest};
+
+/// Default address to ingest metrics events on.
+const DEFAULT_INGEST_ADDR: &str = "127.0.0.1:9125";
+
+/// Default address to serve `/metrics` HTTP endpoint on.
+///
+/// Local only, so the metrics are not exposed to the network unless asked to.
+const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:9126";
+
+/// Deadline of reading a whole HTTP request head and of writing its response,
+/// so a slow client doesn't hold its connection for long.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum size (in bytes) of an HTTP request head (request line and headers),
+/// after which the request is rejected.
+const MAX_HEAD_LEN: usize = 8192;
+
+/// Maximum number of HTTP connections served concurrently, after which the new
+/// ones are closed right away.
+const MAX_HTTP_CONNECTIONS: usize = 64;
+
+/// Pause after failing to accept a connection, so the persistent failures
+/// (like running out of file descriptors) don't spin the CPU.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+fn main() -> io::Result<()> {
+    let mut ingest_addr = DEFAULT_INGEST_ADDR.to_owned();
+    let mut listen_addr = DEFAULT_LISTEN_ADDR.to_owned();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--ingest", Some(addr)) => ingest_addr = addr,
+            ("--listen", Some(addr)) => listen_addr = addr,
+            _ => {
+                eprintln!(
+                    "Usage: metrics-prometheus-exporter \
+                     [--ingest <addr>] [--listen <addr>]\n\n\
+                     Defaults: --ingest {DEFAULT_INGEST_ADDR} \
+                     --listen {DEFAULT_LISTEN_ADDR}",
+                );
+                process::exit(2);
+            }
+        }
+    }
+
+    // Malformed input must never bring the exporter down.
+    let recorder = Arc::new(
+        metrics_prometheus::Recorder::builder()
+            .with_registry(prometheus::Registry::new())
+            .with_failure_strategy(strategy::NoOp)
+            .build(),
+    );
+    let registry = recorder.registry_arc();
+
+    let ingest_listener = TcpListener::bind(&ingest_addr)?;
+    let http_listener = TcpListener::bind(&listen_addr)?;
+    let ingesting =
+        thread::spawn(move || ingest::listen(&ingest_listener, &recorder));
+    drop(thread::spawn(move || serve(&http_listener, &registry)));
+
+    // The exporter is useless without ingesting, so exits once it stops.
+    ingesting.join().map_or_else(
+        |_| Err(io::Error::other("ingesting panicked")),
+        |()| Err(io::Error::other("ingesting stopped")),
+    )
+}
+
+/// Serves `/metrics` HTTP endpoint on the provided [`TcpListener`] with the
+/// metrics of the provided [`prometheus::Registry`], logging failures of
+/// single connections without stopping.
+///
+/// Every connection is served in its own thread, and no more than
+/// [`MAX_HTTP_CONNECTIONS`] of them are served concurrently.
+fn serve(listener: &TcpListener, registry: &Arc<prometheus::Registry>) {
+    // Every serving thread holds a clone, so the strong count tracks them.
+    let active = Arc::new(());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("failed to accept HTTP connection: {e}");
+                thread::sleep(ACCEPT_BACKOFF);
+                continue;
+            }
+        };
+        if Arc::strong_count(&active) > MAX_HTTP_CONNECTIONS {
+            eprintln!(
+                "{MAX_HTTP_CONNECTIONS} HTTP connections are served already, \
+                 closing the new one",
+            );
+            continue;
+        }
+        let registry = Arc::clone(registry);
+        let active = Arc::clone(&active);
+        drop(thread::spawn(move || {
+            if let Err(e) = respond(stream, &registry) {
+                eprintln!("failed to serve HTTP request: {e}");
+            }
+            drop(active);
+        }));
+    }
+}
+
+/// Responds to a single HTTP request on the provided [`TcpStream`], serving
+/// the metrics of the provided [`prometheus::Registry`] on `GET /metrics`.
+fn respond(
+    mut stream: TcpStream,
+    registry: &prometheus::Registry,
+) -> io::Result<()> {
+    let head = read_head(&mut stream, Instant::now() + HTTP_TIMEOUT)?;
+    let request_line = head.split(|b| *b == b'\n').next().unwrap_or_default();
+
+    let (status, body) = if is_metrics_request(request_line) {
+        let body = prometheus::TextEncoder::new()
+            .encode_to_string(&registry.gather())
+            .map_err(io::Error::other)?;
+        ("200 OK", body)
+    } else {
+        ("404 Not Found", String::new())
+    };
+    stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n\
+         {body}",
+        body.len(),
+    )
+}
+
+/// Reads the head (request line and headers) of an HTTP request from the
+/// provided [`TcpStream`].
+///
+/// # Errors
+///
+/// - If the head isn't read completely before the provided `deadline`.
+/// - If the head is longer than [`MAX_HEAD_LEN`] bytes.
+/// - If the [`TcpStream`] fails or is closed before the head is read.
+fn read_head(stream: &mut TcpStream, deadline: Instant) -> io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(1024);
+    let mut chunk = [0; 1024];
+    loop {
+        let remaining = deadline
+            .checked_duration_since(Instant::now())
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "request head timed out",
+                )
+            })?;
+        stream.set_read_timeout(Some(remaining))?;
+        let read = stream.read(&mut chunk)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        // Only the tail may contain the end of the head not checked yet.
+        let from = head.len().saturating_sub(3);
+        head.extend_from_slice(chunk.get(..read).unwrap_or_default());
+        if head.len() > MAX_HEAD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("request head is longer than {MAX_HEAD_LEN} bytes"),
+            ));
+        }
+        let tail = head.get(from..).unwrap_or_default();
+        if tail.windows(4).any(|w| w == b"\r\n\r\n")
+            || tail.windows(2).any(|w| w == b"\n\n")
+        {
+            return Ok(head);
+        }
+    }
+}
+
+/// Checks whether the provided HTTP `request_line` requests `GET /metrics`
+/// (with or without a query).
+fn is_metrics_request(request_line: &[u8]) -> bool {
+    request_line
+        .strip_prefix(b"GET /metrics")
+        .is_some_and(|rest| rest.starts_with(b" ") || rest.starts_with(b"?"))
+}
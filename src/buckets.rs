@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Curated presets of histogram buckets, usable wherever a bucket
+//! configuration is accepted (like [`prometheus::HistogramOpts::buckets()`] or
+//! [`adaptive::Config::with_provisional_buckets()`][0]).
+//!
+//! All the presets are strictly increasing, exclude the implicit `+Inf`
+//! bucket, and are free of floating-point noise (like `0.30000000000000004`).
+//!
+//! [0]: crate::metric::adaptive::Config::with_provisional_buckets
+
+/// Buckets of request latencies in seconds, from half a millisecond to a
+/// minute.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::buckets;
+///
+/// let buckets = buckets::latency_seconds();
+/// assert_eq!(buckets.first(), Some(&0.0005));
+/// assert_eq!(buckets.last(), Some(&60.0));
+/// ```
+#[must_use]
+pub fn latency_seconds() -> Vec<f64> {
+    vec![
+        0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+        2.5, 5.0, 10.0, 30.0, 60.0,
+    ]
+}
+
+/// Buckets of payload sizes in bytes, being powers of 4 from 64 B to 1 GiB.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::buckets;
+///
+/// let buckets = buckets::size_bytes();
+/// assert_eq!(buckets[..3], [64.0, 256.0, 1024.0]);
+/// assert_eq!(buckets.last(), Some(&1_073_741_824.0));
+/// ```
+#[must_use]
+pub fn size_bytes() -> Vec<f64> {
+    (3..=15).map(|i| f64::from(4_u32.pow(i))).collect()
+}
+
+/// Buckets of durations thought of in milliseconds (following the 1-2-5
+/// series from 1 ms to 10 s), converted to seconds, the base unit of
+/// Prometheus.
+///
+/// Converting them by hand (like `ms as f64 / 1000.0`) is a common source of
+/// floating-point noise in the exposed `le` labels.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::buckets;
+///
+/// let buckets = buckets::duration_ms_to_s_converted();
+/// assert_eq!(buckets[..4], [0.001, 0.002, 0.005, 0.01]);
+/// assert_eq!(buckets.last(), Some(&10.0));
+/// ```
+#[must_use]
+pub fn duration_ms_to_s_converted() -> Vec<f64> {
+    [1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000, 2_000, 5_000, 10_000]
+        .into_iter()
+        .map(|ms: u32| round(f64::from(ms) / 1000.0))
+        .collect()
+}
+
+/// Creates `count` exponential buckets, where the lowest one has the provided
+/// `start` upper bound, and each following one's upper bound is the `factor`
+/// times the previous one's.
+///
+/// Unlike the [`prometheus::exponential_buckets()`], the calculated upper
+/// bounds are rounded to 12 significant digits, so are free of
+/// floating-point noise.
+///
+/// # Errors
+///
+/// If `count` is zero, `start` is not positive, or `factor` is not greater
+/// than `1`.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::buckets;
+///
+/// assert_eq!(buckets::exponential(0.1, 3.0, 3)?, [0.1, 0.3, 0.9]);
+/// assert!(buckets::exponential(0.1, 1.0, 3).is_err());
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+pub fn exponential(
+    start: f64,
+    factor: f64,
+    count: usize,
+) -> prometheus::Result<Vec<f64>> {
+    Ok(prometheus::exponential_buckets(start, factor, count)?
+        .into_iter()
+        .map(round)
+        .collect())
+}
+
+/// Rounds the provided `value` to 12 significant digits, eliminating the
+/// floating-point noise.
+fn round(value: f64) -> f64 {
+    format!("{value:.11e}").parse().unwrap_or(value)
+}
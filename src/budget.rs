@@ -0,0 +1,491 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Bounding the number of metrics registered on the fly.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt, mem,
+    sync::{Mutex, PoisonError},
+};
+#[cfg(feature = "timestamps")]
+use std::{collections::BTreeMap, time::Instant};
+
+use crate::config;
+#[cfg(feature = "timestamps")]
+use crate::metric::timestamp::Timestamps;
+
+/// Label value the metrics exceeding a [`Budget`] are recorded with, when the
+/// [`Policy::Overflow`] is applied.
+pub const OVERFLOW_LABEL_VALUE: &str = "__overflow__";
+
+/// Policy to apply on registering a new metric exceeding a [`Budget`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Policy {
+    /// Reject the new metric, passing a [`prometheus::Error`] to the
+    /// [`failure::Strategy`].
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    #[default]
+    Reject,
+
+    /// Evict the least recently updated labeled metric to make room for the
+    /// new one.
+    ///
+    /// Metrics without labels are never evicted, and any [`metrics`] handles
+    /// of the evicted metric obtained before its eviction stop being
+    /// reported.
+    #[cfg(feature = "timestamps")]
+    EvictLeastRecentlyUpdated,
+
+    /// Record the new labeled metric into an overflow one of the same
+    /// family, having all its label values replaced with the
+    /// [`OVERFLOW_LABEL_VALUE`].
+    ///
+    /// Overflow metrics are not counted against the [`Budget`], so every
+    /// family may exceed it by a single overflow metric at most. Metrics
+    /// without labels and new families are rejected, as there is no overflow
+    /// metric for them.
+    Overflow,
+}
+
+/// Limits of the number of metrics registered on the fly.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Budget {
+    /// Maximum number of metric families (distinct metric names).
+    pub max_families: usize,
+
+    /// Maximum number of metrics (distinct metric names along with their
+    /// label values) across all the families.
+    pub max_children: usize,
+}
+
+impl Budget {
+    /// [`Budget`] not limiting anything.
+    pub const UNLIMITED: Self =
+        Self { max_families: usize::MAX, max_children: usize::MAX };
+}
+
+/// Function removing a [`Child`] from its family.
+type Evict = Box<dyn Fn() -> prometheus::Result<()> + Send + Sync>;
+
+/// Position of an evictable [`Child`] in the [`State::lru`] index: the last
+/// update time of the [`Child`] known to the index (if ever updated), and the
+/// sequence number disambiguating equal times.
+#[cfg(feature = "timestamps")]
+type Position = (Option<Instant>, u64);
+
+/// Single metric tracked by a [`Tracker`].
+struct Child {
+    /// Function removing this [`Child`] from its family, if it can be evicted.
+    evict: Option<Evict>,
+
+    /// [`Position`] of this [`Child`] in the [`State::lru`] index, if it can be
+    /// evicted.
+    #[cfg(feature = "timestamps")]
+    position: Option<Position>,
+}
+
+// Manual implementation is required to omit printing the `evict` function.
+impl fmt::Debug for Child {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Child")
+            .field("evictable", &self.evict.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Mutable state of a [`Tracker`].
+#[derive(Debug, Default)]
+struct State {
+    /// Names of the admitted metric families.
+    families: HashSet<String>,
+
+    /// Admitted metrics, identified by their [`metrics::Key`]s.
+    children: HashMap<metrics::Key, Child>,
+
+    /// [`metrics::Key`]s of the evicted metrics, not yet forgotten by a
+    /// [`metrics::Recorder`].
+    evicted: Vec<metrics::Key>,
+
+    /// Index of the evictable [`State::children`] ordered by their
+    /// [`Position`]s, so the least recently updated one is found without
+    /// scanning all of them.
+    ///
+    /// [`Position`]s may be stale (older than the actual last update times),
+    /// so are refreshed lazily on eviction.
+    #[cfg(feature = "timestamps")]
+    lru: BTreeMap<Position, metrics::Key>,
+
+    /// Sequence number of the next [`Position`] in the [`State::lru`] index.
+    #[cfg(feature = "timestamps")]
+    next_seq: u64,
+}
+
+/// Tracker of the metrics registered on the fly, enforcing the current
+/// [`config::Config::budget`] on them according to a [`Policy`].
+///
+/// Reports its utilization via `metrics_prometheus_budget_used` and
+/// `metrics_prometheus_budget_limit` gauges, labeled with the `limit` being
+/// either `families` or `children`.
+#[derive(Debug)]
+pub(crate) struct Tracker {
+    /// [`config::Reloadable`] holding the [`Budget`] enforced by this
+    /// [`Tracker`].
+    config: config::Reloadable,
+
+    /// [`Policy`] applied on exceeding the [`Budget`].
+    pub(crate) policy: Policy,
+
+    /// [`State`] of this [`Tracker`].
+    state: Mutex<State>,
+
+    /// [`Timestamps`] to detect the least recently updated metrics with.
+    #[cfg(feature = "timestamps")]
+    timestamps: Timestamps,
+
+    /// Gauges reporting the used part of the [`Budget`].
+    used: prometheus::IntGaugeVec,
+
+    /// Gauges reporting the [`Budget`] limits.
+    limit: prometheus::IntGaugeVec,
+
+    /// Result of registering the gauges of this [`Tracker`] in a
+    /// [`prometheus::Registry`], if registered.
+    registered: Mutex<Option<Result<(), String>>>,
+}
+
+impl Tracker {
+    /// Creates a new [`Tracker`] enforcing the [`Budget`] of the provided
+    /// [`config::Reloadable`] according to the provided [`Policy`].
+    #[expect( // intentional
+        clippy::expect_used,
+        reason = "gauges are created with the valid constant options"
+    )]
+    pub(crate) fn new(
+        config: config::Reloadable,
+        policy: Policy,
+        #[cfg(feature = "timestamps")] timestamps: Timestamps,
+    ) -> Self {
+        let gauge = |name: &str, help: &str| {
+            prometheus::IntGaugeVec::new(
+                prometheus::Opts::new(name, help),
+                &["limit"],
+            )
+            .expect("valid gauge options")
+        };
+        let used = gauge(
+            "metrics_prometheus_budget_used",
+            "Used part of the metrics budget.",
+        );
+        let limit = gauge(
+            "metrics_prometheus_budget_limit",
+            "Limit of the metrics budget.",
+        );
+
+        let tracker = Self {
+            config,
+            policy,
+            state: Mutex::default(),
+            #[cfg(feature = "timestamps")]
+            timestamps,
+            used,
+            limit,
+            registered: Mutex::default(),
+        };
+        tracker.report(&State::default());
+        tracker
+    }
+
+    /// Refreshes the reported utilization of the [`Budget`], so its limits
+    /// changed at runtime are reflected.
+    pub(crate) fn refresh(&self) {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        self.report(&state);
+        drop(state);
+    }
+
+    /// Registers the gauges of this [`Tracker`] in the provided
+    /// [`prometheus::Registry`], unless registered already.
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register the gauges.
+    pub(crate) fn register(
+        &self,
+        registry: &prometheus::Registry,
+    ) -> prometheus::Result<()> {
+        let mut registered =
+            self.registered.lock().unwrap_or_else(PoisonError::into_inner);
+        let res = registered
+            .get_or_insert_with(|| {
+                registry
+                    .register(Box::new(self.used.clone()))
+                    .and_then(|()| {
+                        registry.register(Box::new(self.limit.clone()))
+                    })
+                    .map_err(|e| e.to_string())
+            })
+            .clone();
+        drop(registered);
+        res.map_err(|e| {
+            prometheus::Error::Msg(format!(
+                "failed to register metrics budget gauges: {e}",
+            ))
+        })
+    }
+
+    /// Unregisters the gauges of this [`Tracker`] from the provided
+    /// [`prometheus::Registry`], if registered, and forgets all the admitted
+    /// metrics.
+    pub(crate) fn unregister(&self, registry: &prometheus::Registry) {
+        let mut registered =
+            self.registered.lock().unwrap_or_else(PoisonError::into_inner);
+        if registered.take().is_some_and(|res| res.is_ok()) {
+            drop(registry.unregister(Box::new(self.used.clone())));
+            drop(registry.unregister(Box::new(self.limit.clone())));
+        }
+        drop(registered);
+
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        *state = State::default();
+        self.report(&state);
+        drop(state);
+    }
+
+    /// Moves the gauges of this [`Tracker`] from the `from`
+    /// [`prometheus::Registry`] into the `to` one, if registered, keeping all
+    /// the admitted metrics.
+    ///
+    /// # Errors
+    ///
+    /// If the `to` [`prometheus::Registry`] fails to register the gauges. In
+    /// such case, they're kept in the `from` one.
+    pub(crate) fn move_to(
+        &self,
+        from: &prometheus::Registry,
+        to: &prometheus::Registry,
+    ) -> prometheus::Result<()> {
+        let registered =
+            self.registered.lock().unwrap_or_else(PoisonError::into_inner);
+        if registered.as_ref().is_some_and(Result::is_ok) {
+            to.register(Box::new(self.used.clone()))?;
+            if let Err(e) = to.register(Box::new(self.limit.clone())) {
+                drop(to.unregister(Box::new(self.used.clone())));
+                return Err(e);
+            }
+            drop(from.unregister(Box::new(self.used.clone())));
+            drop(from.unregister(Box::new(self.limit.clone())));
+        }
+        drop(registered);
+        Ok(())
+    }
+
+    /// Admits a new metric family with the provided `name`.
+    ///
+    /// # Errors
+    ///
+    /// If admitting the family exceeds the [`Budget`].
+    pub(crate) fn admit_family(&self, name: &str) -> prometheus::Result<()> {
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.families.contains(name) {
+            return Ok(());
+        }
+        let max_families = self.config.load().budget.max_families;
+        if state.families.len() >= max_families {
+            return Err(prometheus::Error::Msg(format!(
+                "cannot register `{name}` metric, as it exceeds the budget of \
+                 {max_families} metric families",
+            )));
+        }
+        _ = state.families.insert(name.into());
+        self.report(&state);
+        drop(state);
+        Ok(())
+    }
+
+    /// Releases the previously admitted metric family with the provided
+    /// `name`, if it has failed to be registered.
+    pub(crate) fn release_family(&self, name: &str) {
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        _ = state.families.remove(name);
+        self.report(&state);
+        drop(state);
+    }
+
+    /// Admits a new metric identified by the provided [`metrics::Key`],
+    /// returning the [`metrics::Key`] it should be recorded with.
+    ///
+    /// The provided `evict` function is used to remove the admitted metric
+    /// from its family on eviction, if it can be evicted.
+    ///
+    /// # Errors
+    ///
+    /// If admitting the metric exceeds the [`Budget`] and cannot be resolved
+    /// according to the [`Policy`].
+    pub(crate) fn admit_child<'k, E>(
+        &self,
+        key: &'k metrics::Key,
+        evict: Option<E>,
+    ) -> prometheus::Result<Cow<'k, metrics::Key>>
+    where
+        E: Fn() -> prometheus::Result<()> + Send + Sync + 'static,
+    {
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.children.contains_key(key) {
+            return Ok(Cow::Borrowed(key));
+        }
+        #[cfg_attr(
+            not(feature = "timestamps"),
+            expect( // intentional
+                unused_mut,
+                reason = "evicted only with `timestamps` feature"
+            )
+        )]
+        let mut evicted: Option<Evict> = None;
+        let max_children = self.config.load().budget.max_children;
+        if state.children.len() >= max_children {
+            let exceeded = || exceeded(key, max_children);
+            match self.policy {
+                Policy::Reject => return Err(exceeded()),
+                #[cfg(feature = "timestamps")]
+                Policy::EvictLeastRecentlyUpdated => {
+                    evicted =
+                        Some(self.evict(&mut state).ok_or_else(exceeded)?);
+                }
+                Policy::Overflow => {
+                    if key.labels().next().is_none() {
+                        return Err(exceeded());
+                    }
+                    return Ok(Cow::Owned(metrics::Key::from_parts(
+                        key.name().to_owned(),
+                        key.labels()
+                            .map(|l| {
+                                metrics::Label::new(
+                                    l.key().to_owned(),
+                                    OVERFLOW_LABEL_VALUE,
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    )));
+                }
+            }
+        }
+        let evict = evict.map(|f| -> Evict { Box::new(f) });
+        #[cfg(feature = "timestamps")]
+        let position = evict.is_some().then(|| {
+            let position = (self.timestamps.last_updated(key), state.next_seq);
+            state.next_seq += 1;
+            drop(state.lru.insert(position, key.clone()));
+            position
+        });
+        drop(state.children.insert(
+            key.clone(),
+            Child {
+                evict,
+                #[cfg(feature = "timestamps")]
+                position,
+            },
+        ));
+        self.report(&state);
+        drop(state);
+
+        // The evicted metric is removed from its family after releasing the
+        // lock, as it requires locking the family, so new metrics are not
+        // serialized behind it.
+        if let Some(remove) = evicted {
+            // Failing to remove the metric from its family means it's absent
+            // there already, so there is nothing to do about it.
+            drop(remove());
+        }
+        Ok(Cow::Borrowed(key))
+    }
+
+    /// Releases the previously admitted metric identified by the provided
+    /// [`metrics::Key`], if it has failed to be registered.
+    pub(crate) fn release_child(&self, key: &metrics::Key) {
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(child) = state.children.remove(key) {
+            #[cfg(feature = "timestamps")]
+            if let Some(position) = child.position {
+                drop(state.lru.remove(&position));
+            }
+            drop(child);
+            self.report(&state);
+        }
+        drop(state);
+    }
+
+    /// Takes the [`metrics::Key`]s of the metrics evicted since the last call
+    /// of this method, to be forgotten by a [`metrics::Recorder`].
+    pub(crate) fn take_evicted(&self) -> Vec<metrics::Key> {
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        mem::take(&mut state.evicted)
+    }
+
+    /// Evicts the least recently updated evictable metric from the provided
+    /// [`State`], returning the function removing it from its family, which
+    /// should be called once the [`State`] is unlocked.
+    ///
+    /// Returns [`None`] if there is no metric to evict.
+    #[cfg(feature = "timestamps")]
+    fn evict(&self, state: &mut State) -> Option<Evict> {
+        loop {
+            let ((updated, _), key) = state.lru.pop_first()?;
+            // Last update times only grow, so the stale `Position` of a
+            // metric updated meanwhile is refreshed and retried, while the
+            // first actual one is the least recently updated metric for sure.
+            let actual = self.timestamps.last_updated(&key);
+            if actual > updated {
+                let position = (actual, state.next_seq);
+                state.next_seq += 1;
+                if let Some(child) = state.children.get_mut(&key) {
+                    child.position = Some(position);
+                    drop(state.lru.insert(position, key));
+                }
+                continue;
+            }
+            let Some(Child { evict: Some(evict), .. }) =
+                state.children.remove(&key)
+            else {
+                continue;
+            };
+            state.evicted.push(key);
+            return Some(evict);
+        }
+    }
+
+    /// Reports the utilization of the [`Budget`] by the provided [`State`].
+    fn report(&self, state: &State) {
+        let budget = self.config.load().budget;
+        self.limit
+            .with_label_values(&["families"])
+            .set(i64::try_from(budget.max_families).unwrap_or(i64::MAX));
+        self.limit
+            .with_label_values(&["children"])
+            .set(i64::try_from(budget.max_children).unwrap_or(i64::MAX));
+        self.used
+            .with_label_values(&["families"])
+            .set(i64::try_from(state.families.len()).unwrap_or(i64::MAX));
+        self.used
+            .with_label_values(&["children"])
+            .set(i64::try_from(state.children.len()).unwrap_or(i64::MAX));
+    }
+}
+
+/// Creates a [`prometheus::Error`] describing the metric identified by the
+/// provided [`metrics::Key`] exceeding the `max_children` of a [`Budget`].
+fn exceeded(key: &metrics::Key, max_children: usize) -> prometheus::Error {
+    prometheus::Error::Msg(format!(
+        "cannot register `{}` metric, as it exceeds the budget of \
+         {max_children} metrics",
+        key.name(),
+    ))
+}
@@ -0,0 +1,772 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Catalogs of the metric families registered in [`prometheus::Registry`]s,
+//! and diffs between them.
+//!
+//! Useful for verifying upgrades, or reporting what has changed after a
+//! migration.
+//!
+//! Also, documented [`Entries`] of a central metrics catalog (like a YAML one
+//! kept along with docs) may be applied to a [`Recorder`], keeping its
+//! [`help` description]s in sync.
+//!
+//! Also, a [`Snapshot`] of the metric families of a [`FrozenRecorder`] may be
+//! persisted to disk and restored on the next run.
+//!
+//! [`FrozenRecorder`]: crate::FrozenRecorder
+//! [`help` description]: prometheus::proto::MetricFamily::get_help
+//! [`Recorder`]: crate::Recorder
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    metric::{self, bundle::Schema},
+    storage::{self, mutable::Collection, pending::Kind},
+};
+
+/// Metric family in a catalog of a [`prometheus::Registry`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Family {
+    /// [`Schema`] of this [`Family`].
+    ///
+    /// As inferred from the gathered metrics, its label names include the
+    /// constant labels too.
+    pub schema: Schema,
+
+    /// [`help` description] of this [`Family`].
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub help: String,
+}
+
+impl Family {
+    /// Infers a [`Family`] from the provided gathered
+    /// [`prometheus::proto::MetricFamily`].
+    fn of(family: &prometheus::proto::MetricFamily) -> Self {
+        let mut label_names = family
+            .get_metric()
+            .iter()
+            .flat_map(prometheus::proto::Metric::get_label)
+            .map(|l| l.get_name().to_owned())
+            .collect::<Vec<_>>();
+        label_names.sort_unstable();
+        label_names.dedup();
+        let buckets = family
+            .get_metric()
+            .first()
+            .map(|m| {
+                m.get_histogram()
+                    .get_bucket()
+                    .iter()
+                    .map(prometheus::proto::Bucket::get_upper_bound)
+                    .filter(|b| b.is_finite())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            schema: Schema {
+                name: family.get_name().to_owned(),
+                label_names,
+                kind: family.get_field_type(),
+                buckets,
+            },
+            help: family.get_help().to_owned(),
+        }
+    }
+
+    /// Infers a [`Family`] of the provided `kind` from the provided stored
+    /// [`metric::Bundle`], having the provided `description` and configured
+    /// `buckets` (if known).
+    ///
+    /// Falls back to the [`help` description] of the [`metric::Bundle`]
+    /// itself, if the `description` is empty, and to its collected buckets,
+    /// if the configured ones are unknown.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub(crate) fn of_bundle<S, V>(
+        bundle: &metric::bundle::Either<S, V>,
+        description: &str,
+        kind: prometheus::proto::MetricType,
+        buckets: Option<&[f64]>,
+    ) -> Self
+    where
+        metric::bundle::Either<S, V>: prometheus::core::Collector,
+    {
+        use prometheus::core::Collector as _;
+
+        let mut schema = bundle.schema();
+        schema.kind = kind;
+        if let Some(buckets) = buckets {
+            schema.buckets = buckets.to_vec();
+        }
+        let help = if description.is_empty() {
+            bundle.desc().first().map(|d| d.help.clone()).unwrap_or_default()
+        } else {
+            description.to_owned()
+        };
+        Self { schema, help }
+    }
+}
+
+/// Change of a metric [`Family`] present in both compared catalogs.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Change {
+    /// [`Family`] before the change.
+    pub before: Family,
+
+    /// [`Family`] after the change.
+    pub after: Family,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (before, after) = (&self.before.schema, &self.after.schema);
+        write!(f, "~ {}", after.name)?;
+        if before.kind != after.kind {
+            write!(
+                f,
+                " type: {} -> {};",
+                kind_name(before.kind),
+                kind_name(after.kind),
+            )?;
+        }
+        if self.before.help != self.after.help {
+            write!(
+                f,
+                " help: \"{}\" -> \"{}\";",
+                self.before.help, self.after.help,
+            )?;
+        }
+        if before.label_names != after.label_names {
+            write!(
+                f,
+                " labels: [{}] -> [{}];",
+                before.label_names.join(", "),
+                after.label_names.join(", "),
+            )?;
+        }
+        if before.buckets.len() != after.buckets.len()
+            || before
+                .buckets
+                .iter()
+                .zip(&after.buckets)
+                .any(|(a, b)| a.to_bits() != b.to_bits())
+        {
+            let join = |buckets: &[f64]| {
+                buckets
+                    .iter()
+                    .map(f64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            write!(
+                f,
+                " buckets: [{}] -> [{}];",
+                join(&before.buckets),
+                join(&after.buckets),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the name of the provided [`prometheus::proto::MetricType`], as
+/// rendered in the Prometheus text format.
+const fn kind_name(kind: prometheus::proto::MetricType) -> &'static str {
+    use prometheus::proto::MetricType as T;
+
+    match kind {
+        T::COUNTER => "counter",
+        T::GAUGE => "gauge",
+        T::HISTOGRAM => "histogram",
+        T::SUMMARY => "summary",
+        T::UNTYPED => "untyped",
+    }
+}
+
+/// Difference between catalogs of two [`prometheus::Registry`]s, returned by
+/// the [`diff()`] function.
+///
+/// All the [`Family`]s are sorted by their names.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Diff {
+    /// [`Family`]s present in the second catalog only.
+    pub added: Vec<Family>,
+
+    /// [`Family`]s present in the first catalog only.
+    pub removed: Vec<Family>,
+
+    /// [`Family`]s present in both catalogs, but having different type, help
+    /// description, label names or bucket layout.
+    pub changed: Vec<Change>,
+}
+
+impl Diff {
+    /// Indicates whether the compared catalogs are the same.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for family in &self.added {
+            writeln!(f, "+ {}", family.schema.name)?;
+        }
+        for family in &self.removed {
+            writeln!(f, "- {}", family.schema.name)?;
+        }
+        for change in &self.changed {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the catalog of the metric [`Family`]s registered in the provided
+/// [`prometheus::Registry`], by their names.
+///
+/// Metric families having no metrics yet (like an empty
+/// [`prometheus::IntCounterVec`]) are not gathered, so are not present in the
+/// catalog.
+#[must_use]
+pub fn of(registry: &prometheus::Registry) -> BTreeMap<String, Family> {
+    registry
+        .gather()
+        .iter()
+        .map(|f| (f.get_name().to_owned(), Family::of(f)))
+        .collect()
+}
+
+/// Compares the catalogs of the provided [`prometheus::Registry`]s, returning
+/// the metric [`Family`]s added, removed and changed in the `after` one.
+///
+/// # Example
+///
+/// ```rust
+/// let before = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .build();
+/// metrics::with_local_recorder(&before, || {
+///     metrics::counter!("requests").increment(1);
+///     metrics::gauge!("queue").set(1.0);
+/// });
+///
+/// let after = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .build();
+/// metrics::with_local_recorder(&after, || {
+///     metrics::counter!("requests", "path" => "/").increment(1);
+///     metrics::histogram!("latency").record(0.1);
+/// });
+///
+/// let diff = metrics_prometheus::diff(before.registry(), after.registry());
+/// assert_eq!(
+///     diff.to_string(),
+///     "+ latency\n\
+///      - queue\n\
+///      ~ requests labels: [] -> [path];\n",
+/// );
+/// ```
+#[must_use]
+pub fn diff(
+    before: &prometheus::Registry,
+    after: &prometheus::Registry,
+) -> Diff {
+    let (before, mut after) = (of(before), of(after));
+    let mut diff = Diff::default();
+    for (name, b) in before {
+        match after.remove(&name) {
+            None => diff.removed.push(b),
+            Some(a) if a != b => {
+                diff.changed.push(Change { before: b, after: a });
+            }
+            Some(_) => {}
+        }
+    }
+    diff.added = after.into_values().collect();
+    diff
+}
+
+/// Header line of the [`Snapshot`] file format, identifying its version.
+const SNAPSHOT_HEADER: &str = "# metrics-prometheus catalog v1";
+
+/// Persistable catalog of the metric families of a [`FrozenRecorder`]: their
+/// names, kinds, label names, histogram buckets and [`help` description]s,
+/// but not their values.
+///
+/// Allows CLI tools running repeatedly to restore a [`FrozenRecorder`] at
+/// startup (via the [`Builder::try_with_catalog()`] method), skipping the
+/// re-declaration boilerplate and guaranteeing the schema stability between
+/// runs.
+///
+/// Persisted in a compact line-based text format via its [`Display`] and
+/// [`FromStr`] implementations.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::catalog::Snapshot;
+///
+/// let recorder = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .try_with_counter("requests", &["path"], "Number of requests.")?
+///     .try_with_metric(prometheus::HistogramVec::new(
+///         prometheus::HistogramOpts::new("latency", "Latency.")
+///             .buckets(vec![1.0, 2.0]),
+///         &["path"],
+///     )?)?
+///     .build_frozen();
+/// metrics::with_local_recorder(&recorder, || {
+///     metrics::histogram!("latency", "path" => "/").record(1.5);
+/// });
+/// let path = std::env::temp_dir().join("metrics-prometheus-snapshot.txt");
+/// recorder.catalog().save(&path)?;
+///
+/// // On the next run.
+/// let restored = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .try_with_catalog(&Snapshot::load(&path)?)?
+///     .build_frozen();
+/// metrics::with_local_recorder(&restored, || {
+///     metrics::counter!("requests", "path" => "/").increment(1);
+/// });
+/// // Histogram buckets are kept even without any observations.
+/// assert_eq!(restored.catalog(), recorder.catalog());
+///
+/// let report = prometheus::TextEncoder::new()
+///     .encode_to_string(&restored.registry().gather())?;
+/// assert!(report.contains("# HELP requests Number of requests."));
+/// assert!(report.contains(r#"requests{path="/"} 1"#));
+/// # std::fs::remove_file(path)?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`Builder::try_with_catalog()`]: crate::recorder::Builder::try_with_catalog
+/// [`Display`]: fmt::Display
+/// [`FromStr`]: str::FromStr
+/// [`FrozenRecorder`]: crate::FrozenRecorder
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Snapshot {
+    /// Metric [`Family`]s of this [`Snapshot`], sorted by their names.
+    ///
+    /// Their label names include the variable labels only.
+    pub families: Vec<Family>,
+}
+
+impl Snapshot {
+    /// Writes this [`Snapshot`] into the file at the provided `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Reads a [`Snapshot`] from the file at the provided `path`.
+    ///
+    /// # Errors
+    ///
+    /// - If the file cannot be read.
+    /// - If the file contents is not a valid [`Snapshot`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?.parse().map_err(|e: prometheus::Error| {
+            io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+        })
+    }
+
+    /// Encodes this [`Snapshot`] as a JSON document, annotating its metric
+    /// [`Family`]s with the provided `units` (if any).
+    ///
+    /// Describes the metric families only (name, kind, help, unit, label names
+    /// and histogram buckets), without any of their values, so is suitable
+    /// for documentation portals.
+    ///
+    /// The document has the following shape:
+    /// ```json
+    /// {"families":[
+    ///   {"name":"latency","kind":"histogram","help":"Request latency.",
+    ///    "unit":"seconds","labels":["path"],"buckets":[0.1,1]}
+    /// ]}
+    /// ```
+    #[must_use]
+    pub fn to_json(&self, units: &BTreeMap<String, metrics::Unit>) -> String {
+        let mut out = String::from(r#"{"families":["#);
+        for (i, family) in self.families.iter().enumerate() {
+            let Schema { name, label_names, kind, buckets } = &family.schema;
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(r#"{"name":"#);
+            push_json_string(&mut out, name);
+            out.push_str(r#","kind":""#);
+            out.push_str(kind_name(*kind));
+            out.push_str(r#"","help":"#);
+            push_json_string(&mut out, &family.help);
+            out.push_str(r#","unit":"#);
+            match units.get(name) {
+                Some(unit) => push_json_string(&mut out, unit.as_str()),
+                None => out.push_str("null"),
+            }
+            out.push_str(r#","labels":["#);
+            for (n, label) in label_names.iter().enumerate() {
+                if n > 0 {
+                    out.push(',');
+                }
+                push_json_string(&mut out, label);
+            }
+            out.push_str(r#"],"buckets":["#);
+            // Non-finite bounds are not representable in JSON, while the
+            // `+Inf` one is implicit anyway.
+            let buckets = buckets
+                .iter()
+                .filter(|b| b.is_finite())
+                .map(f64::to_string)
+                .collect::<Vec<_>>();
+            out.push_str(&buckets.join(","));
+            out.push_str("]}");
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{SNAPSHOT_HEADER}")?;
+        for family in &self.families {
+            let Schema { name, label_names, kind, buckets } = &family.schema;
+            let buckets =
+                buckets.iter().map(f64::to_string).collect::<Vec<_>>();
+            writeln!(
+                f,
+                "{}\t{name}\t{}\t{}\t{}",
+                kind_name(*kind),
+                label_names.join(","),
+                buckets.join(","),
+                escape(&family.help),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl str::FromStr for Snapshot {
+    type Err = prometheus::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |n: usize, reason: &str| {
+            prometheus::Error::Msg(format!(
+                "invalid catalog snapshot at line {}: {reason}",
+                n + 1,
+            ))
+        };
+        let split = |field: &str| -> Vec<String> {
+            field
+                .split(',')
+                .filter(|v| !v.is_empty())
+                .map(str::to_owned)
+                .collect()
+        };
+
+        let mut lines = s.lines().enumerate();
+        if lines.next().map(|(_, l)| l) != Some(SNAPSHOT_HEADER) {
+            return Err(invalid(0, "unsupported header"));
+        }
+        let mut families = lines
+            .filter(|(_, l)| !l.is_empty())
+            .map(|(n, line)| {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                let [kind, name, labels, buckets, help] = fields[..] else {
+                    return Err(invalid(n, "expected 5 tab-separated fields"));
+                };
+                let kind = match kind {
+                    "counter" => prometheus::proto::MetricType::COUNTER,
+                    "gauge" => prometheus::proto::MetricType::GAUGE,
+                    "histogram" => prometheus::proto::MetricType::HISTOGRAM,
+                    _ => return Err(invalid(n, "unsupported metric kind")),
+                };
+                let buckets = split(buckets)
+                    .iter()
+                    .map(|b| b.parse::<f64>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| {
+                        invalid(n, &format!("invalid histogram buckets: {e}"))
+                    })?;
+                Ok(Family {
+                    schema: Schema {
+                        name: name.to_owned(),
+                        label_names: split(labels),
+                        kind,
+                        buckets,
+                    },
+                    help: unescape(help),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        families.sort_by(|a, b| a.schema.name.cmp(&b.schema.name));
+        Ok(Self { families })
+    }
+}
+
+/// Escapes the tabs, line breaks and backslashes of the provided `value`, so
+/// it fits a single field of a [`Snapshot`] line.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('\t', r"\t")
+        .replace('\n', r"\n")
+        .replace('\r', r"\r")
+}
+
+/// Appends the provided `value` to the provided `out`put as a quoted JSON
+/// string.
+fn push_json_string(out: &mut String, value: &str) {
+    use fmt::Write as _;
+
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str(r#"\""#),
+            '\\' => out.push_str(r"\\"),
+            '\n' => out.push_str(r"\n"),
+            '\r' => out.push_str(r"\r"),
+            '\t' => out.push_str(r"\t"),
+            c if c.is_control() => {
+                _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Reverts the [`escape()`] of the provided `value`.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(escaped) => out.push(escaped),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Stability level of a documented metric.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Stability {
+    /// Metric is stable and won't be changed in a breaking way.
+    #[default]
+    Stable,
+
+    /// Metric may be changed or removed at any time.
+    Experimental,
+
+    /// Metric is going to be removed, so shouldn't be relied upon.
+    Deprecated,
+}
+
+/// Documented metric in a central metrics catalog.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Entry {
+    /// [`Kind`] of the documented metric, if known.
+    ///
+    /// If not specified, the [`Entry::help`] is applied only to the metric
+    /// registered (or described) already.
+    pub kind: Option<Kind>,
+
+    /// [`help` description] of the documented metric.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub help: String,
+
+    /// [`metrics::Unit`] of the documented metric, if any.
+    ///
+    /// Kept for documentation purposes only, as the Prometheus text format
+    /// doesn't expose units.
+    pub unit: Option<metrics::Unit>,
+
+    /// [`Stability`] of the documented metric.
+    pub stability: Stability,
+}
+
+/// Documented [`Entry`]s of a central metrics catalog, by metric names.
+///
+/// Usually, deserialized from a file (see the [`Recorder::watch_catalog()`]).
+///
+/// [`Recorder::watch_catalog()`]: crate::Recorder::watch_catalog
+pub type Entries = BTreeMap<String, Entry>;
+
+/// Outcome of applying [`Entries`] of a central metrics catalog, returned by
+/// the [`Recorder::apply_catalog()`] method.
+///
+/// [`Recorder::apply_catalog()`]: crate::Recorder::apply_catalog
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Applied {
+    /// Sorted names of the registered metrics missing in the catalog.
+    pub unknown: Vec<String>,
+
+    /// Sorted names of the catalog [`Entry`]s without a [`Kind`], which are
+    /// neither registered nor described yet, so couldn't be applied.
+    pub unapplied: Vec<String>,
+
+    /// Sorted names of the registered metrics documented as
+    /// [`Stability::Deprecated`].
+    pub deprecated: Vec<String>,
+}
+
+impl Applied {
+    /// Indicates whether this [`Applied`] outcome has no issues.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.unknown.is_empty()
+            && self.unapplied.is_empty()
+            && self.deprecated.is_empty()
+    }
+}
+
+/// Applies the provided [`Entries`] to the provided [`storage::Mutable`].
+pub(crate) fn apply(storage: &storage::Mutable, entries: &Entries) -> Applied {
+    /// Checks whether the provided `name` is known to the provided
+    /// [`Collection`].
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn is_known<B>(collection: &Collection<B>, name: &str) -> bool {
+        collection.read().unwrap().contains_key(name)
+    }
+
+    let counters: &Collection<metric::PrometheusIntCounter> =
+        storage::Get::collection(storage);
+    let gauges: &Collection<metric::PrometheusGauge> =
+        storage::Get::collection(storage);
+    let histograms: &Collection<metric::PrometheusHistogram> =
+        storage::Get::collection(storage);
+
+    let mut applied = Applied::default();
+    for (name, entry) in entries {
+        let help = metrics::SharedString::from(entry.help.clone());
+        let kinds = entry.kind.map_or_else(
+            || {
+                [
+                    (Kind::Counter, is_known(counters, name)),
+                    (Kind::Gauge, is_known(gauges, name)),
+                    (Kind::Histogram, is_known(histograms, name)),
+                ]
+                .into_iter()
+                .filter_map(|(kind, known)| known.then_some(kind))
+                .collect()
+            },
+            |kind| vec![kind],
+        );
+        if kinds.is_empty() {
+            applied.unapplied.push(name.clone());
+        }
+        for kind in kinds {
+            match kind {
+                Kind::Counter => storage
+                    .describe::<prometheus::IntCounter>(name, help.clone()),
+                Kind::Gauge => {
+                    storage.describe::<prometheus::Gauge>(name, help.clone());
+                }
+                Kind::Histogram => storage
+                    .describe::<prometheus::Histogram>(name, help.clone()),
+            }
+        }
+    }
+    for name in storage.registered_names() {
+        match entries.get(&name) {
+            None => applied.unknown.push(name),
+            Some(e) if e.stability == Stability::Deprecated => {
+                applied.deprecated.push(name);
+            }
+            Some(_) => {}
+        }
+    }
+    applied
+}
+
+/// Background watcher of a central metrics catalog file, re-applying it to a
+/// [`Recorder`] on its changes, returned by the
+/// [`Recorder::watch_catalog()`] method.
+///
+/// Stops watching once dropped, or once the [`Shutdown`] of the
+/// [`Recorder`] is triggered.
+///
+/// [`Recorder`]: crate::Recorder
+/// [`Recorder::watch_catalog()`]: crate::Recorder::watch_catalog
+/// [`Shutdown`]: crate::tasks::Shutdown
+#[derive(Debug)]
+pub struct Watcher {
+    /// Indicator whether this [`Watcher`] should stop.
+    stop: Arc<AtomicBool>,
+}
+
+impl Watcher {
+    /// Spawns a new [`Watcher`] polling the file at the provided `path` with
+    /// the provided `interval`, and applying its contents parsed with the
+    /// provided `parse` function to the provided [`storage::Mutable`].
+    pub(crate) fn spawn<F, E>(
+        storage: storage::Mutable,
+        path: PathBuf,
+        interval: Duration,
+        parse: F,
+    ) -> Self
+    where
+        F: Fn(&str) -> Result<Entries, E> + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let tasks = storage.tasks.clone();
+        tasks.spawn(move |signal| {
+            let mut last = None;
+            while !stopped.load(Ordering::Relaxed) {
+                // Unreadable or unparsable file is skipped, keeping the last
+                // applied descriptions, until it's fixed.
+                if let Ok(contents) = fs::read_to_string(&path) {
+                    if last.as_ref() != Some(&contents) {
+                        if let Ok(entries) = parse(&contents) {
+                            drop(apply(&storage, &entries));
+                            last = Some(contents);
+                        }
+                    }
+                }
+                if !signal.sleep(interval) {
+                    break;
+                }
+            }
+        });
+        Self { stop }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
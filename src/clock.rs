@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Time sources of time-based features (like [`windowed::Rate`]s, rate
+//! limiting and last update timestamps).
+//!
+//! [`windowed::Rate`]: crate::metric::windowed::Rate
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Shared type-erased [`Clock`].
+pub type Shared = Arc<dyn Clock>;
+
+/// Source of the current time.
+///
+/// Allows to make time-based features deterministic in tests (see the
+/// [`clock::Mock`]), or to supply a custom time source on exotic platforms.
+///
+/// [`clock::Mock`]: Mock
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Returns the current [`Instant`] of this [`Clock`].
+    ///
+    /// Must be monotonically non-decreasing.
+    #[must_use]
+    fn now(&self) -> Instant;
+}
+
+impl<C: Clock + ?Sized> Clock for Box<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// [`Clock`] of the real system time, being the [`Instant::now()`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct System;
+
+impl Clock for System {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Manually advanced [`Clock`] for tests.
+///
+/// Cheaply cloneable, so may be kept at hand after being passed to the
+/// [`Builder::with_clock()`]. All the clones share the same time.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use metrics_prometheus::clock::{self, Clock as _};
+///
+/// let clock = clock::Mock::new();
+/// let start = clock.now();
+/// assert_eq!(clock.now(), start);
+///
+/// clock.clone().advance(Duration::from_secs(5));
+/// assert_eq!(clock.now() - start, Duration::from_secs(5));
+/// ```
+///
+/// [`Builder::with_clock()`]: crate::recorder::Builder::with_clock
+#[derive(Clone, Debug)]
+pub struct Mock(Arc<MockInner>);
+
+/// Shared state of a [`clock::Mock`].
+///
+/// [`clock::Mock`]: Mock
+#[derive(Debug)]
+struct MockInner {
+    /// [`Instant`] this [`clock::Mock`] has been created at.
+    ///
+    /// [`clock::Mock`]: Mock
+    base: Instant,
+
+    /// Nanoseconds this [`clock::Mock`] has been advanced by since the
+    /// [`MockInner::base`].
+    ///
+    /// [`clock::Mock`]: Mock
+    elapsed: AtomicU64,
+}
+
+impl Mock {
+    /// Creates a new [`clock::Mock`] starting at the current real time.
+    ///
+    /// [`clock::Mock`]: Mock
+    #[must_use]
+    pub fn new() -> Self {
+        Self(Arc::new(MockInner {
+            base: Instant::now(),
+            elapsed: AtomicU64::new(0),
+        }))
+    }
+
+    /// Advances this [`clock::Mock`] (and all its clones) by the provided
+    /// [`Duration`].
+    ///
+    /// [`clock::Mock`]: Mock
+    pub fn advance(&self, by: Duration) {
+        let nanos = u64::try_from(by.as_nanos()).unwrap_or(u64::MAX);
+        _ = self.0.elapsed.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+impl Default for Mock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for Mock {
+    fn now(&self) -> Instant {
+        self.0.base
+            + Duration::from_nanos(self.0.elapsed.load(Ordering::Relaxed))
+    }
+}
@@ -0,0 +1,340 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Runtime configuration of metrics registered on the fly, which may be
+//! replaced without rebuilding a [`Recorder`].
+//!
+//! [`Recorder`]: crate::Recorder
+
+use std::{borrow::Cow, sync::Arc};
+
+use arc_swap::ArcSwap;
+
+use crate::{budget, matcher::Matcher, storage};
+
+/// Configuration of sanitization, filtering, default labels and cardinality
+/// limits applied to the metrics registered on the fly.
+///
+/// Changes take effect for the metrics registered after them, while the
+/// already registered ones are kept intact (except being [`Config::denied`],
+/// which silences their handles obtained anew).
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Indicator whether empty metric names, label names and label values are
+    /// rejected.
+    pub strict: bool,
+
+    /// Indicator whether labels with empty values are dropped (instead of
+    /// being rejected) when the [`Config::strict`] is enabled.
+    pub drop_empty_labels: bool,
+
+    /// Indicator whether control characters (like newlines) are stripped from
+    /// label values.
+    pub strip_control_chars: bool,
+
+    /// Maximum [`storage::Lengths`] of metric names, label names and label
+    /// values.
+    pub lengths: storage::Lengths,
+
+    /// [`storage::Overlong`] policy applied to the metrics exceeding the
+    /// [`Config::lengths`].
+    pub overlong: storage::Overlong,
+
+    /// [`Matcher`]s of the metrics to be silently turned into no-op ones,
+    /// instead of being registered.
+    pub denied: Vec<Matcher>,
+
+    /// Labels (names along with values) added to the metrics missing them.
+    ///
+    /// Added to a metric only if its family is not registered yet, or is
+    /// registered with such label, so the existing families are not broken.
+    pub default_labels: Vec<(String, String)>,
+
+    /// [`budget::Budget`] of the metrics registered on the fly.
+    ///
+    /// Enforced only if the budget tracking is enabled on building (via the
+    /// [`Builder::with_metric_budget()`] method, for example).
+    ///
+    /// [`Builder::with_metric_budget()`]:
+    ///     crate::recorder::Builder::with_metric_budget
+    pub budget: budget::Budget,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            drop_empty_labels: false,
+            strip_control_chars: false,
+            lengths: storage::Lengths::UNLIMITED,
+            overlong: storage::Overlong::default(),
+            denied: Vec::new(),
+            default_labels: Vec::new(),
+            budget: budget::Budget::UNLIMITED,
+        }
+    }
+}
+
+impl Config {
+    /// Indicates whether the metric family identified by the provided `name`
+    /// is [`Config::denied`].
+    #[must_use]
+    pub fn is_denied(&self, name: &str) -> bool {
+        self.denied.iter().any(|m| m.matches(name))
+    }
+
+    /// Strips control characters from the label values of the provided
+    /// [`metrics::Key`] if the [`Config::strip_control_chars`] is
+    /// enabled, validates it if the [`Config::strict`] is enabled
+    /// (dropping its labels with empty values if the
+    /// [`Config::drop_empty_labels`] is enabled), and enforces the
+    /// [`Config::lengths`].
+    ///
+    /// Control characters are stripped before validating, so the label values
+    /// consisting of them only are treated as empty ones.
+    ///
+    /// # Errors
+    ///
+    /// - If the [`Config::strict`] is enabled, and the provided
+    ///   [`metrics::Key`] has an empty name, or any of its labels has an empty
+    ///   name or value (unless dropped).
+    /// - If the provided [`metrics::Key`] exceeds the
+    ///   [`Config::lengths`], and the [`Overlong::Reject`] policy is
+    ///   applied.
+    ///
+    /// [`Overlong::Reject`]: storage::Overlong::Reject
+    pub(crate) fn validated<'k>(
+        &self,
+        key: &'k metrics::Key,
+    ) -> prometheus::Result<Cow<'k, metrics::Key>> {
+        let key = match self.stripped(key) {
+            Cow::Borrowed(key) => self.checked(key)?,
+            Cow::Owned(key) => Cow::Owned(self.checked(&key)?.into_owned()),
+        };
+        self.limited(key)
+    }
+
+    /// Strips control characters from the label values of the provided
+    /// [`metrics::Key`] if the [`Config::strip_control_chars`] is
+    /// enabled.
+    fn stripped<'k>(&self, key: &'k metrics::Key) -> Cow<'k, metrics::Key> {
+        if !self.strip_control_chars
+            || !key.labels().any(|l| l.value().contains(char::is_control))
+        {
+            return Cow::Borrowed(key);
+        }
+        let labels = key
+            .labels()
+            .map(|l| {
+                let value = l.value().replace(char::is_control, "");
+                metrics::Label::new(l.key().to_owned(), value)
+            })
+            .collect::<Vec<_>>();
+        Cow::Owned(metrics::Key::from_parts(key.name_shared(), labels))
+    }
+
+    /// Enforces the [`Config::lengths`] on the provided
+    /// [`metrics::Key`], truncating its overlong label values if the
+    /// [`Overlong::Truncate`] policy is applied.
+    ///
+    /// Overlong metric names and label names are always rejected, as
+    /// truncating them would silently merge different metric families (or
+    /// labels), mixing up their values and [`help` description]s.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided [`metrics::Key`] has an overlong name or label name.
+    /// - If the provided [`metrics::Key`] has an overlong label value, and the
+    ///   [`Overlong::Reject`] policy is applied.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    /// [`Overlong::Reject`]: storage::Overlong::Reject
+    /// [`Overlong::Truncate`]: storage::Overlong::Truncate
+    fn limited<'k>(
+        &self,
+        key: Cow<'k, metrics::Key>,
+    ) -> prometheus::Result<Cow<'k, metrics::Key>> {
+        let storage::Lengths { max_name, max_label_name, max_label_value } =
+            self.lengths;
+        let name = key.name();
+        let overlong = |what: String, max: usize| {
+            prometheus::Error::Msg(format!("{what} exceeds {max} bytes"))
+        };
+
+        if name.len() > max_name {
+            return Err(overlong(format!("name of `{name}` metric"), max_name));
+        }
+        for label in key.labels() {
+            if label.key().len() > max_label_name {
+                return Err(overlong(
+                    format!(
+                        "name of `{}` label of `{name}` metric",
+                        label.key()
+                    ),
+                    max_label_name,
+                ));
+            }
+        }
+        let Some(label) =
+            key.labels().find(|l| l.value().len() > max_label_value)
+        else {
+            return Ok(key);
+        };
+        if self.overlong == storage::Overlong::Reject {
+            return Err(overlong(
+                format!("value of `{}` label of `{name}` metric", label.key()),
+                max_label_value,
+            ));
+        }
+
+        let labels = key
+            .labels()
+            .map(|l| {
+                metrics::Label::new(
+                    l.key().to_owned(),
+                    truncated(l.value(), max_label_value).to_owned(),
+                )
+            })
+            .collect::<Vec<_>>();
+        Ok(Cow::Owned(metrics::Key::from_parts(key.name_shared(), labels)))
+    }
+
+    /// Checks the provided [`metrics::Key`] if the [`Config::strict`]
+    /// is enabled, dropping its labels with empty values if the
+    /// [`Config::drop_empty_labels`] is enabled.
+    ///
+    /// # Errors
+    ///
+    /// If the [`Config::strict`] is enabled, and the provided
+    /// [`metrics::Key`] has an empty name, or any of its labels has an empty
+    /// name or value (unless dropped).
+    fn checked<'k>(
+        &self,
+        key: &'k metrics::Key,
+    ) -> prometheus::Result<Cow<'k, metrics::Key>> {
+        if !self.strict {
+            return Ok(Cow::Borrowed(key));
+        }
+
+        let name = key.name();
+        if name.is_empty() {
+            return Err(prometheus::Error::Msg(
+                "metric name cannot be empty".into(),
+            ));
+        }
+        let mut has_empty = false;
+        for label in key.labels() {
+            if label.key().is_empty() {
+                return Err(prometheus::Error::Msg(format!(
+                    "label name of `{name}` metric cannot be empty",
+                )));
+            }
+            if label.value().is_empty() {
+                if !self.drop_empty_labels {
+                    return Err(prometheus::Error::Msg(format!(
+                        "value of `{}` label of `{name}` metric cannot be \
+                         empty",
+                        label.key(),
+                    )));
+                }
+                has_empty = true;
+            }
+        }
+        if !has_empty {
+            return Ok(Cow::Borrowed(key));
+        }
+        let labels = key
+            .labels()
+            .filter(|l| !l.value().is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(Cow::Owned(metrics::Key::from_parts(key.name_shared(), labels)))
+    }
+
+    /// Adds the [`Config::default_labels`] missing in the provided
+    /// [`metrics::Key`].
+    ///
+    /// If the `declared` function returns the label names of the registered
+    /// family, only the ones present there are added. It's called only if
+    /// there are any [`Config::default_labels`].
+    pub(crate) fn defaulted<'k>(
+        &self,
+        key: &'k metrics::Key,
+        declared: impl FnOnce() -> Option<Vec<String>>,
+    ) -> Cow<'k, metrics::Key> {
+        if self.default_labels.is_empty() {
+            return Cow::Borrowed(key);
+        }
+        let declared = declared();
+        let missing = self
+            .default_labels
+            .iter()
+            .filter(|(name, _)| {
+                declared.as_ref().map_or(true, |d| d.contains(name))
+                    && !key.labels().any(|l| l.key() == name)
+            })
+            .map(|(name, value)| {
+                metrics::Label::new(name.clone(), value.clone())
+            })
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Cow::Borrowed(key);
+        }
+        Cow::Owned(metrics::Key::from_parts(
+            key.name_shared(),
+            key.labels().cloned().chain(missing).collect::<Vec<_>>(),
+        ))
+    }
+}
+
+/// Shared [`Config`], which may be replaced at runtime.
+///
+/// Cheaply cloneable, so may be kept at hand after the [`Recorder`] is
+/// installed globally.
+///
+/// [`Recorder`]: crate::Recorder
+#[derive(Clone, Debug, Default)]
+pub struct Reloadable(Arc<ArcSwap<Config>>);
+
+impl Reloadable {
+    /// Creates a new [`Reloadable`] holding the provided [`Config`].
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(config)))
+    }
+
+    /// Returns the current [`Config`].
+    #[must_use]
+    pub fn get(&self) -> Arc<Config> {
+        self.0.load_full()
+    }
+
+    /// Replaces the current [`Config`] with the provided one, taking effect
+    /// on the next registration of a metric.
+    pub fn set(&self, config: Config) {
+        self.0.store(Arc::new(config));
+    }
+
+    /// Applies the provided function to the current [`Config`].
+    ///
+    /// Not atomic in regard to the concurrent [`Reloadable::set()`] calls, so
+    /// is intended to be used while building only.
+    pub(crate) fn update(&self, f: impl FnOnce(&mut Config)) {
+        let mut config = Config::clone(&self.0.load());
+        f(&mut config);
+        self.set(config);
+    }
+
+    /// Loads the current [`Config`] for a short-lived access.
+    pub(crate) fn load(&self) -> arc_swap::Guard<Arc<Config>> {
+        self.0.load()
+    }
+}
+
+/// Truncates the provided `value` to the provided `max` length (in bytes), at
+/// the nearest UTF-8 character boundary.
+fn truncated(value: &str, max: usize) -> &str {
+    if value.len() <= max {
+        return value;
+    }
+    (0..=max).rev().find_map(|end| value.get(..end)).unwrap_or_default()
+}
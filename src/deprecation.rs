@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Deprecated metric names, transparently routed to their replacements.
+//!
+//! Helps to enforce a cleanup of a metrics catalog across a large codebase:
+//! the old names keep working, while their usages are surfaced.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{storage::KeyName, trace};
+
+/// Policy of surfacing usages of a deprecated metric name.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Policy {
+    /// Route the usages to the replacement silently.
+    Silent,
+
+    /// Emit a warning on the first usage of the deprecated name.
+    ///
+    /// The warning is a [`tracing`] event under the `metrics_prometheus`
+    /// target, so is a no-op unless the `tracing` feature is enabled.
+    ///
+    /// [`tracing`]: https://docs.rs/tracing
+    #[default]
+    Warn,
+
+    /// Count the usages of the deprecated name via the
+    /// `metrics_prometheus_deprecated_usages_total` counter (labeled with the
+    /// `name` of the deprecated metric and its `replacement`), registered
+    /// along with the first counted usage.
+    Count,
+}
+
+/// Replacement of a deprecated metric name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Replacement {
+    /// Name of the metric to route the usages to.
+    name: KeyName,
+
+    /// [`Policy`] of surfacing the usages.
+    policy: Policy,
+}
+
+/// Tracker of the usages of deprecated metric names, routing them to their
+/// replacements.
+///
+/// A usage is a registration of a metric (obtaining its handle) via the
+/// deprecated name, not a recording into it.
+#[derive(Debug)]
+pub(crate) struct Tracker {
+    /// [`Replacement`]s of the deprecated metric names.
+    replacements: HashMap<KeyName, Replacement>,
+
+    /// Numbers of usages of the deprecated metric names.
+    usages: Mutex<HashMap<KeyName, u64>>,
+
+    /// Counter reporting the usages counted according to the
+    /// [`Policy::Count`].
+    counter: prometheus::IntCounterVec,
+
+    /// Result of registering the [`Tracker::counter`] in a
+    /// [`prometheus::Registry`], if registered.
+    registered: Mutex<Option<Result<(), String>>>,
+}
+
+impl Tracker {
+    /// Creates a new [`Tracker`] of the provided [`Replacement`]s.
+    #[expect( // intentional
+        clippy::expect_used,
+        reason = "counter is created with the valid constant options"
+    )]
+    fn new(replacements: HashMap<KeyName, Replacement>) -> Self {
+        let counter = prometheus::IntCounterVec::new(
+            prometheus::Opts::new(
+                "metrics_prometheus_deprecated_usages_total",
+                "Number of usages of deprecated metric names.",
+            ),
+            &["name", "replacement"],
+        )
+        .expect("valid counter options");
+        Self {
+            replacements,
+            usages: Mutex::default(),
+            counter,
+            registered: Mutex::default(),
+        }
+    }
+
+    /// Returns a new [`Tracker`] containing the [`Replacement`]s of the
+    /// provided one (if any) along with the provided `old` name replaced with
+    /// the `new` one according to the provided [`Policy`].
+    ///
+    /// Usages tracked by the provided [`Tracker`] are not preserved, so this
+    /// is intended to be used while building only.
+    pub(crate) fn with(
+        tracker: Option<&Self>,
+        old: KeyName,
+        new: KeyName,
+        policy: Policy,
+    ) -> Self {
+        let mut replacements =
+            tracker.map(|t| t.replacements.clone()).unwrap_or_default();
+        drop(replacements.insert(old, Replacement { name: new, policy }));
+        Self::new(replacements)
+    }
+
+    /// Returns the replacement of the provided deprecated metric `name`, if
+    /// it's deprecated.
+    pub(crate) fn replacement(&self, name: &str) -> Option<&str> {
+        self.replacements.get(name).map(|r| r.name.as_str())
+    }
+
+    /// Routes the provided [`metrics::Key`] to its replacement, if its name is
+    /// deprecated, tracking this usage.
+    ///
+    /// The counter of this [`Tracker`] is registered in the provided
+    /// [`prometheus::Registry`] on the first usage counted according to the
+    /// [`Policy::Count`], unless registered already.
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register the counter.
+    /// The replacement [`metrics::Key`] is still returned in such case.
+    pub(crate) fn route(
+        &self,
+        key: &metrics::Key,
+        registry: &prometheus::Registry,
+    ) -> Option<(metrics::Key, prometheus::Result<()>)> {
+        let old = key.name();
+        let Replacement { name, policy } = self.replacements.get(old)?;
+        let routed = metrics::Key::from_parts(
+            name.clone(),
+            key.labels().cloned().collect::<Vec<_>>(),
+        );
+
+        let mut usages =
+            self.usages.lock().unwrap_or_else(PoisonError::into_inner);
+        let count = usages.entry(old.into()).or_default();
+        *count += 1;
+        let first = *count == 1;
+        drop(usages);
+
+        let res = match policy {
+            Policy::Silent => Ok(()),
+            Policy::Warn => {
+                if first {
+                    trace::deprecated(old, name);
+                }
+                Ok(())
+            }
+            Policy::Count => self.count(old, name, registry),
+        };
+        Some((routed, res))
+    }
+
+    /// Counts a usage of the provided deprecated `old` name replaced with the
+    /// `new` one, registering the counter of this [`Tracker`] in the provided
+    /// [`prometheus::Registry`], unless registered already.
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register the counter.
+    fn count(
+        &self,
+        old: &str,
+        new: &str,
+        registry: &prometheus::Registry,
+    ) -> prometheus::Result<()> {
+        self.counter.with_label_values(&[old, new]).inc();
+
+        let mut registered =
+            self.registered.lock().unwrap_or_else(PoisonError::into_inner);
+        let res = registered
+            .get_or_insert_with(|| {
+                registry
+                    .register(Box::new(self.counter.clone()))
+                    .map_err(|e| e.to_string())
+            })
+            .clone();
+        drop(registered);
+        res.map_err(|e| {
+            prometheus::Error::Msg(format!(
+                "failed to register deprecated metrics counter: {e}",
+            ))
+        })
+    }
+
+    /// Unregisters the counter of this [`Tracker`] from the provided
+    /// [`prometheus::Registry`], if registered.
+    pub(crate) fn unregister(&self, registry: &prometheus::Registry) {
+        let mut registered =
+            self.registered.lock().unwrap_or_else(PoisonError::into_inner);
+        if registered.take() == Some(Ok(())) {
+            drop(registry.unregister(Box::new(self.counter.clone())));
+        }
+        drop(registered);
+    }
+
+    /// Returns the numbers of usages of the deprecated metric names, by these
+    /// names.
+    pub(crate) fn usages(&self) -> BTreeMap<String, u64> {
+        let usages = self.usages.lock().unwrap_or_else(PoisonError::into_inner);
+        let counts =
+            usages.iter().map(|(name, count)| (name.clone(), *count)).collect();
+        drop(usages);
+        counts
+    }
+}
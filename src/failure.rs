@@ -1,8 +1,16 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! Definitions for dealing with a [`prometheus::Error`].
 
 #[doc(inline)]
 pub use self::strategy::Strategy;
 
+#[cfg(feature = "never-panic")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "never-panic")]
+use crate::trace;
+
 /// Possible actions on an encountered [`prometheus::Error`] inside
 /// [`metrics::Recorder`] methods.
 #[derive(Clone, Copy, Debug)]
@@ -15,16 +23,314 @@ pub enum Action {
     Panic,
 }
 
+/// Clones the provided [`prometheus::Error`], as it doesn't implement
+/// [`Clone`] itself.
+///
+/// Errors not being cloneable as-is are converted into a
+/// [`prometheus::Error::Msg`] preserving their message.
+// Not a `match`, as variants of `prometheus::Error` depend on the enabled
+// features of `prometheus` crate.
+pub(crate) fn clone_error(e: &prometheus::Error) -> prometheus::Error {
+    if let prometheus::Error::InconsistentCardinality { expect, got } = *e {
+        return prometheus::Error::InconsistentCardinality { expect, got };
+    }
+    if let prometheus::Error::Msg(msg) = e {
+        return prometheus::Error::Msg(msg.clone());
+    }
+    if matches!(e, prometheus::Error::AlreadyReg) {
+        return prometheus::Error::AlreadyReg;
+    }
+    prometheus::Error::Msg(e.to_string())
+}
+
+/// Extension of a [`Strategy`] used by [`metrics::Recorder`]s instead of
+/// calling it directly, so the `never-panic` feature is applied.
+///
+/// With the `never-panic` feature enabled, the [`Action::Panic`] (or a panic
+/// of the [`Strategy`] itself) is never returned, but rather logged as a last
+/// resort and replaced with the [`Action::NoOp`].
+pub(crate) trait Resolve {
+    /// Resolves the [`Action`] to be performed on the encountered
+    /// [`prometheus::Error`] via the [`Strategy::decide()`].
+    fn resolve(&self, res: &prometheus::Error) -> Action;
+
+    /// Resolves the [`Action`] to be performed on the [`prometheus::Error`]
+    /// encountered for the metric identified by the provided
+    /// [`metrics::Key`] via the [`Strategy::decide_for()`].
+    fn resolve_for(
+        &self,
+        key: &metrics::Key,
+        res: &prometheus::Error,
+    ) -> Action;
+}
+
+impl<S: Strategy + ?Sized> Resolve for S {
+    fn resolve(&self, res: &prometheus::Error) -> Action {
+        guarded(|| self.decide(res), None, res)
+    }
+
+    fn resolve_for(
+        &self,
+        key: &metrics::Key,
+        res: &prometheus::Error,
+    ) -> Action {
+        guarded(|| self.decide_for(key, res), Some(key), res)
+    }
+}
+
+/// Calls the provided `decide` function, never returning the
+/// [`Action::Panic`] if the `never-panic` feature is enabled.
+#[cfg(not(feature = "never-panic"))]
+fn guarded(
+    decide: impl FnOnce() -> Action,
+    _: Option<&metrics::Key>,
+    _: &prometheus::Error,
+) -> Action {
+    decide()
+}
+
+/// Calls the provided `decide` function, never returning the
+/// [`Action::Panic`] if the `never-panic` feature is enabled.
+///
+/// The suppressed [`Action::Panic`] is reported only once per the provided
+/// [`metrics::Key`] (see [`REPORTED`] for details), as the same failing
+/// [`metrics::Key`] is resolved on every its usage.
+#[cfg(feature = "never-panic")]
+fn guarded(
+    decide: impl FnOnce() -> Action,
+    key: Option<&metrics::Key>,
+    res: &prometheus::Error,
+) -> Action {
+    use std::panic::{self, AssertUnwindSafe};
+
+    // A panicking `Strategy` is treated as deciding to panic.
+    let action =
+        panic::catch_unwind(AssertUnwindSafe(decide)).unwrap_or(Action::Panic);
+    if matches!(action, Action::Panic) && !key.is_some_and(reported_already) {
+        trace::panic_suppressed(res);
+    }
+    Action::NoOp
+}
+
+/// Hashes of the [`metrics::Key`]s, whose suppressed panics have been reported
+/// already.
+///
+/// Direct-mapped by the top bits of a hash, so takes a fixed amount of memory
+/// and is lock-free. A [`metrics::Key`] evicted by a colliding one is reported
+/// once again.
+#[cfg(feature = "never-panic")]
+static REPORTED: [AtomicU64; 1 << REPORTED_BITS] =
+    [const { AtomicU64::new(0) }; 1 << REPORTED_BITS];
+
+/// Number of hash bits addressing the [`REPORTED`] slots.
+#[cfg(feature = "never-panic")]
+const REPORTED_BITS: u32 = 10;
+
+/// Checks whether a suppressed panic has been reported already for the
+/// provided [`metrics::Key`], marking it as reported otherwise.
+#[cfg(feature = "never-panic")]
+fn reported_already(key: &metrics::Key) -> bool {
+    // Zero denotes an empty slot.
+    let hash = key.get_hash().max(1);
+    usize::try_from(hash >> (u64::BITS - REPORTED_BITS))
+        .ok()
+        .and_then(|i| REPORTED.get(i))
+        .is_some_and(|slot| slot.swap(hash, Ordering::Relaxed) == hash)
+}
+
+/// Panics with the provided `message`, unless the `never-panic` feature is
+/// enabled, in which case the `message` is logged as a last resort instead.
+#[cfg_attr(not(feature = "never-panic"), track_caller)]
+pub(crate) fn panic_or_log(message: &str) {
+    #[cfg(not(feature = "never-panic"))]
+    panic!("{message}");
+    #[cfg(feature = "never-panic")]
+    trace::panic_suppressed(&message);
+}
+
 /// Strategies for dealing with a [`prometheus::Error`].
 pub mod strategy {
+    use std::{fmt, sync::Arc};
+
+    use crate::matcher;
+
     use super::Action;
 
     /// Strategy deciding which [`Action`] should be performed on an encountered
     /// [`prometheus::Error`] inside [`metrics::Recorder`] methods.
-    pub trait Strategy {
+    ///
+    /// # Contract
+    ///
+    /// A [`Strategy`] is stored inside a [`metrics::Recorder`], which is
+    /// usually installed globally and, so, shared between threads. That's why
+    /// it's required to be [`Send`] and [`Sync`], so any interior state should
+    /// be synchronized.
+    ///
+    /// The [`Strategy::decide()`] method is called on registration paths of
+    /// metrics, so should be cheap, and must not call into the
+    /// [`metrics::Recorder`] it's stored in.
+    ///
+    /// This trait is object safe, so a [`Strategy`] may be chosen at runtime
+    /// as a `Box<dyn Strategy>` (or an `Arc<dyn Strategy>`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::failure::{strategy, Strategy};
+    ///
+    /// let strategy: Box<dyn Strategy> = if cfg!(debug_assertions) {
+    ///     Box::new(strategy::Panic)
+    /// } else {
+    ///     Box::new(strategy::NoOp)
+    /// };
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(prometheus::Registry::new())
+    ///     .with_failure_strategy(strategy)
+    ///     .build();
+    /// # drop(recorder);
+    /// ```
+    pub trait Strategy: Send + Sync {
         /// Inspects the encountered [`prometheus::Error`] and returns the
         /// [`Action`] to be performed.
         fn decide(&self, res: &prometheus::Error) -> Action;
+
+        /// Inspects the [`prometheus::Error`] encountered for the metric
+        /// identified by the provided [`metrics::Key`], and returns the
+        /// [`Action`] to be performed.
+        ///
+        /// Called by [`metrics::Recorder`]s instead of the
+        /// [`Strategy::decide()`] whenever the failed metric is known. By
+        /// default, ignores the [`metrics::Key`] and calls the
+        /// [`Strategy::decide()`].
+        fn decide_for(
+            &self,
+            key: &metrics::Key,
+            res: &prometheus::Error,
+        ) -> Action {
+            _ = key;
+            self.decide(res)
+        }
+    }
+
+    #[warn(clippy::missing_trait_methods)]
+    impl<S: Strategy + ?Sized> Strategy for Box<S> {
+        fn decide(&self, res: &prometheus::Error) -> Action {
+            (**self).decide(res)
+        }
+
+        fn decide_for(
+            &self,
+            key: &metrics::Key,
+            res: &prometheus::Error,
+        ) -> Action {
+            (**self).decide_for(key, res)
+        }
+    }
+
+    #[warn(clippy::missing_trait_methods)]
+    impl<S: Strategy + ?Sized> Strategy for Arc<S> {
+        fn decide(&self, res: &prometheus::Error) -> Action {
+            (**self).decide(res)
+        }
+
+        fn decide_for(
+            &self,
+            key: &metrics::Key,
+            res: &prometheus::Error,
+        ) -> Action {
+            (**self).decide_for(key, res)
+        }
+    }
+
+    /// [`Strategy`] overriding the wrapped one for the metrics matched by
+    /// [`Matcher`]s, so specific critical metrics may panic on misuse, while
+    /// the rest of them are ignored silently, for example.
+    ///
+    /// The overrides are consulted in the [`Strategy::decide_for()`] only
+    /// (the first matching one wins), while the [`Strategy::decide()`] (not
+    /// aware of the failed metric) always uses the wrapped [`Strategy`].
+    ///
+    /// Usually, is created via the [`Builder::with_failure_strategy_for()`]
+    /// method.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::failure::{strategy, Action, Strategy as _};
+    ///
+    /// let strategy = strategy::PerMetric::new(strategy::NoOp)
+    ///     .with_override("payments_total", strategy::Panic);
+    ///
+    /// let err = prometheus::Error::AlreadyReg;
+    /// let key = metrics::Key::from_name("payments_total");
+    /// assert!(matches!(strategy.decide_for(&key, &err), Action::Panic));
+    /// let key = metrics::Key::from_name("requests_total");
+    /// assert!(matches!(strategy.decide_for(&key, &err), Action::NoOp));
+    /// ```
+    ///
+    /// [`Builder::with_failure_strategy_for()`]:
+    ///     crate::recorder::Builder::with_failure_strategy_for
+    /// [`Matcher`]: crate::Matcher
+    #[derive(Clone)]
+    pub struct PerMetric<S> {
+        /// [`Strategy`] used for the metrics not matched by any override.
+        default: S,
+
+        /// Overriding [`Strategy`]s of the matched metrics.
+        overrides: Arc<matcher::Rules<Arc<dyn Strategy>>>,
+    }
+
+    impl<S> PerMetric<S> {
+        /// Wraps the provided `default` [`Strategy`] to be overridden for the
+        /// specific metrics.
+        #[must_use]
+        pub fn new(default: S) -> Self {
+            Self { default, overrides: Arc::default() }
+        }
+
+        /// Overrides the wrapped [`Strategy`] with the provided one for the
+        /// metrics matched by the provided [`Matcher`].
+        ///
+        /// [`Matcher`]: crate::Matcher
+        #[must_use]
+        pub fn with_override<F>(
+            mut self,
+            matcher: impl Into<crate::Matcher>,
+            strategy: F,
+        ) -> Self
+        where
+            F: Strategy + 'static,
+        {
+            Arc::make_mut(&mut self.overrides)
+                .push(matcher, Arc::new(strategy));
+            self
+        }
+    }
+
+    impl<S: fmt::Debug> fmt::Debug for PerMetric<S> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("PerMetric")
+                .field("default", &self.default)
+                .finish_non_exhaustive()
+        }
+    }
+
+    #[warn(clippy::missing_trait_methods)]
+    impl<S: Strategy> Strategy for PerMetric<S> {
+        fn decide(&self, res: &prometheus::Error) -> Action {
+            self.default.decide(res)
+        }
+
+        fn decide_for(
+            &self,
+            key: &metrics::Key,
+            res: &prometheus::Error,
+        ) -> Action {
+            self.overrides.find(key.name()).map_or_else(
+                || self.default.decide_for(key, res),
+                |strategy| strategy.decide_for(key, res),
+            )
+        }
     }
 
     /// [`Strategy`] returning always [`Action::NoOp`].
@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`actix_web`] middleware recording metrics of served HTTP requests.
+
+use std::time::Instant;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    Error,
+};
+
+use super::UNMATCHED_PATH;
+
+/// Returns the route template matched by the provided [`ServiceRequest`], or
+/// the [`UNMATCHED_PATH`] if it isn't matched by any route.
+#[must_use]
+pub fn path(req: &ServiceRequest) -> String {
+    req.match_pattern().unwrap_or_else(|| UNMATCHED_PATH.into())
+}
+
+/// [`actix_web`] middleware recording metrics of the served
+/// [`ServiceRequest`]s (see the [`http`] module for details).
+///
+/// Intended to be used with the [`actix_web::middleware::from_fn()`].
+///
+/// # Errors
+///
+/// If the wrapped [`actix_web`] service fails to serve the [`ServiceRequest`].
+/// The metrics are recorded regardless, with the `status` of the
+/// [`actix_web::ResponseError`].
+///
+/// # Example
+///
+/// ```rust
+/// use actix_web::{middleware::from_fn, test, web, App};
+/// use metrics_prometheus::http;
+///
+/// let recorder = metrics_prometheus::install();
+///
+/// actix_web::rt::System::new().block_on(async {
+///     let app = test::init_service(
+///         App::new()
+///             .wrap(from_fn(http::actix::track))
+///             .route("/user/{id}", web::get().to(|| async { "user" })),
+///     )
+///     .await;
+///     for uri in ["/user/1", "/user/2", "/unknown"] {
+///         let req = test::TestRequest::get().uri(uri).to_request();
+///         let _ = test::call_service(&app, req).await;
+///     }
+/// });
+///
+/// let families = recorder
+///     .registry()
+///     .gather()
+///     .into_iter()
+///     .filter(|f| f.get_name() == http::REQUESTS_TOTAL)
+///     .collect::<Vec<_>>();
+/// let report = prometheus::TextEncoder::new().encode_to_string(&families)?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP http_requests_total http_requests_total
+/// ## TYPE http_requests_total counter
+/// http_requests_total{method="GET",path="/user/{id}",status="200"} 2
+/// http_requests_total{method="GET",path="<unmatched>",status="404"} 1
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`http`]: super
+pub async fn track(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let method = req.method().clone();
+    let path = path(&req);
+
+    let res = next.call(req).await;
+
+    let status = match &res {
+        Ok(resp) => resp.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    super::record(method.as_str(), path, status.as_u16(), started);
+    res
+}
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`axum`] middleware recording metrics of served HTTP requests.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use super::UNMATCHED_PATH;
+
+/// Returns the route template matched by the provided [`Request`], or the
+/// [`UNMATCHED_PATH`] if it hasn't been matched by any route.
+#[must_use]
+pub fn path(req: &Request) -> String {
+    req.extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| UNMATCHED_PATH.into(), |p| p.as_str().into())
+}
+
+/// [`axum`] middleware recording metrics of the served [`Request`]s (see the
+/// [`http`] module for details).
+///
+/// Intended to be used with the [`axum::middleware::from_fn()`] and applied
+/// via the [`axum::Router::layer()`], so the matched route template is known
+/// already.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{body::Body, http::Request, routing::get, Router};
+/// use metrics_prometheus::http;
+/// use tower::ServiceExt as _;
+///
+/// let recorder = metrics_prometheus::install();
+///
+/// let app = Router::new()
+///     .route("/user/{id}", get(|| async { "user" }))
+///     .layer(axum::middleware::from_fn(http::axum::track));
+///
+/// tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+///     for (method, uri) in [
+///         ("GET", "/user/1"),
+///         ("GET", "/user/2"),
+///         ("GET", "/unknown"),
+///         ("PURGE", "/unknown"),
+///     ] {
+///         let req = Request::builder()
+///             .method(method)
+///             .uri(uri)
+///             .body(Body::empty())
+///             .unwrap();
+///         let _ = app.clone().oneshot(req).await.unwrap();
+///     }
+/// });
+///
+/// let families = recorder
+///     .registry()
+///     .gather()
+///     .into_iter()
+///     .filter(|f| f.get_name() == http::REQUESTS_TOTAL)
+///     .collect::<Vec<_>>();
+/// let report = prometheus::TextEncoder::new().encode_to_string(&families)?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP http_requests_total http_requests_total
+/// ## TYPE http_requests_total counter
+/// http_requests_total{method="GET",path="/user/{id}",status="200"} 2
+/// http_requests_total{method="GET",path="<unmatched>",status="404"} 1
+/// http_requests_total{method="other",path="<unmatched>",status="404"} 1
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`http`]: super
+pub async fn track(req: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = req.method().clone();
+    let path = path(&req);
+
+    let resp = next.run(req).await;
+
+    super::record(method.as_str(), path, resp.status().as_u16(), started);
+    resp
+}
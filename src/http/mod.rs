@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Middlewares recording metrics of HTTP requests served by web frameworks.
+//!
+//! Every served HTTP request increments the [`REQUESTS_TOTAL`] counter and
+//! records its duration into the [`REQUEST_DURATION_SECONDS`] histogram, both
+//! labeled with `method`, `path` and `status`.
+//!
+//! The `path` label holds the matched route template (like `/user/{id}`)
+//! rather than the raw URI, so its cardinality stays bounded. HTTP requests
+//! not matched by any route are labeled with the [`UNMATCHED_PATH`].
+//!
+//! Similarly, the `method` label holds only the standard HTTP methods (like
+//! `GET` or `POST`), while any other (extension) method is labeled with the
+//! [`OTHER_METHOD`].
+
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
+
+use std::time::Instant;
+
+/// Name of the counter of served HTTP requests.
+pub const REQUESTS_TOTAL: &str = "http_requests_total";
+
+/// Name of the histogram of served HTTP requests durations (in seconds).
+pub const REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+
+/// Value of the `path` label for HTTP requests not matched by any route.
+pub const UNMATCHED_PATH: &str = "<unmatched>";
+
+/// Value of the `method` label for HTTP requests with a non-standard method.
+pub const OTHER_METHOD: &str = "other";
+
+/// Standard HTTP methods used as values of the `method` label as is.
+const STANDARD_METHODS: [&str; 9] = [
+    "CONNECT", "DELETE", "GET", "HEAD", "OPTIONS", "PATCH", "POST", "PUT",
+    "TRACE",
+];
+
+/// Records metrics of an HTTP request served with the provided `method`,
+/// route `path` template and response `status`, having been `started` at the
+/// provided [`Instant`].
+fn record(method: &str, path: String, status: u16, started: Instant) {
+    let labels = vec![
+        metrics::Label::new("method", method_label(method)),
+        metrics::Label::new("path", path),
+        metrics::Label::new("status", status.to_string()),
+    ];
+    metrics::counter!(REQUESTS_TOTAL, labels.clone()).increment(1);
+    metrics::histogram!(REQUEST_DURATION_SECONDS, labels)
+        .record(started.elapsed());
+}
+
+/// Returns the value of the `method` label for the provided HTTP `method`,
+/// keeping its cardinality bounded.
+fn method_label(method: &str) -> &'static str {
+    STANDARD_METHODS.into_iter().find(|m| *m == method).unwrap_or(OTHER_METHOD)
+}
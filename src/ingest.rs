@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Ingestion of metrics events over a simple line protocol.
+//!
+//! Every line describes a single event in the following form:
+//! ```text
+//! <kind> <name> <value> [<label>=<value> ...]
+//! ```
+//! where:
+//! - `<kind>` is one of `counter`, `gauge` or `histogram`;
+//! - `<value>` is an unsigned integer incrementing a `counter`, or a floating
+//!   point number otherwise;
+//! - a `gauge` `<value>` prefixed with `+=` or `-=` increments or decrements
+//!   it, and sets it otherwise (so `-5` sets it to a negative value).
+//!
+//! Empty lines, lines starting with `#` and lines longer than
+//! [`MAX_LINE_LEN`] bytes are ignored.
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Read as _},
+    net::TcpListener,
+    str::{self, FromStr},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use crate::trace;
+
+/// Maximum length (in bytes) of a single line, including its terminating
+/// newline.
+///
+/// Longer lines are skipped without being buffered.
+pub const MAX_LINE_LEN: usize = 4096;
+
+/// Timeout of waiting for the next line on a connection accepted by
+/// [`listen`], after which the connection is closed.
+pub const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum number of connections [`listen`] reads concurrently.
+///
+/// Connections accepted above this limit are closed right away.
+pub const MAX_CONNECTIONS: usize = 256;
+
+/// Pause after failing to accept a connection in [`listen`], so the persistent
+/// failures (like running out of file descriptors) don't spin the CPU.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// [`metrics::Metadata`] of all the ingested metrics.
+static METADATA: metrics::Metadata<'static> = metrics::Metadata::new(
+    module_path!(),
+    metrics::Level::INFO,
+    Some(module_path!()),
+);
+
+/// Value of a [`Event::Gauge`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GaugeValue {
+    /// Sets the gauge to the value.
+    Absolute(f64),
+
+    /// Increments the gauge by the value.
+    Increment(f64),
+
+    /// Decrements the gauge by the value.
+    Decrement(f64),
+}
+
+/// Single ingested metrics event.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// Incrementing a counter.
+    Counter {
+        /// [`metrics::Key`] of the counter.
+        key: metrics::Key,
+
+        /// Value to increment the counter by.
+        value: u64,
+    },
+
+    /// Changing a gauge.
+    Gauge {
+        /// [`metrics::Key`] of the gauge.
+        key: metrics::Key,
+
+        /// [`GaugeValue`] to change the gauge with.
+        value: GaugeValue,
+    },
+
+    /// Recording a histogram observation.
+    Histogram {
+        /// [`metrics::Key`] of the histogram.
+        key: metrics::Key,
+
+        /// Observed value.
+        value: f64,
+    },
+}
+
+impl Event {
+    /// Records this [`Event`] into the provided [`metrics::Recorder`].
+    pub fn record<R: metrics::Recorder + ?Sized>(&self, recorder: &R) {
+        match self {
+            Self::Counter { key, value } => {
+                recorder.register_counter(key, &METADATA).increment(*value);
+            }
+            Self::Gauge { key, value } => {
+                let gauge = recorder.register_gauge(key, &METADATA);
+                match *value {
+                    GaugeValue::Absolute(v) => gauge.set(v),
+                    GaugeValue::Increment(v) => gauge.increment(v),
+                    GaugeValue::Decrement(v) => gauge.decrement(v),
+                }
+            }
+            Self::Histogram { key, value } => {
+                recorder.register_histogram(key, &METADATA).record(*value);
+            }
+        }
+    }
+}
+
+impl FromStr for Event {
+    type Err = prometheus::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let malformed = |reason: &dyn fmt::Display| {
+            prometheus::Error::Msg(format!(
+                "malformed metrics event `{line}`: {reason}",
+            ))
+        };
+
+        let mut parts = line.split_whitespace();
+        let (Some(kind), Some(name), Some(value)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(malformed(&"expected `<kind> <name> <value>`"));
+        };
+        let labels = parts
+            .map(|l| {
+                l.split_once('=')
+                    .map(|(k, v)| {
+                        metrics::Label::new(k.to_owned(), v.to_owned())
+                    })
+                    .ok_or_else(|| malformed(&"expected `<label>=<value>`"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = metrics::Key::from_parts(name.to_owned(), labels);
+
+        match kind {
+            "counter" => Ok(Self::Counter {
+                key,
+                value: value.parse().map_err(|e| malformed(&e))?,
+            }),
+            "gauge" => {
+                let parse = |v: &str| v.parse().map_err(|e| malformed(&e));
+                let value = if let Some(v) = value.strip_prefix("+=") {
+                    GaugeValue::Increment(parse(v)?)
+                } else if let Some(v) = value.strip_prefix("-=") {
+                    GaugeValue::Decrement(parse(v)?)
+                } else {
+                    GaugeValue::Absolute(parse(value)?)
+                };
+                Ok(Self::Gauge { key, value })
+            }
+            "histogram" => Ok(Self::Histogram {
+                key,
+                value: value.parse().map_err(|e| malformed(&e))?,
+            }),
+            _ => Err(malformed(
+                &"expected `counter`, `gauge` or `histogram` kind",
+            )),
+        }
+    }
+}
+
+/// Reads [`Event`]s line by line from the provided `reader` till its end, and
+/// records them into the provided [`metrics::Recorder`].
+///
+/// Malformed lines and lines longer than [`MAX_LINE_LEN`] bytes are skipped.
+///
+/// Returns the number of the recorded [`Event`]s.
+///
+/// # Errors
+///
+/// If reading from the provided `reader` fails.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::ingest;
+///
+/// let recorder = metrics_prometheus::Recorder::builder()
+///     .with_registry(prometheus::Registry::new())
+///     .build();
+///
+/// let events = "\
+///     counter requests 2 method=GET\n\
+///     gauge queue 5\n\
+///     gauge queue -=2\n\
+///     gauge temperature -5.5\n\
+///     # comment\n\
+///     unknown line\n";
+/// assert_eq!(ingest::read(events.as_bytes(), &recorder)?, 4);
+///
+/// let report = prometheus::TextEncoder::new()
+///     .encode_to_string(&recorder.registry().gather())?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP queue queue
+/// ## TYPE queue gauge
+/// queue 3
+/// ## HELP requests requests
+/// ## TYPE requests counter
+/// requests{method="GET"} 2
+/// ## HELP temperature temperature
+/// ## TYPE temperature gauge
+/// temperature -5.5
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn read<R>(mut reader: impl BufRead, recorder: &R) -> io::Result<usize>
+where
+    R: metrics::Recorder + ?Sized,
+{
+    let limit = u64::try_from(MAX_LINE_LEN).unwrap_or(u64::MAX);
+    let mut count = 0;
+    let mut buf = Vec::with_capacity(MAX_LINE_LEN);
+    loop {
+        buf.clear();
+        if reader.by_ref().take(limit).read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        if buf.len() == MAX_LINE_LEN && !buf.ends_with(b"\n") {
+            // Skip the rest of the too long line without buffering it.
+            while buf.len() == MAX_LINE_LEN && !buf.ends_with(b"\n") {
+                buf.clear();
+                _ = reader.by_ref().take(limit).read_until(b'\n', &mut buf)?;
+            }
+            continue;
+        }
+        let Ok(line) = str::from_utf8(&buf) else {
+            continue;
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Ok(event) = line.parse::<Event>() {
+            event.record(recorder);
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Accepts TCP connections on the provided [`TcpListener`], and [`read`]s
+/// [`Event`]s from each of them in a separate thread, recording them into the
+/// provided [`metrics::Recorder`].
+///
+/// Never returns: failures to accept or read a connection are logged without
+/// stopping. Connections idle for longer than [`READ_TIMEOUT`] are closed, and
+/// no more than [`MAX_CONNECTIONS`] of them are read concurrently.
+pub fn listen<R>(listener: &TcpListener, recorder: &Arc<R>)
+where
+    R: metrics::Recorder + Send + Sync + 'static,
+{
+    // Every reading thread holds a clone, so the strong count tracks them.
+    let active = Arc::new(());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                trace::ingest_failed(&format!("failed to accept: {e}"));
+                thread::sleep(ACCEPT_BACKOFF);
+                continue;
+            }
+        };
+        if Arc::strong_count(&active) > MAX_CONNECTIONS {
+            trace::ingest_failed(&format!(
+                "{MAX_CONNECTIONS} connections are read already, closing the \
+                 new one",
+            ));
+            continue;
+        }
+        if let Err(e) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+            trace::ingest_failed(&format!("failed to set read timeout: {e}"));
+            continue;
+        }
+        let recorder = Arc::clone(recorder);
+        let active = Arc::clone(&active);
+        drop(thread::spawn(move || {
+            if let Err(e) = read(BufReader::new(stream), &*recorder) {
+                trace::ingest_failed(&format!("failed to read: {e}"));
+            }
+            drop(active);
+        }));
+    }
+}
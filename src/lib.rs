@@ -1,3 +1,5 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 // These links overwrite the ones in `README.md`
 // to become proper intra-doc links in Rust docs.
 //! [`.freeze()`]: FreezableRecorder::freeze()
@@ -81,6 +83,7 @@
     clippy::format_push_string,
     clippy::get_unwrap,
     clippy::if_then_some_else_none,
+    clippy::implicit_clone,
     clippy::imprecise_flops,
     clippy::infinite_loop,
     clippy::iter_on_empty_collections,
@@ -134,7 +137,6 @@
     clippy::string_lit_as_bytes,
     clippy::string_lit_chars_any,
     clippy::string_slice,
-    clippy::string_to_string,
     clippy::suboptimal_flops,
     clippy::suspicious_operation_groupings,
     clippy::suspicious_xor_used_as_pow,
@@ -190,23 +192,74 @@
     variant_size_differences
 )]
 
+pub mod buckets;
+pub mod budget;
+pub mod catalog;
+pub mod clock;
+pub mod config;
+pub mod deprecation;
 pub mod failure;
+#[cfg(any(feature = "actix", feature = "axum"))]
+pub mod http;
+#[cfg(feature = "ingest")]
+pub mod ingest;
+#[doc(hidden)]
+pub mod macros;
+pub mod matcher;
 pub mod metric;
+pub mod openmetrics;
+#[cfg(feature = "opentelemetry")]
+pub mod otel;
 pub mod recorder;
+#[cfg(feature = "relabel")]
+pub mod relabel;
 pub mod storage;
+mod sync;
+pub mod target;
+pub mod tasks;
+#[cfg(feature = "proptest")]
+pub mod testing;
+mod trace;
+
+use std::sync::{Mutex, PoisonError};
 
 // For surviving MSRV check only.
 // TODO: Fix in `prometheus` crate.
 use thiserror as _;
+// Used in doctests and integration tests only.
+#[cfg(all(test, metrics_prometheus_integration))]
+use testcontainers as _;
+#[cfg(test)]
+use {static_assertions as _, tokio as _, tower as _};
 
 #[doc(inline)]
 pub use self::{
+    catalog::{diff, Diff as CatalogDiff},
+    matcher::Matcher,
     metric::Metric,
     recorder::{
-        Freezable as FreezableRecorder, Frozen as FrozenRecorder, Recorder,
+        any::Recorder as AnyRecorder, duration::RecordDuration,
+        freezable::Report as FreezeReport, Freezable as FreezableRecorder,
+        Frozen as FrozenRecorder, Pausable as PausableRecorder,
+        RateLimited as RateLimitedRecorder, Recorder,
     },
 };
 
+/// [SPDX] license expression of this crate.
+///
+/// ```rust
+/// assert_eq!(metrics_prometheus::LICENSE, "MIT OR Apache-2.0");
+/// ```
+///
+/// [SPDX]: https://spdx.org/licenses
+pub const LICENSE: &str = env!("CARGO_PKG_LICENSE");
+
+/// Full text of the MIT license of this crate.
+pub const LICENSE_MIT: &str = include_str!("../LICENSE-MIT");
+
+/// Full text of the Apache-2.0 license of this crate.
+pub const LICENSE_APACHE: &str = include_str!("../LICENSE-APACHE");
+
 /// Tries to install a default [`Recorder`] (backed by the
 /// [`prometheus::default_registry()`]) with the
 /// [`metrics::set_global_recorder()`].
@@ -215,8 +268,85 @@ pub use self::{
 ///
 /// If the [`Recorder`] fails to be installed with the
 /// [`metrics::set_global_recorder()`].
+#[expect( // intentional
+    clippy::significant_drop_tightening,
+    reason = "lock on `INSTALLED` is intentionally held till the end of the \
+              scope, to perform the installation atomically"
+)]
 pub fn try_install() -> Result<Recorder, metrics::SetRecorderError<Recorder>> {
-    Recorder::builder().try_build_and_install()
+    let mut installed =
+        INSTALLED.lock().unwrap_or_else(PoisonError::into_inner);
+    let rec = Recorder::builder().try_build_and_install()?;
+    *installed = Some(rec.clone());
+    Ok(rec)
+}
+
+/// Default [`Recorder`] installed with the [`metrics::set_global_recorder()`]
+/// by this crate, if any.
+static INSTALLED: Mutex<Option<Recorder>> = Mutex::new(None);
+
+/// Tries to install a default [`Recorder`] (backed by the
+/// [`prometheus::default_registry()`]) with the
+/// [`metrics::set_global_recorder()`], or returns the already installed one.
+///
+/// Only a default [`Recorder`] installed via this function, [`try_install()`]
+/// or [`install()`] is reused, as [`metrics`] crate provides no way to
+/// downcast the installed global [`metrics::Recorder`]. Any other one
+/// (including a [`Recorder`] installed via [`Builder::build_and_install()`],
+/// [`install_freezable()`] or directly via the
+/// [`metrics::set_global_recorder()`]) is not detected, and so, is reported
+/// as an error. This allows several independent components to initialize
+/// metrics idempotently, as long as all of them use this function.
+///
+/// # Errors
+///
+/// If the [`Recorder`] fails to be installed with the
+/// [`metrics::set_global_recorder()`], and the global [`metrics::Recorder`]
+/// hasn't been installed via this function, [`try_install()`] or
+/// [`install()`].
+///
+/// [`Builder::build_and_install()`]: recorder::Builder::build_and_install
+///
+/// # Example
+///
+/// ```rust
+/// let first = metrics_prometheus::install();
+///
+/// // Doesn't panic, unlike calling `install()` twice.
+/// let res = metrics_prometheus::try_install_or_reuse();
+/// assert!(res.is_ok(), "cannot reuse `Recorder`: {}", res.unwrap_err());
+///
+/// res.unwrap().register_metric(prometheus::Gauge::new("value", "help")?);
+/// metrics::gauge!("value").increment(1.0);
+///
+/// let report = prometheus::TextEncoder::new()
+///     .encode_to_string(&first.registry().gather())?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP value help
+/// ## TYPE value gauge
+/// value 1
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+#[expect( // intentional
+    clippy::significant_drop_tightening,
+    reason = "lock on `INSTALLED` is intentionally held till the end of the \
+              scope, to perform the installation atomically"
+)]
+pub fn try_install_or_reuse(
+) -> Result<Recorder, metrics::SetRecorderError<Recorder>> {
+    let mut installed =
+        INSTALLED.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(rec) = &*installed {
+        return Ok(rec.clone());
+    }
+    let rec = Recorder::builder().try_build_and_install()?;
+    *installed = Some(rec.clone());
+    Ok(rec)
 }
 
 /// Tries to install a default [`FreezableRecorder`] (backed by the
@@ -246,7 +376,12 @@ pub fn try_install_freezable(
               using the returned `Recorder` directly"
 )]
 pub fn install() -> Recorder {
-    Recorder::builder().build_and_install()
+    try_install().unwrap_or_else(|e| {
+        panic!(
+            "failed to install `metrics_prometheus::Recorder` with \
+             `metrics::set_global_recorder()`: {e}",
+        )
+    })
 }
 
 /// Installs a default [`FreezableRecorder`] (backed by the
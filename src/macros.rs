@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Macros for terse metrics pre-registration.
+
+// Re-exported for using in macros only.
+#[doc(hidden)]
+pub use prometheus;
+
+/// Pre-registers the declared metrics in the provided [`Builder`], expanding
+/// into the corresponding [`Builder::try_with_metric()`] calls.
+///
+/// Each declaration has the following form (terminated by `;`):
+/// ```text
+/// <kind> "<name>" ["<label>", ...] help "<help>" buckets [<bucket>, ...]
+/// ```
+/// where:
+/// - `<kind>` is one of `counter`, `gauge` or `histogram`;
+/// - `[...]` label names are optional, and make a [`prometheus::MetricVec`]
+///   being registered instead of a single metric;
+/// - `help "..."` is optional, and defaults to the metric name (the same way
+///   as for metrics registered on the fly);
+/// - `buckets [...]` is optional, and is allowed for `histogram`s only.
+///
+/// Evaluates into a [`prometheus::Result`] of the provided [`Builder`].
+///
+/// # Errors
+///
+/// If any of the declared metrics cannot be created or registered.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::Recorder;
+///
+/// let registry = metrics_prometheus::preregister! {
+///     Recorder::builder();
+///     counter "requests_total"["method", "status"] help "Total requests.";
+///     histogram "latency_seconds" buckets [0.01, 0.1, 1.0];
+///     gauge "queue_depth";
+/// }?
+/// .build_frozen_and_install();
+///
+/// metrics::counter!(
+///     "requests_total", "method" => "GET", "status" => "200",
+/// ).increment(1);
+/// metrics::histogram!("latency_seconds").record(0.05);
+/// metrics::gauge!("queue_depth").set(3.0);
+///
+/// let report =
+///     prometheus::TextEncoder::new().encode_to_string(&registry.gather())?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP latency_seconds latency_seconds
+/// ## TYPE latency_seconds histogram
+/// latency_seconds_bucket{le="0.01"} 0
+/// latency_seconds_bucket{le="0.1"} 1
+/// latency_seconds_bucket{le="1"} 1
+/// latency_seconds_bucket{le="+Inf"} 1
+/// latency_seconds_sum 0.05
+/// latency_seconds_count 1
+/// ## HELP queue_depth queue_depth
+/// ## TYPE queue_depth gauge
+/// queue_depth 3
+/// ## HELP requests_total Total requests.
+/// ## TYPE requests_total counter
+/// requests_total{method="GET",status="200"} 1
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`Builder`]: crate::recorder::Builder
+/// [`Builder::try_with_metric()`]: crate::recorder::Builder::try_with_metric
+/// [`prometheus::MetricVec`]: prometheus::core::MetricVec
+#[macro_export]
+macro_rules! preregister {
+    (
+        $builder:expr;
+        $(
+            $kind:ident $name:literal
+            $( [ $( $label:literal ),* $(,)? ] )?
+            $( help $help:literal )?
+            $( buckets [ $( $bucket:literal ),* $(,)? ] )?
+        );* $(;)?
+    ) => {
+        'preregister: {
+            let builder = $builder;
+            $(
+                let metric = $crate::preregister!(
+                    @metric $kind
+                    ($name, $crate::preregister!(@help $name $( $help )?))
+                    { $( [ $( $label ),* ] )? }
+                    { $( [ $( $bucket ),* ] )? }
+                );
+                let builder = match metric
+                    .and_then(|m| builder.try_with_metric(m))
+                {
+                    ::core::result::Result::Ok(b) => b,
+                    ::core::result::Result::Err(e) => {
+                        break 'preregister ::core::result::Result::Err(e);
+                    }
+                };
+            )*
+            ::core::result::Result::<
+                _,
+                $crate::macros::prometheus::Error,
+            >::Ok(builder)
+        }
+    };
+
+    (@help $name:literal) => { $name };
+    (@help $name:literal $help:literal) => { $help };
+
+    (@metric counter ($name:expr, $help:expr) {} {}) => {
+        $crate::macros::prometheus::IntCounter::new($name, $help)
+    };
+    (@metric counter ($name:expr, $help:expr)
+     { [ $( $label:literal ),* ] } {}) => {
+        $crate::macros::prometheus::IntCounterVec::new(
+            $crate::macros::prometheus::Opts::new($name, $help),
+            &[ $( $label ),* ],
+        )
+    };
+    (@metric gauge ($name:expr, $help:expr) {} {}) => {
+        $crate::macros::prometheus::Gauge::new($name, $help)
+    };
+    (@metric gauge ($name:expr, $help:expr)
+     { [ $( $label:literal ),* ] } {}) => {
+        $crate::macros::prometheus::GaugeVec::new(
+            $crate::macros::prometheus::Opts::new($name, $help),
+            &[ $( $label ),* ],
+        )
+    };
+    (@metric histogram ($name:expr, $help:expr) {}
+     { $( [ $( $bucket:literal ),* ] )? }) => {
+        $crate::macros::prometheus::Histogram::with_opts(
+            $crate::macros::prometheus::HistogramOpts::new($name, $help)
+                $( .buckets(::std::vec![ $( $bucket ),* ]) )?,
+        )
+    };
+    (@metric histogram ($name:expr, $help:expr)
+     { [ $( $label:literal ),* ] }
+     { $( [ $( $bucket:literal ),* ] )? }) => {
+        $crate::macros::prometheus::HistogramVec::new(
+            $crate::macros::prometheus::HistogramOpts::new($name, $help)
+                $( .buckets(::std::vec![ $( $bucket ),* ]) )?,
+            &[ $( $label ),* ],
+        )
+    };
+}
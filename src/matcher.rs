@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Matching of metric names for applying per-metric configuration.
+
+/// Matcher of a metric name, used for applying a per-metric configuration to
+/// the metrics registered on the fly.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Matcher {
+    /// Matches the whole metric name exactly.
+    Full(String),
+
+    /// Matches the beginning of a metric name.
+    Prefix(String),
+
+    /// Matches the end of a metric name.
+    Suffix(String),
+}
+
+impl Matcher {
+    /// Checks whether the provided metric `name` is matched by this
+    /// [`Matcher`].
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Full(full) => name == full,
+            Self::Prefix(prefix) => name.starts_with(prefix),
+            Self::Suffix(suffix) => name.ends_with(suffix),
+        }
+    }
+}
+
+impl From<&str> for Matcher {
+    /// Creates a [`Matcher::Full`] out of the provided metric `name`.
+    fn from(name: &str) -> Self {
+        Self::Full(name.into())
+    }
+}
+
+impl From<String> for Matcher {
+    /// Creates a [`Matcher::Full`] out of the provided metric `name`.
+    fn from(name: String) -> Self {
+        Self::Full(name)
+    }
+}
+
+/// Ordered list of [`Matcher`]s associated with some per-metric configuration,
+/// where the first matching [`Matcher`] wins.
+#[derive(Clone, Debug)]
+pub struct Rules<T>(Vec<(Matcher, T)>);
+
+// Manual implementation is required to omit the redundant `T: Default` trait
+// bound imposed by `#[derive(Default)]`.
+impl<T> Default for Rules<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T> Rules<T> {
+    /// Appends a new rule, applying the provided `value` to the metrics
+    /// matched by the provided [`Matcher`].
+    pub fn push(&mut self, matcher: impl Into<Matcher>, value: T) {
+        self.0.push((matcher.into(), value));
+    }
+
+    /// Returns the value of the first rule matching the provided metric `name`,
+    /// if any.
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&T> {
+        self.0.iter().find_map(|(m, v)| m.matches(name).then_some(v))
+    }
+
+    /// Indicates whether this list contains no rules.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -0,0 +1,412 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`prometheus::Histogram`]s family automatically tuning its buckets.
+
+use std::{
+    collections::HashMap,
+    fmt, mem,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+/// Configuration of an [`adaptive::Histogram`].
+///
+/// [`adaptive::Histogram`]: Histogram
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Number of observations to collect with the provisional buckets before
+    /// tuning them.
+    warm_up: usize,
+
+    /// Number of log-spaced buckets to tune the histogram into.
+    buckets: u32,
+
+    /// Wide provisional buckets used during the warm-up.
+    provisional: Vec<f64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            warm_up: 1000,
+            buckets: 12,
+            provisional: vec![
+                0.000_001,
+                0.000_01,
+                0.000_1,
+                0.001,
+                0.01,
+                0.1,
+                1.0,
+                10.0,
+                100.0,
+                1_000.0,
+                10_000.0,
+                100_000.0,
+                1_000_000.0,
+            ],
+        }
+    }
+}
+
+impl Config {
+    /// Creates a new default [`Config`]: 1000 warm-up observations, 12 tuned
+    /// buckets and provisional buckets being powers of 10 from `1e-6` to `1e6`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of observations to collect before tuning the buckets.
+    #[must_use]
+    pub const fn with_warm_up(mut self, observations: usize) -> Self {
+        self.warm_up = observations;
+        self
+    }
+
+    /// Sets the number of log-spaced buckets to tune the histogram into.
+    #[must_use]
+    pub const fn with_buckets(mut self, count: u32) -> Self {
+        self.buckets = count;
+        self
+    }
+
+    /// Sets the provisional buckets used during the warm-up.
+    #[must_use]
+    pub fn with_provisional_buckets(mut self, buckets: Vec<f64>) -> Self {
+        self.provisional = buckets;
+        self
+    }
+
+    /// Calculates log-spaced buckets covering the range of the provided
+    /// observed `values`.
+    ///
+    /// Falls back to the provisional buckets if there is no positive finite
+    /// value to calculate the range from.
+    fn tune(&self, values: impl Iterator<Item = f64>) -> Vec<f64> {
+        let range = values.filter(|v| v.is_finite() && *v > 0.0).fold(
+            None,
+            |range: Option<(f64, f64)>, v| {
+                Some(
+                    range.map_or((v, v), |(min, max)| (min.min(v), max.max(v))),
+                )
+            },
+        );
+        let Some((min, max)) = range else {
+            return self.provisional.clone();
+        };
+        if self.buckets < 2 || min >= max {
+            return vec![round(max)];
+        }
+
+        let ratio = max / min;
+        let last = f64::from(self.buckets - 1);
+        let mut buckets = (0..self.buckets)
+            .map(|i| round(min * ratio.powf(f64::from(i) / last)))
+            .collect::<Vec<_>>();
+        buckets.dedup_by(|next, prev| next <= prev);
+        buckets
+    }
+}
+
+/// Rounds the provided `value` to 3 significant digits, so the tuned buckets
+/// remain readable in the exposition.
+fn round(value: f64) -> f64 {
+    format!("{value:.2e}").parse().unwrap_or(value)
+}
+
+/// State of an [`adaptive::Histogram`].
+///
+/// [`adaptive::Histogram`]: Histogram
+#[derive(Debug)]
+enum State {
+    /// Histogram is warming up with the provisional buckets, remembering all
+    /// the observations to replay them once tuned.
+    WarmingUp {
+        /// [`prometheus::HistogramVec`] with the provisional buckets.
+        histograms: prometheus::HistogramVec,
+
+        /// Observations recorded so far, along with their label values.
+        observations: Vec<(Arc<[String]>, f64)>,
+    },
+
+    /// Histogram has been tuned already.
+    Tuned(prometheus::HistogramVec),
+}
+
+impl State {
+    /// Returns the current [`prometheus::HistogramVec`] of this [`State`].
+    const fn histograms(&self) -> &prometheus::HistogramVec {
+        match self {
+            Self::WarmingUp { histograms, .. } | Self::Tuned(histograms) => {
+                histograms
+            }
+        }
+    }
+}
+
+/// Shared inner state of an [`adaptive::Histogram`].
+///
+/// [`adaptive::Histogram`]: Histogram
+#[derive(Debug)]
+struct Family {
+    /// [`prometheus::core::Desc`] of this [`Family`], staying the same after
+    /// the buckets are tuned.
+    desc: prometheus::core::Desc,
+
+    /// [`prometheus::HistogramOpts`] (without buckets) to create the tuned
+    /// [`prometheus::HistogramVec`] with.
+    opts: prometheus::HistogramOpts,
+
+    /// Names of the labels of this [`Family`].
+    label_names: Vec<String>,
+
+    /// [`Config`] of this [`Family`].
+    config: Config,
+
+    /// Current [`State`] of this [`Family`].
+    state: RwLock<State>,
+
+    /// [`Child`]ren of this [`Family`], identified by their label values.
+    children: RwLock<HashMap<Arc<[String]>, Arc<Child>>>,
+}
+
+/// Family of [`prometheus::Histogram`]s automatically tuning its buckets.
+///
+/// Starts with wide provisional buckets, and re-registers itself with
+/// log-spaced buckets covering the observed range once the configured number
+/// of warm-up observations is collected. All the observations recorded during
+/// the warm-up are replayed into the tuned histograms.
+#[derive(Clone, Debug)]
+pub struct Histogram(Arc<Family>);
+
+impl Histogram {
+    /// Creates a new [`adaptive::Histogram`] for the provided
+    /// [`metrics::Key`].
+    ///
+    /// # Errors
+    ///
+    /// If the provisional [`prometheus::HistogramVec`] cannot be created.
+    ///
+    /// [`adaptive::Histogram`]: Histogram
+    pub fn new(key: &metrics::Key, config: Config) -> prometheus::Result<Self> {
+        let mut label_names =
+            key.labels().map(|l| l.key().to_owned()).collect::<Vec<_>>();
+        // Sorting guarantees the identical schema regardless of the labels
+        // order at the call site registering it first.
+        label_names.sort_unstable();
+        let names = label_names.iter().map(String::as_str).collect::<Vec<_>>();
+        // We use `key.name()` as `help` description here, because `prometheus`
+        // crate doesn't allow to make it empty.
+        let opts = prometheus::HistogramOpts::new(key.name(), key.name());
+        let histograms = prometheus::HistogramVec::new(
+            opts.clone().buckets(config.provisional.clone()),
+            &names,
+        )?;
+        let desc = prometheus::core::Collector::desc(&histograms)
+            .first()
+            .copied()
+            .cloned()
+            .ok_or_else(|| {
+                prometheus::Error::Msg("no `Desc` for histogram".into())
+            })?;
+        Ok(Self(Arc::new(Family {
+            desc,
+            opts,
+            label_names,
+            config,
+            state: RwLock::new(State::WarmingUp {
+                histograms,
+                observations: Vec::new(),
+            }),
+            children: RwLock::default(),
+        })))
+    }
+
+    /// Indicates whether this [`adaptive::Histogram`] has tuned its buckets
+    /// already.
+    ///
+    /// [`adaptive::Histogram`]: Histogram
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn is_tuned(&self) -> bool {
+        matches!(*self.0.state.read().unwrap(), State::Tuned(_))
+    }
+
+    /// Returns a [`Child`] of this [`adaptive::Histogram`] identified by the
+    /// labels of the provided [`metrics::Key`].
+    ///
+    /// # Errors
+    ///
+    /// If the labels of the provided [`metrics::Key`] don't comply with the
+    /// labels of this [`adaptive::Histogram`].
+    ///
+    /// [`adaptive::Histogram`]: Histogram
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn child(&self, key: &metrics::Key) -> prometheus::Result<Arc<Child>> {
+        if key.labels().count() != self.0.label_names.len() {
+            return Err(prometheus::Error::InconsistentCardinality {
+                expect: self.0.label_names.len(),
+                got: key.labels().count(),
+            });
+        }
+        let values = self
+            .0
+            .label_names
+            .iter()
+            .map(|name| {
+                key.labels()
+                    .find(|l| l.key() == name)
+                    .map(|l| l.value().to_owned())
+                    .ok_or_else(|| {
+                        prometheus::Error::Msg(format!(
+                            "label name `{name}` missing in label map",
+                        ))
+                    })
+            })
+            .collect::<prometheus::Result<Arc<[String]>>>()?;
+
+        if let Some(child) = self.0.children.read().unwrap().get(&values) {
+            return Ok(Arc::clone(child));
+        }
+        Ok(Arc::clone(
+            self.0
+                .children
+                .write()
+                .unwrap()
+                .entry(Arc::clone(&values))
+                .or_insert_with(|| {
+                    Arc::new(Child {
+                        family: Arc::clone(&self.0),
+                        values,
+                        tuned: OnceLock::new(),
+                    })
+                }),
+        ))
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Histogram {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.0.desc]
+    }
+
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.0.state.read().unwrap().histograms().collect()
+    }
+}
+
+/// Single [`prometheus::Histogram`] of an [`adaptive::Histogram`], identified
+/// by its label values.
+///
+/// [`adaptive::Histogram`]: Histogram
+pub struct Child {
+    /// [`Family`] this [`Child`] belongs to.
+    family: Arc<Family>,
+
+    /// Label values identifying this [`Child`] in its [`Family`].
+    values: Arc<[String]>,
+
+    /// Tuned [`prometheus::Histogram`], cached for fast access once the
+    /// [`Family`] has been tuned.
+    tuned: OnceLock<prometheus::Histogram>,
+}
+
+// Manual implementation is required to omit printing the whole `Family`.
+impl fmt::Debug for Child {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Child")
+            .field("name", &self.family.opts.common_opts.name)
+            .field("values", &self.values)
+            .field("tuned", &self.tuned)
+            .finish()
+    }
+}
+
+impl Child {
+    /// Returns label values of this [`Child`] as string slices.
+    fn values(&self) -> Vec<&str> {
+        self.values.iter().map(String::as_str).collect()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::HistogramFn for Child {
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn record(&self, value: f64) {
+        if let Some(h) = self.tuned.get() {
+            h.observe(value);
+            return;
+        }
+
+        let mut state = self.family.state.write().unwrap();
+        match &mut *state {
+            State::Tuned(histograms) => {
+                self.tuned
+                    .get_or_init(|| {
+                        histograms.with_label_values(&self.values())
+                    })
+                    .observe(value);
+            }
+            State::WarmingUp { histograms, observations } => {
+                histograms.with_label_values(&self.values()).observe(value);
+                observations.push((Arc::clone(&self.values), value));
+                if observations.len() >= self.family.config.warm_up {
+                    let observations = mem::take(observations);
+                    let buckets = self
+                        .family
+                        .config
+                        .tune(observations.iter().map(|(_, v)| *v));
+                    let names = self
+                        .family
+                        .label_names
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>();
+                    // In the unlikely case of failing to create the tuned
+                    // histograms, we just keep using the provisional ones.
+                    let tuned = prometheus::HistogramVec::new(
+                        self.family.opts.clone().buckets(buckets),
+                        &names,
+                    )
+                    .map_or_else(
+                        |_| histograms.clone(),
+                        |tuned| {
+                            for (values, v) in observations {
+                                let values = values
+                                    .iter()
+                                    .map(String::as_str)
+                                    .collect::<Vec<_>>();
+                                tuned.with_label_values(&values).observe(v);
+                            }
+                            tuned
+                        },
+                    );
+                    *state = State::Tuned(tuned);
+                }
+            }
+        }
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        for _ in 0..count {
+            self.record(value);
+        }
+    }
+}
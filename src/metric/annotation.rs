@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-child annotations of metrics, used for introspection only (not exposed
+//! in Prometheus exposition, which uses the family-level `help` description).
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+/// Labels identifying a single metric in its family, sorted by their names.
+pub type Labels = Vec<(String, String)>;
+
+/// Thread-safe collection of annotations of the metrics in a single family,
+/// identified by their [`Labels`] (regardless of their order).
+#[derive(Clone, Debug, Default)]
+pub struct Annotations(Arc<RwLock<BTreeMap<Labels, String>>>);
+
+impl Annotations {
+    /// Sets the provided `annotation` for the metric identified by the labels
+    /// of the provided [`metrics::Key`], replacing the previous one (if any).
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn set(&self, key: &metrics::Key, annotation: impl Into<String>) {
+        drop(self.0.write().unwrap().insert(labels(key), annotation.into()));
+    }
+
+    /// Returns the annotation of the metric identified by the labels of the
+    /// provided [`metrics::Key`], if any.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn get(&self, key: &metrics::Key) -> Option<String> {
+        self.0.read().unwrap().get(&labels(key)).cloned()
+    }
+
+    /// Returns all the annotations of the metrics in the family, keyed by their
+    /// [`Labels`].
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn all(&self) -> BTreeMap<Labels, String> {
+        self.0.read().unwrap().clone()
+    }
+}
+
+/// Collects the [`Labels`] of the provided [`metrics::Key`].
+pub(crate) fn labels(key: &metrics::Key) -> Labels {
+    let mut labels = key
+        .labels()
+        .map(|l| (l.key().to_owned(), l.value().to_owned()))
+        .collect::<Labels>();
+    labels.sort_unstable();
+    labels
+}
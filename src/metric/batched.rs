@@ -0,0 +1,399 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Histograms accumulating their observations in per-thread
+//! [`prometheus::local::LocalHistogram`]s.
+//!
+//! The buffered observations are flushed into the underlying
+//! [`prometheus::Histogram`] on overflow, on thread exit and at gather.
+//!
+//! Drastically reduces the number of atomic operations on the hot path of
+//! extremely hot histograms (a flush increments every bucket once, by the
+//! number of the buffered observations falling into it), at the cost of
+//! bounded staleness: a buffer holds at most its capacity of observations,
+//! and only until the next gather.
+//!
+//! Every thread records into its own buffer, found via a thread-local map
+//! without any synchronization. The buffers are registered in their
+//! [`batched::Histogram`], so a gather drains the buffers of all the threads
+//! (including the idle ones), and that's the only case a buffer lock is
+//! contended.
+//!
+//! [`batched::Histogram`]: Histogram
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    hash::{BuildHasherDefault, Hasher},
+    sync::{Arc, Mutex, PoisonError, RwLock, Weak},
+};
+
+use prometheus::local::LocalHistogram;
+
+use super::Metric;
+
+/// Buffer of a single [`batched::Histogram`] of a single thread.
+///
+/// [`batched::Histogram`]: Histogram
+struct Buffer {
+    /// [`LocalHistogram`] accumulating the observations.
+    local: LocalHistogram,
+
+    /// Number of the observations buffered in the [`Buffer::local`]
+    /// histogram.
+    pending: usize,
+}
+
+impl Buffer {
+    /// Flushes this [`Buffer`] contents into the underlying
+    /// [`prometheus::Histogram`], emptying it.
+    ///
+    /// Returns whether there was anything to flush.
+    fn drain(&mut self) -> bool {
+        if self.pending == 0 {
+            return false;
+        }
+        self.local.flush();
+        self.pending = 0;
+        true
+    }
+}
+
+/// [`Buffer`] of a single thread, registered in the [`Shared`] state of its
+/// [`batched::Histogram`], so can be drained by a gather.
+///
+/// [`batched::Histogram`]: Histogram
+type Slot = Mutex<Buffer>;
+
+/// Thread-local reference to a [`Slot`], flushing it on thread exit.
+struct Local {
+    /// [`Shared`] state of the [`batched::Histogram`] this [`Local`] belongs
+    /// to.
+    ///
+    /// Keeps the address identifying this [`Local`] from being reused while
+    /// it exists.
+    ///
+    /// [`batched::Histogram`]: Histogram
+    owner: Weak<Shared>,
+
+    /// [`Slot`] of the current thread.
+    slot: Arc<Slot>,
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        // Hands the buffered observations over on thread exit.
+        if let Some(owner) = self.owner.upgrade() {
+            owner.drain(&self.slot);
+        }
+    }
+}
+
+/// [`Hasher`] of the addresses identifying thread-local [`Slot`]s, being much
+/// cheaper than the default one on the hot path.
+#[derive(Clone, Copy, Debug, Default)]
+struct AddressHasher(u64);
+
+impl Hasher for AddressHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0.rotate_left(8) ^ u64::from(*b))
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        // Fibonacci hashing spreads the aligned addresses over all the bits.
+        self.0 = u64::try_from(i)
+            .unwrap_or(u64::MAX)
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    }
+}
+
+/// Thread-local [`Slot`]s, identified by the address of the [`Shared`] state
+/// of their [`batched::Histogram`].
+///
+/// [`batched::Histogram`]: Histogram
+type Locals = HashMap<usize, Local, BuildHasherDefault<AddressHasher>>;
+
+thread_local! {
+    /// [`Slot`]s of the current thread, identified by the address of the
+    /// [`Shared`] state of their [`batched::Histogram`].
+    ///
+    /// [`batched::Histogram`]: Histogram
+    static LOCALS: RefCell<Locals> = RefCell::default();
+}
+
+/// Family of [`batched::Histogram`]s sharing the same name, identified by
+/// their [`metrics::Key`]s.
+///
+/// [`batched::Histogram`]: Histogram
+#[derive(Clone, Debug)]
+pub struct Family(Arc<Inner>);
+
+/// Shared state of a [`batched::Family`].
+///
+/// [`batched::Family`]: Family
+#[derive(Debug)]
+struct Inner {
+    /// Maximum number of observations a per-thread buffer holds before being
+    /// flushed.
+    capacity: usize,
+
+    /// [`batched::Histogram`]s of this family, identified by their
+    /// [`metrics::Key`]s.
+    ///
+    /// [`batched::Histogram`]: Histogram
+    children: RwLock<HashMap<metrics::Key, Histogram>>,
+}
+
+impl Family {
+    /// Creates a new empty [`batched::Family`], with its per-thread buffers
+    /// holding the provided `capacity` of observations (at least `1`).
+    ///
+    /// [`batched::Family`]: Family
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Inner {
+            capacity: capacity.max(1),
+            children: RwLock::default(),
+        }))
+    }
+
+    /// Returns the [`batched::Histogram`] identified by the provided
+    /// [`metrics::Key`], creating it over the provided
+    /// [`prometheus::Histogram`] [`Metric`] if it doesn't exist yet.
+    ///
+    /// [`batched::Histogram`]: Histogram
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn child(
+        &self,
+        key: &metrics::Key,
+        histogram: Arc<Metric<prometheus::Histogram>>,
+    ) -> Histogram {
+        if let Some(child) = self.0.children.read().unwrap().get(key) {
+            return child.clone();
+        }
+        self.0
+            .children
+            .write()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| Histogram::new(histogram, self.0.capacity))
+            .clone()
+    }
+
+    /// Flushes and forgets the [`batched::Histogram`] identified by the
+    /// provided [`metrics::Key`], if any.
+    ///
+    /// [`batched::Histogram`]: Histogram
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn forget(&self, key: &metrics::Key) {
+        let forgotten = self.0.children.write().unwrap().remove(key);
+        if let Some(histogram) = forgotten {
+            histogram.flush();
+        }
+    }
+
+    /// Flushes all the [`batched::Histogram`]s of this [`batched::Family`]
+    /// (see the [`batched::Histogram::flush()`] method for details).
+    ///
+    /// [`batched::Family`]: Family
+    /// [`batched::Histogram`]: Histogram
+    /// [`batched::Histogram::flush()`]: Histogram::flush
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[expect( // intentional
+        clippy::iter_over_hash_type,
+        reason = "order of flushing doesn't matter"
+    )]
+    pub fn flush(&self) {
+        for histogram in self.0.children.read().unwrap().values() {
+            histogram.flush();
+        }
+    }
+}
+
+/// [`metrics::Histogram`] accumulating its observations in per-thread
+/// [`LocalHistogram`]s.
+///
+/// The buffers are flushed into the underlying [`prometheus::Histogram`] once
+/// overflowed, on thread exit, or when the [`batched::Histogram::flush()`]
+/// method is called (at gather).
+///
+/// [`batched::Histogram`]: Histogram
+/// [`batched::Histogram::flush()`]: Histogram::flush
+#[derive(Clone)]
+pub struct Histogram(Arc<Shared>);
+
+/// Shared state of a [`batched::Histogram`].
+///
+/// [`batched::Histogram`]: Histogram
+struct Shared {
+    /// Underlying [`prometheus::Histogram`] [`Metric`] the observations are
+    /// flushed into.
+    metric: Arc<Metric<prometheus::Histogram>>,
+
+    /// Maximum number of observations a [`Slot`] holds before being flushed.
+    capacity: usize,
+
+    /// [`Slot`]s of all the threads recorded into this [`batched::Histogram`],
+    /// drained by the [`batched::Histogram::flush()`].
+    ///
+    /// The [`Slot`]s of the exited threads are pruned on the next
+    /// registration.
+    ///
+    /// [`batched::Histogram`]: Histogram
+    /// [`batched::Histogram::flush()`]: Histogram::flush
+    slots: Mutex<Vec<Weak<Slot>>>,
+}
+
+impl Shared {
+    /// Drains the provided [`Slot`] into the underlying
+    /// [`prometheus::Histogram`].
+    #[cfg_attr(
+        not(feature = "timestamps"),
+        expect( // intentional
+            clippy::unused_self,
+            reason = "`self` is used for touching the metric only"
+        )
+    )]
+    fn drain(&self, slot: &Slot) {
+        if slot.lock().unwrap_or_else(PoisonError::into_inner).drain() {
+            #[cfg(feature = "timestamps")]
+            self.metric.touch();
+        }
+    }
+}
+
+impl Histogram {
+    /// Creates a new [`batched::Histogram`] flushing its observations into the
+    /// provided [`prometheus::Histogram`] [`Metric`], with its per-thread
+    /// buffers holding the provided `capacity` of observations (at least
+    /// `1`).
+    ///
+    /// [`batched::Histogram`]: Histogram
+    #[must_use]
+    pub fn new(
+        metric: Arc<Metric<prometheus::Histogram>>,
+        capacity: usize,
+    ) -> Self {
+        Self(Arc::new(Shared {
+            metric,
+            capacity: capacity.max(1),
+            slots: Mutex::default(),
+        }))
+    }
+
+    /// Flushes the buffers of all the threads into the underlying
+    /// [`prometheus::Histogram`].
+    pub fn flush(&self) {
+        let slots = self
+            .0
+            .slots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        for slot in slots {
+            self.0.drain(&slot);
+        }
+    }
+
+    /// Records the provided `value` observed `count` times into the buffer of
+    /// the current thread, flushing it if overflowed.
+    fn observe(&self, value: f64, count: usize) {
+        let id = self.id();
+        let buffered = LOCALS.try_with(|locals| {
+            let mut locals = locals.borrow_mut();
+            if !locals.contains_key(&id) {
+                locals.retain(|_, l| l.owner.strong_count() > 0);
+            }
+            let local = locals.entry(id).or_insert_with(|| self.register());
+            let mut buffer =
+                local.slot.lock().unwrap_or_else(PoisonError::into_inner);
+            for _ in 0..count {
+                buffer.local.observe(value);
+            }
+            buffer.pending = buffer.pending.saturating_add(count);
+            if buffer.pending >= self.0.capacity {
+                drop(buffer);
+                self.0.drain(&local.slot);
+            }
+        });
+        if buffered.is_err() {
+            // Thread-local storage is being destroyed already.
+            metrics::HistogramFn::record_many(&*self.0.metric, value, count);
+        }
+    }
+
+    /// Registers a new [`Slot`] of the current thread in this
+    /// [`batched::Histogram`].
+    ///
+    /// [`batched::Histogram`]: Histogram
+    fn register(&self) -> Local {
+        let slot = Arc::new(Mutex::new(Buffer {
+            local: self.0.metric.metric.local(),
+            pending: 0,
+        }));
+        let mut slots =
+            self.0.slots.lock().unwrap_or_else(PoisonError::into_inner);
+        slots.retain(|s| s.strong_count() > 0);
+        slots.push(Arc::downgrade(&slot));
+        drop(slots);
+        Local { owner: Arc::downgrade(&self.0), slot }
+    }
+
+    /// Returns the address of the [`Shared`] state of this
+    /// [`batched::Histogram`], identifying its thread-local [`Slot`]s.
+    ///
+    /// [`batched::Histogram`]: Histogram
+    fn id(&self) -> usize {
+        #[expect( // intentional
+            clippy::as_conversions,
+            reason = "pointer address is used as an identifier only"
+        )]
+        let id = Arc::as_ptr(&self.0) as usize;
+        id
+    }
+}
+
+// Manual implementation is required to omit printing the whole buffers.
+impl fmt::Debug for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Histogram")
+            .field("metric", &self.0.metric)
+            .field("capacity", &self.0.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::HistogramFn for Histogram {
+    fn record(&self, value: f64) {
+        self.observe(value, 1);
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        if count > 0 {
+            self.observe(value, count);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-child callbacks refreshing values of gauges right before they're
+//! gathered.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use super::annotation::{self, Labels};
+
+/// Callback returning the current value of a gauge.
+pub type Callback = Arc<dyn Fn() -> f64 + Send + Sync>;
+
+/// Thread-safe collection of [`Callback`]s of the gauges in a single family,
+/// identified by their [`Labels`] (regardless of their order).
+#[derive(Clone, Default)]
+pub struct Callbacks(
+    Arc<RwLock<BTreeMap<Labels, (prometheus::Gauge, Callback)>>>,
+);
+
+impl Callbacks {
+    /// Sets the provided [`Callback`] refreshing the provided `gauge`
+    /// identified by the labels of the provided [`metrics::Key`], replacing
+    /// the previous one (if any).
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn set(
+        &self,
+        key: &metrics::Key,
+        gauge: prometheus::Gauge,
+        callback: Callback,
+    ) {
+        let labels = annotation::labels(key);
+        drop(self.0.write().unwrap().insert(labels, (gauge, callback)));
+    }
+
+    /// Removes the [`Callback`] of the gauge identified by the labels of the
+    /// provided [`metrics::Key`], if any.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn remove(&self, key: &metrics::Key) {
+        drop(self.0.write().unwrap().remove(&annotation::labels(key)));
+    }
+
+    /// Refreshes the values of all the gauges by invoking their [`Callback`]s.
+    ///
+    /// [`Callback`]s are invoked without holding any locks, so may use these
+    /// [`Callbacks`] themselves.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn refresh(&self) {
+        let callbacks =
+            self.0.read().unwrap().values().cloned().collect::<Vec<_>>();
+        for (gauge, callback) in callbacks {
+            gauge.set(callback());
+        }
+    }
+}
+
+impl fmt::Debug for Callbacks {
+    #[expect( // intentional
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let callbacks = self.0.read().unwrap();
+        f.debug_tuple("Callbacks")
+            .field(&callbacks.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
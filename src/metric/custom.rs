@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! User-defined [`prometheus`] metrics families pluggable into a [`Storage`],
+//! and so, usable via [`metrics`] crate interfaces.
+//!
+//! [`Storage`]: crate::storage::Mutable
+
+use std::{fmt, sync::Arc};
+
+/// User-defined [`prometheus`] metrics family, resolving [`metrics`] handles
+/// for the [`metrics::Key`]s of its metrics.
+///
+/// Unlike the [`Bundled`] trait, this one is not sealed, so allows to plug any
+/// custom [`prometheus::core::Collector`] (like a quantile sketch exposed as a
+/// gauge family) into a [`Recorder`] via the
+/// [`Builder::with_custom_bundle()`].
+///
+/// All the methods return [`None`] by default, meaning that this
+/// [`CustomBundle`] doesn't provide metrics of the kind, so the usual ones are
+/// registered instead.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::{
+///     atomic::{AtomicU64, Ordering},
+///     Arc,
+/// };
+///
+/// use metrics_prometheus::metric::CustomBundle;
+/// use prometheus::{
+///     core::{Collector, Desc},
+///     proto,
+/// };
+///
+/// /// Gauge family exposing the maximum value ever set.
+/// #[derive(Debug)]
+/// struct MaxGauge {
+///     desc: Desc,
+///     max: Arc<AtomicU64>,
+/// }
+///
+/// impl Collector for MaxGauge {
+///     fn desc(&self) -> Vec<&Desc> {
+///         vec![&self.desc]
+///     }
+///
+///     fn collect(&self) -> Vec<proto::MetricFamily> {
+///         let mut gauge = proto::Gauge::default();
+///         gauge.set_value(self.max.load(Ordering::Relaxed) as f64);
+///         let mut metric = proto::Metric::default();
+///         metric.set_gauge(gauge);
+///         let mut family = proto::MetricFamily::default();
+///         family.set_name(self.desc.fq_name.clone());
+///         family.set_help(self.desc.help.clone());
+///         family.set_field_type(proto::MetricType::GAUGE);
+///         family.mut_metric().push(metric);
+///         vec![family]
+///     }
+/// }
+///
+/// impl CustomBundle for MaxGauge {
+///     fn gauge(
+///         &self,
+///         _: &metrics::Key,
+///     ) -> Option<prometheus::Result<metrics::Gauge>> {
+///         Some(Ok(metrics::Gauge::from_arc(Arc::new(Max(Arc::clone(
+///             &self.max,
+///         ))))))
+///     }
+/// }
+///
+/// struct Max(Arc<AtomicU64>);
+///
+/// impl metrics::GaugeFn for Max {
+///     fn increment(&self, _: f64) {}
+///
+///     fn decrement(&self, _: f64) {}
+///
+///     fn set(&self, value: f64) {
+///         _ = self.0.fetch_max(value as u64, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let desc =
+///     Desc::new("max".into(), "help".into(), vec![], Default::default())?;
+/// let recorder = metrics_prometheus::Recorder::builder()
+///     .with_registry(prometheus::Registry::new())
+///     .with_custom_bundle(MaxGauge { desc, max: Arc::default() })
+///     .build();
+///
+/// metrics::with_local_recorder(&recorder, || {
+///     metrics::gauge!("max").set(5.0);
+///     metrics::gauge!("max").set(3.0);
+/// });
+///
+/// let encoder = prometheus::TextEncoder::new();
+/// let report = encoder.encode_to_string(&recorder.registry().gather())?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP max help
+/// ## TYPE max gauge
+/// max 5
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`Builder::with_custom_bundle()`]:
+///     crate::recorder::Builder::with_custom_bundle
+/// [`Bundled`]: super::Bundled
+/// [`Recorder`]: crate::Recorder
+pub trait CustomBundle: prometheus::core::Collector {
+    /// Resolves a [`metrics::Counter`] identified by the provided
+    /// [`metrics::Key`] out of this [`CustomBundle`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`metrics::Key`] cannot be resolved into a [`metrics::Counter`]
+    /// (like having inconsistent labels), so the configured
+    /// [`failure::Strategy`] decides what to do with it.
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    fn counter(
+        &self,
+        _: &metrics::Key,
+    ) -> Option<prometheus::Result<metrics::Counter>> {
+        None
+    }
+
+    /// Resolves a [`metrics::Gauge`] identified by the provided
+    /// [`metrics::Key`] out of this [`CustomBundle`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`metrics::Key`] cannot be resolved into a [`metrics::Gauge`]
+    /// (like having inconsistent labels), so the configured
+    /// [`failure::Strategy`] decides what to do with it.
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    fn gauge(
+        &self,
+        _: &metrics::Key,
+    ) -> Option<prometheus::Result<metrics::Gauge>> {
+        None
+    }
+
+    /// Resolves a [`metrics::Histogram`] identified by the provided
+    /// [`metrics::Key`] out of this [`CustomBundle`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`metrics::Key`] cannot be resolved into a [`metrics::Histogram`]
+    /// (like having inconsistent labels), so the configured
+    /// [`failure::Strategy`] decides what to do with it.
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    fn histogram(
+        &self,
+        _: &metrics::Key,
+    ) -> Option<prometheus::Result<metrics::Histogram>> {
+        None
+    }
+}
+
+/// Shared type-erased [`CustomBundle`], registered in a [`Storage`].
+///
+/// [`Storage`]: crate::storage::Mutable
+#[derive(Clone)]
+pub struct Shared(Arc<dyn CustomBundle>);
+
+impl Shared {
+    /// Wraps the provided [`CustomBundle`] to be shared.
+    #[must_use]
+    pub fn new<B: CustomBundle + 'static>(bundle: B) -> Self {
+        Self(Arc::new(bundle))
+    }
+}
+
+impl fmt::Debug for Shared {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.0.desc().into_iter().map(|d| &d.fq_name);
+        f.debug_tuple("Shared").field(&names.collect::<Vec<_>>()).finish()
+    }
+}
+
+impl AsRef<dyn CustomBundle> for Shared {
+    fn as_ref(&self) -> &(dyn CustomBundle + 'static) {
+        &*self.0
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Shared {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        self.0.desc()
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.0.collect()
+    }
+}
@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Companion `*_min` and `*_max` gauges of histograms, tracking the extreme
+//! observed values (which [`prometheus::Histogram`]s cannot answer).
+//!
+//! Useful in environments where Prometheus recording rules are not available.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::clock::{self, Clock as _};
+
+use super::annotation::{self, Labels};
+
+/// Moment of resetting the tracked extreme values of an
+/// [`extrema::Family`].
+///
+/// [`extrema::Family`]: Family
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Reset {
+    /// Reset on each scrape, so the gauges expose the extreme values observed
+    /// since the previous scrape.
+    #[default]
+    OnScrape,
+
+    /// Reset on the first scrape after the provided window elapses, so the
+    /// gauges expose the extreme values observed in the current window.
+    Every(Duration),
+}
+
+/// Pair of gauge families exposing the minimum and maximum values observed by
+/// a histogram family.
+///
+/// Children without observations since the last [`Reset`] are not exposed.
+#[derive(Clone)]
+pub struct Family(Arc<Inner>);
+
+/// Shared state of an [`extrema::Family`].
+///
+/// [`extrema::Family`]: Family
+struct Inner {
+    /// [`prometheus::core::Desc`] of the exposed `*_min` gauge family.
+    min: prometheus::core::Desc,
+
+    /// [`prometheus::core::Desc`] of the exposed `*_max` gauge family.
+    max: prometheus::core::Desc,
+
+    /// [`Reset`] of the tracked values.
+    reset: Reset,
+
+    /// [`Clock`] the [`Reset::Every`] windows are timed with.
+    ///
+    /// [`Clock`]: clock::Clock
+    clock: clock::Shared,
+
+    /// Moment of the last [`Reset`].
+    last_reset: Mutex<Instant>,
+
+    /// [`Child`]ren of this family, identified by their [`Labels`].
+    children: RwLock<BTreeMap<Labels, Arc<Child>>>,
+}
+
+impl Family {
+    /// Creates a new [`extrema::Family`] of the histogram family with the
+    /// provided `name`, resetting its values according to the provided
+    /// [`Reset`] timed with the provided [`Clock`].
+    ///
+    /// # Errors
+    ///
+    /// If the provided `name` is invalid.
+    ///
+    /// [`Clock`]: clock::Clock
+    /// [`extrema::Family`]: Family
+    pub fn new(
+        name: &str,
+        reset: Reset,
+        clock: clock::Shared,
+    ) -> prometheus::Result<Self> {
+        let desc = |suffix: &str, extreme: &str| {
+            prometheus::core::Desc::new(
+                format!("{name}_{suffix}"),
+                format!("{extreme} of `{name}` observations since last reset."),
+                vec![],
+                HashMap::new(),
+            )
+        };
+        Ok(Self(Arc::new(Inner {
+            min: desc("min", "Minimum")?,
+            max: desc("max", "Maximum")?,
+            reset,
+            last_reset: Mutex::new(clock.now()),
+            clock,
+            children: RwLock::default(),
+        })))
+    }
+
+    /// Returns the [`Reset`] of this [`extrema::Family`].
+    ///
+    /// [`extrema::Family`]: Family
+    #[must_use]
+    pub fn reset(&self) -> Reset {
+        self.0.reset
+    }
+
+    /// Returns the [`Child`] identified by the labels of the provided
+    /// [`metrics::Key`], creating it if it doesn't exist yet.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn child(&self, key: &metrics::Key) -> Arc<Child> {
+        let labels = annotation::labels(key);
+        if let Some(child) = self.0.children.read().unwrap().get(&labels) {
+            return Arc::clone(child);
+        }
+        Arc::clone(self.0.children.write().unwrap().entry(labels).or_default())
+    }
+
+    /// Decides whether the tracked values should be reset on the current
+    /// scrape.
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`Mutex` usage is fully panic-safe here"
+    )]
+    fn resets_now(&self) -> bool {
+        match self.0.reset {
+            Reset::OnScrape => true,
+            Reset::Every(window) => {
+                let now = self.0.clock.now();
+                let mut last = self.0.last_reset.lock().unwrap();
+                let elapsed = now.saturating_duration_since(*last) >= window;
+                if elapsed {
+                    *last = now;
+                }
+                elapsed
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Family")
+            .field("min", &self.0.min)
+            .field("max", &self.0.max)
+            .field("reset", &self.0.reset)
+            .finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Family {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.0.min, &self.0.max]
+    }
+
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        use prometheus::proto;
+
+        let family = |desc: &prometheus::core::Desc| {
+            let mut family = proto::MetricFamily::default();
+            family.set_name(desc.fq_name.clone());
+            family.set_help(desc.help.clone());
+            family.set_field_type(proto::MetricType::GAUGE);
+            family
+        };
+        let gauge = |labels: &Labels, extreme: f64| {
+            let mut metric = proto::Metric::default();
+            metric.set_label(
+                labels
+                    .iter()
+                    .map(|(name, value)| {
+                        let mut label = proto::LabelPair::default();
+                        label.set_name(name.clone());
+                        label.set_value(value.clone());
+                        label
+                    })
+                    .collect(),
+            );
+            let mut gauge = proto::Gauge::default();
+            gauge.set_value(extreme);
+            metric.set_gauge(gauge);
+            metric
+        };
+
+        let reset = self.resets_now();
+        let (mut min, mut max) = (family(&self.0.min), family(&self.0.max));
+        for (labels, child) in &*self.0.children.read().unwrap() {
+            if let Some((lo, hi)) = child.take(reset) {
+                min.mut_metric().push(gauge(labels, lo));
+                max.mut_metric().push(gauge(labels, hi));
+            }
+        }
+        if min.get_metric().is_empty() {
+            return vec![];
+        }
+        vec![min, max]
+    }
+}
+
+/// Extreme values observed by a single histogram of an [`extrema::Family`].
+///
+/// [`extrema::Family`]: Family
+#[derive(Debug)]
+pub struct Child {
+    /// Bits of the minimum observed [`f64`] value.
+    min: AtomicU64,
+
+    /// Bits of the maximum observed [`f64`] value.
+    max: AtomicU64,
+}
+
+impl Default for Child {
+    fn default() -> Self {
+        Self {
+            min: AtomicU64::new(f64::INFINITY.to_bits()),
+            max: AtomicU64::new(f64::NEG_INFINITY.to_bits()),
+        }
+    }
+}
+
+impl Child {
+    /// Tracks the provided observed `value`. [`f64::NAN`] is ignored.
+    pub fn observe(&self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        _ = self.min.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |b| {
+            (value < f64::from_bits(b)).then_some(value.to_bits())
+        });
+        _ = self.max.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |b| {
+            (value > f64::from_bits(b)).then_some(value.to_bits())
+        });
+    }
+
+    /// Returns the minimum and maximum observed values, if any, resetting
+    /// them if `reset` is `true`.
+    fn take(&self, reset: bool) -> Option<(f64, f64)> {
+        let (min, max) = if reset {
+            (
+                self.min.swap(f64::INFINITY.to_bits(), Ordering::Relaxed),
+                self.max.swap(f64::NEG_INFINITY.to_bits(), Ordering::Relaxed),
+            )
+        } else {
+            (self.min.load(Ordering::Relaxed), self.max.load(Ordering::Relaxed))
+        };
+        let (min, max) = (f64::from_bits(min), f64::from_bits(max));
+        (min <= max).then_some((min, max))
+    }
+}
+
+/// [`metrics::Histogram`] tracking its observations in a [`Child`] of an
+/// [`extrema::Family`].
+///
+/// [`extrema::Family`]: Family
+#[derive(Debug)]
+pub struct Histogram {
+    /// Tracked [`metrics::Histogram`].
+    histogram: metrics::Histogram,
+
+    /// [`Child`] tracking the extreme values.
+    child: Arc<Child>,
+}
+
+impl Histogram {
+    /// Wraps the provided [`metrics::Histogram`] to track its observations in
+    /// the provided [`Child`].
+    #[must_use]
+    pub const fn new(histogram: metrics::Histogram, child: Arc<Child>) -> Self {
+        Self { histogram, child }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::HistogramFn for Histogram {
+    fn record(&self, value: f64) {
+        self.histogram.record(value);
+        self.child.observe(value);
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        self.histogram.record_many(value, count);
+        if count > 0 {
+            self.child.observe(value);
+        }
+    }
+}
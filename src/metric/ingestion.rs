@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Reconciling different styles of feeding values into
+//! [`prometheus::IntCounter`]s.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use crate::{
+    failure::{self, Resolve as _},
+    Metric,
+};
+
+/// Style the values are fed into a counter with by the instrumented code.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Mode {
+    /// Counter is fed with deltas via [`metrics::Counter::increment()`] only.
+    Delta,
+
+    /// Counter is fed with pre-aggregated totals via
+    /// [`metrics::Counter::absolute()`] only.
+    ///
+    /// The totals are converted into deltas against the previously fed total,
+    /// so a decreasing total is treated as a reset of the upstream counter,
+    /// and the [`prometheus::IntCounter`] keeps being monotonic.
+    Absolute,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delta => write!(f, "delta"),
+            Self::Absolute => write!(f, "absolute"),
+        }
+    }
+}
+
+/// Creates a [`prometheus::Error`] describing the counter identified by the
+/// provided `name` being fed in a style different from its declared [`Mode`].
+#[must_use]
+pub fn mismatch(name: &str, mode: Mode) -> prometheus::Error {
+    let call = match mode {
+        Mode::Delta => "absolute()",
+        Mode::Absolute => "increment()",
+    };
+    prometheus::Error::Msg(format!(
+        "`{name}` counter is declared to be fed with {mode} values, so cannot \
+         be fed via `{call}`",
+    ))
+}
+
+/// Thread-safe collection of the last totals fed into the counters in the
+/// [`Mode::Absolute`], identified by a metric name and its labels (regardless
+/// of their order).
+#[derive(Clone, Debug, Default)]
+pub struct Totals(Arc<RwLock<HashMap<metrics::Key, Arc<AtomicU64>>>>);
+
+impl Totals {
+    /// Returns the last total fed into the counter identified by the provided
+    /// [`metrics::Key`], creating it if it doesn't exist yet.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn get_or_create(&self, key: &metrics::Key) -> Arc<AtomicU64> {
+        if let Some(total) = self.0.read().unwrap().get(key) {
+            return Arc::clone(total);
+        }
+        Arc::clone(self.0.write().unwrap().entry(key.clone()).or_default())
+    }
+}
+
+/// [`prometheus::IntCounter`] accepting values in its declared [`Mode`] only.
+///
+/// Feeding values in a different style is treated as an error, and so, either
+/// panics or is ignored, according to the [`failure::Action`] decided upon the
+/// counter registration.
+#[derive(Debug)]
+pub struct Counter {
+    /// [`prometheus::IntCounter`] being fed.
+    metric: Arc<Metric<prometheus::IntCounter>>,
+
+    /// Name of the fed [`prometheus::IntCounter`].
+    name: String,
+
+    /// Declared [`Mode`] of feeding the [`prometheus::IntCounter`].
+    mode: Mode,
+
+    /// Last total fed in the [`Mode::Absolute`].
+    total: Arc<AtomicU64>,
+
+    /// [`failure::Action`] to perform on a [`Mode`] mismatch.
+    on_mismatch: failure::Action,
+}
+
+impl Counter {
+    /// Wraps the provided [`prometheus::IntCounter`] `metric` identified by
+    /// the provided [`metrics::Key`] to be fed in the provided [`Mode`],
+    /// deciding the [`failure::Action`] to be performed on a [`Mode`]
+    /// mismatch with the provided [`failure::Strategy`].
+    #[must_use]
+    pub fn new<S: failure::Strategy + ?Sized>(
+        metric: Arc<Metric<prometheus::IntCounter>>,
+        key: &metrics::Key,
+        mode: Mode,
+        totals: &Totals,
+        strategy: &S,
+    ) -> Self {
+        let on_mismatch =
+            strategy.resolve_for(key, &mismatch(key.name(), mode));
+        let total = match mode {
+            Mode::Delta => Arc::default(),
+            Mode::Absolute => totals.get_or_create(key),
+        };
+        Self { metric, name: key.name().into(), mode, total, on_mismatch }
+    }
+
+    /// Performs the [`failure::Action`] on a [`Mode`] mismatch.
+    fn mismatch(&self) {
+        match self.on_mismatch {
+            failure::Action::NoOp => {}
+            failure::Action::Panic => {
+                panic!("{}", mismatch(&self.name, self.mode))
+            }
+        }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::CounterFn for Counter {
+    fn increment(&self, value: u64) {
+        match self.mode {
+            Mode::Delta => self.metric.increment(value),
+            Mode::Absolute => self.mismatch(),
+        }
+    }
+
+    fn absolute(&self, value: u64) {
+        match self.mode {
+            Mode::Delta => self.mismatch(),
+            Mode::Absolute => {
+                let prev = self.total.swap(value, Ordering::Relaxed);
+                // Decreased total means the upstream counter has been reset,
+                // so the whole total is the delta since that reset.
+                let delta = value.checked_sub(prev).unwrap_or(value);
+                self.metric.increment(delta);
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`prometheus::IntGauge`]s driven through the float-based [`metrics`] API.
+//!
+//! Integer-backed gauges cannot be registered on the fly yet, but may be
+//! plugged into a [`Recorder`] via a [`CustomBundle`] resolving [`Gauge`]s.
+//!
+//! [`CustomBundle`]: super::CustomBundle
+//! [`Recorder`]: crate::Recorder
+
+use std::{fmt, sync::Arc};
+
+use crate::failure::{self, strategy::PanicInDebugNoOpInRelease, Resolve as _};
+
+/// Handling of fractional (and non-finite) values applied to an integer
+/// [`Gauge`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Fraction {
+    /// Round to the nearest integer (half away from zero).
+    #[default]
+    Round,
+
+    /// Truncate the fractional part.
+    Truncate,
+
+    /// Reject the value, letting the [`failure::Strategy`] of the [`Gauge`]
+    /// decide what to do with the error.
+    Reject,
+}
+
+/// [`prometheus::IntGauge`] implementing [`metrics::GaugeFn`] with the
+/// configured [`Fraction`] handling and saturating arithmetic.
+///
+/// Values out of [`i64`] range saturate to [`i64::MIN`] or [`i64::MAX`], as
+/// do increments and decrements overflowing it. Non-finite values are always
+/// rejected.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use metrics_prometheus::{failure::strategy, metric::integer};
+///
+/// let int = prometheus::IntGauge::new("connections", "help")?;
+/// let gauge = metrics::Gauge::from_arc(Arc::new(integer::Gauge::new(
+///     int.clone(),
+///     integer::Fraction::Truncate,
+/// )));
+///
+/// gauge.set(3.9);
+/// assert_eq!(int.get(), 3);
+/// gauge.increment(1.5);
+/// assert_eq!(int.get(), 4);
+/// gauge.set(f64::MAX);
+/// gauge.increment(1.0);
+/// assert_eq!(int.get(), i64::MAX);
+///
+/// let strict = integer::Gauge::new(int.clone(), integer::Fraction::Reject)
+///     .with_failure_strategy(strategy::NoOp);
+/// metrics::GaugeFn::set(&strict, 0.5);
+/// assert_eq!(int.get(), i64::MAX);
+///
+/// // Updates via different `Gauge`s of the same `prometheus::IntGauge` are
+/// // never lost.
+/// int.set(0);
+/// let other = integer::Gauge::new(int.clone(), integer::Fraction::Round);
+/// std::thread::scope(|s| {
+///     for g in [&strict, &other] {
+///         _ = s.spawn(move || {
+///             for _ in 0..1000 {
+///                 metrics::GaugeFn::increment(g, 1.0);
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(int.get(), 2000);
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Gauge {
+    /// [`prometheus::IntGauge`] exposing the value.
+    metric: prometheus::IntGauge,
+
+    /// [`Fraction`] handling of the values.
+    fraction: Fraction,
+
+    /// [`failure::Strategy`] to apply when a value is rejected.
+    strategy: Arc<dyn failure::Strategy>,
+}
+
+impl Gauge {
+    /// Wraps the provided [`prometheus::IntGauge`] to be driven with the
+    /// provided [`Fraction`] handling.
+    ///
+    /// [`PanicInDebugNoOpInRelease`] [`failure::Strategy`] is used for the
+    /// rejected values by default.
+    #[must_use]
+    pub fn new(gauge: prometheus::IntGauge, fraction: Fraction) -> Self {
+        Self {
+            metric: gauge,
+            fraction,
+            strategy: Arc::new(PanicInDebugNoOpInRelease),
+        }
+    }
+
+    /// Sets the provided [`failure::Strategy`] to apply when a value is
+    /// rejected.
+    #[must_use]
+    pub fn with_failure_strategy<S>(mut self, strategy: S) -> Self
+    where
+        S: failure::Strategy + 'static,
+    {
+        self.strategy = Arc::new(strategy);
+        self
+    }
+
+    /// Returns the [`Fraction`] handling of this [`Gauge`].
+    #[must_use]
+    pub const fn fraction(&self) -> Fraction {
+        self.fraction
+    }
+
+    /// Converts the provided `value` into an integer according to the
+    /// [`Fraction`] handling, applying the [`failure::Strategy`] if it's
+    /// rejected.
+    ///
+    /// # Panics
+    ///
+    /// If the `value` is rejected and the [`failure::Strategy`] decides so.
+    fn convert(&self, value: f64) -> Option<i64> {
+        use prometheus::core::Collector as _;
+
+        let converted = if value.is_finite() {
+            match self.fraction {
+                Fraction::Round => Some(value.round()),
+                Fraction::Truncate => Some(value.trunc()),
+                Fraction::Reject => (value.fract() == 0.0).then_some(value),
+            }
+        } else {
+            None
+        };
+        if let Some(v) = converted {
+            #[expect( // intentional
+                clippy::as_conversions,
+                clippy::cast_possible_truncation,
+                reason = "float to integer `as` conversion saturates"
+            )]
+            return Some(v as i64);
+        }
+
+        let e = prometheus::Error::Msg(format!(
+            "value `{value}` cannot be applied to `{}` integer gauge",
+            self.metric.desc().first().map_or("", |d| d.fq_name.as_str()),
+        ));
+        match self.strategy.resolve(&e) {
+            failure::Action::NoOp => None,
+            failure::Action::Panic => panic!("{e}"),
+        }
+    }
+
+    /// Updates the value of this [`Gauge`] with the provided saturating
+    /// function.
+    ///
+    /// The change is applied to the [`prometheus::IntGauge`] atomically, as
+    /// it's the only source of truth, so no concurrent updates are lost (even
+    /// the ones via other [`Gauge`]s wrapping the same
+    /// [`prometheus::IntGauge`]). However, the saturation is computed against
+    /// the value observed before the change, so may be missed by the updates
+    /// racing at the very bounds of [`i64`].
+    fn update(&self, f: impl FnOnce(i64) -> i64) {
+        let current = self.metric.get();
+        self.metric.add(f(current).wrapping_sub(current));
+    }
+}
+
+impl fmt::Debug for Gauge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gauge")
+            .field("metric", &self.metric)
+            .field("fraction", &self.fraction)
+            .finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::GaugeFn for Gauge {
+    fn increment(&self, value: f64) {
+        if let Some(delta) = self.convert(value) {
+            self.update(|v| v.saturating_add(delta));
+        }
+    }
+
+    fn decrement(&self, value: f64) {
+        if let Some(delta) = self.convert(value) {
+            self.update(|v| v.saturating_sub(delta));
+        }
+    }
+
+    fn set(&self, value: f64) {
+        if let Some(v) = self.convert(value) {
+            self.metric.set(v);
+        }
+    }
+}
@@ -0,0 +1,1156 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Machinery around [`prometheus`] metrics for making them usable via
+//! [`metrics`] crate.
+
+pub mod adaptive;
+pub mod annotation;
+pub mod batched;
+pub mod callback;
+pub mod custom;
+pub mod extrema;
+pub mod ingestion;
+pub mod integer;
+pub mod monotonic;
+#[cfg(feature = "origins")]
+pub mod origin;
+pub mod quantile;
+pub mod raw;
+pub mod striped;
+pub mod threshold;
+#[cfg(feature = "timestamps")]
+pub mod timestamp;
+pub mod windowed;
+
+use std::{
+    iter,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+use arc_swap::ArcSwap;
+use sealed::sealed;
+use smallvec::SmallVec;
+
+use self::bundle::Either;
+
+#[doc(inline)]
+pub use self::{bundle::Bundle, custom::CustomBundle};
+
+/// Wrapper allowing implementing [`metrics::CounterFn`], [`metrics::GaugeFn`]
+/// and [`metrics::HistogramFn`] for [`prometheus`] metrics.
+#[cfg_attr(
+    feature = "timestamps",
+    expect( // intentional
+        clippy::struct_field_names,
+        reason = "consistent with other wrappers of `prometheus` metrics"
+    )
+)]
+#[derive(Clone, Debug)]
+pub struct Metric<M> {
+    /// [`prometheus`] metric itself.
+    metric: M,
+
+    /// Shadow of the last absolute value set via the
+    /// [`metrics::CounterFn::absolute()`], arbitrating concurrent operations,
+    /// or [`None`] if the absolute values are set by resetting the
+    /// [`prometheus`] metric.
+    ///
+    /// Created lazily on the first [`metrics::CounterFn::absolute()`] call, so
+    /// only the counters setting their absolute values allocate it, and shared
+    /// by all the clones made after that.
+    shadow: Option<OnceLock<Arc<AtomicU64>>>,
+
+    /// [`LastUpdated`] time of the [`prometheus`] metric, touched on every
+    /// its update, if tracked.
+    ///
+    /// [`LastUpdated`]: timestamp::LastUpdated
+    #[cfg(feature = "timestamps")]
+    last_updated: Option<Arc<timestamp::LastUpdated>>,
+}
+
+impl<M> Metric<M> {
+    /// Wraps the provided [`prometheus`] `metric`.
+    #[must_use]
+    pub const fn wrap(metric: M) -> Self {
+        Self {
+            metric,
+            shadow: Some(OnceLock::new()),
+            #[cfg(feature = "timestamps")]
+            last_updated: None,
+        }
+    }
+
+    /// Makes this [`Metric`] to set its absolute value (via the
+    /// [`metrics::CounterFn::absolute()`]) by resetting the [`prometheus`]
+    /// metric, rather than incrementing it by the delta.
+    ///
+    /// This allows the absolute value to decrease, at the cost of the
+    /// [`prometheus`] metric being observable as reset to zero in the meantime,
+    /// and races between concurrent [`metrics::CounterFn::absolute()`] calls.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics::CounterFn as _;
+    /// use metrics_prometheus::Metric;
+    ///
+    /// let counter = prometheus::IntCounter::new("value", "help")?;
+    ///
+    /// let metric = Metric::wrap(counter.clone());
+    /// metric.absolute(10);
+    /// metric.absolute(5);
+    /// assert_eq!(counter.get(), 10);
+    ///
+    /// let metric = Metric::wrap(counter.clone()).with_resetting_absolute();
+    /// metric.absolute(5);
+    /// assert_eq!(counter.get(), 5);
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    #[must_use]
+    pub fn with_resetting_absolute(mut self) -> Self {
+        self.shadow = None;
+        self
+    }
+
+    /// Creates the shadow of the absolute value of this [`Metric`] eagerly, so
+    /// it's shared by all the clones of this [`Metric`] made afterwards.
+    #[must_use]
+    pub(crate) fn with_shadow(self) -> Self {
+        if let Some(shadow) = &self.shadow {
+            _ = shadow.get_or_init(Arc::default);
+        }
+        self
+    }
+
+    /// Makes this [`Metric`] to touch the provided [`LastUpdated`] time on
+    /// every its update.
+    ///
+    /// [`LastUpdated`]: timestamp::LastUpdated
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn with_last_updated(
+        mut self,
+        last_updated: Arc<timestamp::LastUpdated>,
+    ) -> Self {
+        self.last_updated = Some(last_updated);
+        self
+    }
+
+    /// Unwraps this [`Metric`] returning its inner [`prometheus`] metric
+    #[must_use]
+    pub fn into_inner(self) -> M {
+        self.metric
+    }
+
+    /// Touches the [`LastUpdated`] time of this [`Metric`], if tracked.
+    ///
+    /// [`LastUpdated`]: timestamp::LastUpdated
+    #[cfg(feature = "timestamps")]
+    fn touch(&self) {
+        if let Some(ts) = &self.last_updated {
+            ts.touch();
+        }
+    }
+}
+
+/// Typed handle of a pre-registered [`prometheus`] metric.
+///
+/// Allows to use the [`prometheus`] metric directly on hot paths (bypassing
+/// any lookups in a [`metrics::Recorder`]), while it's still being usable via
+/// [`metrics`] crate interfaces.
+///
+/// Always refers to the [`prometheus`] metric actually registered in a
+/// [`prometheus::Registry`], even if an already registered one with the
+/// identical schema has been reused.
+#[derive(Clone, Debug)]
+pub struct Handle<M>(M);
+
+impl<M> Handle<M> {
+    /// Wraps the provided registered [`prometheus`] `metric`.
+    #[must_use]
+    pub const fn wrap(metric: M) -> Self {
+        Self(metric)
+    }
+
+    /// Unwraps this [`Handle`] returning its inner [`prometheus`] metric.
+    #[must_use]
+    pub fn into_inner(self) -> M {
+        self.0
+    }
+}
+
+impl<M> AsRef<M> for Handle<M> {
+    fn as_ref(&self) -> &M {
+        &self.0
+    }
+}
+
+impl<M> Deref for Handle<M> {
+    type Target = M;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<M> AsRef<M> for Metric<M> {
+    fn as_ref(&self) -> &M {
+        &self.metric
+    }
+}
+
+impl<M> AsMut<M> for Metric<M> {
+    fn as_mut(&mut self) -> &mut M {
+        &mut self.metric
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::CounterFn for Metric<prometheus::IntCounter> {
+    fn increment(&self, value: u64) {
+        self.metric.inc_by(value);
+        #[cfg(feature = "timestamps")]
+        self.touch();
+    }
+
+    fn absolute(&self, value: u64) {
+        let Some(shadow) = &self.shadow else {
+            // `prometheus::IntCounter` doesn't provide any atomic way to set
+            // its absolute value, so the implementation below may introduce
+            // races when two `.absolute()` operations content, leading to the
+            // incorrect value of a sum of two absolute values.
+            self.metric.reset();
+            self.metric.inc_by(value);
+            #[cfg(feature = "timestamps")]
+            self.touch();
+            return;
+        };
+
+        // `prometheus::IntCounter` is never reset here, so is never observed
+        // dipping. Instead, the `shadow` arbitrates concurrent `.absolute()`
+        // operations, so each delta is applied exactly once, while the current
+        // value accounts for the increments made in the meantime.
+        let shadow = shadow.get_or_init(Arc::default);
+        let mut last = shadow.load(Ordering::Acquire);
+        loop {
+            let current = last.max(self.metric.get());
+            if value <= current {
+                break;
+            }
+            match shadow.compare_exchange_weak(
+                last,
+                value,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    self.metric.inc_by(value - current);
+                    break;
+                }
+                Err(actual) => last = actual,
+            }
+        }
+        #[cfg(feature = "timestamps")]
+        self.touch();
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::GaugeFn for Metric<prometheus::Gauge> {
+    fn increment(&self, value: f64) {
+        self.metric.add(value);
+        #[cfg(feature = "timestamps")]
+        self.touch();
+    }
+
+    fn decrement(&self, value: f64) {
+        self.metric.sub(value);
+        #[cfg(feature = "timestamps")]
+        self.touch();
+    }
+
+    fn set(&self, value: f64) {
+        self.metric.set(value);
+        #[cfg(feature = "timestamps")]
+        self.touch();
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::HistogramFn for Metric<prometheus::Histogram> {
+    fn record(&self, value: f64) {
+        self.metric.observe(value);
+        #[cfg(feature = "timestamps")]
+        self.touch();
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        for _ in 0..count {
+            self.record(value);
+        }
+    }
+}
+
+/// Fallible [`Metric`] stored in [`metrics::Registry`].
+///
+/// We're obligated to store [`Fallible`] metrics inside [`metrics::Registry`],
+/// because panicking earlier, rather than inside directly called
+/// [`metrics::Recorder`] methods, will poison locks the [`metrics::Registry`]
+/// is built upon on.
+///
+/// [`metrics::Registry`]: metrics_util::registry::Registry
+#[derive(Debug)]
+pub struct Fallible<M>(pub Arc<prometheus::Result<Arc<Metric<M>>>>);
+
+// Manual implementation is required to omit the redundant `M: Clone` trait
+// bound imposed by `#[derive(Clone)]`.
+impl<M> Clone for Fallible<M> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<M> From<prometheus::Result<Arc<Metric<M>>>> for Fallible<M> {
+    fn from(res: prometheus::Result<Arc<Metric<M>>>) -> Self {
+        Self(Arc::new(res))
+    }
+}
+
+impl<M> Fallible<M> {
+    /// Mimics [`Result::as_ref()`] method for this [`Fallible`].
+    ///
+    /// # Errors
+    ///
+    /// If this [`Fallible`] contains a [`prometheus::Error`].
+    pub fn as_ref(&self) -> Result<&Arc<Metric<M>>, &prometheus::Error> {
+        (*self.0).as_ref()
+    }
+}
+
+// Not really used, only implemented to satisfy
+// `metrics_util::registry::Storage` requirements for stored items.
+#[warn(clippy::missing_trait_methods)]
+impl<M> metrics::CounterFn for Fallible<M>
+where
+    Metric<M>: metrics::CounterFn,
+{
+    fn increment(&self, value: u64) {
+        if let Ok(m) = &*self.0 {
+            m.increment(value);
+        }
+    }
+
+    fn absolute(&self, value: u64) {
+        if let Ok(m) = &*self.0 {
+            m.absolute(value);
+        }
+    }
+}
+
+// Not really used, only implemented to satisfy
+// `metrics_util::registry::Storage` requirements for stored items.
+#[warn(clippy::missing_trait_methods)]
+impl<M> metrics::GaugeFn for Fallible<M>
+where
+    Metric<M>: metrics::GaugeFn,
+{
+    fn increment(&self, value: f64) {
+        if let Ok(m) = &*self.0 {
+            m.increment(value);
+        }
+    }
+
+    fn decrement(&self, value: f64) {
+        if let Ok(m) = &*self.0 {
+            m.decrement(value);
+        }
+    }
+
+    fn set(&self, value: f64) {
+        if let Ok(m) = &*self.0 {
+            m.set(value);
+        }
+    }
+}
+
+// Not really used, only implemented to satisfy
+// `metrics_util::registry::Storage` requirements for stored items.
+#[warn(clippy::missing_trait_methods)]
+impl<M> metrics::HistogramFn for Fallible<M>
+where
+    Metric<M>: metrics::HistogramFn,
+{
+    fn record(&self, value: f64) {
+        if let Ok(m) = &*self.0 {
+            m.record(value);
+        }
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        if let Ok(m) = &*self.0 {
+            for _ in 0..count {
+                m.record(value);
+            }
+        }
+    }
+}
+
+/// [`prometheus`] metric with an ability to substitute its [`help` description]
+/// after registration in a [`prometheus::Registry`].
+///
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+#[derive(Clone, Debug, Default)]
+pub struct Describable<Metric> {
+    /// Swappable [`help` description] of the [`prometheus`] metric.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub(crate) description: Arc<ArcSwap<metrics::SharedString>>,
+
+    /// Per-child [`Annotations`] of the [`prometheus`] metric, used for
+    /// introspection only.
+    ///
+    /// [`Annotations`]: annotation::Annotations
+    pub(crate) annotations: annotation::Annotations,
+
+    /// Per-child [`Callbacks`] refreshing values of the [`prometheus`] gauges
+    /// right before they're collected.
+    ///
+    /// [`Callbacks`]: callback::Callbacks
+    pub(crate) callbacks: callback::Callbacks,
+
+    /// [`Origins`] of the [`prometheus`] metric, used for introspection only.
+    ///
+    /// [`Origins`]: origin::Origins
+    #[cfg(feature = "origins")]
+    pub(crate) origins: origin::Origins,
+
+    /// [`prometheus`] metric itself.
+    pub(crate) metric: Metric,
+
+    /// Configured buckets of the [`prometheus`] histogram metric (excluding
+    /// the implicit `+Inf` one), if known.
+    ///
+    /// Required to describe the schema of a [`prometheus::HistogramVec`]
+    /// without children, as its buckets cannot be inspected otherwise.
+    pub(crate) buckets: Option<Arc<[f64]>>,
+
+    /// Cached [`prometheus::core::Desc`]s of the [`prometheus`] metric,
+    /// computed on the first [`Collector::desc()`] call.
+    ///
+    /// Never invalidated on the [`help` description] swap, as the
+    /// [`prometheus::core::Desc`]s always keep the original one (see the
+    /// [`Collector::desc()`] implementation for details).
+    ///
+    /// [`Collector::desc()`]: prometheus::core::Collector::desc
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    descs: OnceLock<Vec<prometheus::core::Desc>>,
+}
+
+impl<M> Describable<M> {
+    /// Wraps the provided [`prometheus`] `metric` into a [`Describable`] one.
+    #[must_use]
+    pub fn wrap(metric: M) -> Self {
+        Self {
+            description: Arc::default(),
+            annotations: annotation::Annotations::default(),
+            callbacks: callback::Callbacks::default(),
+            #[cfg(feature = "origins")]
+            origins: origin::Origins::default(),
+            metric,
+            buckets: None,
+            descs: OnceLock::new(),
+        }
+    }
+
+    /// Generates a [`Default`] [`prometheus`] metric with the provided
+    /// [`help` description].
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[must_use]
+    pub fn only_description(help: impl Into<metrics::SharedString>) -> Self
+    where
+        M: Default,
+    {
+        Self {
+            description: Arc::new(ArcSwap::new(Arc::new(help.into()))),
+            annotations: annotation::Annotations::default(),
+            callbacks: callback::Callbacks::default(),
+            #[cfg(feature = "origins")]
+            origins: origin::Origins::default(),
+            metric: M::default(),
+            buckets: None,
+            descs: OnceLock::new(),
+        }
+    }
+
+    /// Returns the per-child [`Annotations`] of this [`Describable`] metric,
+    /// used for introspection only.
+    ///
+    /// [`Annotations`]: annotation::Annotations
+    #[must_use]
+    pub const fn annotations(&self) -> &annotation::Annotations {
+        &self.annotations
+    }
+
+    /// Returns the [`Origins`] of this [`Describable`] metric, used for
+    /// introspection only.
+    ///
+    /// [`Origins`]: origin::Origins
+    #[cfg(feature = "origins")]
+    #[must_use]
+    pub const fn origins(&self) -> &origin::Origins {
+        &self.origins
+    }
+
+    /// Maps the wrapped [`prometheus`] metric `into` another one, preserving
+    /// the current overwritten [`help` description], annotations, callbacks
+    /// and origins (if any).
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[must_use]
+    pub fn map<Into>(self, into: impl FnOnce(M) -> Into) -> Describable<Into> {
+        Describable {
+            description: self.description,
+            annotations: self.annotations,
+            callbacks: self.callbacks,
+            #[cfg(feature = "origins")]
+            origins: self.origins,
+            metric: into(self.metric),
+            buckets: self.buckets,
+            descs: OnceLock::new(),
+        }
+    }
+}
+
+impl<M> Describable<Option<M>> {
+    /// Transposes this [`Describable`] [`Option`]al metric into an [`Option`]
+    /// of a [`Describable`] metric.
+    #[must_use]
+    pub fn transpose(self) -> Option<Describable<M>> {
+        self.metric.map(|metric| Describable {
+            description: self.description,
+            annotations: self.annotations,
+            callbacks: self.callbacks,
+            #[cfg(feature = "origins")]
+            origins: self.origins,
+            metric,
+            buckets: self.buckets,
+            descs: OnceLock::new(),
+        })
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl<M> prometheus::core::Collector for Describable<M>
+where
+    M: prometheus::core::Collector,
+{
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        // We could omit changing `help` field here, because `Collector::desc()`
+        // method is used by `prometheus::Registry` only for metrics
+        // registration and validation in its `.register()` and `.unregister()`
+        // methods. When `prometheus::Registry` `.gather()`s metrics, it invokes
+        // `Collector::collect()` method, where we do the actual `help` field
+        // substitution.
+        // This is also what allows caching `Desc`s here without ever
+        // invalidating them, sparing repeated registration attempts from
+        // recomputing them.
+        self.descs
+            .get_or_init(|| self.metric.desc().into_iter().cloned().collect())
+            .iter()
+            .collect()
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.callbacks.refresh();
+        let mut out = self.metric.collect();
+        // `ArcSwap::load()` is lock-free and doesn't touch the reference
+        // counter of the current `help` description in the common case (unlike
+        // `ArcSwap::load_full()`), so scraping thousands of families doesn't
+        // cause contention on it.
+        let new_help = self.description.load();
+        if !new_help.is_empty() {
+            for mf in &mut out {
+                mf.set_help((**new_help).to_string());
+            }
+        }
+        out
+    }
+}
+
+/// Custom conversion trait to convert between foreign types.
+trait To<T> {
+    /// Converts this reference into a `T` value.
+    fn to(&self) -> T;
+}
+
+impl To<prometheus::Opts> for metrics::Key {
+    fn to(&self) -> prometheus::Opts {
+        // We use `key.name()` as `help` description here, because `prometheus`
+        // crate doesn't allow to make it empty.
+        prometheus::Opts::new(self.name(), self.name())
+    }
+}
+
+impl To<prometheus::HistogramOpts> for metrics::Key {
+    fn to(&self) -> prometheus::HistogramOpts {
+        // We use `key.name()` as `help` description here, because `prometheus`
+        // crate doesn't allow to make it empty.
+        prometheus::HistogramOpts::new(self.name(), self.name())
+    }
+}
+
+/// [`prometheus`] metric being [`Bundle`]d.
+#[sealed]
+pub trait Bundled: Clone {
+    /// Type of a [`Bundle`] bundling this [`prometheus`] metric.
+    type Bundle: Bundle;
+
+    /// Indicates whether a [`Metric`] of this [`prometheus`] metric may set
+    /// its absolute value, so requires a shadow of it.
+    const ABSOLUTE: bool = false;
+
+    /// Wraps this [`prometheus`] metric into its [`Bundle`].
+    fn into_bundle(self) -> Self::Bundle;
+
+    /// Extracts this [`prometheus`] metric out of the provided [`Bundle`].
+    ///
+    /// [`None`] is returned if the provided [`Bundle`] holds a metric of a
+    /// different type.
+    fn from_bundle(bundle: &Self::Bundle) -> Option<Self>;
+}
+
+#[sealed]
+impl Bundled for prometheus::IntCounter {
+    type Bundle = PrometheusIntCounter;
+
+    const ABSOLUTE: bool = true;
+
+    fn into_bundle(self) -> Self::Bundle {
+        PrometheusIntCounter::Single(self)
+    }
+
+    fn from_bundle(bundle: &Self::Bundle) -> Option<Self> {
+        match bundle {
+            PrometheusIntCounter::Single(m) => Some(m.clone()),
+            PrometheusIntCounter::Vec(_) => None,
+        }
+    }
+}
+
+#[sealed]
+impl Bundled for prometheus::IntCounterVec {
+    type Bundle = PrometheusIntCounter;
+
+    fn into_bundle(self) -> Self::Bundle {
+        PrometheusIntCounter::Vec(self)
+    }
+
+    fn from_bundle(bundle: &Self::Bundle) -> Option<Self> {
+        match bundle {
+            PrometheusIntCounter::Vec(m) => Some(m.clone()),
+            PrometheusIntCounter::Single(_) => None,
+        }
+    }
+}
+
+#[sealed]
+impl Bundled for prometheus::Gauge {
+    type Bundle = PrometheusGauge;
+
+    fn into_bundle(self) -> Self::Bundle {
+        PrometheusGauge::Single(self)
+    }
+
+    fn from_bundle(bundle: &Self::Bundle) -> Option<Self> {
+        match bundle {
+            PrometheusGauge::Single(m) => Some(m.clone()),
+            PrometheusGauge::Vec(_) => None,
+        }
+    }
+}
+
+#[sealed]
+impl Bundled for prometheus::GaugeVec {
+    type Bundle = PrometheusGauge;
+
+    fn into_bundle(self) -> Self::Bundle {
+        PrometheusGauge::Vec(self)
+    }
+
+    fn from_bundle(bundle: &Self::Bundle) -> Option<Self> {
+        match bundle {
+            PrometheusGauge::Vec(m) => Some(m.clone()),
+            PrometheusGauge::Single(_) => None,
+        }
+    }
+}
+
+#[sealed]
+impl Bundled for prometheus::Histogram {
+    type Bundle = PrometheusHistogram;
+
+    fn into_bundle(self) -> Self::Bundle {
+        PrometheusHistogram::Single(self)
+    }
+
+    fn from_bundle(bundle: &Self::Bundle) -> Option<Self> {
+        match bundle {
+            PrometheusHistogram::Single(m) => Some(m.clone()),
+            PrometheusHistogram::Vec(_) => None,
+        }
+    }
+}
+
+#[sealed]
+impl Bundled for prometheus::HistogramVec {
+    type Bundle = PrometheusHistogram;
+
+    fn into_bundle(self) -> Self::Bundle {
+        PrometheusHistogram::Vec(self)
+    }
+
+    fn from_bundle(bundle: &Self::Bundle) -> Option<Self> {
+        match bundle {
+            PrometheusHistogram::Vec(m) => Some(m.clone()),
+            PrometheusHistogram::Single(_) => None,
+        }
+    }
+}
+
+/// Collects names of the provided labels, sorted in lexicographical order.
+///
+/// Sorting guarantees the identical schema of a [`prometheus::MetricVec`]
+/// regardless of the labels order at the call site registering it first. Must
+/// be kept consistent with the [`storage::resolve()`] function.
+///
+/// [`prometheus::MetricVec`]: prometheus::core::MetricVec
+/// [`storage::resolve()`]: crate::storage::resolve
+fn label_names<'k>(
+    first: &'k metrics::Label,
+    rest: impl Iterator<Item = &'k metrics::Label>,
+) -> SmallVec<[&'k str; 10]> {
+    let mut names = iter::once(first)
+        .chain(rest)
+        .map(metrics::Label::key)
+        .collect::<SmallVec<[_; 10]>>();
+    names.sort_unstable();
+    names
+}
+
+/// [`Bundle`] of [`prometheus::IntCounter`] metrics.
+pub type PrometheusIntCounter =
+    Either<prometheus::IntCounter, prometheus::IntCounterVec>;
+
+impl TryFrom<&metrics::Key> for PrometheusIntCounter {
+    type Error = prometheus::Error;
+
+    fn try_from(key: &metrics::Key) -> Result<Self, Self::Error> {
+        let mut labels_iter = key.labels();
+        Ok(if let Some(first_label) = labels_iter.next() {
+            let label_names = label_names(first_label, labels_iter);
+            Self::Vec(prometheus::IntCounterVec::new(key.to(), &label_names)?)
+        } else {
+            Self::Single(prometheus::IntCounter::with_opts(key.to())?)
+        })
+    }
+}
+
+/// [`Bundle`] of [`prometheus::Gauge`] metrics.
+pub type PrometheusGauge = Either<prometheus::Gauge, prometheus::GaugeVec>;
+
+impl TryFrom<&metrics::Key> for PrometheusGauge {
+    type Error = prometheus::Error;
+
+    fn try_from(key: &metrics::Key) -> Result<Self, Self::Error> {
+        let mut labels_iter = key.labels();
+        Ok(if let Some(first_label) = labels_iter.next() {
+            let label_names = label_names(first_label, labels_iter);
+            Self::Vec(prometheus::GaugeVec::new(key.to(), &label_names)?)
+        } else {
+            Self::Single(prometheus::Gauge::with_opts(key.to())?)
+        })
+    }
+}
+
+/// [`Bundle`] of [`prometheus::Histogram`] metrics.
+pub type PrometheusHistogram =
+    Either<prometheus::Histogram, prometheus::HistogramVec>;
+
+impl PrometheusHistogram {
+    /// Creates a new [`PrometheusHistogram`] identified by the provided
+    /// [`metrics::Key`], having the provided `buckets` instead of the
+    /// [`prometheus::DEFAULT_BUCKETS`].
+    ///
+    /// # Errors
+    ///
+    /// If the provided `buckets` are not strictly increasing, or the provided
+    /// [`metrics::Key`] is invalid.
+    pub(crate) fn with_buckets(
+        key: &metrics::Key,
+        buckets: Vec<f64>,
+    ) -> prometheus::Result<Self> {
+        let opts: prometheus::HistogramOpts = key.to();
+        histogram(key, opts.buckets(buckets))
+    }
+}
+
+impl TryFrom<&metrics::Key> for PrometheusHistogram {
+    type Error = prometheus::Error;
+
+    fn try_from(key: &metrics::Key) -> Result<Self, Self::Error> {
+        histogram(key, key.to())
+    }
+}
+
+/// Creates a new [`PrometheusHistogram`] identified by the provided
+/// [`metrics::Key`] with the provided [`prometheus::HistogramOpts`].
+///
+/// # Errors
+///
+/// If the [`PrometheusHistogram`] cannot be created with the provided
+/// [`prometheus::HistogramOpts`].
+fn histogram(
+    key: &metrics::Key,
+    opts: prometheus::HistogramOpts,
+) -> prometheus::Result<PrometheusHistogram> {
+    let mut labels_iter = key.labels();
+    Ok(if let Some(first_label) = labels_iter.next() {
+        let label_names = label_names(first_label, labels_iter);
+        Either::Vec(prometheus::HistogramVec::new(opts, &label_names)?)
+    } else {
+        Either::Single(prometheus::Histogram::with_opts(opts)?)
+    })
+}
+
+/// Definitions of [`Bundle`] machinery.
+pub mod bundle {
+    use std::{
+        collections::HashMap,
+        hash::{Hash, Hasher},
+    };
+
+    use sealed::sealed;
+
+    /// Identity of a [`Bundle`]'s schema, allowing to compare registered
+    /// metric families reliably.
+    ///
+    /// Help descriptions and constant labels are not part of a [`Schema`].
+    #[derive(Clone, Debug)]
+    pub struct Schema {
+        /// Fully-qualified name of the metric family.
+        pub name: String,
+
+        /// Names of the variable labels, sorted in lexicographical order.
+        pub label_names: Vec<String>,
+
+        /// Kind of the metrics in the family.
+        pub kind: prometheus::proto::MetricType,
+
+        /// Upper bounds of the histogram buckets (excluding the implicit
+        /// `+Inf` one).
+        ///
+        /// Always empty for non-histogram metrics. Also empty for a
+        /// [`prometheus::HistogramVec`] not having any metrics yet, as its
+        /// buckets cannot be inspected before.
+        pub buckets: Vec<f64>,
+    }
+
+    impl PartialEq for Schema {
+        fn eq(&self, other: &Self) -> bool {
+            self.name == other.name
+                && self.label_names == other.label_names
+                && self.kind == other.kind
+                && self.buckets.len() == other.buckets.len()
+                && self
+                    .buckets
+                    .iter()
+                    .zip(&other.buckets)
+                    .all(|(a, b)| a.to_bits() == b.to_bits())
+        }
+    }
+
+    impl Eq for Schema {}
+
+    impl Hash for Schema {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.name.hash(state);
+            self.label_names.hash(state);
+            self.kind.hash(state);
+            for b in &self.buckets {
+                b.to_bits().hash(state);
+            }
+        }
+    }
+
+    /// Either a single [`prometheus::Metric`] or a [`prometheus::MetricVec`] of
+    /// them, forming a [`Bundle`].
+    ///
+    /// [`prometheus::Metric`]: prometheus::core::Metric
+    /// [`prometheus::MetricVec`]: prometheus::core::MetricVec
+    #[derive(Clone, Copy, Debug)]
+    pub enum Either<Single, Vec> {
+        /// Single [`prometheus::Metric`].
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        Single(Single),
+
+        /// [`prometheus::MetricVec`] of [`prometheus::Metric`]s.
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        /// [`prometheus::MetricVec`]: prometheus::core::MetricVec
+        Vec(Vec),
+    }
+
+    impl<S, V> Either<S, V>
+    where
+        Self: prometheus::core::Collector,
+    {
+        /// Returns the [`Schema`] of this [`Bundle`].
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// use metrics_prometheus::metric::PrometheusHistogram;
+        ///
+        /// let key = metrics::Key::from_parts("latency", &[("path", "/")]);
+        /// let a = PrometheusHistogram::try_from(&key)?;
+        /// let b = PrometheusHistogram::try_from(&key)?;
+        /// assert_eq!(a.schema().name, "latency");
+        /// assert_eq!(a.schema().label_names, ["path"]);
+        /// assert_eq!(a.schema(), b.schema());
+        ///
+        /// let other = metrics::Key::from_parts("latency", &[("code", "200")]);
+        /// let c = PrometheusHistogram::try_from(&other)?;
+        /// assert_ne!(a.schema(), c.schema());
+        /// # Ok::<_, prometheus::Error>(())
+        /// ```
+        #[must_use]
+        pub fn schema(&self) -> Schema {
+            use prometheus::core::Collector as _;
+
+            let desc = self.desc();
+            let families = self.collect();
+            let name = desc
+                .first()
+                .map(|d| d.fq_name.clone())
+                .or_else(|| families.first().map(|f| f.get_name().to_owned()))
+                .unwrap_or_default();
+            let mut label_names = desc
+                .first()
+                .map(|d| d.variable_labels.clone())
+                .unwrap_or_default();
+            label_names.sort_unstable();
+            let kind = families
+                .first()
+                .map_or(prometheus::proto::MetricType::UNTYPED, |f| {
+                    f.get_field_type()
+                });
+            let buckets = families
+                .first()
+                .and_then(|f| f.get_metric().first())
+                .map(|m| {
+                    m.get_histogram()
+                        .get_bucket()
+                        .iter()
+                        .map(prometheus::proto::Bucket::get_upper_bound)
+                        .filter(|b| b.is_finite())
+                        .collect()
+                })
+                .unwrap_or_default();
+            Schema { name, label_names, kind, buckets }
+        }
+    }
+
+    #[warn(clippy::missing_trait_methods)]
+    impl<S, V> prometheus::core::Collector for Either<S, V>
+    where
+        S: prometheus::core::Collector,
+        V: prometheus::core::Collector,
+    {
+        fn desc(&self) -> Vec<&prometheus::core::Desc> {
+            match self {
+                Self::Single(m) => m.desc(),
+                Self::Vec(v) => v.desc(),
+            }
+        }
+
+        fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+            match self {
+                Self::Single(m) => m.collect(),
+                Self::Vec(v) => v.collect(),
+            }
+        }
+    }
+
+    /// [`prometheus::MetricVec`] of [`prometheus::Metric`]s.
+    ///
+    /// [`prometheus::Metric`]: prometheus::core::Metric
+    /// [`prometheus::MetricVec`]: prometheus::core::MetricVec
+    #[sealed]
+    pub trait MetricVec {
+        /// Type of [`prometheus::Metric`]s forming this [`MetricVec`].
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        type Metric: prometheus::core::Metric;
+
+        /// Calls [`prometheus::MetricVec::get_metric_with()`][0] method of this
+        /// [`MetricVec`].
+        ///
+        /// # Errors
+        ///
+        /// If a [`prometheus::Metric`] cannot be identified or created for the
+        /// provided label `values`.
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        /// [0]: prometheus::core::MetricVec::get_metric_with()
+        fn get_metric_with(
+            &self,
+            labels: &HashMap<&str, &str>,
+        ) -> prometheus::Result<Self::Metric>;
+
+        /// Calls [`prometheus::MetricVec::remove()`][0] method of this
+        /// [`MetricVec`].
+        ///
+        /// # Errors
+        ///
+        /// If a [`prometheus::Metric`] cannot be identified for the provided
+        /// label `values`.
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        /// [0]: prometheus::core::MetricVec::remove()
+        fn remove(
+            &self,
+            labels: &HashMap<&str, &str>,
+        ) -> prometheus::Result<()>;
+    }
+
+    #[sealed]
+    impl<M, B> MetricVec for prometheus::core::MetricVec<B>
+    where
+        M: prometheus::core::Metric,
+        B: prometheus::core::MetricVecBuilder<M = M>,
+    {
+        type Metric = M;
+
+        fn get_metric_with(
+            &self,
+            labels: &HashMap<&str, &str>,
+        ) -> prometheus::Result<M> {
+            self.get_metric_with(labels)
+        }
+
+        fn remove(
+            &self,
+            labels: &HashMap<&str, &str>,
+        ) -> prometheus::Result<()> {
+            self.remove(labels)
+        }
+    }
+
+    /// Bundle of a [`prometheus::Metric`]s family.
+    ///
+    /// [`Either`] a single [`prometheus::Metric`] or a
+    /// [`prometheus::MetricVec`] of them.
+    ///
+    /// [`prometheus::Metric`]: prometheus::core::Metric
+    /// [`prometheus::MetricVec`]: prometheus::core::MetricVec
+    #[sealed]
+    pub trait Bundle {
+        /// Type of a single [`prometheus::Metric`] that may be stored in this
+        /// [`Bundle`].
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        type Single: prometheus::core::Metric;
+
+        /// Type of a [`prometheus::MetricVec`] that may be stored in this
+        /// [`Bundle`].
+        ///
+        /// [`prometheus::MetricVec`]: prometheus::core::MetricVec
+        type Vec: MetricVec<Metric = Self::Single>;
+
+        /// Returns a single [`prometheus::Metric`] of this [`Bundle`],
+        /// identified by the provided [`metrics::Key`].
+        ///
+        /// # Errors
+        ///
+        /// If the provided [`metrics::Key`] cannot identify any
+        /// [`prometheus::Metric`] in this [`Bundle`].
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        fn get_single_metric(
+            &self,
+            key: &metrics::Key,
+        ) -> prometheus::Result<Self::Single>;
+
+        /// Removes a single [`prometheus::Metric`] identified by the provided
+        /// [`metrics::Key`] from this [`Bundle`].
+        ///
+        /// # Errors
+        ///
+        /// If the provided [`metrics::Key`] cannot identify any
+        /// [`prometheus::Metric`] in this [`Bundle`], or it cannot be removed
+        /// (being the single [`prometheus::Metric`] of this [`Bundle`]).
+        ///
+        /// [`prometheus::Metric`]: prometheus::core::Metric
+        fn remove_single_metric(
+            &self,
+            key: &metrics::Key,
+        ) -> prometheus::Result<()>;
+    }
+
+    #[sealed]
+    impl<M, B> Bundle for Either<M, prometheus::core::MetricVec<B>>
+    where
+        M: prometheus::core::Metric + Clone,
+        B: prometheus::core::MetricVecBuilder<M = M>,
+    {
+        type Single = M;
+        type Vec = prometheus::core::MetricVec<B>;
+
+        fn get_single_metric(
+            &self,
+            key: &metrics::Key,
+        ) -> prometheus::Result<M> {
+            match self {
+                Self::Single(c) => {
+                    if key.labels().next().is_some() {
+                        return Err(
+                            prometheus::Error::InconsistentCardinality {
+                                expect: 0,
+                                got: key.labels().count(),
+                            },
+                        );
+                    }
+                    Ok(c.clone())
+                }
+                Self::Vec(v) => {
+                    let labels =
+                        key.labels().map(|l| (l.key(), l.value())).collect();
+                    v.get_metric_with(&labels)
+                }
+            }
+        }
+
+        fn remove_single_metric(
+            &self,
+            key: &metrics::Key,
+        ) -> prometheus::Result<()> {
+            match self {
+                Self::Single(_) => Err(prometheus::Error::Msg(format!(
+                    "cannot remove the single `{}` metric",
+                    key.name(),
+                ))),
+                Self::Vec(v) => {
+                    let labels =
+                        key.labels().map(|l| (l.key(), l.value())).collect();
+                    MetricVec::remove(v, &labels)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Guarding [`prometheus::IntCounter`]s against regressions.
+
+use std::sync::Arc;
+
+use crate::{
+    failure::{self, Resolve as _},
+    Metric,
+};
+
+/// Creates a [`prometheus::Error`] describing a regression of the counter
+/// identified by the provided `name`, from the `current` value to the
+/// `attempted` one.
+#[must_use]
+pub fn regression(
+    name: &str,
+    current: u64,
+    attempted: u64,
+) -> prometheus::Error {
+    prometheus::Error::Msg(format!(
+        "`{name}` counter cannot regress from {current} to {attempted}, as \
+         this breaks its monotonicity",
+    ))
+}
+
+/// Creates a [`prometheus::Error`] describing a counter identified by the
+/// provided `name` being used as a non-monotonic metric.
+#[must_use]
+pub fn misuse(name: &str) -> prometheus::Error {
+    prometheus::Error::Msg(format!(
+        "`{name}` metric is registered as a counter already, so cannot be \
+         used as a non-monotonic one",
+    ))
+}
+
+/// [`prometheus::IntCounter`] refusing to regress when its absolute value is
+/// set.
+///
+/// Any attempt to set a value lower than the current one is treated as an
+/// error, and so, either panics or is ignored, according to the
+/// [`failure::Action`] decided upon the counter registration.
+#[derive(Debug)]
+pub struct Counter {
+    /// [`prometheus::IntCounter`] being guarded.
+    metric: Arc<Metric<prometheus::IntCounter>>,
+
+    /// Name of the guarded [`prometheus::IntCounter`].
+    name: String,
+
+    /// [`failure::Action`] to perform on a regression.
+    on_regression: failure::Action,
+}
+
+impl Counter {
+    /// Guards the provided [`prometheus::IntCounter`] `metric` identified by
+    /// the provided `name`, deciding the [`failure::Action`] to be performed
+    /// on its regression with the provided [`failure::Strategy`].
+    #[must_use]
+    pub fn guard<S: failure::Strategy + ?Sized>(
+        metric: Arc<Metric<prometheus::IntCounter>>,
+        name: &str,
+        strategy: &S,
+    ) -> Self {
+        // The exact values are unknown upfront, so the `failure::Strategy` is
+        // provided with the regression from the maximum possible value.
+        let on_regression = strategy.resolve_for(
+            &metrics::Key::from_name(name.to_owned()),
+            &regression(name, u64::MAX, 0),
+        );
+        Self { metric, name: name.into(), on_regression }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::CounterFn for Counter {
+    fn increment(&self, value: u64) {
+        self.metric.increment(value);
+    }
+
+    fn absolute(&self, value: u64) {
+        let current = (*self.metric).as_ref().get();
+        if value < current {
+            match self.on_regression {
+                failure::Action::NoOp => {}
+                failure::Action::Panic => {
+                    panic!("{}", regression(&self.name, current, value))
+                }
+            }
+            return;
+        }
+        self.metric.absolute(value);
+    }
+}
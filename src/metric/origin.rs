@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Origins of metric families (when and where they were first described and
+//! registered), used for introspection only (like answering "who created this
+//! metric?" during incident reviews).
+
+use std::{
+    sync::{Arc, OnceLock},
+    time::SystemTime,
+};
+
+/// Time a metric family was first described or registered at, along with the
+/// [`metrics::Metadata`] of the caller (if known).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Origin {
+    /// [`SystemTime`] this [`Origin`] was captured at.
+    at: SystemTime,
+
+    /// Target of the caller, if known.
+    target: Option<String>,
+
+    /// Module path of the caller, if known.
+    module_path: Option<String>,
+}
+
+impl Origin {
+    /// Captures a new [`Origin`] at the current [`SystemTime`], with the
+    /// target and module path of the provided [`metrics::Metadata`] of the
+    /// caller (if known).
+    ///
+    /// [`metrics::Metadata`] is the only reliable source of the caller, as
+    /// [`metrics`] macros dispatch dynamically (so hide the source location
+    /// of the caller), and it's not provided on describing.
+    #[must_use]
+    pub fn capture(metadata: Option<&metrics::Metadata<'_>>) -> Self {
+        Self {
+            at: SystemTime::now(),
+            target: metadata.map(|m| m.target().to_owned()),
+            module_path: metadata
+                .and_then(metrics::Metadata::module_path)
+                .map(ToOwned::to_owned),
+        }
+    }
+
+    /// Returns the [`SystemTime`] this [`Origin`] was captured at.
+    #[must_use]
+    pub const fn at(&self) -> SystemTime {
+        self.at
+    }
+
+    /// Returns the target of the caller, if known.
+    #[must_use]
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Returns the module path of the caller, if known.
+    #[must_use]
+    pub fn module_path(&self) -> Option<&str> {
+        self.module_path.as_deref()
+    }
+}
+
+/// Thread-safe [`Origin`]s of a single metric family, captured once and never
+/// changed afterwards.
+#[derive(Clone, Debug, Default)]
+pub struct Origins(Arc<Inner>);
+
+/// Shared state of [`Origins`].
+#[derive(Debug, Default)]
+struct Inner {
+    /// [`Origin`] of the first description of the metric family.
+    described: OnceLock<Origin>,
+
+    /// [`Origin`] of the first registration of the metric family.
+    registered: OnceLock<Origin>,
+}
+
+impl Origins {
+    /// Returns the [`Origin`] of the first description of the metric family,
+    /// if it has been described.
+    #[must_use]
+    pub fn described(&self) -> Option<&Origin> {
+        self.0.described.get()
+    }
+
+    /// Returns the [`Origin`] of the first registration of the metric family,
+    /// if it has been registered.
+    #[must_use]
+    pub fn registered(&self) -> Option<&Origin> {
+        self.0.registered.get()
+    }
+
+    /// Sets the [`Origin`] of the first description of the metric family, if
+    /// it isn't set yet.
+    pub(crate) fn describe(&self, origin: impl FnOnce() -> Origin) {
+        _ = self.0.described.get_or_init(origin);
+    }
+
+    /// Sets the [`Origin`] of the first registration of the metric family, if
+    /// it isn't set yet.
+    pub(crate) fn register(&self, origin: impl FnOnce() -> Origin) {
+        _ = self.0.registered.get_or_init(origin);
+    }
+}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Companion `*_quantile` gauges of histograms, exposing approximate quantiles
+//! computed at gather time out of the histogram buckets.
+//!
+//! Useful for lightweight scrapers not capable of applying
+//! `histogram_quantile()` themselves.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+/// Gauge family exposing approximate quantiles of a histogram family, labeled
+/// with the `quantile`.
+///
+/// Quantiles are computed on every gathering out of the cumulative bucket
+/// counts of the histogram, linearly interpolating inside the bucket the
+/// quantile falls into (the same way Prometheus' `histogram_quantile()`
+/// does). So, they're approximations, as precise as the buckets are.
+///
+/// Children without observations are not exposed.
+#[derive(Clone)]
+pub struct Family(Arc<Inner>);
+
+/// Shared state of a [`quantile::Family`].
+///
+/// [`quantile::Family`]: Family
+struct Inner {
+    /// [`prometheus::core::Desc`] of the exposed `*_quantile` gauge family.
+    desc: prometheus::core::Desc,
+
+    /// Sorted quantiles to be exposed.
+    quantiles: Arc<[f64]>,
+
+    /// Histogram family the quantiles are computed of.
+    histogram: Arc<dyn prometheus::core::Collector>,
+}
+
+impl Family {
+    /// Creates a new [`quantile::Family`] of the provided `histogram` family
+    /// with the provided `name`, exposing the provided `quantiles` of it.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `name` is invalid, or any of the provided `quantiles`
+    /// is not within `[0, 1]` range.
+    ///
+    /// [`quantile::Family`]: Family
+    pub fn new(
+        name: &str,
+        quantiles: &[f64],
+        histogram: Arc<dyn prometheus::core::Collector>,
+    ) -> prometheus::Result<Self> {
+        if let Some(q) = quantiles.iter().find(|q| !(0.0..=1.0).contains(*q)) {
+            return Err(prometheus::Error::Msg(format!(
+                "`{name}` histogram has `{q}` quantile out of `[0, 1]` range",
+            )));
+        }
+        let mut quantiles = quantiles.to_vec();
+        quantiles.sort_by(f64::total_cmp);
+        quantiles.dedup();
+        Ok(Self(Arc::new(Inner {
+            desc: prometheus::core::Desc::new(
+                format!("{name}_quantile"),
+                format!(
+                    "Approximate quantiles of `{name}` observations, \
+                     interpolated from histogram buckets.",
+                ),
+                vec![],
+                HashMap::new(),
+            )?,
+            quantiles: quantiles.into(),
+            histogram,
+        })))
+    }
+
+    /// Returns the sorted quantiles of this [`quantile::Family`].
+    ///
+    /// [`quantile::Family`]: Family
+    #[must_use]
+    pub fn quantiles(&self) -> &[f64] {
+        &self.0.quantiles
+    }
+}
+
+impl fmt::Debug for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Family")
+            .field("desc", &self.0.desc)
+            .field("quantiles", &self.0.quantiles)
+            .finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Family {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.0.desc]
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        use prometheus::proto;
+
+        let mut family = proto::MetricFamily::default();
+        family.set_name(self.0.desc.fq_name.clone());
+        family.set_help(self.0.desc.help.clone());
+        family.set_field_type(proto::MetricType::GAUGE);
+        for histograms in self.0.histogram.collect() {
+            if histograms.get_field_type() != proto::MetricType::HISTOGRAM {
+                continue;
+            }
+            for histogram in histograms.get_metric() {
+                let h = histogram.get_histogram();
+                let buckets = h
+                    .get_bucket()
+                    .iter()
+                    .filter(|b| b.get_upper_bound().is_finite())
+                    .map(|b| (b.get_upper_bound(), b.get_cumulative_count()))
+                    .collect::<Vec<_>>();
+                for q in &*self.0.quantiles {
+                    let Some(value) =
+                        quantile(*q, &buckets, h.get_sample_count())
+                    else {
+                        continue;
+                    };
+                    let mut label = proto::LabelPair::default();
+                    label.set_name("quantile".into());
+                    label.set_value(q.to_string());
+                    let mut metric = proto::Metric::default();
+                    metric.set_label(
+                        histogram
+                            .get_label()
+                            .iter()
+                            .cloned()
+                            .chain([label])
+                            .collect(),
+                    );
+                    let mut gauge = proto::Gauge::default();
+                    gauge.set_value(value);
+                    metric.set_gauge(gauge);
+                    family.mut_metric().push(metric);
+                }
+            }
+        }
+        if family.get_metric().is_empty() {
+            return vec![];
+        }
+        vec![family]
+    }
+}
+
+/// Computes the `q` quantile out of the provided cumulative `buckets` (upper
+/// bounds along with cumulative counts, without the `+Inf` one) of a
+/// histogram having `count` observations in total.
+///
+/// Follows the semantics of Prometheus' `histogram_quantile()`: the value is
+/// linearly interpolated inside the bucket the quantile falls into, and the
+/// highest finite upper bound is returned if it falls into the `+Inf` one.
+///
+/// Returns [`None`] if there are no observations.
+#[expect( // intentional
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "counts are interpolated as `f64` anyway"
+)]
+fn quantile(q: f64, buckets: &[(f64, u64)], count: u64) -> Option<f64> {
+    if count == 0 {
+        return None;
+    }
+    let rank = q * count as f64;
+    let Some(b) = buckets.iter().position(|(_, c)| *c as f64 >= rank) else {
+        // Falls into the `+Inf` bucket.
+        return Some(buckets.last().map_or(f64::INFINITY, |(ub, _)| *ub));
+    };
+    let (end, cumulative) = buckets[b];
+    let (start, below) = match b.checked_sub(1) {
+        Some(prev) => buckets[prev],
+        // The lowest bucket is assumed to start at zero, unless its upper
+        // bound is not positive.
+        None if end <= 0.0 => return Some(end),
+        None => (0.0, 0),
+    };
+    let in_bucket = cumulative - below;
+    if in_bucket == 0 {
+        return Some(end);
+    }
+    Some((end - start).mul_add((rank - below as f64) / in_bucket as f64, start))
+}
@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Histograms merged out of pre-aggregated bucket data imported from other
+//! systems (like client-side aggregation).
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+/// Pre-aggregated snapshot of histogram observations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BucketCounts {
+    /// Buckets as `(upper_bound, cumulative_count)` pairs, sorted by their
+    /// strictly increasing upper bounds, the same way as in Prometheus
+    /// exposition.
+    ///
+    /// The implicit `+Inf` bucket is represented by the `count` field, so may
+    /// be omitted.
+    pub buckets: Vec<(f64, u64)>,
+
+    /// Sum of all the observed values.
+    pub sum: f64,
+
+    /// Number of all the observed values.
+    pub count: u64,
+}
+
+impl BucketCounts {
+    /// Validates this [`BucketCounts`] and strips the explicit `+Inf` bucket
+    /// out of it (if any).
+    ///
+    /// # Errors
+    ///
+    /// If the upper bounds aren't strictly increasing, or the cumulative
+    /// counts are decreasing or exceed the `count`.
+    fn normalized(&self) -> prometheus::Result<Self> {
+        let invalid = |reason: &str| {
+            prometheus::Error::Msg(format!("invalid bucket counts: {reason}"))
+        };
+
+        let mut buckets = self.buckets.clone();
+        if buckets.last().is_some_and(|(le, _)| *le == f64::INFINITY) {
+            _ = buckets.pop();
+        }
+        let mut prev: Option<(f64, u64)> = None;
+        for &(le, cnt) in &buckets {
+            if le.is_nan() {
+                return Err(invalid("upper bound cannot be NaN"));
+            }
+            if let Some((prev_le, prev_cnt)) = prev {
+                if le <= prev_le {
+                    return Err(invalid("upper bounds must strictly increase"));
+                }
+                if cnt < prev_cnt {
+                    return Err(invalid("cumulative counts cannot decrease"));
+                }
+            }
+            prev = Some((le, cnt));
+        }
+        if prev.is_some_and(|(_, cnt)| cnt > self.count) {
+            return Err(invalid("cumulative counts cannot exceed the count"));
+        }
+        Ok(Self { buckets, sum: self.sum, count: self.count })
+    }
+
+    /// Merges the provided normalized [`BucketCounts`] snapshot into this
+    /// one.
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`BucketCounts`] has different upper bounds.
+    fn merge(&mut self, other: &Self) -> prometheus::Result<()> {
+        if self.buckets.len() != other.buckets.len()
+            || self
+                .buckets
+                .iter()
+                .zip(&other.buckets)
+                .any(|((a, _), (b, _))| a.total_cmp(b).is_ne())
+        {
+            return Err(prometheus::Error::Msg(
+                "cannot merge bucket counts with different upper bounds".into(),
+            ));
+        }
+        for ((_, cnt), (_, other_cnt)) in
+            self.buckets.iter_mut().zip(&other.buckets)
+        {
+            *cnt = cnt.saturating_add(*other_cnt);
+        }
+        self.sum += other.sum;
+        self.count = self.count.saturating_add(other.count);
+        Ok(())
+    }
+}
+
+/// Family of histograms merged out of pre-aggregated [`BucketCounts`]
+/// snapshots, identified by their label values.
+#[derive(Clone, Debug)]
+pub struct Histogram(Arc<Family>);
+
+/// Shared state of a [`raw::Histogram`].
+///
+/// [`raw::Histogram`]: Histogram
+#[derive(Debug)]
+struct Family {
+    /// [`prometheus::core::Desc`] of this [`Family`].
+    desc: prometheus::core::Desc,
+
+    /// Merged [`BucketCounts`] of this [`Family`], identified by their label
+    /// values.
+    children: RwLock<BTreeMap<Vec<String>, BucketCounts>>,
+}
+
+impl Histogram {
+    /// Creates a new [`raw::Histogram`] family with the provided `name` and
+    /// variable `labels` names.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `name` or `labels` are invalid.
+    ///
+    /// [`raw::Histogram`]: Histogram
+    pub fn new(name: &str, labels: Vec<String>) -> prometheus::Result<Self> {
+        // We use `name` as `help` description here, because `prometheus`
+        // crate doesn't allow to make it empty.
+        let desc = prometheus::core::Desc::new(
+            name.into(),
+            name.into(),
+            labels,
+            HashMap::new(),
+        )?;
+        Ok(Self(Arc::new(Family { desc, children: RwLock::default() })))
+    }
+
+    /// Returns names of the variable labels of this [`raw::Histogram`] family.
+    ///
+    /// [`raw::Histogram`]: Histogram
+    #[must_use]
+    pub fn labels(&self) -> &[String] {
+        &self.0.desc.variable_labels
+    }
+
+    /// Merges the provided [`BucketCounts`] snapshot into the histogram
+    /// identified by the provided label `values`.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided label `values` don't match the labels of this
+    ///   [`raw::Histogram`] family.
+    /// - If the provided [`BucketCounts`] are invalid, or have upper bounds
+    ///   different from the previously merged ones.
+    ///
+    /// [`raw::Histogram`]: Histogram
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn merge(
+        &self,
+        values: Vec<String>,
+        counts: &BucketCounts,
+    ) -> prometheus::Result<()> {
+        if values.len() != self.0.desc.variable_labels.len() {
+            return Err(prometheus::Error::InconsistentCardinality {
+                expect: self.0.desc.variable_labels.len(),
+                got: values.len(),
+            });
+        }
+        let counts = counts.normalized()?;
+
+        let mut children = self.0.children.write().unwrap();
+        if let Some(merged) = children.get_mut(&values) {
+            merged.merge(&counts)?;
+        } else {
+            drop(children.insert(values, counts));
+        }
+        drop(children);
+        Ok(())
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Histogram {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.0.desc]
+    }
+
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        let desc = &self.0.desc;
+        let children = self.0.children.read().unwrap();
+        if children.is_empty() {
+            return vec![];
+        }
+
+        let mut family = prometheus::proto::MetricFamily::default();
+        family.set_name(desc.fq_name.clone());
+        family.set_help(desc.help.clone());
+        family.set_field_type(prometheus::proto::MetricType::HISTOGRAM);
+        family.set_metric(
+            children
+                .iter()
+                .map(|(values, counts)| {
+                    let mut histogram = prometheus::proto::Histogram::default();
+                    histogram.set_sample_count(counts.count);
+                    histogram.set_sample_sum(counts.sum);
+                    histogram.set_bucket(
+                        counts
+                            .buckets
+                            .iter()
+                            .map(|&(le, cnt)| {
+                                let mut bucket =
+                                    prometheus::proto::Bucket::default();
+                                bucket.set_upper_bound(le);
+                                bucket.set_cumulative_count(cnt);
+                                bucket
+                            })
+                            .collect(),
+                    );
+
+                    let mut metric = prometheus::proto::Metric::default();
+                    metric.set_label(
+                        desc.variable_labels
+                            .iter()
+                            .zip(values)
+                            .map(|(name, value)| {
+                                let mut label =
+                                    prometheus::proto::LabelPair::default();
+                                label.set_name(name.clone());
+                                label.set_value(value.clone());
+                                label
+                            })
+                            .collect(),
+                    );
+                    metric.set_histogram(histogram);
+                    metric
+                })
+                .collect(),
+        );
+        drop(children);
+        vec![family]
+    }
+}
@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Gauges accumulating their increments and decrements in multiple shards,
+//! and summing them into the underlying [`metrics::Gauge`] at gather.
+//!
+//! Reduces the contention on a single atomic of extremely hot gauges (like
+//! in-flight requests changed by hundreds of tasks), as the threads change
+//! different shards in the most cases.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError, RwLock,
+    },
+};
+
+/// Counter of the threads changed any [`striped::Gauge`], used to spread them
+/// over the shards.
+///
+/// [`striped::Gauge`]: Gauge
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Index of the current thread, spreading it over the shards.
+    static THREAD: usize = THREADS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Family of [`striped::Gauge`]s sharing the same name, identified by their
+/// [`metrics::Key`]s.
+///
+/// [`striped::Gauge`]: Gauge
+#[derive(Clone, Debug)]
+pub struct Family(Arc<Inner>);
+
+/// Shared state of a [`striped::Family`].
+///
+/// [`striped::Family`]: Family
+#[derive(Debug)]
+struct Inner {
+    /// Number of shards of every [`striped::Gauge`] of this family.
+    ///
+    /// [`striped::Gauge`]: Gauge
+    shards: usize,
+
+    /// [`striped::Gauge`]s of this family, identified by their
+    /// [`metrics::Key`]s.
+    ///
+    /// [`striped::Gauge`]: Gauge
+    children: RwLock<HashMap<metrics::Key, Gauge>>,
+}
+
+impl Family {
+    /// Creates a new empty [`striped::Family`], with its gauges having the
+    /// provided number of `shards` (at least `1`).
+    ///
+    /// [`striped::Family`]: Family
+    #[must_use]
+    pub fn new(shards: usize) -> Self {
+        Self(Arc::new(Inner {
+            shards: shards.max(1),
+            children: RwLock::default(),
+        }))
+    }
+
+    /// Returns the [`striped::Gauge`] identified by the provided
+    /// [`metrics::Key`], creating it over the provided [`metrics::Gauge`] if
+    /// it doesn't exist yet.
+    ///
+    /// [`striped::Gauge`]: Gauge
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn child(&self, key: &metrics::Key, gauge: metrics::Gauge) -> Gauge {
+        if let Some(child) = self.0.children.read().unwrap().get(key) {
+            return child.clone();
+        }
+        self.0
+            .children
+            .write()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| Gauge::new(gauge, self.0.shards))
+            .clone()
+    }
+
+    /// Flushes and forgets the [`striped::Gauge`] identified by the provided
+    /// [`metrics::Key`], if any.
+    ///
+    /// [`striped::Gauge`]: Gauge
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn forget(&self, key: &metrics::Key) {
+        let forgotten = self.0.children.write().unwrap().remove(key);
+        if let Some(gauge) = forgotten {
+            gauge.flush();
+        }
+    }
+
+    /// Flushes the shards of all the [`striped::Gauge`]s of this
+    /// [`striped::Family`].
+    ///
+    /// [`striped::Family`]: Family
+    /// [`striped::Gauge`]: Gauge
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[expect( // intentional
+        clippy::iter_over_hash_type,
+        reason = "order of flushing doesn't matter"
+    )]
+    pub fn flush(&self) {
+        for gauge in self.0.children.read().unwrap().values() {
+            gauge.flush();
+        }
+    }
+}
+
+/// [`metrics::Gauge`] accumulating its increments and decrements in multiple
+/// shards.
+///
+/// The shards are summed into the underlying [`metrics::Gauge`] explicitly
+/// via the [`striped::Gauge::flush()`] method, while setting the value
+/// discards them.
+///
+/// [`striped::Gauge`]: Gauge
+/// [`striped::Gauge::flush()`]: Gauge::flush
+#[derive(Clone)]
+pub struct Gauge(Arc<Shared>);
+
+/// Shared state of a [`striped::Gauge`].
+///
+/// [`striped::Gauge`]: Gauge
+struct Shared {
+    /// Underlying [`metrics::Gauge`] the shards are summed into.
+    gauge: metrics::Gauge,
+
+    /// Shards holding the not flushed yet delta of the value.
+    shards: Box<[Shard]>,
+
+    /// Lock serializing the cold [`striped::Gauge::flush()`] and
+    /// [`metrics::GaugeFn::set()`] operations, so a delta taken out of the
+    /// shards before setting the value is never applied after it.
+    ///
+    /// [`striped::Gauge::flush()`]: Gauge::flush
+    cold: Mutex<()>,
+}
+
+/// Single shard of a [`striped::Gauge`], holding bits of an [`f64`] delta.
+///
+/// Aligned to a cache line, so the shards don't contend with each other.
+///
+/// [`striped::Gauge`]: Gauge
+#[derive(Debug, Default)]
+#[repr(align(128))]
+struct Shard(AtomicU64);
+
+impl Shard {
+    /// Adds the provided `delta` to this [`Shard`].
+    fn add(&self, delta: f64) {
+        // `Err` is impossible here, as the closure always returns `Some`.
+        _ = self.0.fetch_update(Ordering::AcqRel, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + delta).to_bits())
+        });
+    }
+
+    /// Takes the delta out of this [`Shard`], resetting it to zero.
+    fn take(&self) -> f64 {
+        f64::from_bits(self.0.swap(0.0_f64.to_bits(), Ordering::AcqRel))
+    }
+}
+
+impl Gauge {
+    /// Creates a new [`striped::Gauge`] summing its shards into the provided
+    /// [`metrics::Gauge`], with the provided number of `shards` (at least
+    /// `1`).
+    ///
+    /// [`striped::Gauge`]: Gauge
+    #[must_use]
+    pub fn new(gauge: metrics::Gauge, shards: usize) -> Self {
+        Self(Arc::new(Shared {
+            gauge,
+            shards: (0..shards.max(1)).map(|_| Shard::default()).collect(),
+            cold: Mutex::new(()),
+        }))
+    }
+
+    /// Sums the shards into the underlying [`metrics::Gauge`], resetting
+    /// them.
+    pub fn flush(&self) {
+        let _cold = self.0.cold.lock().unwrap_or_else(PoisonError::into_inner);
+        let delta = self.0.shards.iter().map(Shard::take).sum::<f64>();
+        if delta != 0.0 {
+            self.0.gauge.increment(delta);
+        }
+    }
+
+    /// Returns the [`Shard`] of the current thread.
+    fn shard(&self) -> &Shard {
+        let shards = &self.0.shards;
+        // Thread-local storage being destroyed already falls back to the
+        // first shard.
+        let index = THREAD.try_with(|i| *i).unwrap_or_default();
+        &shards[index % shards.len()]
+    }
+}
+
+// Manual implementation is required to omit printing all the shards.
+impl fmt::Debug for Gauge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gauge")
+            .field("gauge", &self.0.gauge)
+            .field("shards", &self.0.shards.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::GaugeFn for Gauge {
+    fn increment(&self, value: f64) {
+        self.shard().add(value);
+    }
+
+    fn decrement(&self, value: f64) {
+        self.shard().add(-value);
+    }
+
+    fn set(&self, value: f64) {
+        let _cold = self.0.cold.lock().unwrap_or_else(PoisonError::into_inner);
+        for shard in &*self.0.shards {
+            _ = shard.take();
+        }
+        self.0.gauge.set(value);
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Companion `*_over_threshold_total` counters of histograms, counting the
+//! observations exceeding the configured thresholds (like SLO latency
+//! targets).
+//!
+//! Enables simple SLO burn alerts without applying `histogram_quantile()` to
+//! huge sets of buckets.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use super::annotation::{self, Labels};
+
+/// Counter family exposing the numbers of observations of a histogram family
+/// exceeding the configured thresholds, labeled with `le` of the threshold.
+///
+/// Children are exposed right after being registered, so the counters exist
+/// before the first observation exceeding a threshold.
+#[derive(Clone)]
+pub struct Family(Arc<Inner>);
+
+/// Shared state of a [`threshold::Family`].
+///
+/// [`threshold::Family`]: Family
+struct Inner {
+    /// [`prometheus::core::Desc`] of the exposed `*_over_threshold_total`
+    /// counter family.
+    desc: prometheus::core::Desc,
+
+    /// Sorted thresholds the observations are compared against.
+    thresholds: Arc<[f64]>,
+
+    /// [`Child`]ren of this family, identified by their [`Labels`].
+    children: RwLock<BTreeMap<Labels, Arc<Child>>>,
+}
+
+impl Family {
+    /// Creates a new [`threshold::Family`] of the histogram family with the
+    /// provided `name`, counting the observations exceeding the provided
+    /// `thresholds`.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `name` is invalid, or any of the provided `thresholds`
+    /// is [`f64::NAN`].
+    ///
+    /// [`threshold::Family`]: Family
+    pub fn new(name: &str, thresholds: &[f64]) -> prometheus::Result<Self> {
+        if thresholds.iter().any(|t| t.is_nan()) {
+            return Err(prometheus::Error::Msg(format!(
+                "`{name}` histogram has `NaN` threshold",
+            )));
+        }
+        let mut thresholds = thresholds.to_vec();
+        thresholds.sort_by(f64::total_cmp);
+        thresholds.dedup();
+        Ok(Self(Arc::new(Inner {
+            desc: prometheus::core::Desc::new(
+                format!("{name}_over_threshold_total"),
+                format!("Number of `{name}` observations over threshold."),
+                vec![],
+                HashMap::new(),
+            )?,
+            thresholds: thresholds.into(),
+            children: RwLock::default(),
+        })))
+    }
+
+    /// Returns the sorted thresholds of this [`threshold::Family`].
+    ///
+    /// [`threshold::Family`]: Family
+    #[must_use]
+    pub fn thresholds(&self) -> &[f64] {
+        &self.0.thresholds
+    }
+
+    /// Returns the [`Child`] identified by the labels of the provided
+    /// [`metrics::Key`], creating it if it doesn't exist yet.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn child(&self, key: &metrics::Key) -> Arc<Child> {
+        let labels = annotation::labels(key);
+        if let Some(child) = self.0.children.read().unwrap().get(&labels) {
+            return Arc::clone(child);
+        }
+        Arc::clone(
+            self.0
+                .children
+                .write()
+                .unwrap()
+                .entry(labels)
+                .or_insert_with(|| Arc::new(Child::new(&self.0.thresholds))),
+        )
+    }
+}
+
+impl fmt::Debug for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Family")
+            .field("desc", &self.0.desc)
+            .field("thresholds", &self.0.thresholds)
+            .finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Family {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.0.desc]
+    }
+
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        use prometheus::proto;
+
+        let label = |name: &str, value: String| {
+            let mut label = proto::LabelPair::default();
+            label.set_name(name.to_owned());
+            label.set_value(value);
+            label
+        };
+
+        let mut family = proto::MetricFamily::default();
+        family.set_name(self.0.desc.fq_name.clone());
+        family.set_help(self.0.desc.help.clone());
+        family.set_field_type(proto::MetricType::COUNTER);
+        for (labels, child) in &*self.0.children.read().unwrap() {
+            for (threshold, count) in
+                self.0.thresholds.iter().zip(&child.counts)
+            {
+                let mut metric = proto::Metric::default();
+                metric.set_label(
+                    labels
+                        .iter()
+                        .map(|(n, v)| label(n, v.clone()))
+                        .chain([label("le", threshold.to_string())])
+                        .collect(),
+                );
+                let mut counter = proto::Counter::default();
+                #[expect( // intentional
+                    clippy::as_conversions,
+                    clippy::cast_precision_loss,
+                    reason = "counters are exposed as `f64` anyway"
+                )]
+                counter.set_value(count.load(Ordering::Relaxed) as f64);
+                metric.set_counter(counter);
+                family.mut_metric().push(metric);
+            }
+        }
+        if family.get_metric().is_empty() {
+            return vec![];
+        }
+        vec![family]
+    }
+}
+
+/// Numbers of observations of a single histogram of a [`threshold::Family`]
+/// exceeding its thresholds.
+///
+/// [`threshold::Family`]: Family
+#[derive(Debug)]
+pub struct Child {
+    /// Sorted thresholds of the [`threshold::Family`].
+    ///
+    /// [`threshold::Family`]: Family
+    thresholds: Arc<[f64]>,
+
+    /// Numbers of observations exceeding each of the thresholds.
+    counts: Box<[AtomicU64]>,
+}
+
+impl Child {
+    /// Creates a new [`Child`] counting the observations exceeding the
+    /// provided sorted `thresholds`.
+    fn new(thresholds: &Arc<[f64]>) -> Self {
+        Self {
+            thresholds: Arc::clone(thresholds),
+            counts: thresholds.iter().map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Counts the provided `value` observed `count` times against the
+    /// thresholds. [`f64::NAN`] is ignored.
+    pub fn observe(&self, value: f64, count: u64) {
+        // Thresholds are sorted, so the exceeded ones form a prefix.
+        let exceeded = self.thresholds.partition_point(|t| value > *t);
+        for counter in self.counts.iter().take(exceeded) {
+            _ = counter.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+}
+
+/// [`metrics::Histogram`] counting its observations exceeding the thresholds
+/// in a [`Child`] of a [`threshold::Family`].
+///
+/// [`threshold::Family`]: Family
+#[derive(Debug)]
+pub struct Histogram {
+    /// Tracked [`metrics::Histogram`].
+    histogram: metrics::Histogram,
+
+    /// [`Child`] counting the observations exceeding the thresholds.
+    child: Arc<Child>,
+}
+
+impl Histogram {
+    /// Wraps the provided [`metrics::Histogram`] to count its observations
+    /// exceeding the thresholds in the provided [`Child`].
+    #[must_use]
+    pub const fn new(histogram: metrics::Histogram, child: Arc<Child>) -> Self {
+        Self { histogram, child }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::HistogramFn for Histogram {
+    fn record(&self, value: f64) {
+        self.histogram.record(value);
+        self.child.observe(value, 1);
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        self.histogram.record_many(value, count);
+        self.child.observe(value, u64::try_from(count).unwrap_or(u64::MAX));
+    }
+}
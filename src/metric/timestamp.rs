@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracking of the last update time of [`prometheus`] metrics.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::clock::{self, Clock as _};
+
+/// Last update time of a single [`prometheus`] metric.
+#[derive(Debug)]
+pub struct LastUpdated {
+    /// [`Clock`] providing the current time.
+    ///
+    /// [`Clock`]: clock::Clock
+    clock: clock::Shared,
+
+    /// [`Instant`] the [`LastUpdated::elapsed`] nanoseconds are counted from.
+    base: Instant,
+
+    /// Nanoseconds elapsed since the [`LastUpdated::base`] till the last
+    /// update, increased by one.
+    ///
+    /// Zero means that the metric has never been updated.
+    elapsed: AtomicU64,
+}
+
+impl LastUpdated {
+    /// Creates a new [`LastUpdated`] of a never updated metric.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(clock::System))
+    }
+
+    /// Creates a new [`LastUpdated`] of a never updated metric, using the
+    /// provided [`Clock`] as the time source.
+    ///
+    /// [`Clock`]: clock::Clock
+    #[must_use]
+    pub fn with_clock(clock: clock::Shared) -> Self {
+        let base = clock.now();
+        Self { clock, base, elapsed: AtomicU64::new(0) }
+    }
+
+    /// Marks the metric as updated just now.
+    pub fn touch(&self) {
+        let since = self.clock.now().saturating_duration_since(self.base);
+        let elapsed = u64::try_from(since.as_nanos())
+            .unwrap_or(u64::MAX)
+            .saturating_add(1);
+        self.elapsed.store(elapsed, Ordering::Relaxed);
+    }
+
+    /// Returns the [`Instant`] the metric was updated last time, if ever.
+    #[must_use]
+    pub fn get(&self) -> Option<Instant> {
+        match self.elapsed.load(Ordering::Relaxed) {
+            0 => None,
+            n => Some(self.base + Duration::from_nanos(n - 1)),
+        }
+    }
+}
+
+impl Default for LastUpdated {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thread-safe collection of [`LastUpdated`] times, identified by a metric
+/// name and its labels (regardless of their order).
+#[derive(Clone, Debug)]
+pub struct Timestamps {
+    /// [`LastUpdated`] times of the metrics.
+    entries: Arc<RwLock<HashMap<metrics::Key, Arc<LastUpdated>>>>,
+
+    /// [`Clock`] the newly created [`LastUpdated`] times use.
+    ///
+    /// [`Clock`]: clock::Clock
+    clock: clock::Shared,
+}
+
+impl Default for Timestamps {
+    fn default() -> Self {
+        Self { entries: Arc::default(), clock: Arc::new(clock::System) }
+    }
+}
+
+impl Timestamps {
+    /// Makes the [`LastUpdated`] times created by these [`Timestamps`] (and
+    /// not their clones made before) to use the provided [`Clock`] as the
+    /// time source.
+    ///
+    /// The already tracked [`LastUpdated`] times are still shared with the
+    /// clones.
+    ///
+    /// [`Clock`]: clock::Clock
+    #[must_use]
+    pub fn with_clock(mut self, clock: clock::Shared) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the [`LastUpdated`] time of the metric identified by the
+    /// provided [`metrics::Key`], creating it if it doesn't exist yet.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn get_or_create(&self, key: &metrics::Key) -> Arc<LastUpdated> {
+        let key = normalize(key.name(), key.labels().cloned());
+        if let Some(ts) = self.entries.read().unwrap().get(&key) {
+            return Arc::clone(ts);
+        }
+        Arc::clone(self.entries.write().unwrap().entry(key).or_insert_with(
+            || Arc::new(LastUpdated::with_clock(Arc::clone(&self.clock))),
+        ))
+    }
+
+    /// Returns the [`Instant`] the metric identified by the provided `name`
+    /// and `labels` was updated last time.
+    ///
+    /// [`None`] is returned if the metric has never been updated.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn get(&self, name: &str, labels: &[(&str, &str)]) -> Option<Instant> {
+        let key = normalize(
+            name,
+            labels.iter().map(|(k, v)| {
+                metrics::Label::new((*k).to_owned(), (*v).to_owned())
+            }),
+        );
+        self.entries.read().unwrap().get(&key).and_then(|ts| ts.get())
+    }
+
+    /// Returns the [`Instant`] the metric identified by the provided
+    /// [`metrics::Key`] was updated last time.
+    ///
+    /// [`None`] is returned if the metric has never been updated.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn last_updated(&self, key: &metrics::Key) -> Option<Instant> {
+        let key = normalize(key.name(), key.labels().cloned());
+        self.entries.read().unwrap().get(&key).and_then(|ts| ts.get())
+    }
+}
+
+/// Creates a [`metrics::Key`] out of the provided `name` and `labels` sorted by
+/// their names.
+fn normalize(
+    name: &str,
+    labels: impl Iterator<Item = metrics::Label>,
+) -> metrics::Key {
+    let mut labels = labels.collect::<Vec<_>>();
+    labels.sort_unstable_by(|a, b| a.key().cmp(b.key()));
+    metrics::Key::from_parts(name.to_owned(), labels)
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Gauges derived from counters, exposing their increase over a sliding time
+//! window (like "requests in the last 5 minutes").
+//!
+//! Useful in environments where Prometheus recording rules are not available.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::clock::{self, Clock as _};
+
+/// Values of a counter family, identified by their label pairs.
+type Values = BTreeMap<Vec<(String, String)>, f64>;
+
+/// Source of the counter [`prometheus::proto::MetricFamily`]s a
+/// [`windowed::Rate`] is derived from.
+///
+/// Returns [`None`] if the counters cannot be read at the moment (so no
+/// snapshot is taken).
+///
+/// [`windowed::Rate`]: Rate
+pub type Source =
+    Box<dyn Fn() -> Option<Vec<prometheus::proto::MetricFamily>> + Send + Sync>;
+
+/// Gauge family exposing the increase of a source counter family over a
+/// sliding time window.
+///
+/// Keeps a ring buffer of the source counter snapshots, taken on each
+/// collection (and once on creation), so the window slides with scrapes. The
+/// increase is calculated against the latest snapshot taken at or before the
+/// window start (or the oldest one, if there is none yet). A counter reset is
+/// treated as an increase from zero.
+#[derive(Clone)]
+pub struct Rate(Arc<Inner>);
+
+/// Shared state of a [`windowed::Rate`].
+///
+/// [`windowed::Rate`]: Rate
+struct Inner {
+    /// [`prometheus::core::Desc`] of the exposed gauge family.
+    desc: prometheus::core::Desc,
+
+    /// Duration of the sliding time window.
+    window: Duration,
+
+    /// [`Clock`] the snapshots are timed with.
+    ///
+    /// [`Clock`]: clock::Clock
+    clock: clock::Shared,
+
+    /// [`Source`] of the counter values.
+    source: Source,
+
+    /// Ring buffer of the taken snapshots of the counter values.
+    snapshots: Mutex<VecDeque<(Instant, Values)>>,
+}
+
+impl Rate {
+    /// Creates a new [`windowed::Rate`] gauge family with the provided `name`
+    /// and `help` description, exposing the increase of the counters provided
+    /// by the `source` over the provided `window`, timed with the provided
+    /// [`Clock`].
+    ///
+    /// # Errors
+    ///
+    /// If the provided `name` or `help` are invalid.
+    ///
+    /// [`Clock`]: clock::Clock
+    /// [`windowed::Rate`]: Rate
+    pub fn new(
+        name: &str,
+        help: String,
+        window: Duration,
+        source: Source,
+        clock: clock::Shared,
+    ) -> prometheus::Result<Self> {
+        let desc = prometheus::core::Desc::new(
+            name.into(),
+            help,
+            vec![],
+            HashMap::new(),
+        )?;
+        let snapshot =
+            (clock.now(), source().map(|f| values(&f)).unwrap_or_default());
+        Ok(Self(Arc::new(Inner {
+            desc,
+            window,
+            clock,
+            source,
+            snapshots: Mutex::new(VecDeque::from([snapshot])),
+        })))
+    }
+
+    /// Returns the duration of the sliding time window of this
+    /// [`windowed::Rate`].
+    ///
+    /// [`windowed::Rate`]: Rate
+    #[must_use]
+    pub fn window(&self) -> Duration {
+        self.0.window
+    }
+}
+
+impl fmt::Debug for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rate")
+            .field("desc", &self.0.desc)
+            .field("window", &self.0.window)
+            .finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Rate {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.0.desc]
+    }
+
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`Mutex` usage is fully panic-safe here"
+    )]
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        use prometheus::proto;
+
+        let now = self.0.clock.now();
+        let Some(current) = (self.0.source)().map(|f| values(&f)) else {
+            return vec![];
+        };
+
+        let mut snapshots = self.0.snapshots.lock().unwrap();
+        snapshots.push_back((now, current.clone()));
+        if let Some(start) = now.checked_sub(self.0.window) {
+            while snapshots.get(1).is_some_and(|(at, _)| *at <= start) {
+                drop(snapshots.pop_front());
+            }
+        }
+        let baseline = snapshots.front().map(|(_, v)| v.clone());
+        drop(snapshots);
+        let baseline = baseline.unwrap_or_default();
+
+        if current.is_empty() {
+            return vec![];
+        }
+        let desc = &self.0.desc;
+        let mut family = proto::MetricFamily::default();
+        family.set_name(desc.fq_name.clone());
+        family.set_help(desc.help.clone());
+        family.set_field_type(proto::MetricType::GAUGE);
+        for (labels, total) in current {
+            let prev = baseline.get(&labels).copied().unwrap_or_default();
+            let increase = if total < prev { total } else { total - prev };
+
+            let mut metric = proto::Metric::default();
+            metric.set_label(
+                labels
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let mut label = proto::LabelPair::default();
+                        label.set_name(name);
+                        label.set_value(value);
+                        label
+                    })
+                    .collect(),
+            );
+            let mut gauge = proto::Gauge::default();
+            gauge.set_value(increase);
+            metric.set_gauge(gauge);
+            family.mut_metric().push(metric);
+        }
+        vec![family]
+    }
+}
+
+/// Extracts [`Values`] of the counters out of the provided
+/// [`prometheus::proto::MetricFamily`]s.
+fn values(families: &[prometheus::proto::MetricFamily]) -> Values {
+    families
+        .iter()
+        .flat_map(prometheus::proto::MetricFamily::get_metric)
+        .map(|m| {
+            let labels = m
+                .get_label()
+                .iter()
+                .map(|l| (l.get_name().to_owned(), l.get_value().to_owned()))
+                .collect();
+            (labels, m.get_counter().get_value())
+        })
+        .collect()
+}
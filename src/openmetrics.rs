@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Encoding of [`prometheus`] metrics in the [OpenMetrics] text format,
+//! emitting the [`metrics::Unit`]s of their families as `# UNIT` lines.
+//!
+//! [OpenMetrics]: https://openmetrics.io
+
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use prometheus::proto;
+
+/// [Content type] of the [OpenMetrics] text format.
+///
+/// [Content type]: https://en.wikipedia.org/wiki/Media_type
+/// [OpenMetrics]: https://openmetrics.io
+pub const CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Returns the [OpenMetrics] unit of the provided [`metrics::Unit`], being a
+/// required suffix of the names of its metric families.
+///
+/// Returns [`None`] for the dimensionless [`metrics::Unit::Count`].
+///
+/// [OpenMetrics]: https://openmetrics.io
+#[must_use]
+pub fn unit(unit: metrics::Unit) -> Option<&'static str> {
+    (unit != metrics::Unit::Count).then(|| unit.as_str())
+}
+
+/// Checks whether the provided metric `name` ends with a unit suffix
+/// disagreeing with the provided declared [`metrics::Unit`].
+///
+/// # Errors
+///
+/// If the provided metric `name` has a suffix of another [`metrics::Unit`].
+pub fn check_suffix(
+    name: &str,
+    declared: metrics::Unit,
+) -> prometheus::Result<()> {
+    let base = name.strip_suffix("_total").unwrap_or(name);
+    let declared = unit(declared);
+    if declared.is_some_and(|u| has_suffix(base, u)) {
+        return Ok(());
+    }
+    let conflicting = UNITS
+        .iter()
+        .filter_map(|u| unit(*u))
+        .filter(|u| Some(*u) != declared)
+        .find(|u| has_suffix(base, u));
+    conflicting.map_or(Ok(()), |suffix| {
+        Err(prometheus::Error::Msg(format!(
+            "`{name}` metric has `{suffix}` unit suffix, but `{}` unit is \
+             declared",
+            declared.unwrap_or("count"),
+        )))
+    })
+}
+
+/// Returns the [`metrics::Unit`] the provided metric `name` has the suffix
+/// of, if any.
+#[must_use]
+pub(crate) fn suffix_unit(name: &str) -> Option<metrics::Unit> {
+    let base = name.strip_suffix("_total").unwrap_or(name);
+    UNITS.into_iter().find(|u| unit(*u).is_some_and(|s| has_suffix(base, s)))
+}
+
+/// All the existing [`metrics::Unit`]s.
+const UNITS: [metrics::Unit; 17] = [
+    metrics::Unit::Count,
+    metrics::Unit::Percent,
+    metrics::Unit::Seconds,
+    metrics::Unit::Milliseconds,
+    metrics::Unit::Microseconds,
+    metrics::Unit::Nanoseconds,
+    metrics::Unit::Tebibytes,
+    metrics::Unit::Gibibytes,
+    metrics::Unit::Mebibytes,
+    metrics::Unit::Kibibytes,
+    metrics::Unit::Bytes,
+    metrics::Unit::TerabitsPerSecond,
+    metrics::Unit::GigabitsPerSecond,
+    metrics::Unit::MegabitsPerSecond,
+    metrics::Unit::KilobitsPerSecond,
+    metrics::Unit::BitsPerSecond,
+    metrics::Unit::CountPerSecond,
+];
+
+/// Checks whether the provided metric `name` ends with the provided `unit`
+/// suffix (separated with `_`).
+fn has_suffix(name: &str, unit: &str) -> bool {
+    name.strip_suffix(unit).is_some_and(|n| n.ends_with('_'))
+}
+
+/// Encodes the provided [`proto::MetricFamily`]s in the [OpenMetrics] text
+/// format, emitting `# UNIT` lines for the families having their
+/// [`metrics::Unit`] provided in the `units` (by the family name).
+///
+/// Family names lacking the required unit suffix get it appended.
+///
+/// [OpenMetrics]: https://openmetrics.io
+#[must_use]
+pub fn encode(
+    families: &[proto::MetricFamily],
+    units: &BTreeMap<String, metrics::Unit>,
+) -> String {
+    let mut out = String::new();
+    for family in families {
+        let name = family.get_name();
+        let kind = family.get_field_type();
+        let (mut base, type_name) = match kind {
+            proto::MetricType::COUNTER => {
+                (name.strip_suffix("_total").unwrap_or(name), "counter")
+            }
+            proto::MetricType::GAUGE => (name, "gauge"),
+            proto::MetricType::SUMMARY => (name, "summary"),
+            proto::MetricType::UNTYPED => (name, "unknown"),
+            proto::MetricType::HISTOGRAM => (name, "histogram"),
+        };
+        let declared = units.get(name).copied().and_then(unit);
+        let suffixed;
+        if let Some(u) = declared {
+            if !has_suffix(base, u) {
+                suffixed = format!("{base}_{u}");
+                base = &suffixed;
+            }
+        }
+
+        _ = writeln!(out, "# TYPE {base} {type_name}");
+        if let Some(u) = declared {
+            _ = writeln!(out, "# UNIT {base} {u}");
+        }
+        _ = writeln!(out, "# HELP {base} {}", escape(family.get_help()));
+        for metric in family.get_metric() {
+            samples(&mut out, base, kind, metric);
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Writes the sample lines of the provided [`proto::Metric`] of the provided
+/// [`proto::MetricType`] into the provided `out`put.
+fn samples(
+    out: &mut String,
+    base: &str,
+    kind: proto::MetricType,
+    metric: &proto::Metric,
+) {
+    let labels = metric
+        .get_label()
+        .iter()
+        .map(|l| (l.get_name(), escape(l.get_value())))
+        .collect::<Vec<_>>();
+    let timestamp = (metric.get_timestamp_ms() != 0).then(|| {
+        #[expect( // intentional
+            clippy::as_conversions,
+            clippy::cast_precision_loss,
+            reason = "precision loss is acceptable for timestamps"
+        )]
+        let ms = metric.get_timestamp_ms() as f64;
+        ms / 1000.0
+    });
+    let mut emit = |name: &str, extra: Option<(&str, String)>, v: f64| {
+        out.push_str(base);
+        out.push_str(name);
+        let mut pairs = labels
+            .iter()
+            .map(|(n, l)| (*n, l.as_str()))
+            .chain(extra.as_ref().map(|(n, l)| (*n, l.as_str())))
+            .peekable();
+        if pairs.peek().is_some() {
+            out.push('{');
+            for (i, (n, l)) in pairs.enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                _ = write!(out, "{n}=\"{l}\"");
+            }
+            out.push('}');
+        }
+        _ = write!(out, " {}", number(v));
+        if let Some(ts) = timestamp {
+            _ = write!(out, " {}", number(ts));
+        }
+        out.push('\n');
+    };
+    #[expect( // intentional
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        reason = "no better conversion for sample counts"
+    )]
+    let count = |c: u64| c as f64;
+
+    match kind {
+        proto::MetricType::COUNTER => {
+            emit("_total", None, metric.get_counter().get_value());
+        }
+        proto::MetricType::GAUGE => {
+            emit("", None, metric.get_gauge().get_value());
+        }
+        proto::MetricType::UNTYPED => {
+            // Deprecated only without `prometheus/protobuf` feature, which may
+            // be enabled by any other crate in the dependency graph.
+            #[expect( // intentional
+                clippy::allow_attributes,
+                reason = "`deprecated` lint depends on dependency graph"
+            )]
+            #[allow( // intentional
+                deprecated,
+                reason = "no other way to read untyped metrics"
+            )]
+            let v = metric.get_untyped().get_value();
+            emit("", None, v);
+        }
+        proto::MetricType::SUMMARY => {
+            let summary = metric.get_summary();
+            for q in summary.get_quantile() {
+                let quantile = ("quantile", number(q.get_quantile()));
+                emit("", Some(quantile), q.get_value());
+            }
+            emit("_sum", None, summary.get_sample_sum());
+            emit("_count", None, count(summary.get_sample_count()));
+        }
+        proto::MetricType::HISTOGRAM => {
+            let histogram = metric.get_histogram();
+            let mut has_inf = false;
+            for b in histogram.get_bucket() {
+                let bound = b.get_upper_bound();
+                has_inf |= bound == f64::INFINITY;
+                let le = ("le", number(bound));
+                emit("_bucket", Some(le), count(b.get_cumulative_count()));
+            }
+            let total = count(histogram.get_sample_count());
+            if !has_inf {
+                emit("_bucket", Some(("le", number(f64::INFINITY))), total);
+            }
+            emit("_count", None, total);
+            emit("_sum", None, histogram.get_sample_sum());
+        }
+    }
+}
+
+/// Formats the provided `value` as an [OpenMetrics] number.
+///
+/// [OpenMetrics]: https://openmetrics.io
+fn number(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value == f64::INFINITY {
+        "+Inf".into()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".into()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes the provided `value` of a `# HELP` line or a label.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '\n' => escaped.push_str(r"\n"),
+            '"' => escaped.push_str(r#"\""#),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Integration with [`opentelemetry_prometheus`] exporter.
+//!
+//! Allows [`metrics`] and [OpenTelemetry] instrumented code to converge on a
+//! single [`prometheus::Registry`], and so, a single scrape endpoint.
+//!
+//! [OpenTelemetry]: https://opentelemetry.io
+
+use std::collections::HashMap;
+
+/// Starts building an [`opentelemetry_prometheus::PrometheusExporter`]
+/// exporting [OpenTelemetry] metrics into the provided
+/// [`prometheus::Registry`].
+///
+/// Use it with the [`Recorder::registry()`] to export [OpenTelemetry] metrics
+/// along with the [`metrics`] ones, or, vice versa, pass the same
+/// [`prometheus::Registry`] to the [`Builder::with_registry()`] method to
+/// record [`metrics`] into the registry backing the [OpenTelemetry] exporter.
+///
+/// # Example
+///
+/// ```rust
+/// use opentelemetry::{metrics::MeterProvider as _, KeyValue};
+/// use opentelemetry_sdk::{metrics::SdkMeterProvider, Resource};
+///
+/// let resource = Resource::new([KeyValue::new("service.name", "api")]);
+/// let registry = metrics_prometheus::otel::registry(&resource)?;
+///
+/// let recorder = metrics_prometheus::Recorder::builder()
+///     .with_registry(&registry)
+///     .build();
+/// let exporter = metrics_prometheus::otel::exporter(&registry)
+///     .without_scope_info()
+///     .without_target_info()
+///     .build()?;
+/// let provider = SdkMeterProvider::builder()
+///     .with_reader(exporter)
+///     .with_resource(resource)
+///     .build();
+///
+/// metrics::with_local_recorder(&recorder, || {
+///     metrics::counter!("requests_total").increment(1);
+/// });
+/// provider
+///     .meter("app")
+///     .u64_counter("otel_requests")
+///     .with_description("OpenTelemetry requests.")
+///     .init()
+///     .add(2, &[]);
+///
+/// let report =
+///     prometheus::TextEncoder::new().encode_to_string(&registry.gather())?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP otel_requests_total OpenTelemetry requests.
+/// ## TYPE otel_requests_total counter
+/// otel_requests_total{service_name="api"} 2
+/// ## HELP requests_total requests_total
+/// ## TYPE requests_total counter
+/// requests_total{service_name="api"} 1
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`Builder::with_registry()`]: crate::recorder::Builder::with_registry
+/// [`Recorder::registry()`]: crate::Recorder::registry
+/// [OpenTelemetry]: https://opentelemetry.io
+#[must_use]
+pub fn exporter(
+    registry: &prometheus::Registry,
+) -> opentelemetry_prometheus::ExporterBuilder {
+    opentelemetry_prometheus::exporter().with_registry(registry.clone())
+}
+
+/// Creates a new [`prometheus::Registry`] attaching the attributes of the
+/// provided [`Resource`] as default labels to all the metrics registered in
+/// it.
+///
+/// See the [`labels()`] function for the details of conversion.
+///
+/// # Errors
+///
+/// If the converted labels are invalid.
+///
+/// [`Resource`]: opentelemetry_sdk::Resource
+pub fn registry(
+    resource: &opentelemetry_sdk::Resource,
+) -> prometheus::Result<prometheus::Registry> {
+    prometheus::Registry::new_custom(None, Some(labels(resource)))
+}
+
+/// Converts the attributes of the provided [`Resource`] into [`prometheus`]
+/// labels.
+///
+/// Attribute keys are sanitized into valid label names the same way
+/// [`opentelemetry_prometheus`] does (like `service.name` becoming
+/// `service_name`). Values of the keys sanitized into the same label name are
+/// joined with `;`, in the order of their original keys.
+///
+/// # Example
+///
+/// ```rust
+/// use opentelemetry::KeyValue;
+/// use opentelemetry_sdk::Resource;
+///
+/// let labels = metrics_prometheus::otel::labels(&Resource::new([
+///     KeyValue::new("service.name", "api"),
+///     KeyValue::new("1st", 1),
+/// ]));
+/// assert_eq!(labels["service_name"], "api");
+/// assert_eq!(labels["_1st"], "1");
+/// ```
+///
+/// [`Resource`]: opentelemetry_sdk::Resource
+#[must_use]
+pub fn labels(
+    resource: &opentelemetry_sdk::Resource,
+) -> HashMap<String, String> {
+    let mut attrs = resource
+        .iter()
+        .map(|(key, value): (&opentelemetry::Key, &opentelemetry::Value)| {
+            (key.as_str(), value.as_str())
+        })
+        .collect::<Vec<_>>();
+    attrs.sort_unstable_by_key(|(key, _)| *key);
+
+    let mut labels = HashMap::<String, String>::new();
+    for (key, value) in attrs {
+        _ = labels
+            .entry(sanitize(key))
+            .and_modify(|v| {
+                v.push(';');
+                v.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    labels
+}
+
+/// Sanitizes the provided [OpenTelemetry] attribute `key` into a valid
+/// [`prometheus`] label name.
+///
+/// [OpenTelemetry]: https://opentelemetry.io
+fn sanitize(key: &str) -> String {
+    let prefix = key.starts_with(|c: char| c.is_ascii_digit()).then_some('_');
+    prefix
+        .into_iter()
+        .chain(
+            key.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }),
+        )
+        .collect()
+}
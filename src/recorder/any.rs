@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Type-erased [`metrics::Recorder`], uniformly representing any flavor of
+//! this crate's recorders with any [`failure::Strategy`].
+//!
+//! [`failure::Strategy`]: crate::failure::Strategy
+
+use std::{fmt, sync::Arc};
+
+use crate::failure;
+
+/// Type-erased [`metrics::Recorder`] wrapping any flavor of this crate's
+/// recorders (with any [`failure::Strategy`]), or any other
+/// [`metrics::Recorder`].
+///
+/// Useful for storing recorders as trait objects in application state, and
+/// swapping or passing them uniformly, without carrying the generic
+/// [`failure::Strategy`] parameter around.
+///
+/// Cheaply cloneable, with all the clones sharing the same wrapped
+/// [`metrics::Recorder`].
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::{failure::strategy, AnyRecorder};
+///
+/// let registry = prometheus::Registry::new();
+/// let recorders: [AnyRecorder; 2] = [
+///     metrics_prometheus::Recorder::builder()
+///         .with_registry(&registry)
+///         .with_failure_strategy(strategy::NoOp)
+///         .build()
+///         .into(),
+///     metrics_prometheus::Recorder::builder()
+///         .with_registry(prometheus::Registry::new())
+///         .build_freezable()
+///         .into(),
+/// ];
+///
+/// for recorder in &recorders {
+///     metrics::with_local_recorder(recorder, || {
+///         metrics::counter!("requests").increment(1);
+///     });
+/// }
+///
+/// let report =
+///     prometheus::TextEncoder::new().encode_to_string(&registry.gather())?;
+/// assert!(report.contains("requests 1"));
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`failure::Strategy`]: failure::Strategy
+#[derive(Clone)]
+pub struct Recorder(Arc<dyn metrics::Recorder + Send + Sync>);
+
+impl Recorder {
+    /// Wraps the provided [`metrics::Recorder`] into a type-erased
+    /// [`AnyRecorder`].
+    ///
+    /// [`AnyRecorder`]: Recorder
+    #[must_use]
+    pub fn new<R>(recorder: R) -> Self
+    where
+        R: metrics::Recorder + Send + Sync + 'static,
+    {
+        Self(Arc::new(recorder))
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyRecorder").finish_non_exhaustive()
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::Recorder for Recorder {
+    fn describe_counter(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.0.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.0.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.0.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Counter {
+        self.0.register_counter(key, metadata)
+    }
+
+    fn register_gauge(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Gauge {
+        self.0.register_gauge(key, metadata)
+    }
+
+    fn register_histogram(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Histogram {
+        self.0.register_histogram(key, metadata)
+    }
+}
+
+impl<S> From<super::Recorder<S>> for Recorder
+where
+    S: failure::Strategy + 'static,
+{
+    fn from(recorder: super::Recorder<S>) -> Self {
+        Self::new(recorder)
+    }
+}
+
+impl<S> From<super::Freezable<S>> for Recorder
+where
+    S: failure::Strategy + 'static,
+{
+    fn from(recorder: super::Freezable<S>) -> Self {
+        Self::new(recorder)
+    }
+}
+
+impl<S> From<super::Frozen<S>> for Recorder
+where
+    S: failure::Strategy + 'static,
+{
+    fn from(recorder: super::Frozen<S>) -> Self {
+        Self::new(recorder)
+    }
+}
+
+impl<R> From<super::Pausable<R>> for Recorder
+where
+    R: metrics::Recorder + Send + Sync + 'static,
+{
+    fn from(recorder: super::Pausable<R>) -> Self {
+        Self::new(recorder)
+    }
+}
+
+impl<R> From<super::RateLimited<R>> for Recorder
+where
+    R: metrics::Recorder + Send + Sync + 'static,
+{
+    fn from(recorder: super::RateLimited<R>) -> Self {
+        Self::new(recorder)
+    }
+}
+
+impl<S> From<super::Recorder<S>> for Box<dyn metrics::Recorder + Send + Sync>
+where
+    S: failure::Strategy + 'static,
+{
+    fn from(recorder: super::Recorder<S>) -> Self {
+        Box::new(recorder)
+    }
+}
+
+impl<S> From<super::Freezable<S>> for Box<dyn metrics::Recorder + Send + Sync>
+where
+    S: failure::Strategy + 'static,
+{
+    fn from(recorder: super::Freezable<S>) -> Self {
+        Box::new(recorder)
+    }
+}
+
+impl<S> From<super::Frozen<S>> for Box<dyn metrics::Recorder + Send + Sync>
+where
+    S: failure::Strategy + 'static,
+{
+    fn from(recorder: super::Frozen<S>) -> Self {
+        Box::new(recorder)
+    }
+}
+
+impl From<Recorder> for Box<dyn metrics::Recorder + Send + Sync> {
+    fn from(recorder: Recorder) -> Self {
+        Box::new(recorder)
+    }
+}
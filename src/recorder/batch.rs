@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Request-scoped batching of metrics changes, applied all at once on commit.
+
+use crate::failure;
+
+/// Buffered change of a single metric.
+#[derive(Clone, Debug)]
+enum Change {
+    /// Incrementing a counter by the value.
+    CounterIncrement(metrics::Key, u64),
+
+    /// Setting a gauge to the value.
+    GaugeSet(metrics::Key, f64),
+
+    /// Incrementing a gauge by the value.
+    GaugeIncrement(metrics::Key, f64),
+
+    /// Decrementing a gauge by the value.
+    GaugeDecrement(metrics::Key, f64),
+
+    /// Recording the value into a histogram.
+    HistogramRecord(metrics::Key, f64),
+}
+
+/// [`Change`] resolved into a handle of the changed metric.
+enum Resolved {
+    /// Incrementing the [`metrics::Counter`] by the value.
+    CounterIncrement(metrics::Counter, u64),
+
+    /// Setting the [`metrics::Gauge`] to the value.
+    GaugeSet(metrics::Gauge, f64),
+
+    /// Incrementing the [`metrics::Gauge`] by the value.
+    GaugeIncrement(metrics::Gauge, f64),
+
+    /// Decrementing the [`metrics::Gauge`] by the value.
+    GaugeDecrement(metrics::Gauge, f64),
+
+    /// Recording the value into the [`metrics::Histogram`].
+    HistogramRecord(metrics::Histogram, f64),
+}
+
+/// Batch of metrics changes buffered in memory and applied to a [`Recorder`]
+/// all at once on [`Batch::commit()`], or discarded on [`Drop`], returned by
+/// the [`Recorder::batch()`] method.
+///
+/// Useful for recording metrics of a request only when it actually completes,
+/// and for reducing per-call overhead in tight loops.
+///
+/// [`Batch::commit()`] resolves all the changed metrics before applying any
+/// change, so either all the changes are applied, or none of them (if any of
+/// the metrics fails to be registered). Concurrent readers, however, may
+/// observe the changes being applied one by one.
+///
+/// # Example
+///
+/// ```rust
+/// let recorder = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .build();
+///
+/// let mut batch = recorder.batch();
+/// batch
+///     .increment_counter("requests", &[("path", "/users")], 1)
+///     .record_histogram("response_size_bytes", &[("path", "/users")], 512.0);
+/// drop(batch); // request failed, so nothing is recorded
+///
+/// let mut batch = recorder.batch();
+/// for _ in 0..3 {
+///     batch.increment_counter("requests", &[("path", "/users")], 1);
+/// }
+/// batch.set_gauge("in_flight", &[("path", "/users")], 2.0);
+///
+/// let report = prometheus::TextEncoder::new()
+///     .encode_to_string(&recorder.registry().gather())?;
+/// assert!(!report.contains("requests"));
+///
+/// batch.commit()?;
+///
+/// let report = prometheus::TextEncoder::new()
+///     .encode_to_string(&recorder.registry().gather())?;
+/// assert!(report.contains(r#"requests{path="/users"} 3"#));
+/// assert!(report.contains(r#"in_flight{path="/users"} 2"#));
+/// assert!(!report.contains("response_size_bytes"));
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`Recorder`]: super::Recorder
+/// [`Recorder::batch()`]: super::Recorder::batch
+#[derive(Debug)]
+#[must_use = "`Batch` discards all its changes unless committed"]
+pub struct Batch<'r, S> {
+    /// [`Recorder`] to apply the buffered changes to.
+    ///
+    /// [`Recorder`]: super::Recorder
+    recorder: &'r super::Recorder<S>,
+
+    /// Buffered [`Change`]s in the order they were made.
+    changes: Vec<Change>,
+}
+
+impl<'r, S> Batch<'r, S> {
+    /// Creates a new empty [`Batch`] of changes to the provided [`Recorder`].
+    ///
+    /// [`Recorder`]: super::Recorder
+    pub(super) const fn new(recorder: &'r super::Recorder<S>) -> Self {
+        Self { recorder, changes: Vec::new() }
+    }
+
+    /// Buffers incrementing the counter identified by the provided `name` and
+    /// `labels` by the provided `value`.
+    pub fn increment_counter<N, L>(
+        &mut self,
+        name: N,
+        labels: L,
+        value: u64,
+    ) -> &mut Self
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels,
+    {
+        self.changes.push(Change::CounterIncrement(
+            metrics::Key::from_parts(name, labels),
+            value,
+        ));
+        self
+    }
+
+    /// Buffers setting the gauge identified by the provided `name` and
+    /// `labels` to the provided `value`.
+    pub fn set_gauge<N, L>(
+        &mut self,
+        name: N,
+        labels: L,
+        value: f64,
+    ) -> &mut Self
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels,
+    {
+        self.changes.push(Change::GaugeSet(
+            metrics::Key::from_parts(name, labels),
+            value,
+        ));
+        self
+    }
+
+    /// Buffers incrementing the gauge identified by the provided `name` and
+    /// `labels` by the provided `value`.
+    pub fn increment_gauge<N, L>(
+        &mut self,
+        name: N,
+        labels: L,
+        value: f64,
+    ) -> &mut Self
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels,
+    {
+        self.changes.push(Change::GaugeIncrement(
+            metrics::Key::from_parts(name, labels),
+            value,
+        ));
+        self
+    }
+
+    /// Buffers decrementing the gauge identified by the provided `name` and
+    /// `labels` by the provided `value`.
+    pub fn decrement_gauge<N, L>(
+        &mut self,
+        name: N,
+        labels: L,
+        value: f64,
+    ) -> &mut Self
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels,
+    {
+        self.changes.push(Change::GaugeDecrement(
+            metrics::Key::from_parts(name, labels),
+            value,
+        ));
+        self
+    }
+
+    /// Buffers recording the provided `value` into the histogram identified by
+    /// the provided `name` and `labels`.
+    pub fn record_histogram<N, L>(
+        &mut self,
+        name: N,
+        labels: L,
+        value: f64,
+    ) -> &mut Self
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels,
+    {
+        self.changes.push(Change::HistogramRecord(
+            metrics::Key::from_parts(name, labels),
+            value,
+        ));
+        self
+    }
+
+    /// Returns the number of changes buffered in this [`Batch`].
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Indicates whether this [`Batch`] has no buffered changes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl<S: failure::Strategy> Batch<'_, S> {
+    /// Applies all the changes buffered in this [`Batch`] to its [`Recorder`].
+    ///
+    /// All the changed metrics are resolved before applying any change, so
+    /// nothing is applied if any of them fails to be registered (though the
+    /// metrics resolved before the failed one remain registered, untouched).
+    ///
+    /// # Errors
+    ///
+    /// If any of the changed metrics cannot be registered, regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// [`Recorder`]: super::Recorder
+    pub fn commit(self) -> prometheus::Result<()> {
+        let Self { recorder, changes } = self;
+
+        let resolved = changes
+            .into_iter()
+            .map(|change| {
+                Ok(match change {
+                    Change::CounterIncrement(key, v) => {
+                        let counter = recorder.checked_counter(&key)?;
+                        Resolved::CounterIncrement(counter, v)
+                    }
+                    Change::GaugeSet(key, v) => {
+                        Resolved::GaugeSet(recorder.checked_gauge(&key)?, v)
+                    }
+                    Change::GaugeIncrement(key, v) => {
+                        let gauge = recorder.checked_gauge(&key)?;
+                        Resolved::GaugeIncrement(gauge, v)
+                    }
+                    Change::GaugeDecrement(key, v) => {
+                        let gauge = recorder.checked_gauge(&key)?;
+                        Resolved::GaugeDecrement(gauge, v)
+                    }
+                    Change::HistogramRecord(key, v) => {
+                        let histogram = recorder.checked_histogram(&key)?;
+                        Resolved::HistogramRecord(histogram, v)
+                    }
+                })
+            })
+            .collect::<prometheus::Result<Vec<_>>>()?;
+
+        for change in resolved {
+            match change {
+                Resolved::CounterIncrement(c, v) => c.increment(v),
+                Resolved::GaugeSet(g, v) => g.set(v),
+                Resolved::GaugeIncrement(g, v) => g.increment(v),
+                Resolved::GaugeDecrement(g, v) => g.decrement(v),
+                Resolved::HistogramRecord(h, v) => h.record(v),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Scrape handlers caching the rendered metrics, so heavy
+//! [`prometheus::Registry`]s scraped by multiple Prometheus servers are not
+//! re-encoded on each scrape.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    clock::{self, Clock as _},
+    storage,
+};
+
+use super::{try_render_text, PartialRender};
+
+/// Scrape handler serving the metrics rendered in the Prometheus text format,
+/// returned by the [`Recorder::cached_renderer()`] method.
+///
+/// Serves the cached text body, if the last gathering is younger than the
+/// configured TTL. Concurrent scrapes are single-flighted: only one of them
+/// gathers the metrics, while the others wait for it and serve its result.
+///
+/// Renders the metrics the same way the [`Recorder::try_render_text()`]
+/// method does, so a panicking [`prometheus::core::Collector`] doesn't fail
+/// the whole scrape.
+///
+/// Cheaply cloneable, with all the clones sharing the same cache.
+///
+/// [`Recorder::cached_renderer()`]: super::Recorder::cached_renderer
+/// [`Recorder::try_render_text()`]: super::Recorder::try_render_text
+#[derive(Clone)]
+pub struct Renderer {
+    /// [`storage::Mutable`] of the [`Recorder`] to render the metrics of.
+    ///
+    /// [`Recorder`]: super::Recorder
+    storage: storage::Mutable,
+
+    /// [`Clock`] the age of the cached text body is measured with.
+    ///
+    /// [`Clock`]: clock::Clock
+    clock: clock::Shared,
+
+    /// Maximum age of the cached text body to be served.
+    ttl: Duration,
+
+    /// Last rendered text body.
+    ///
+    /// Locked for the whole rendering, so concurrent scrapes wait for it
+    /// instead of rendering the same metrics again.
+    cache: Arc<Mutex<Option<Cached>>>,
+}
+
+/// Text body cached by a [`Renderer`].
+#[derive(Debug)]
+struct Cached {
+    /// [`Instant`] this text body was rendered at.
+    at: Instant,
+
+    /// Rendered text body itself.
+    text: Arc<str>,
+}
+
+impl Renderer {
+    /// Creates a new [`Renderer`] of the metrics of the provided
+    /// [`storage::Mutable`], caching them for the provided `ttl`.
+    pub(super) fn new(storage: storage::Mutable, ttl: Duration) -> Self {
+        let clock = Arc::clone(&storage.clock);
+        Self { storage, clock, ttl, cache: Arc::default() }
+    }
+
+    /// Returns the TTL of the text body cached by this [`Renderer`].
+    #[must_use]
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Renders the metrics in the Prometheus text format, or returns the
+    /// cached text body, if it's younger than the [`Renderer::ttl()`].
+    ///
+    /// Partial renders are not cached, so the next call renders the metrics
+    /// again.
+    ///
+    /// # Errors
+    ///
+    /// With a [`PartialRender`] containing the rendered metrics, if any
+    /// [`prometheus::core::Collector`] panics, the gathered metrics cannot be
+    /// encoded, or the merged [`prometheus::Registry`]s contain duplicated
+    /// metric families.
+    pub fn render(&self) -> Result<Arc<str>, PartialRender> {
+        let mut cache =
+            self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = self.clock.now();
+        if let Some(cached) = &*cache {
+            if now.saturating_duration_since(cached.at) < self.ttl {
+                return Ok(Arc::clone(&cached.text));
+            }
+        }
+
+        let text: Arc<str> = try_render_text(&self.storage)?.into();
+        *cache = Some(Cached { at: self.clock.now(), text: Arc::clone(&text) });
+        drop(cache);
+        Ok(text)
+    }
+
+    /// Drops the cached text body, so the next [`Renderer::render()`] call
+    /// gathers the metrics again.
+    pub fn invalidate(&self) {
+        *self.cache.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+impl fmt::Debug for Renderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Renderer")
+            .field("ttl", &self.ttl)
+            .field("clock", &self.clock)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Ergonomic recording of [`Duration`]s into histograms, converted into their
+//! time units.
+
+use std::time::Duration;
+
+use crate::{failure, openmetrics};
+
+/// Extension of recorders for recording [`Duration`]s into histograms,
+/// converted into their time units.
+///
+/// The [`metrics::Unit`] of a histogram is the one declared via the
+/// [`metrics::describe_histogram!`] macro, or the one suffixing its name
+/// (like `latency_milliseconds`), or [`metrics::Unit::Seconds`] otherwise.
+/// This avoids the common bug of recording milliseconds into a histogram
+/// measured in seconds (or vice versa).
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use metrics_prometheus::RecordDuration as _;
+///
+/// let recorder = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .build();
+/// metrics::with_local_recorder(&recorder, || {
+///     metrics::describe_histogram!(
+///         "latency",
+///         metrics::Unit::Milliseconds,
+///         "Request latency.",
+///     );
+/// });
+///
+/// let elapsed = Duration::from_millis(1500);
+/// recorder.record_duration("latency", &[("op", "read")], elapsed)?;
+/// recorder.record_duration("latency_seconds", &[("op", "read")], elapsed)?;
+///
+/// let report = prometheus::TextEncoder::new()
+///     .encode_to_string(&recorder.registry().gather())?;
+/// assert!(report.contains(r#"latency_sum{op="read"} 1500"#));
+/// assert!(report.contains(r#"latency_seconds_sum{op="read"} 1.5"#));
+///
+/// // Bytes are not a time unit.
+/// let err =
+///     recorder.record_duration("payload_bytes", &[("op", "read")], elapsed);
+/// assert!(err.is_err());
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+pub trait RecordDuration {
+    /// Records the provided `duration` into the histogram identified by the
+    /// provided `name` and `labels`, converted into its time unit.
+    ///
+    /// # Errors
+    ///
+    /// - If the histogram has a non-time [`metrics::Unit`].
+    /// - If the histogram's name has a suffix of another [`metrics::Unit`]
+    ///   than the declared one.
+    /// - If the histogram cannot be registered.
+    fn record_duration<N, L>(
+        &self,
+        name: N,
+        labels: L,
+        duration: Duration,
+    ) -> prometheus::Result<()>
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels;
+}
+
+impl<S: failure::Strategy> RecordDuration for super::Recorder<S> {
+    fn record_duration<N, L>(
+        &self,
+        name: N,
+        labels: L,
+        duration: Duration,
+    ) -> prometheus::Result<()>
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels,
+    {
+        let name = name.into();
+        let value =
+            in_unit(name.as_str(), self.storage.unit(name.as_str()), duration)?;
+        self.checked_histogram(&metrics::Key::from_parts(name, labels))?
+            .record(value);
+        Ok(())
+    }
+}
+
+/// Converts the provided `duration` into the time unit of the histogram with
+/// the provided `name` and `declared` [`metrics::Unit`] (if any).
+///
+/// # Errors
+///
+/// - If the histogram has a non-time [`metrics::Unit`].
+/// - If the provided `name` has a suffix of another [`metrics::Unit`] than the
+///   `declared` one.
+pub(super) fn in_unit(
+    name: &str,
+    declared: Option<metrics::Unit>,
+    duration: Duration,
+) -> prometheus::Result<f64> {
+    use metrics::Unit as U;
+
+    let unit = if let Some(declared) = declared {
+        openmetrics::check_suffix(name, declared)?;
+        declared
+    } else {
+        openmetrics::suffix_unit(name).unwrap_or(U::Seconds)
+    };
+    let secs = duration.as_secs_f64();
+    match unit {
+        U::Seconds => Ok(secs),
+        U::Milliseconds => Ok(secs * 1e3),
+        U::Microseconds => Ok(secs * 1e6),
+        U::Nanoseconds => Ok(secs * 1e9),
+        U::Count
+        | U::Percent
+        | U::Tebibytes
+        | U::Gibibytes
+        | U::Mebibytes
+        | U::Kibibytes
+        | U::Bytes
+        | U::TerabitsPerSecond
+        | U::GigabitsPerSecond
+        | U::MegabitsPerSecond
+        | U::KilobitsPerSecond
+        | U::BitsPerSecond
+        | U::CountPerSecond => Err(prometheus::Error::Msg(format!(
+            "`{name}` histogram is measured in `{}`, not in time units",
+            unit.as_str(),
+        ))),
+    }
+}
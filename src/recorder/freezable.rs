@@ -1,11 +1,33 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! [`metrics::Recorder`] being able to stop registering new metrics in the
 //! benefit of providing fast access to already registered ones.
 
-use std::sync::{Arc, OnceLock};
+#[cfg(feature = "timestamps")]
+use std::time::Instant;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock, PoisonError, RwLock, RwLockReadGuard,
+        RwLockWriteGuard,
+    },
+    thread,
+    time::Duration,
+};
+
+use arc_swap::ArcSwapOption;
 
-use crate::{failure::strategy::PanicInDebugNoOpInRelease, metric, storage};
+#[cfg(feature = "relabel")]
+use crate::relabel;
+use crate::{
+    config,
+    failure::{self, strategy::PanicInDebugNoOpInRelease},
+    metric, storage, trace,
+};
 
-use super::Builder;
+use super::{duration, Builder};
 
 /// [`metrics::Recorder`] being essential a usual [`Recorder`], which is able to
 /// become a [`Frozen`] one at some point after creation.
@@ -34,7 +56,7 @@ use super::Builder;
 /// recorder.register_metric(prometheus::Gauge::new("value", "help")?);
 ///
 /// let report = prometheus::TextEncoder::new()
-///     .encode_to_string(&prometheus::default_registry().gather())?;
+///     .encode_to_string(&recorder.registry().gather())?;
 /// assert_eq!(
 ///     report.trim(),
 ///     r#"
@@ -57,7 +79,7 @@ use super::Builder;
 /// metrics::gauge!("new").increment(2.0);
 ///
 /// let report = prometheus::TextEncoder::new()
-///     .encode_to_string(&prometheus::default_registry().gather())?;
+///     .encode_to_string(&recorder.registry().gather())?;
 /// assert_eq!(
 ///     report.trim(),
 ///     r#"
@@ -104,8 +126,9 @@ use super::Builder;
 /// depending on whether it has been [`.freeze()`]d, plus an [`AtomicBool`]
 /// loading to check whether it has been actually [`.freeze()`]d.
 ///
-/// So, before [`.freeze()`] it's: [`AtomicBool`] loading plus [`read`-lock] on
-/// a sharded [`HashMap`] plus [`Arc`] cloning.
+/// So, before [`.freeze()`] it's: [`AtomicBool`] loading plus incrementing and
+/// decrementing a per-thread sharded [`AtomicUsize`] of the freezing handover
+/// plus [`read`-lock] on a sharded [`HashMap`] plus [`Arc`] cloning.
 ///
 /// And after [`.freeze()`]: [`AtomicBool`] loading plus regular [`HashMap`]
 /// lookup plus [`Arc`] cloning.
@@ -140,9 +163,12 @@ use super::Builder;
 ///
 /// // This panics, as such labeling is not allowed by `prometheus` crate.
 /// metrics::counter!("count", "whose" => "mine").increment(1);
+/// # #[cfg(feature = "never-panic")]
+/// # panic!("suppressed by `never-panic` feature");
 /// ```
 ///
 /// [`AtomicBool`]: std::sync::atomic::AtomicBool
+/// [`AtomicUsize`]: std::sync::atomic::AtomicUsize
 /// [`failure::Strategy`]: crate::failure::Strategy
 /// [`FreezableRecorder`]: Recorder
 /// [`Frozen`]: super::Frozen
@@ -151,6 +177,18 @@ use super::Builder;
 /// [`Recorder`]: super::Recorder
 /// [`.freeze()`]: Self::freeze()
 /// [`read`-lock]: std::sync::RwLock::read()
+///
+/// # Freezing
+///
+/// [`.freeze()`] waits for all the in-flight operations on the not yet frozen
+/// metrics to complete, and holds off the new ones until the frozen storage is
+/// fully built. So, no metric is lost or registered twice across the
+/// transition: every operation is performed either before draining the usual
+/// storage, or after the frozen one is published.
+///
+/// The only exception is describing metrics, which doesn't wait for the
+/// [`.freeze()`] to complete: the descriptions made meanwhile are buffered and
+/// applied to the frozen storage right before it's published.
 #[derive(Clone, Debug)]
 pub struct Recorder<FailureStrategy = PanicInDebugNoOpInRelease> {
     /// Usual [`Recorder`] for registering metrics on the fly.
@@ -164,6 +202,35 @@ pub struct Recorder<FailureStrategy = PanicInDebugNoOpInRelease> {
     ///
     /// [`FrozenRecorder`]: super::Frozen
     frozen: Arc<OnceLock<super::Frozen<FailureStrategy>>>,
+
+    /// [`FrozenRecorder`] for fast access to the metrics frozen selectively
+    /// via the [`Recorder::freeze_names()`] method.
+    ///
+    /// This one is built by draining only the selected metrics out of the
+    /// [`Recorder::usual`].
+    ///
+    /// [`FrozenRecorder`]: super::Frozen
+    partial: Arc<ArcSwapOption<super::Frozen<FailureStrategy>>>,
+
+    /// [`Handover`] of the operations on the not yet frozen metrics to the
+    /// frozen ones.
+    ///
+    /// Entered by the operations on the [`Recorder::usual`] and the
+    /// [`Recorder::partial`], and awaited by the freezing, so it never drains
+    /// the [`Recorder::usual`] concurrently with them.
+    handover: Arc<Handover>,
+
+    /// [`pending::Description`]s of the metrics described while this
+    /// [`FreezableRecorder`] is being [`.freeze()`]d, applied to the frozen
+    /// metrics before they're published.
+    ///
+    /// [`Some`] only while the [`.freeze()`] is in progress, so the describing
+    /// doesn't wait for it to complete.
+    ///
+    /// [`.freeze()`]: Recorder::freeze()
+    /// [`FreezableRecorder`]: Recorder
+    /// [`pending::Description`]: storage::pending::Description
+    deferred: Arc<Mutex<Option<Vec<storage::pending::Description>>>>,
 }
 
 impl Recorder {
@@ -182,7 +249,13 @@ impl<S> Recorder<S> {
     /// [`Freezable`]: Recorder
     /// [`Recorder`]: super::Recorder
     pub(super) fn wrap(usual: super::Recorder<S>) -> Self {
-        Self { usual, frozen: Arc::default() }
+        Self {
+            usual,
+            frozen: Arc::default(),
+            partial: Arc::default(),
+            handover: Arc::default(),
+            deferred: Arc::default(),
+        }
     }
 
     /// Returns the underlying [`prometheus::Registry`] backing this
@@ -207,15 +280,475 @@ impl<S> Recorder<S> {
     ///
     /// // panics: Duplicate metrics collector registration attempted
     /// metrics::counter!("value").increment(1);
+    /// # #[cfg(feature = "never-panic")]
+    /// # panic!("suppressed by `never-panic` feature");
     /// # Ok::<_, prometheus::Error>(())
     /// ```
     ///
     /// [`FreezableRecorder`]: Recorder
     #[must_use]
-    pub const fn registry(&self) -> &prometheus::Registry {
+    pub fn registry(&self) -> &prometheus::Registry {
         &self.usual.storage.prometheus
     }
 
+    /// Returns the underlying [`prometheus::Registry`] backing this
+    /// [`FreezableRecorder`] as a cheaply cloneable [`Arc`].
+    ///
+    /// Useful for handing the [`prometheus::Registry`] over to a `'static`
+    /// task (like an HTTP server exposing the metrics).
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    #[must_use]
+    pub fn registry_arc(&self) -> Arc<prometheus::Registry> {
+        self.usual.registry_arc()
+    }
+
+    /// Renders the metrics of this [`FreezableRecorder`] in the Prometheus text
+    /// format, keeping only the series matching the provided `predicate`.
+    ///
+    /// See the [`Recorder::render_text_filtered()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the gathered metrics cannot be encoded.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::render_text_filtered()`]:
+    ///     super::Recorder::render_text_filtered
+    pub fn render_text_filtered<F>(
+        &self,
+        predicate: F,
+    ) -> prometheus::Result<String>
+    where
+        F: FnMut(&str, &[(&str, &str)]) -> bool,
+    {
+        self.usual.render_text_filtered(predicate)
+    }
+
+    /// Returns a [`cached::Renderer`] serving the metrics of this
+    /// [`FreezableRecorder`] in the Prometheus text format, and caching the
+    /// rendered text body for the provided `ttl`.
+    ///
+    /// See the [`Recorder::cached_renderer()`] method for details.
+    ///
+    /// [`cached::Renderer`]: super::cached::Renderer
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::cached_renderer()`]: super::Recorder::cached_renderer
+    #[must_use]
+    pub fn cached_renderer(&self, ttl: Duration) -> super::cached::Renderer {
+        self.usual.cached_renderer(ttl)
+    }
+
+    /// Returns [`storage::Counts`] of the metric families registered via this
+    /// [`FreezableRecorder`] (the frozen ones, once it's [`.freeze()`]d).
+    ///
+    /// See the [`Recorder::metric_counts()`] method for details.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::metric_counts()`]: super::Recorder::metric_counts
+    /// [`.freeze()`]: Recorder::freeze()
+    #[must_use]
+    pub fn metric_counts(&self) -> storage::Counts {
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.metric_counts();
+        }
+        let mut counts = self.usual.metric_counts();
+        if let Some(partial) = self.partial.load().as_ref() {
+            let frozen = partial.metric_counts();
+            counts.counters += frozen.counters;
+            counts.gauges += frozen.gauges;
+            counts.histograms += frozen.histograms;
+        }
+        counts
+    }
+
+    /// Returns the numbers of accesses to the metrics unknown to this
+    /// [`FreezableRecorder`] once it's [`.freeze()`]d (not registered before
+    /// freezing), by their names.
+    ///
+    /// See the [`FrozenRecorder::unknown_keys()`] method for details. Empty
+    /// until this [`FreezableRecorder`] is [`.freeze()`]d.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`FrozenRecorder::unknown_keys()`]: super::Frozen::unknown_keys
+    /// [`.freeze()`]: Recorder::freeze()
+    #[must_use]
+    pub fn unknown_keys(&self) -> BTreeMap<String, u64> {
+        self.frozen.get().map(super::Frozen::unknown_keys).unwrap_or_default()
+    }
+
+    /// Returns [`storage::Counts`] of the metric families having a
+    /// [`help` description] in this [`FreezableRecorder`], but never
+    /// registered (orphan descriptions).
+    ///
+    /// See the [`Recorder::orphan_counts()`] method for details. Empty once
+    /// this [`FreezableRecorder`] is [`.freeze()`]d.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    /// [`Recorder::orphan_counts()`]: super::Recorder::orphan_counts
+    /// [`.freeze()`]: Recorder::freeze()
+    #[must_use]
+    pub fn orphan_counts(&self) -> storage::Counts {
+        if self.frozen.get().is_some() {
+            return storage::Counts::default();
+        }
+        self.usual.orphan_counts()
+    }
+
+    /// Unregisters all the [`prometheus`] metrics registered via this
+    /// [`FreezableRecorder`] from its [`prometheus::Registry`], restoring it to
+    /// its prior state.
+    ///
+    /// See the [`Recorder::shutdown()`] method for details. Once frozen, this
+    /// [`FreezableRecorder`] doesn't register any new metrics afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build_freezable();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("count").increment(1);
+    /// });
+    /// recorder.freeze();
+    /// assert_eq!(recorder.registry().gather().len(), 1);
+    ///
+    /// recorder.shutdown();
+    /// assert_eq!(recorder.registry().gather().len(), 0);
+    /// ```
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::shutdown()`]: super::Recorder::shutdown
+    pub fn shutdown(&self) {
+        if let Some(frozen) = self.frozen.get() {
+            frozen.shutdown();
+        }
+        if let Some(partial) = self.partial.load().as_ref() {
+            partial.shutdown();
+        }
+        self.usual.shutdown();
+    }
+
+    /// Sets the provided `annotation` of the counter identified by the
+    /// provided [`metrics::Key`].
+    ///
+    /// See the [`Recorder::annotate_counter()`] method for details.
+    ///
+    /// [`Recorder::annotate_counter()`]: super::Recorder::annotate_counter
+    pub fn annotate_counter(
+        &self,
+        key: &metrics::Key,
+        annotation: impl Into<String>,
+    ) {
+        self.usual.annotate_counter(key, annotation);
+    }
+
+    /// Sets the provided `annotation` of the gauge identified by the provided
+    /// [`metrics::Key`].
+    ///
+    /// See the [`Recorder::annotate_counter()`] method for details.
+    ///
+    /// [`Recorder::annotate_counter()`]: super::Recorder::annotate_counter
+    pub fn annotate_gauge(
+        &self,
+        key: &metrics::Key,
+        annotation: impl Into<String>,
+    ) {
+        self.usual.annotate_gauge(key, annotation);
+    }
+
+    /// Sets the provided `annotation` of the histogram identified by the
+    /// provided [`metrics::Key`].
+    ///
+    /// See the [`Recorder::annotate_counter()`] method for details.
+    ///
+    /// [`Recorder::annotate_counter()`]: super::Recorder::annotate_counter
+    pub fn annotate_histogram(
+        &self,
+        key: &metrics::Key,
+        annotation: impl Into<String>,
+    ) {
+        self.usual.annotate_histogram(key, annotation);
+    }
+
+    /// Sets the provided `callback` refreshing the value of the gauge
+    /// identified by the provided `name` and `labels` right before it's
+    /// gathered, replacing the previous one (if any).
+    ///
+    /// See the [`Recorder::set_collect_callback()`] method for details.
+    ///
+    /// Once this [`FreezableRecorder`] is [`.freeze()`]d, the `callback` is set
+    /// on the frozen gauge, so the gauge must be registered before freezing.
+    ///
+    /// # Errors
+    ///
+    /// - If the gauge cannot be registered (like having invalid name or label
+    ///   names different from the already registered ones).
+    /// - If the gauge wasn't registered before this [`FreezableRecorder`] is
+    ///   [`.freeze()`]d.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build_freezable();
+    ///
+    /// recorder.set_collect_callback("queue_size", &[], || 1.0)?;
+    /// recorder.freeze();
+    ///
+    /// recorder.set_collect_callback("queue_size", &[], || 2.0)?;
+    /// assert!(recorder.set_collect_callback("unknown", &[], || 3.0).is_err());
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP queue_size queue_size
+    /// ## TYPE queue_size gauge
+    /// queue_size 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::set_collect_callback()`]:
+    ///     super::Recorder::set_collect_callback
+    /// [`.freeze()`]: Recorder::freeze()
+    pub fn set_collect_callback<F>(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        callback: F,
+    ) -> prometheus::Result<()>
+    where
+        F: Fn() -> f64 + Send + Sync + 'static,
+    {
+        let key = super::callback_key(name, labels);
+        let callback = Arc::new(callback);
+        let _handover = self.hold_off_freezing();
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.storage.set_collect_callback(&key, callback);
+        }
+        if let Some(partial) =
+            self.partial.load().as_ref().filter(|p| p.storage.contains(name))
+        {
+            return partial.storage.set_collect_callback(&key, callback);
+        }
+        self.usual.storage.set_collect_callback(&key, callback)
+    }
+
+    /// Registers a [`StateSet`] with the provided `name` and `states`,
+    /// being in the first of them.
+    ///
+    /// See the [`Recorder::register_state_set()`] method for details.
+    ///
+    /// Once this [`FreezableRecorder`] is [`.freeze()`]d, the gauges must be
+    /// registered before freezing (see the [`Recorder::set_collect_callback()`]
+    /// method for details).
+    ///
+    /// # Errors
+    ///
+    /// - If the provided `states` are empty or contain duplicates.
+    /// - If the gauges cannot be registered (like having invalid name or
+    ///   label names different from the already registered ones).
+    /// - If the gauges weren't registered before this [`FreezableRecorder`] is
+    ///   [`.freeze()`]d.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::register_state_set()`]: super::Recorder::register_state_set
+    /// [`.freeze()`]: Recorder::freeze()
+    /// [`StateSet`]: super::state_set::StateSet
+    pub fn register_state_set<I>(
+        &self,
+        name: &str,
+        states: I,
+    ) -> prometheus::Result<super::state_set::StateSet>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let set = super::state_set::StateSet::new(
+            name,
+            states.into_iter().map(Into::into).collect(),
+        )?;
+        for (idx, state) in set.states().iter().enumerate() {
+            self.set_collect_callback(
+                name,
+                &[(name, state.as_str())],
+                set.callback(idx),
+            )?;
+        }
+        Ok(set)
+    }
+
+    /// Registers an [`Info`] with the provided `name` and `labels` payload.
+    ///
+    /// See the [`Recorder::register_info()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the gauge cannot be created (like having invalid name or label
+    /// names) or registered in the [`prometheus::Registry`].
+    ///
+    /// [`Info`]: super::info::Info
+    /// [`Recorder::register_info()`]: super::Recorder::register_info
+    pub fn register_info<I, K, V>(
+        &self,
+        name: &str,
+        labels: I,
+    ) -> prometheus::Result<super::info::Info>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.usual.register_info(name, labels)
+    }
+
+    /// Returns all the annotations of the metrics in the family identified by
+    /// the provided `name`, keyed by their [`annotation::Labels`].
+    ///
+    /// See the [`Recorder::annotations()`] method for details.
+    ///
+    /// [`annotation::Labels`]: crate::metric::annotation::Labels
+    /// [`Recorder::annotations()`]: super::Recorder::annotations
+    #[must_use]
+    pub fn annotations(
+        &self,
+        name: &str,
+    ) -> BTreeMap<metric::annotation::Labels, String> {
+        self.usual.annotations(name)
+    }
+
+    /// Returns the [`Origins`] of the metric family identified by the provided
+    /// `name` (when and where it was first described and registered), if it's
+    /// known.
+    ///
+    /// See the [`Recorder::origins()`] method for details.
+    ///
+    /// [`Origins`]: metric::origin::Origins
+    /// [`Recorder::origins()`]: super::Recorder::origins
+    #[cfg(feature = "origins")]
+    #[must_use]
+    pub fn origins(&self, name: &str) -> Option<metric::origin::Origins> {
+        self.usual.origins(name)
+    }
+
+    /// Merges the provided pre-aggregated [`BucketCounts`] snapshot into the
+    /// histogram identified by the provided `name` and `labels`.
+    ///
+    /// Works regardless of whether this [`FreezableRecorder`] is frozen or
+    /// not. See the [`Recorder::record_histogram_raw()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// See the [`Recorder::record_histogram_raw()`] method.
+    ///
+    /// [`BucketCounts`]: metric::raw::BucketCounts
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::record_histogram_raw()`]:
+    ///     super::Recorder::record_histogram_raw
+    pub fn record_histogram_raw(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        counts: &metric::raw::BucketCounts,
+    ) -> prometheus::Result<()> {
+        self.usual.record_histogram_raw(name, labels, counts)
+    }
+
+    /// Registers a gauge with the provided `name`, exposing the increase of the
+    /// `source` counter over the provided sliding time `window`.
+    ///
+    /// Works regardless of whether this [`FreezableRecorder`] is frozen or
+    /// not. See the [`Recorder::windowed_rate()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// See the [`Recorder::windowed_rate()`] method.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::windowed_rate()`]: super::Recorder::windowed_rate
+    pub fn windowed_rate(
+        &self,
+        name: &str,
+        source: &str,
+        window: Duration,
+    ) -> prometheus::Result<()> {
+        self.usual.windowed_rate(name, source, window)
+    }
+
+    /// Returns a mirror of this [`FreezableRecorder`], sharing all its metrics
+    /// (and freezing along with it), but skipping the ones it's authoritative
+    /// for (see the [`Builder::with_authoritative()`] method).
+    ///
+    /// [`Builder::with_authoritative()`]: super::Builder::with_authoritative
+    /// [`FreezableRecorder`]: Recorder
+    #[must_use]
+    pub fn mirror(&self) -> Self
+    where
+        S: Clone,
+    {
+        Self {
+            usual: self.usual.mirror(),
+            frozen: Arc::clone(&self.frozen),
+            partial: Arc::clone(&self.partial),
+            handover: Arc::clone(&self.handover),
+            deferred: Arc::clone(&self.deferred),
+        }
+    }
+
+    /// Returns the [`relabel::Rules`] applied at gather time to the metrics
+    /// registered via this [`FreezableRecorder`], allowing to replace them at
+    /// runtime.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    #[cfg(feature = "relabel")]
+    #[must_use]
+    pub const fn relabel_rules(&self) -> &relabel::Rules {
+        self.usual.relabel_rules()
+    }
+
+    /// Returns the [`config::Reloadable`] configuration of the metrics
+    /// registered via this [`FreezableRecorder`], shared by its frozen part.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    #[must_use]
+    pub const fn config(&self) -> &config::Reloadable {
+        self.usual.config()
+    }
+
+    /// Replaces the [`config::Config`] of the metrics registered via this
+    /// [`FreezableRecorder`] with the provided one.
+    ///
+    /// See the [`Recorder::update_config()`] method for details.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::update_config()`]: super::Recorder::update_config
+    pub fn update_config(&self, config: config::Config) {
+        self.usual.update_config(config);
+    }
+
+    /// Returns the numbers of usages (registrations) of the deprecated metric
+    /// names via this [`FreezableRecorder`], by these names, shared with its
+    /// frozen part.
+    ///
+    /// See the [`Builder::with_deprecated()`] method for details.
+    ///
+    /// [`Builder::with_deprecated()`]: super::Builder::with_deprecated
+    /// [`FreezableRecorder`]: Recorder
+    #[must_use]
+    pub fn deprecated_usages(&self) -> BTreeMap<String, u64> {
+        self.usual.deprecated_usages()
+    }
+
     /// Tries to register the provided [`prometheus`] `metric` in the underlying
     /// [`prometheus::Registry`] in the way making it usable via this
     /// [`FreezableRecorder`] (and, so, [`metrics`] crate interfaces).
@@ -249,7 +782,7 @@ impl<S> Recorder<S> {
     /// counter.with_label_values(&["foreign", "owned"]).inc_by(3);
     ///
     /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    ///     .encode_to_string(&recorder.registry().gather())?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
@@ -343,7 +876,7 @@ impl<S> Recorder<S> {
     /// gauge.with_label_values(&["foreign", "owned"]).set(3.0);
     ///
     /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    ///     .encode_to_string(&recorder.registry().gather())?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
@@ -403,6 +936,23 @@ impl<S> Recorder<S> {
         }
     }
 
+    /// Returns the [`Instant`] the metric identified by the provided `name`
+    /// and `labels` (in any order) was updated last time via this
+    /// [`FreezableRecorder`].
+    ///
+    /// [`None`] is returned if the metric has never been updated.
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn last_updated(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+    ) -> Option<Instant> {
+        self.usual.last_updated(name, labels)
+    }
+
     /// Freezes this [`FreezableRecorder`], making it unable to register new
     /// [`prometheus`] metrics in the benefit of providing faster access to the
     /// already registered ones.
@@ -417,8 +967,10 @@ impl<S> Recorder<S> {
     /// plus an [`AtomicBool`] loading to check whether it has been actually
     /// [`.freeze()`]d.
     ///
-    /// So, before [`.freeze()`] it's: [`AtomicBool`] loading plus [`read`-lock]
-    /// on a sharded [`HashMap`] plus [`Arc`] cloning.
+    /// So, before [`.freeze()`] it's: [`AtomicBool`] loading plus incrementing
+    /// and decrementing a per-thread sharded [`AtomicUsize`] of the freezing
+    /// handover plus [`read`-lock] on a sharded [`HashMap`] plus [`Arc`]
+    /// cloning.
     ///
     /// And after [`.freeze()`]: [`AtomicBool`] loading plus regular [`HashMap`]
     /// lookup plus [`Arc`] cloning.
@@ -463,18 +1015,381 @@ impl<S> Recorder<S> {
     /// ```
     ///
     /// [`AtomicBool`]: std::sync::atomic::AtomicBool
+    /// [`AtomicUsize`]: std::sync::atomic::AtomicUsize
     /// [`FreezableRecorder`]: Recorder
     /// [`FrozenRecorder`]: super::Frozen
     /// [`HashMap`]: std::collections::HashMap
     /// [`.freeze()`]: Recorder::freeze()
+    /// [`read`-lock]: std::sync::RwLock::read()
     pub fn freeze(&self)
     where
         S: Clone,
     {
-        _ = self.frozen.get_or_init(|| super::Frozen {
-            storage: (&self.usual.storage).into(),
+        if self.frozen.get().is_some() {
+            return;
+        }
+        let handover = self.handover.freeze();
+        *self.deferred.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some(Vec::new());
+        _ = self.frozen.get_or_init(|| {
+            let _span = trace::freezing(false);
+            let storage =
+                storage::Immutable::drained(&self.usual.storage, |_| true);
+            let storage = match self.partial.load().as_ref() {
+                Some(partial) => partial.storage.merged(&storage),
+                None => storage,
+            };
+            // Applied before publishing, so the later descriptions, applied
+            // directly to the published frozen metrics, are not overwritten.
+            let deferred = self
+                .deferred
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            for description in deferred.into_iter().flatten() {
+                storage.describe_pending(description);
+            }
+            super::Frozen {
+                storage,
+                failure_strategy: self.usual.failure_strategy.clone(),
+            }
+        });
+        drop(
+            self.deferred.lock().unwrap_or_else(PoisonError::into_inner).take(),
+        );
+        drop(handover);
+    }
+
+    /// Freezes only the metric families identified by the provided `names`,
+    /// moving them into the immutable storage, while leaving the rest of
+    /// metrics mutable.
+    ///
+    /// This gives the fast path of a [`FrozenRecorder`] to extremely hot
+    /// metrics, without giving up registering other metrics on the fly. The
+    /// selectively frozen metrics are looked up first, falling back to the
+    /// usual ones. May be called multiple times, accumulating the frozen
+    /// metrics, which are included into the immutable storage once this
+    /// [`FreezableRecorder`] is [`.freeze()`]d completely.
+    ///
+    /// Only the already registered metrics may be frozen, so the listed
+    /// metrics, being not registered yet, remain mutable.
+    ///
+    /// No-op, if this [`FreezableRecorder`] has been [`.freeze()`]d already.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(prometheus::Registry::new())
+    ///     .build_freezable();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("hot").increment(1);
+    ///     metrics::counter!("cold").increment(1);
+    /// });
+    /// recorder.freeze_names(["hot"]);
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     // Hits the fast path.
+    ///     metrics::counter!("hot").increment(1);
+    ///     // Still registered on the fly.
+    ///     metrics::counter!("new").increment(1);
+    /// });
+    ///
+    /// let encoder = prometheus::TextEncoder::new();
+    /// let report = encoder.encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP cold cold
+    /// ## TYPE cold counter
+    /// cold 1
+    /// ## HELP hot hot
+    /// ## TYPE hot counter
+    /// hot 2
+    /// ## HELP new new
+    /// ## TYPE new counter
+    /// new 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    /// [`FrozenRecorder`]: super::Frozen
+    /// [`.freeze()`]: Recorder::freeze()
+    // TODO: Try remove on Rust 1.84 upgrade.
+    #[expect( // anonymous lifetimes in `impl Trait` are unstable
+        single_use_lifetimes,
+        reason = "anonymous lifetimes in `impl Trait` are unstable"
+    )]
+    pub fn freeze_names<'n>(&self, names: impl IntoIterator<Item = &'n str>)
+    where
+        S: Clone,
+    {
+        let handover = self.handover.freeze();
+        if self.frozen.get().is_some() {
+            return;
+        }
+        let _span = trace::freezing(true);
+        let frozen = Arc::new(super::Frozen {
+            storage: storage::Immutable::from_names(&self.usual.storage, names),
             failure_strategy: self.usual.failure_strategy.clone(),
         });
+        drop(self.partial.rcu(|partial| {
+            partial.as_ref().map_or_else(
+                || Arc::clone(&frozen),
+                |partial| {
+                    Arc::new(super::Frozen {
+                        storage: partial.storage.merged(&frozen.storage),
+                        failure_strategy: self.usual.failure_strategy.clone(),
+                    })
+                },
+            )
+        }));
+        drop(handover);
+    }
+
+    /// Same as the [`.freeze()`] method, but additionally returns a
+    /// [`FreezeReport`] about the instrumentation hygiene of the metrics
+    /// registered so far, so it can be logged or asserted on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(prometheus::Registry::new())
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build_freezable();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!("forgotten", "Never registered.");
+    ///     metrics::counter!("idle");
+    ///     metrics::counter!("requests", "path" => "/").increment(1);
+    ///     metrics::counter!("requests", "method" => "GET").increment(1);
+    /// });
+    ///
+    /// let report = recorder.freeze_with_report();
+    /// assert_eq!(report.described_only, ["forgotten"]);
+    /// assert_eq!(report.unrecorded, ["idle"]);
+    /// assert_eq!(
+    ///     report.label_anomalies["requests"],
+    ///     [vec!["method".to_owned()], vec!["path".to_owned()]].into(),
+    /// );
+    /// assert!(!report.is_clean());
+    /// ```
+    ///
+    /// [`.freeze()`]: Recorder::freeze()
+    /// [`FreezeReport`]: Report
+    pub fn freeze_with_report(&self) -> Report
+    where
+        S: Clone,
+    {
+        let report = Report {
+            described_only: self.usual.storage.described_only(),
+            unrecorded: self.usual.storage.unrecorded(),
+            label_anomalies: label_anomalies(&self.usual.metrics),
+        };
+        self.freeze();
+        report
+    }
+
+    /// Returns a counter identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its registration (or resolution, once
+    /// this [`FreezableRecorder`] is [`.freeze()`]d), regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the counter cannot be registered or resolved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(prometheus::Registry::new())
+    ///     .build_freezable();
+    ///
+    /// let key = metrics::Key::from_name("requests");
+    /// recorder.checked_counter(&key)?.increment(1);
+    /// recorder.freeze();
+    /// recorder.checked_counter(&key)?.increment(1);
+    ///
+    /// // No new metrics can be registered once frozen.
+    /// let key = metrics::Key::from_name("unknown");
+    /// assert!(recorder.checked_counter(&key).is_err());
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::checked_counter()`]: super::Recorder::checked_counter
+    /// [`.freeze()`]: Recorder::freeze()
+    pub fn checked_counter(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Counter>
+    where
+        S: failure::Strategy,
+    {
+        if self.usual.storage.is_mirrored(key.name()) {
+            return Ok(metrics::Counter::noop());
+        }
+        let _handover = self.hold_off_freezing();
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.checked_counter(key);
+        }
+        if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.name()))
+        {
+            return partial.checked_counter(key);
+        }
+        self.usual.checked_counter(key)
+    }
+
+    /// Returns a gauge identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its registration (or resolution, once
+    /// this [`FreezableRecorder`] is [`.freeze()`]d), regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the gauge cannot be registered or resolved.
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::checked_counter()`]: super::Recorder::checked_counter
+    /// [`.freeze()`]: Recorder::freeze()
+    pub fn checked_gauge(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Gauge>
+    where
+        S: failure::Strategy,
+    {
+        if self.usual.storage.is_mirrored(key.name()) {
+            return Ok(metrics::Gauge::noop());
+        }
+        let _handover = self.hold_off_freezing();
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.checked_gauge(key);
+        }
+        if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.name()))
+        {
+            return partial.checked_gauge(key);
+        }
+        self.usual.checked_gauge(key)
+    }
+
+    /// Returns a histogram identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its registration (or resolution, once
+    /// this [`FreezableRecorder`] is [`.freeze()`]d), regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the histogram cannot be registered or resolved.
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    /// [`FreezableRecorder`]: Recorder
+    /// [`Recorder::checked_counter()`]: super::Recorder::checked_counter
+    /// [`.freeze()`]: Recorder::freeze()
+    pub fn checked_histogram(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Histogram>
+    where
+        S: failure::Strategy,
+    {
+        if self.usual.storage.is_mirrored(key.name()) {
+            return Ok(metrics::Histogram::noop());
+        }
+        let _handover = self.hold_off_freezing();
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.checked_histogram(key);
+        }
+        if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.name()))
+        {
+            return partial.checked_histogram(key);
+        }
+        self.usual.checked_histogram(key)
+    }
+
+    /// Defers the provided [`pending::Description`] till the [`.freeze()`] in
+    /// progress publishes the frozen metrics, returning it back if no
+    /// [`.freeze()`] is in progress.
+    ///
+    /// [`.freeze()`]: Recorder::freeze()
+    /// [`pending::Description`]: storage::pending::Description
+    fn defer_description(
+        &self,
+        description: storage::pending::Description,
+    ) -> Result<(), storage::pending::Description> {
+        if self.frozen.get().is_some() {
+            return Err(description);
+        }
+        let mut deferred =
+            self.deferred.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(queue) = deferred.as_mut() {
+            queue.push(description);
+            Ok(())
+        } else {
+            Err(description)
+        }
+    }
+
+    /// Holds off freezing of this [`FreezableRecorder`] while the returned
+    /// guard is alive, if it's not frozen yet.
+    ///
+    /// Returns [`None`] once this [`FreezableRecorder`] is frozen, so the
+    /// operations on the frozen metrics never touch the [`Handover`].
+    ///
+    /// [`FreezableRecorder`]: Recorder
+    fn hold_off_freezing(&self) -> Option<Entered<'_>> {
+        self.frozen.get().is_none().then(|| self.handover.enter())
+    }
+}
+
+impl<S: failure::Strategy> duration::RecordDuration for Recorder<S> {
+    fn record_duration<N, L>(
+        &self,
+        name: N,
+        labels: L,
+        duration: Duration,
+    ) -> prometheus::Result<()>
+    where
+        N: Into<metrics::KeyName>,
+        L: metrics::IntoLabels,
+    {
+        let name = name.into();
+        // Units are declared in the usual storage only, even once frozen.
+        let value = duration::in_unit(
+            name.as_str(),
+            self.usual.storage.unit(name.as_str()),
+            duration,
+        )?;
+        self.checked_histogram(&metrics::Key::from_parts(name, labels))?
+            .record(value);
+        Ok(())
     }
 }
 
@@ -490,8 +1405,32 @@ where
         unit: Option<metrics::Unit>,
         description: metrics::SharedString,
     ) {
+        let Err(storage::pending::Description {
+            name: key,
+            description,
+            unit,
+            ..
+        }) = self.defer_description(storage::pending::Description {
+            kind: storage::pending::Kind::Counter,
+            name: key,
+            description,
+            unit,
+            #[cfg(feature = "origins")]
+            origin: metric::origin::Origin::capture(None),
+        })
+        else {
+            return;
+        };
+        let _handover = self.hold_off_freezing();
         if let Some(frozen) = self.frozen.get() {
             frozen.describe_counter(key, unit, description);
+        } else if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.as_str()))
+        {
+            partial.describe_counter(key, unit, description);
         } else {
             self.usual.describe_counter(key, unit, description);
         }
@@ -503,8 +1442,32 @@ where
         unit: Option<metrics::Unit>,
         description: metrics::SharedString,
     ) {
+        let Err(storage::pending::Description {
+            name: key,
+            description,
+            unit,
+            ..
+        }) = self.defer_description(storage::pending::Description {
+            kind: storage::pending::Kind::Gauge,
+            name: key,
+            description,
+            unit,
+            #[cfg(feature = "origins")]
+            origin: metric::origin::Origin::capture(None),
+        })
+        else {
+            return;
+        };
+        let _handover = self.hold_off_freezing();
         if let Some(frozen) = self.frozen.get() {
             frozen.describe_gauge(key, unit, description);
+        } else if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.as_str()))
+        {
+            partial.describe_gauge(key, unit, description);
         } else {
             self.usual.describe_gauge(key, unit, description);
         }
@@ -516,8 +1479,32 @@ where
         unit: Option<metrics::Unit>,
         description: metrics::SharedString,
     ) {
+        let Err(storage::pending::Description {
+            name: key,
+            description,
+            unit,
+            ..
+        }) = self.defer_description(storage::pending::Description {
+            kind: storage::pending::Kind::Histogram,
+            name: key,
+            description,
+            unit,
+            #[cfg(feature = "origins")]
+            origin: metric::origin::Origin::capture(None),
+        })
+        else {
+            return;
+        };
+        let _handover = self.hold_off_freezing();
         if let Some(frozen) = self.frozen.get() {
             frozen.describe_histogram(key, unit, description);
+        } else if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.as_str()))
+        {
+            partial.describe_histogram(key, unit, description);
         } else {
             self.usual.describe_histogram(key, unit, description);
         }
@@ -528,10 +1515,22 @@ where
         key: &metrics::Key,
         metadata: &metrics::Metadata<'_>,
     ) -> metrics::Counter {
-        self.frozen.get().map_or_else(
-            || self.usual.register_counter(key, metadata),
-            |frozen| frozen.register_counter(key, metadata),
-        )
+        if self.usual.storage.is_mirrored(key.name()) {
+            return metrics::Counter::noop();
+        }
+        let _handover = self.hold_off_freezing();
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.register_counter(key, metadata);
+        }
+        if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.name()))
+        {
+            return partial.register_counter(key, metadata);
+        }
+        self.usual.register_counter(key, metadata)
     }
 
     fn register_gauge(
@@ -539,10 +1538,22 @@ where
         key: &metrics::Key,
         metadata: &metrics::Metadata<'_>,
     ) -> metrics::Gauge {
-        self.frozen.get().map_or_else(
-            || self.usual.register_gauge(key, metadata),
-            |frozen| frozen.register_gauge(key, metadata),
-        )
+        if self.usual.storage.is_mirrored(key.name()) {
+            return metrics::Gauge::noop();
+        }
+        let _handover = self.hold_off_freezing();
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.register_gauge(key, metadata);
+        }
+        if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.name()))
+        {
+            return partial.register_gauge(key, metadata);
+        }
+        self.usual.register_gauge(key, metadata)
     }
 
     fn register_histogram(
@@ -550,9 +1561,244 @@ where
         key: &metrics::Key,
         metadata: &metrics::Metadata<'_>,
     ) -> metrics::Histogram {
-        self.frozen.get().map_or_else(
-            || self.usual.register_histogram(key, metadata),
-            |frozen| frozen.register_histogram(key, metadata),
+        if self.usual.storage.is_mirrored(key.name()) {
+            return metrics::Histogram::noop();
+        }
+        let _handover = self.hold_off_freezing();
+        if let Some(frozen) = self.frozen.get() {
+            return frozen.register_histogram(key, metadata);
+        }
+        if let Some(partial) = self
+            .partial
+            .load()
+            .as_ref()
+            .filter(|p| p.storage.contains(key.name()))
+        {
+            return partial.register_histogram(key, metadata);
+        }
+        self.usual.register_histogram(key, metadata)
+    }
+}
+
+/// Number of [`Handover`] shards spreading the in-flight operations of
+/// different threads over different cache lines.
+const HANDOVER_SHARDS: usize = 16;
+
+/// Counter of the threads entered any [`Handover`], used to spread them over
+/// its shards.
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Index of the current thread, spreading it over the [`Handover`] shards.
+    static THREAD: usize = THREADS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Handover of the operations on the not yet frozen metrics of a
+/// [`FreezableRecorder`] to the frozen ones.
+///
+/// The operations don't lock anything, unless a freezing is in progress,
+/// rather counting themselves as in-flight in a shard of the current thread,
+/// while the freezing waits for all the shards to become empty before
+/// draining the metrics.
+///
+/// [`FreezableRecorder`]: Recorder
+#[derive(Debug)]
+struct Handover {
+    /// Numbers of the in-flight operations, sharded by threads.
+    in_flight: [InFlight; HANDOVER_SHARDS],
+
+    /// Indicator whether a freezing is in progress, so the new operations
+    /// should wait for it to complete.
+    freezing: AtomicBool,
+
+    /// Lock serializing freezings, and [`read`-lock]ed by the operations
+    /// waiting for the freezing in progress to complete.
+    ///
+    /// [`read`-lock]: RwLock::read()
+    lock: RwLock<()>,
+}
+
+impl Default for Handover {
+    fn default() -> Self {
+        Self {
+            in_flight: Default::default(),
+            freezing: AtomicBool::new(false),
+            lock: RwLock::new(()),
+        }
+    }
+}
+
+/// Number of the in-flight operations in a single shard of a [`Handover`].
+///
+/// Aligned to a cache line, so the shards don't contend with each other.
+#[derive(Debug, Default)]
+#[repr(align(128))]
+struct InFlight(AtomicUsize);
+
+impl Handover {
+    /// Enters an operation, which is never run concurrently with a freezing,
+    /// until the returned [`Entered`] guard is dropped.
+    ///
+    /// Waits for the freezing in progress (if any) to complete.
+    fn enter(&self) -> Entered<'_> {
+        // Thread-local storage being destroyed already falls back to the
+        // first shard.
+        let index = THREAD.try_with(|i| *i).unwrap_or_default();
+        let shard = &self.in_flight[index % HANDOVER_SHARDS];
+
+        // `SeqCst` pairs with the one in `Handover::freeze()`, so either the
+        // freezing sees this operation in-flight, or this operation sees the
+        // freezing in progress.
+        _ = shard.0.fetch_add(1, Ordering::SeqCst);
+        if !self.freezing.load(Ordering::SeqCst) {
+            return Entered::InFlight(shard);
+        }
+        _ = shard.0.fetch_sub(1, Ordering::SeqCst);
+        Entered::Waited(
+            self.lock.read().unwrap_or_else(PoisonError::into_inner),
         )
     }
+
+    /// Starts a freezing, waiting for all the in-flight operations to
+    /// complete, and holding off the new ones until the returned guard is
+    /// dropped.
+    fn freeze(&self) -> Freezing<'_> {
+        let lock = self.lock.write().unwrap_or_else(PoisonError::into_inner);
+        self.freezing.store(true, Ordering::SeqCst);
+        while self.in_flight.iter().any(|s| s.0.load(Ordering::SeqCst) > 0) {
+            thread::yield_now();
+        }
+        Freezing { handover: self, _lock: lock }
+    }
+}
+
+/// Guard of an operation entered into a [`Handover`].
+#[derive(Debug)]
+enum Entered<'h> {
+    /// Operation is counted as in-flight in the referred shard.
+    InFlight(&'h InFlight),
+
+    /// Operation has waited for a freezing to complete, holding off the next
+    /// one.
+    Waited(
+        #[expect( // intentional
+            dead_code,
+            reason = "held for its `Drop` only"
+        )]
+        RwLockReadGuard<'h, ()>,
+    ),
+}
+
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        if let Self::InFlight(shard) = self {
+            _ = shard.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Guard of a freezing in progress of a [`Handover`].
+#[derive(Debug)]
+struct Freezing<'h> {
+    /// [`Handover`] being frozen.
+    handover: &'h Handover,
+
+    /// Lock serializing freezings.
+    _lock: RwLockWriteGuard<'h, ()>,
+}
+
+impl Drop for Freezing<'_> {
+    fn drop(&mut self) {
+        self.handover.freezing.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Report about the instrumentation hygiene of the metrics registered in a
+/// [`FreezableRecorder`], returned by its
+/// [`.freeze_with_report()`][Recorder::freeze_with_report] method.
+///
+/// [`FreezableRecorder`]: Recorder
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report {
+    /// Sorted names of the metrics having a [`help` description], but never
+    /// registered.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub described_only: Vec<String>,
+
+    /// Sorted names of the registered metrics, having no values recorded into
+    /// them.
+    ///
+    /// As [`prometheus`] metrics don't track whether they were ever recorded,
+    /// the metric is considered so, if all its values are zero (so a gauge
+    /// explicitly set to zero is reported too).
+    pub unrecorded: Vec<String>,
+
+    /// Names of the metrics registered with different sets of label names,
+    /// along with all these sets.
+    pub label_anomalies: BTreeMap<String, BTreeSet<Vec<String>>>,
+}
+
+impl Report {
+    /// Indicates whether this [`Report`] has no issues.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.described_only.is_empty()
+            && self.unrecorded.is_empty()
+            && self.label_anomalies.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "no issues");
+        }
+        let mut issues = Vec::new();
+        if !self.described_only.is_empty() {
+            issues.push(format!(
+                "described, but never registered: {}",
+                self.described_only.join(", "),
+            ));
+        }
+        if !self.unrecorded.is_empty() {
+            issues.push(format!(
+                "registered, but never recorded: {}",
+                self.unrecorded.join(", "),
+            ));
+        }
+        for (name, sets) in &self.label_anomalies {
+            let sets = sets
+                .iter()
+                .map(|labels| format!("[{}]", labels.join(", ")))
+                .collect::<Vec<_>>();
+            issues.push(format!(
+                "`{name}` registered with different labels: {}",
+                sets.join(", "),
+            ));
+        }
+        write!(f, "{}", issues.join("; "))
+    }
+}
+
+/// Collects names of the metrics registered in the provided
+/// [`metrics::Registry`] with different sets of label names, along with all
+/// these sets.
+///
+/// [`metrics::Registry`]: metrics_util::registry::Registry
+fn label_anomalies(
+    registry: &metrics_util::registry::Registry<metrics::Key, storage::Mutable>,
+) -> BTreeMap<String, BTreeSet<Vec<String>>> {
+    let mut all = BTreeMap::<String, BTreeSet<Vec<String>>>::new();
+    let mut collect = |key: &metrics::Key| {
+        let mut labels =
+            key.labels().map(|l| l.key().to_owned()).collect::<Vec<_>>();
+        labels.sort_unstable();
+        _ = all.entry(key.name().to_owned()).or_default().insert(labels);
+    };
+    registry.visit_counters(|key, _| collect(key));
+    registry.visit_gauges(|key, _| collect(key));
+    registry.visit_histograms(|key, _| collect(key));
+    all.retain(|_, sets| sets.len() > 1);
+    all
 }
@@ -1,10 +1,17 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! Fast and read-only [`metrics::Recorder`].
 
-use std::sync::Arc;
+#[cfg(feature = "timestamps")]
+use std::time::Instant;
+use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
 
+#[cfg(feature = "relabel")]
+use crate::relabel;
 use crate::{
-    failure::{self, strategy::PanicInDebugNoOpInRelease},
-    storage,
+    catalog, config,
+    failure::{self, strategy::PanicInDebugNoOpInRelease, Resolve as _},
+    metric, storage, trace,
 };
 
 use super::Builder;
@@ -26,6 +33,7 @@ use super::Builder;
 ///
 /// ```rust
 /// let registry = metrics_prometheus::Recorder::builder()
+///     .with_default_registry()
 ///     .with_metric(prometheus::IntCounterVec::new(
 ///         prometheus::opts!("count", "help"),
 ///         &["whose", "kind"],
@@ -138,6 +146,8 @@ use super::Builder;
 /// metrics::gauge!("value").increment(1.0);
 /// // This panics, as such labeling is not allowed by `prometheus` crate.
 /// metrics::gauge!("value", "whose" => "mine").increment(2.0);
+/// # #[cfg(feature = "never-panic")]
+/// # panic!("suppressed by `never-panic` feature");
 /// # Ok::<_, prometheus::Error>(())
 /// ```
 ///
@@ -165,6 +175,445 @@ impl Recorder {
     }
 }
 
+impl<S> Recorder<S> {
+    /// Creates a new [`FrozenRecorder`] on top of the provided
+    /// [`storage::Immutable`], applying the provided [`failure::Strategy`]
+    /// when a [`prometheus::Error`] is encountered.
+    ///
+    /// Useful along with a [`storage::immutable::Builder`] to construct a
+    /// [`FrozenRecorder`] out of a known upfront set of [`prometheus`]
+    /// metrics.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub const fn from_storage(
+        storage: storage::Immutable,
+        failure_strategy: S,
+    ) -> Self {
+        Self { storage, failure_strategy }
+    }
+
+    /// Returns the underlying [`storage::Immutable`] of this
+    /// [`FrozenRecorder`].
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub const fn storage(&self) -> &storage::Immutable {
+        &self.storage
+    }
+
+    /// Returns the underlying [`prometheus::Registry`] backing this
+    /// [`FrozenRecorder`].
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub fn registry(&self) -> &prometheus::Registry {
+        &self.storage.prometheus
+    }
+
+    /// Returns the underlying [`prometheus::Registry`] backing this
+    /// [`FrozenRecorder`] as a cheaply cloneable [`Arc`].
+    ///
+    /// Useful for handing the [`prometheus::Registry`] over to a `'static`
+    /// task (like an HTTP server exposing the metrics).
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub fn registry_arc(&self) -> Arc<prometheus::Registry> {
+        Arc::clone(&self.storage.prometheus)
+    }
+
+    /// Renders the metrics of this [`FrozenRecorder`] in the Prometheus text
+    /// format, keeping only the series matching the provided `predicate`.
+    ///
+    /// See the [`Recorder::render_text_filtered()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the gathered metrics cannot be encoded.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    /// [`Recorder::render_text_filtered()`]:
+    ///     super::Recorder::render_text_filtered
+    pub fn render_text_filtered<F>(
+        &self,
+        predicate: F,
+    ) -> prometheus::Result<String>
+    where
+        F: FnMut(&str, &[(&str, &str)]) -> bool,
+    {
+        super::render_text_filtered(
+            self.registry(),
+            &self.storage.merged,
+            predicate,
+        )
+    }
+
+    /// Returns the [`catalog::Snapshot`] of the metric families of this
+    /// [`FrozenRecorder`], which may be persisted and restored on the next
+    /// run via the [`Builder::try_with_catalog()`] method.
+    ///
+    /// See the [`catalog::Snapshot`] for an example.
+    ///
+    /// [`Builder::try_with_catalog()`]: super::Builder::try_with_catalog
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub fn catalog(&self) -> catalog::Snapshot {
+        self.storage.catalog()
+    }
+
+    /// Returns the numbers of accesses to the metrics unknown to this
+    /// [`FrozenRecorder`] (not registered before it was built), by their
+    /// names.
+    ///
+    /// Such metrics are silently no-op, so this allows to discover the
+    /// instrumentation added after freezing, which is being dropped.
+    ///
+    /// Empty, unless enabled via the [`Builder::with_unknown_keys_tracked()`]
+    /// method.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_metric(prometheus::IntCounter::new("requests", "help")?)
+    ///     .with_unknown_keys_tracked(1, true)
+    ///     .build_frozen();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests").increment(1);
+    ///     metrics::counter!("retries").increment(1);
+    ///     metrics::counter!("retries").increment(1);
+    ///     // Doesn't fit into the tracked names.
+    ///     metrics::gauge!("queue").set(1.0);
+    /// });
+    ///
+    /// let unknown = recorder.unknown_keys();
+    /// assert_eq!(unknown.len(), 2);
+    /// assert_eq!(unknown["retries"], 2);
+    /// assert_eq!(unknown["__overflow__"], 1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(
+    ///     r#"metrics_prometheus_frozen_unknown_total{name="retries"} 2"#,
+    /// ));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Builder::with_unknown_keys_tracked()`]:
+    ///     super::Builder::with_unknown_keys_tracked
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub fn unknown_keys(&self) -> BTreeMap<String, u64> {
+        self.storage.unknown_keys()
+    }
+
+    /// Returns [`storage::Counts`] of the metric families of this
+    /// [`FrozenRecorder`].
+    ///
+    /// See the [`Recorder::metric_counts()`] method for details.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    /// [`Recorder::metric_counts()`]: super::Recorder::metric_counts
+    #[must_use]
+    pub fn metric_counts(&self) -> storage::Counts {
+        self.storage.metric_counts()
+    }
+
+    /// Unregisters all the [`prometheus`] metrics of this [`FrozenRecorder`]
+    /// from its [`prometheus::Registry`], restoring it to its prior state.
+    ///
+    /// The already obtained [`metrics`] handles keep working, but their values
+    /// are not exported anymore.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    pub fn shutdown(&self) {
+        self.storage.unregister_all();
+    }
+
+    /// Returns the [`relabel::Rules`] applied at gather time to the metrics
+    /// of this [`FrozenRecorder`], allowing to replace them at runtime.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    #[cfg(feature = "relabel")]
+    #[must_use]
+    pub const fn relabel_rules(&self) -> &relabel::Rules {
+        &self.storage.relabel
+    }
+
+    /// Returns the [`config::Reloadable`] configuration of the metrics of this
+    /// [`FrozenRecorder`].
+    ///
+    /// Only the [`config::Config::denied`] and
+    /// [`config::Config::default_labels`] apply to a [`FrozenRecorder`], as
+    /// it doesn't register metrics on the fly.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub const fn config(&self) -> &config::Reloadable {
+        &self.storage.config
+    }
+
+    /// Replaces the [`config::Config`] of the metrics of this
+    /// [`FrozenRecorder`] with the provided one.
+    ///
+    /// See the [`FrozenRecorder::config()`] method for details.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    /// [`FrozenRecorder::config()`]: Recorder::config
+    pub fn update_config(&self, config: config::Config) {
+        self.storage.config.set(config);
+    }
+
+    /// Returns the numbers of usages (registrations) of the deprecated metric
+    /// names via this [`FrozenRecorder`], by these names.
+    ///
+    /// See the [`Builder::with_deprecated()`] method for details.
+    ///
+    /// [`Builder::with_deprecated()`]: super::Builder::with_deprecated
+    /// [`FrozenRecorder`]: Recorder
+    #[must_use]
+    pub fn deprecated_usages(&self) -> BTreeMap<String, u64> {
+        self.storage.deprecated_usages()
+    }
+
+    /// Returns the [`Instant`] the metric identified by the provided `name`
+    /// and `labels` (in any order) was updated last time via this
+    /// [`FrozenRecorder`].
+    ///
+    /// [`None`] is returned if the metric has never been updated.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn last_updated(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+    ) -> Option<Instant> {
+        self.storage.timestamps.get(name, labels)
+    }
+}
+
+impl<S: failure::Strategy> Recorder<S> {
+    /// Returns a counter identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its resolution, regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the counter cannot be resolved, or hasn't been registered before
+    /// this [`FrozenRecorder`] was built.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    /// [`Recorder::checked_counter()`]: super::Recorder::checked_counter
+    pub fn checked_counter(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Counter> {
+        let key = &*self.normalized(key, super::METADATA.target());
+        if self.storage.is_denied(key.name()) {
+            return Ok(metrics::Counter::noop());
+        }
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().counter(key))
+        {
+            return res;
+        }
+        // TODO: Eliminate this `Arc` allocation via `metrics` PR.
+        self.storage
+            .get_metric::<prometheus::IntCounter>(key)
+            .unwrap_or_else(|| Err(unregistered(key)))
+            .map(|m| self.counter(key, Arc::new(m)))
+    }
+
+    /// Returns a gauge identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its resolution, regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the gauge cannot be resolved, or hasn't been registered before this
+    /// [`FrozenRecorder`] was built.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    /// [`Recorder::checked_counter()`]: super::Recorder::checked_counter
+    pub fn checked_gauge(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Gauge> {
+        let key = &*self.normalized(key, super::METADATA.target());
+        if self.storage.is_denied(key.name()) {
+            return Ok(metrics::Gauge::noop());
+        }
+        if let Some(res) =
+            self.storage.custom(key.name()).and_then(|b| b.as_ref().gauge(key))
+        {
+            return res;
+        }
+        // TODO: Eliminate this `Arc` allocation via `metrics` PR.
+        self.storage
+            .get_metric::<prometheus::Gauge>(key)
+            .or_else(|| self.storage.counter_misuse(key.name()).map(Err))
+            .unwrap_or_else(|| Err(unregistered(key)))
+            .map(|m| metrics::Gauge::from_arc(Arc::new(m)))
+    }
+
+    /// Returns a histogram identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its resolution, regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the histogram cannot be resolved, or hasn't been registered before
+    /// this [`FrozenRecorder`] was built.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    /// [`Recorder::checked_counter()`]: super::Recorder::checked_counter
+    pub fn checked_histogram(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Histogram> {
+        let key = &*self.normalized(key, super::METADATA.target());
+        if self.storage.is_denied(key.name()) {
+            return Ok(metrics::Histogram::noop());
+        }
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().histogram(key))
+        {
+            return res;
+        }
+        if let Some(res) = self.storage.adaptive_histogram(key) {
+            return res.map(|h| {
+                self.with_over_threshold(key, metrics::Histogram::from_arc(h))
+            });
+        }
+        // TODO: Eliminate this `Arc` allocation via `metrics` PR.
+        self.storage
+            .get_metric::<prometheus::Histogram>(key)
+            .or_else(|| self.storage.counter_misuse(key.name()).map(Err))
+            .unwrap_or_else(|| Err(unregistered(key)))
+            .map(|m| {
+                let histogram = metrics::Histogram::from_arc(Arc::new(m));
+                self.with_over_threshold(key, histogram)
+            })
+    }
+
+    /// Wraps the provided [`metrics::Histogram`] identified by the provided
+    /// [`metrics::Key`] into a [`metric::threshold::Histogram`], if it's
+    /// accompanied by `*_over_threshold_total` counters.
+    fn with_over_threshold(
+        &self,
+        key: &metrics::Key,
+        histogram: metrics::Histogram,
+    ) -> metrics::Histogram {
+        match self.storage.over_threshold(key) {
+            None => histogram,
+            Some(child) => metrics::Histogram::from_arc(Arc::new(
+                metric::threshold::Histogram::new(histogram, child),
+            )),
+        }
+    }
+
+    /// Normalizes the provided [`metrics::Key`] of a metric registered by the
+    /// provided `target` (as reported by [`metrics::Metadata::target()`]):
+    /// prefixes its name according to the [`target::Prefix`] rules, and
+    /// routes it to its replacement, if its name is deprecated.
+    ///
+    /// Shared by all the resolution paths, so they resolve the same
+    /// [`metrics::Key`] into the same metric.
+    ///
+    /// [`target::Prefix`]: crate::target::Prefix
+    fn normalized<'k>(
+        &self,
+        key: &'k metrics::Key,
+        target: &str,
+    ) -> Cow<'k, metrics::Key> {
+        self.route_deprecated(self.storage.prefixed(key, target))
+    }
+
+    /// Routes the provided [`metrics::Key`] to its replacement, if its name is
+    /// deprecated.
+    fn route_deprecated<'k>(
+        &self,
+        key: Cow<'k, metrics::Key>,
+    ) -> Cow<'k, metrics::Key> {
+        let Some((routed, res)) = self.storage.route_deprecated(&key) else {
+            return key;
+        };
+        if let Err(e) = res {
+            match self.failure_strategy.resolve_for(&key, &e) {
+                failure::Action::NoOp => {}
+                failure::Action::Panic => {
+                    panic!("failed to track deprecated metric: {e}")
+                }
+            }
+        }
+        Cow::Owned(routed)
+    }
+
+    /// Tracks an access to the metric identified by the provided
+    /// [`metrics::Key`], if it's unknown to this [`FrozenRecorder`], and
+    /// tracking of unknown metrics is enabled.
+    ///
+    /// [`FrozenRecorder`]: Recorder
+    fn track_unknown(&self, key: &metrics::Key) {
+        if let Err(e) = self.storage.track_unknown(key.name()) {
+            match self.failure_strategy.resolve_for(key, &e) {
+                failure::Action::NoOp => {}
+                failure::Action::Panic => {
+                    panic!("failed to track unknown metric: {e}")
+                }
+            }
+        }
+    }
+
+    /// Converts the provided [`prometheus::IntCounter`] into a
+    /// [`metrics::Counter`], reconciling its declared
+    /// [`metric::ingestion::Mode`] or guarding it against regressions, if
+    /// required.
+    fn counter(
+        &self,
+        key: &metrics::Key,
+        counter: Arc<metric::Metric<prometheus::IntCounter>>,
+    ) -> metrics::Counter {
+        if let Some(mode) = self.storage.ingestion.find(key.name()) {
+            return metrics::Counter::from_arc(Arc::new(
+                metric::ingestion::Counter::new(
+                    counter,
+                    key,
+                    *mode,
+                    &self.storage.totals,
+                    &self.failure_strategy,
+                ),
+            ));
+        }
+        if self.storage.monotonic {
+            metrics::Counter::from_arc(Arc::new(
+                metric::monotonic::Counter::guard(
+                    counter,
+                    key.name(),
+                    &self.failure_strategy,
+                ),
+            ))
+        } else {
+            metrics::Counter::from_arc(counter)
+        }
+    }
+}
+
 #[warn(clippy::missing_trait_methods)]
 impl<S> metrics::Recorder for Recorder<S>
 where
@@ -176,10 +625,10 @@ where
         _: Option<metrics::Unit>,
         description: metrics::SharedString,
     ) {
-        self.storage.describe::<prometheus::IntCounter>(
-            key.as_str(),
-            description.into_owned(),
-        );
+        let key = self.storage.replace_deprecated(key);
+        trace::described("counter", key.as_str());
+        self.storage
+            .describe::<prometheus::IntCounter>(key.as_str(), description);
     }
 
     fn describe_gauge(
@@ -188,10 +637,9 @@ where
         _: Option<metrics::Unit>,
         description: metrics::SharedString,
     ) {
-        self.storage.describe::<prometheus::Gauge>(
-            key.as_str(),
-            description.into_owned(),
-        );
+        let key = self.storage.replace_deprecated(key);
+        trace::described("gauge", key.as_str());
+        self.storage.describe::<prometheus::Gauge>(key.as_str(), description);
     }
 
     fn describe_histogram(
@@ -200,48 +648,97 @@ where
         _: Option<metrics::Unit>,
         description: metrics::SharedString,
     ) {
-        self.storage.describe::<prometheus::Histogram>(
-            key.as_str(),
-            description.into_owned(),
-        );
+        let key = self.storage.replace_deprecated(key);
+        trace::described("histogram", key.as_str());
+        self.storage
+            .describe_adaptive_histogram(key.as_str(), description.clone());
+        self.storage
+            .describe::<prometheus::Histogram>(key.as_str(), description);
     }
 
     fn register_counter(
         &self,
         key: &metrics::Key,
-        _: &metrics::Metadata<'_>,
+        metadata: &metrics::Metadata<'_>,
     ) -> metrics::Counter {
-        self.storage
-            .get_metric::<prometheus::IntCounter>(key)
-            .and_then(|res| {
-                res.map_err(|e| match self.failure_strategy.decide(&e) {
-                    failure::Action::NoOp => (),
+        let key = &*self.normalized(key, metadata.target());
+        if self.storage.is_denied(key.name()) {
+            return metrics::Counter::noop();
+        }
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().counter(key))
+        {
+            return res.unwrap_or_else(|e| {
+                match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Counter::noop(),
                     failure::Action::Panic => panic!(
+                        "failed to register custom `prometheus` metric: {e}",
+                    ),
+                }
+            });
+        }
+
+        let metric = self.storage.get_metric::<prometheus::IntCounter>(key);
+        if metric.is_none() {
+            self.track_unknown(key);
+        }
+        metric
+            .and_then(|res| {
+                res.map_err(|e| {
+                    match self.failure_strategy.resolve_for(key, &e) {
+                        failure::Action::NoOp => (),
+                        failure::Action::Panic => panic!(
                         "failed to register `prometheus::IntCounter` metric: \
                          {e}",
                     ),
+                    }
                 })
                 .ok()
             })
             .map_or_else(metrics::Counter::noop, |m| {
                 // TODO: Eliminate this `Arc` allocation via `metrics` PR.
-                metrics::Counter::from_arc(Arc::new(m))
+                self.counter(key, Arc::new(m))
             })
     }
 
     fn register_gauge(
         &self,
         key: &metrics::Key,
-        _: &metrics::Metadata<'_>,
+        metadata: &metrics::Metadata<'_>,
     ) -> metrics::Gauge {
-        self.storage
-            .get_metric::<prometheus::Gauge>(key)
-            .and_then(|res| {
-                res.map_err(|e| match self.failure_strategy.decide(&e) {
-                    failure::Action::NoOp => (),
+        let key = &*self.normalized(key, metadata.target());
+        if self.storage.is_denied(key.name()) {
+            return metrics::Gauge::noop();
+        }
+        if let Some(res) =
+            self.storage.custom(key.name()).and_then(|b| b.as_ref().gauge(key))
+        {
+            return res.unwrap_or_else(|e| {
+                match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Gauge::noop(),
                     failure::Action::Panic => panic!(
+                        "failed to register custom `prometheus` metric: {e}",
+                    ),
+                }
+            });
+        }
+
+        let metric = self.storage.get_metric::<prometheus::Gauge>(key);
+        if metric.is_none() {
+            self.track_unknown(key);
+        }
+        metric
+            .or_else(|| self.storage.counter_misuse(key.name()).map(Err))
+            .and_then(|res| {
+                res.map_err(|e| {
+                    match self.failure_strategy.resolve_for(key, &e) {
+                        failure::Action::NoOp => (),
+                        failure::Action::Panic => panic!(
                         "failed to register `prometheus::Gauge` metric: {e}",
                     ),
+                    }
                 })
                 .ok()
             })
@@ -254,23 +751,74 @@ where
     fn register_histogram(
         &self,
         key: &metrics::Key,
-        _: &metrics::Metadata<'_>,
+        metadata: &metrics::Metadata<'_>,
     ) -> metrics::Histogram {
-        self.storage
-            .get_metric::<prometheus::Histogram>(key)
-            .and_then(|res| {
-                res.map_err(|e| match self.failure_strategy.decide(&e) {
-                    failure::Action::NoOp => (),
+        let key = &*self.normalized(key, metadata.target());
+        if self.storage.is_denied(key.name()) {
+            return metrics::Histogram::noop();
+        }
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().histogram(key))
+        {
+            return res.unwrap_or_else(|e| {
+                match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Histogram::noop(),
                     failure::Action::Panic => panic!(
+                        "failed to register custom `prometheus` metric: {e}",
+                    ),
+                }
+            });
+        }
+
+        if let Some(res) = self.storage.adaptive_histogram(key) {
+            let histogram = res.map_or_else(
+                |e| match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Histogram::noop(),
+                    failure::Action::Panic => panic!(
+                        "failed to register adaptive `prometheus::Histogram` \
+                         metric: {e}",
+                    ),
+                },
+                metrics::Histogram::from_arc,
+            );
+            return self.with_over_threshold(key, histogram);
+        }
+
+        let metric = self.storage.get_metric::<prometheus::Histogram>(key);
+        if metric.is_none() {
+            self.track_unknown(key);
+        }
+        metric
+            .or_else(|| self.storage.counter_misuse(key.name()).map(Err))
+            .and_then(|res| {
+                res.map_err(|e| {
+                    match self.failure_strategy.resolve_for(key, &e) {
+                        failure::Action::NoOp => (),
+                        failure::Action::Panic => panic!(
                         "failed to register `prometheus::Histogram` metric: \
                          {e}",
                     ),
+                    }
                 })
                 .ok()
             })
             .map_or_else(metrics::Histogram::noop, |m| {
                 // TODO: Eliminate this `Arc` allocation via `metrics` PR.
-                metrics::Histogram::from_arc(Arc::new(m))
+                let histogram = metrics::Histogram::from_arc(Arc::new(m));
+                self.with_over_threshold(key, histogram)
             })
     }
 }
+
+/// Creates a [`prometheus::Error`] of the provided [`metrics::Key`] being not
+/// registered in a [`FrozenRecorder`].
+///
+/// [`FrozenRecorder`]: Recorder
+fn unregistered(key: &metrics::Key) -> prometheus::Error {
+    prometheus::Error::Msg(format!(
+        "`{}` metric is not registered in `FrozenRecorder`",
+        key.name(),
+    ))
+}
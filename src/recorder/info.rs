@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [OpenMetrics `Info`][0] handles, exposing textual information as labels of
+//! an always-`1` gauge.
+//!
+//! [0]: https://prometheus.io/docs/specs/om/open_metrics_spec/#info
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// Handle of a [OpenMetrics `Info`][0], returned by the
+/// [`Recorder::register_info()`] method.
+///
+/// Exposed as a single always-`1` gauge, carrying its payload in the labels.
+///
+/// [`Recorder::register_info()`]: super::Recorder::register_info
+/// [0]: https://prometheus.io/docs/specs/om/open_metrics_spec/#info
+#[derive(Clone, Debug)]
+pub struct Info(Arc<Inner>);
+
+/// Shared state of an [`Info`].
+#[derive(Debug)]
+struct Inner {
+    /// [`prometheus::core::Desc`] of the gauge of an [`Info`].
+    ///
+    /// Declares no labels, so the payload may be replaced freely, without
+    /// re-registering the gauge in a [`prometheus::Registry`].
+    desc: prometheus::core::Desc,
+
+    /// Labels of the gauge, being the payload of an [`Info`].
+    labels: Mutex<Vec<(String, String)>>,
+}
+
+impl Info {
+    /// Registers a new [`Info`] with the provided `name` and `labels` payload
+    /// in the provided [`prometheus::Registry`].
+    ///
+    /// # Errors
+    ///
+    /// If the provided `name` or `labels` are invalid, or the gauge cannot be
+    /// registered.
+    pub(super) fn register(
+        registry: &prometheus::Registry,
+        name: &str,
+        labels: Vec<(String, String)>,
+    ) -> prometheus::Result<Self> {
+        validate(name, &labels)?;
+        let desc = prometheus::core::Desc::new(
+            name.into(),
+            name.into(),
+            vec![],
+            HashMap::new(),
+        )?;
+        let info = Self(Arc::new(Inner { desc, labels: Mutex::new(labels) }));
+        registry.register(Box::new(info.clone()))?;
+        Ok(info)
+    }
+
+    /// Returns the name of the gauge of this [`Info`].
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.0.desc.fq_name
+    }
+
+    /// Returns the current labels payload of this [`Info`].
+    #[must_use]
+    pub fn labels(&self) -> Vec<(String, String)> {
+        self.0.labels.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Replaces the labels payload of this [`Info`] with the provided one.
+    ///
+    /// The payload is swapped atomically, so the [`Info`] is never missing in
+    /// the exposition. Label names may differ from the previous ones.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `labels` are invalid. The previous payload is kept in
+    /// such case.
+    pub fn set<I, K, V>(&self, labels: I) -> prometheus::Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let labels = labels
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect::<Vec<_>>();
+        validate(self.name(), &labels)?;
+        *self.0.labels.lock().unwrap_or_else(PoisonError::into_inner) = labels;
+        Ok(())
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Info {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.0.desc]
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        use prometheus::proto;
+
+        let mut labels = self.labels();
+        labels.sort_unstable();
+
+        let mut metric = proto::Metric::default();
+        metric.set_label(
+            labels
+                .into_iter()
+                .map(|(name, value)| {
+                    let mut label = proto::LabelPair::default();
+                    label.set_name(name);
+                    label.set_value(value);
+                    label
+                })
+                .collect(),
+        );
+        let mut gauge = proto::Gauge::default();
+        gauge.set_value(1.0);
+        metric.set_gauge(gauge);
+
+        let mut family = proto::MetricFamily::default();
+        family.set_name(self.0.desc.fq_name.clone());
+        family.set_help(self.0.desc.help.clone());
+        family.set_field_type(proto::MetricType::GAUGE);
+        family.mut_metric().push(metric);
+        vec![family]
+    }
+}
+
+/// Validates the provided `labels` payload of an [`Info`] with the provided
+/// `name`.
+///
+/// # Errors
+///
+/// If the provided `name` or `labels` are invalid (like having invalid or
+/// duplicated label names).
+fn validate(name: &str, labels: &[(String, String)]) -> prometheus::Result<()> {
+    let const_labels = labels.iter().cloned().collect::<HashMap<_, _>>();
+    if const_labels.len() != labels.len() {
+        return Err(prometheus::Error::Msg(format!(
+            "`{name}` info has duplicated label names",
+        )));
+    }
+    drop(prometheus::core::Desc::new(
+        name.into(),
+        name.into(),
+        vec![],
+        const_labels,
+    )?);
+    Ok(())
+}
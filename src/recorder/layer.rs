@@ -1,7 +1,13 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! [`metrics::Layer`] implementations.
 //!
 //! [`metrics::Layer`]: Layer
 
+use metrics_util::layers::{Fanout, FanoutBuilder};
+
+use crate::failure;
+
 pub use metrics_util::layers::Layer;
 
 /// No-op [`metrics::Layer`] which returns the received [`metrics::Recorder`]
@@ -62,3 +68,200 @@ where
         self.0.layer(self.1.layer(inner))
     }
 }
+
+/// Either of two [`metrics::Layer`]s (or [`metrics::Recorder`]s produced by
+/// them), allowing to choose one of them at runtime while keeping a single
+/// type.
+///
+/// Used by the [`Builder::with_layer_when()`] method to toggle a
+/// [`metrics::Layer`] by configuration (see [`Optional`]).
+///
+/// [`Builder::with_layer_when()`]: super::Builder::with_layer_when
+/// [`metrics::Layer`]: Layer
+#[derive(Clone, Copy, Debug)]
+pub enum Either<L, R> {
+    /// Left [`metrics::Layer`] or [`metrics::Recorder`].
+    ///
+    /// [`metrics::Layer`]: Layer
+    Left(L),
+
+    /// Right [`metrics::Layer`] or [`metrics::Recorder`].
+    ///
+    /// [`metrics::Layer`]: Layer
+    Right(R),
+}
+
+/// [`metrics::Layer`] enabled or disabled at runtime, being either the
+/// provided one or an [`Identity`].
+///
+/// [`metrics::Layer`]: Layer
+pub type Optional<L> = Either<L, Identity>;
+
+#[warn(clippy::missing_trait_methods)]
+impl<Rec, L, R> Layer<Rec> for Either<L, R>
+where
+    L: Layer<Rec>,
+    R: Layer<Rec>,
+{
+    type Output = Either<L::Output, R::Output>;
+
+    fn layer(&self, inner: Rec) -> Self::Output {
+        match self {
+            Self::Left(l) => Either::Left(l.layer(inner)),
+            Self::Right(r) => Either::Right(r.layer(inner)),
+        }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl<L, R> metrics::Recorder for Either<L, R>
+where
+    L: metrics::Recorder,
+    R: metrics::Recorder,
+{
+    fn describe_counter(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        match self {
+            Self::Left(l) => l.describe_counter(key, unit, description),
+            Self::Right(r) => r.describe_counter(key, unit, description),
+        }
+    }
+
+    fn describe_gauge(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        match self {
+            Self::Left(l) => l.describe_gauge(key, unit, description),
+            Self::Right(r) => r.describe_gauge(key, unit, description),
+        }
+    }
+
+    fn describe_histogram(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        match self {
+            Self::Left(l) => l.describe_histogram(key, unit, description),
+            Self::Right(r) => r.describe_histogram(key, unit, description),
+        }
+    }
+
+    fn register_counter(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Counter {
+        match self {
+            Self::Left(l) => l.register_counter(key, metadata),
+            Self::Right(r) => r.register_counter(key, metadata),
+        }
+    }
+
+    fn register_gauge(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Gauge {
+        match self {
+            Self::Left(l) => l.register_gauge(key, metadata),
+            Self::Right(r) => r.register_gauge(key, metadata),
+        }
+    }
+
+    fn register_histogram(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Histogram {
+        match self {
+            Self::Left(l) => l.register_histogram(key, metadata),
+            Self::Right(r) => r.register_histogram(key, metadata),
+        }
+    }
+}
+
+/// Chains this [`Recorder`] with the wrapped `inner` [`metrics::Recorder`],
+/// forwarding all the calls to both of them (via a [`Fanout`]).
+///
+/// Useful for recording the same metrics into another [`metrics::Recorder`]
+/// along (like a [`DebuggingRecorder`] in tests), without constructing a
+/// [`Fanout`] manually.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::recorder::layer::Layer as _;
+///
+/// let outer = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .build();
+/// let inner = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .build();
+/// let chained = outer.layer(inner.clone());
+///
+/// metrics::with_local_recorder(&chained, || {
+///     metrics::counter!("requests").increment(1);
+/// });
+///
+/// let encoder = prometheus::TextEncoder::new();
+/// for recorder in [&outer, &inner] {
+///     let report = encoder.encode_to_string(&recorder.registry().gather())?;
+///     assert!(report.contains("requests 1"));
+/// }
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`DebuggingRecorder`]:
+///     https://docs.rs/metrics-util/0.19/metrics_util/debugging
+/// [`Fanout`]: metrics_util::layers::Fanout
+/// [`Recorder`]: super::Recorder
+#[warn(clippy::missing_trait_methods)]
+impl<S, R> Layer<R> for super::Recorder<S>
+where
+    S: failure::Strategy + Clone + 'static,
+    R: metrics::Recorder + Sync + 'static,
+{
+    type Output = Fanout;
+
+    fn layer(&self, inner: R) -> Self::Output {
+        FanoutBuilder::default()
+            .add_recorder(self.clone())
+            .add_recorder(inner)
+            .build()
+    }
+}
+
+/// Chains this [`FreezableRecorder`] with the wrapped `inner`
+/// [`metrics::Recorder`], forwarding all the calls to both of them (via a
+/// [`Fanout`]).
+///
+/// See the [`Recorder`]'s [`Layer`] implementation for details.
+///
+/// [`Fanout`]: metrics_util::layers::Fanout
+/// [`FreezableRecorder`]: super::Freezable
+/// [`Recorder`]: super::Recorder
+#[warn(clippy::missing_trait_methods)]
+impl<S, R> Layer<R> for super::Freezable<S>
+where
+    S: failure::Strategy + Clone + 'static,
+    R: metrics::Recorder + Sync + 'static,
+{
+    type Output = Fanout;
+
+    fn layer(&self, inner: R) -> Self::Output {
+        FanoutBuilder::default()
+            .add_recorder(self.clone())
+            .add_recorder(inner)
+            .build()
+    }
+}
@@ -1,19 +1,49 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! [`metrics::Recorder`] implementations.
 
+pub mod any;
+pub mod batch;
+pub mod cached;
+pub mod duration;
 pub mod freezable;
 pub mod frozen;
+pub mod info;
 pub mod layer;
+pub mod pausable;
+pub mod rate_limited;
+pub mod state_set;
+pub mod typed;
 
-use std::{borrow::Cow, fmt, sync::Arc};
+#[cfg(feature = "timestamps")]
+use std::time::Instant;
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
+#[cfg(feature = "relabel")]
+use crate::relabel;
 use crate::{
-    failure::{self, strategy::PanicInDebugNoOpInRelease},
-    metric, storage,
+    budget, catalog, clock, config, deprecation,
+    failure::{self, strategy::PanicInDebugNoOpInRelease, Resolve as _},
+    metric, openmetrics, storage, target, tasks, trace,
 };
 
+use self::typed::METADATA;
+
 pub use metrics_util::layers::Layer;
 
-pub use self::{freezable::Recorder as Freezable, frozen::Recorder as Frozen};
+pub use self::{
+    freezable::Recorder as Freezable, frozen::Recorder as Frozen,
+    pausable::Recorder as Pausable, rate_limited::Recorder as RateLimited,
+};
 
 /// [`metrics::Recorder`] registering metrics in a [`prometheus::Registry`] and
 /// powered by a [`metrics::Registry`] built on top of a [`storage::Mutable`].
@@ -42,7 +72,7 @@ pub use self::{freezable::Recorder as Freezable, frozen::Recorder as Frozen};
 /// recorder.register_metric(prometheus::Gauge::new("value", "help")?);
 ///
 /// let report = prometheus::TextEncoder::new()
-///     .encode_to_string(&prometheus::default_registry().gather())?;
+///     .encode_to_string(&recorder.registry().gather())?;
 /// assert_eq!(
 ///     report.trim(),
 ///     r#"
@@ -142,6 +172,8 @@ pub use self::{freezable::Recorder as Freezable, frozen::Recorder as Frozen};
 /// metrics::counter!("count", "kind" => "owned").increment(1);
 /// // This panics, as such labeling is not allowed by `prometheus` crate.
 /// metrics::counter!("count", "whose" => "mine").increment(1);
+/// # #[cfg(feature = "never-panic")]
+/// # panic!("suppressed by `never-panic` feature");
 /// ```
 ///
 /// [`HashMap`]: std::collections::HashMap
@@ -211,535 +243,4630 @@ impl<S> Recorder<S> {
     ///
     /// // panics: Duplicate metrics collector registration attempted
     /// metrics::counter!("value").increment(1);
+    /// # #[cfg(feature = "never-panic")]
+    /// # panic!("suppressed by `never-panic` feature");
     /// # Ok::<_, prometheus::Error>(())
     /// ```
     #[must_use]
-    pub const fn registry(&self) -> &prometheus::Registry {
+    pub fn registry(&self) -> &prometheus::Registry {
         &self.storage.prometheus
     }
 
-    /// Tries to register the provided [`prometheus`] `metric` in the underlying
-    /// [`prometheus::Registry`] in the way making it usable via this
-    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
-    ///
-    /// Accepts only the following [`prometheus`] metrics:
-    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
-    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
-    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
-    ///
-    /// # Errors
+    /// Returns the underlying [`prometheus::Registry`] backing this
+    /// [`Recorder`] as a cheaply cloneable [`Arc`].
     ///
-    /// If the underlying [`prometheus::Registry`] fails to register the
-    /// provided `metric`.
+    /// Useful for handing the [`prometheus::Registry`] over to a `'static`
+    /// task (like an HTTP server exposing the metrics).
     ///
     /// # Example
     ///
     /// ```rust
     /// let recorder = metrics_prometheus::install();
     ///
-    /// let counter = prometheus::IntCounterVec::new(
-    ///     prometheus::opts!("value", "help"),
-    ///     &["whose", "kind"],
-    /// )?;
+    /// let registry = recorder.registry_arc();
+    /// let server = std::thread::spawn(move || registry.gather().len());
     ///
-    /// recorder.try_register_metric(counter.clone())?;
+    /// metrics::counter!("count").increment(1);
+    /// assert!(server.join().is_ok());
+    /// ```
+    #[must_use]
+    pub fn registry_arc(&self) -> Arc<prometheus::Registry> {
+        Arc::clone(&self.storage.prometheus)
+    }
+
+    /// Converts this [`Recorder`] into the one applying the provided
+    /// [`failure::Strategy`] instead.
     ///
-    /// counter.with_label_values(&["mine", "owned"]).inc();
-    /// counter.with_label_values(&["foreign", "ref"]).inc_by(2);
-    /// counter.with_label_values(&["foreign", "owned"]).inc_by(3);
+    /// The returned [`Recorder`] shares the same storage and
+    /// [`prometheus::Registry`] with this one (no metrics are re-registered),
+    /// so differently-behaving handles may be derived for different
+    /// components (like [`strategy::Panic`] for startup code, and
+    /// [`strategy::NoOp`] afterwards).
     ///
-    /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&prometheus::default_registry().gather())?;
-    /// assert_eq!(
-    ///     report.trim(),
-    ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value counter
-    /// value{kind="owned",whose="foreign"} 3
-    /// value{kind="owned",whose="mine"} 1
-    /// value{kind="ref",whose="foreign"} 2
-    ///     "#
-    ///     .trim(),
-    /// );
+    /// # Example
     ///
-    /// metrics::counter!(
-    ///     "value", "whose" => "mine", "kind" => "owned",
-    /// ).increment(1);
-    /// metrics::counter!(
-    ///     "value", "whose" => "mine", "kind" => "ref",
-    /// ).increment(1);
-    /// metrics::counter!(
-    ///     "value", "kind" => "owned", "whose" => "foreign",
-    /// ).increment(1);
+    /// ```rust
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// let startup = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_failure_strategy(strategy::Panic)
+    ///     .build();
+    /// let runtime = startup.clone().map_failure_strategy(strategy::NoOp);
+    ///
+    /// metrics::with_local_recorder(&startup, || {
+    ///     metrics::counter!("requests").increment(1);
+    /// });
+    /// metrics::with_local_recorder(&runtime, || {
+    ///     metrics::counter!("requests").increment(1);
+    ///     // `prometheus` doesn't allow different label names in the same
+    ///     // family, but doesn't panic here.
+    ///     metrics::counter!("requests", "path" => "/").increment(1);
+    /// });
     ///
     /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&recorder.registry().gather())?;
-    /// assert_eq!(
-    ///     report.trim(),
-    ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value counter
-    /// value{kind="owned",whose="foreign"} 4
-    /// value{kind="owned",whose="mine"} 2
-    /// value{kind="ref",whose="foreign"} 2
-    /// value{kind="ref",whose="mine"} 1
-    ///     "#
-    ///     .trim(),
-    /// );
+    ///     .encode_to_string(&startup.registry().gather())?;
+    /// assert!(report.contains("requests 2"));
     /// # Ok::<_, prometheus::Error>(())
     /// ```
-    pub fn try_register_metric<M>(&self, metric: M) -> prometheus::Result<()>
-    where
-        M: metric::Bundled + prometheus::core::Collector,
-        <M as metric::Bundled>::Bundle:
-            prometheus::core::Collector + Clone + 'static,
-        storage::Mutable: storage::Get<
-            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
-        >,
-    {
-        self.storage.register_external(metric)
+    ///
+    /// [`strategy::NoOp`]: failure::strategy::NoOp
+    /// [`strategy::Panic`]: failure::strategy::Panic
+    #[must_use]
+    pub fn map_failure_strategy<F>(self, strategy: F) -> Recorder<F> {
+        Recorder {
+            metrics: self.metrics,
+            storage: self.storage,
+            failure_strategy: strategy,
+        }
     }
 
-    /// Registers the provided [`prometheus`] `metric` in the underlying
-    /// [`prometheus::Registry`] in the way making it usable via this
-    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    /// Renders the metrics of this [`Recorder`] in the Prometheus text format,
+    /// keeping only the series matching the provided `predicate`.
     ///
-    /// Accepts only the following [`prometheus`] metrics:
-    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
-    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
-    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    /// The `predicate` accepts a metric family name and the labels of its
+    /// series. Families without any matching series are omitted completely.
+    /// Useful for serving per-tenant scrapes out of a large multi-tenant
+    /// [`prometheus::Registry`].
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// If the underlying [`prometheus::Registry`] fails to register the
-    /// provided `metric`.
+    /// If the gathered metrics cannot be encoded.
     ///
     /// # Example
     ///
     /// ```rust
     /// let recorder = metrics_prometheus::install();
     ///
-    /// let gauge = prometheus::GaugeVec::new(
-    ///     prometheus::opts!("value", "help"),
-    ///     &["whose", "kind"],
-    /// )?;
-    ///
-    /// recorder.register_metric(gauge.clone());
-    ///
-    /// gauge.with_label_values(&["mine", "owned"]).inc();
-    /// gauge.with_label_values(&["foreign", "ref"]).set(2.0);
-    /// gauge.with_label_values(&["foreign", "owned"]).set(3.0);
+    /// metrics::counter!("requests", "tenant" => "acme").increment(1);
+    /// metrics::counter!("requests", "tenant" => "initech").increment(2);
+    /// metrics::gauge!("tenants").set(2.0);
     ///
-    /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// let report = recorder.render_text_filtered(|_, labels| {
+    ///     labels.contains(&("tenant", "acme"))
+    /// })?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value gauge
-    /// value{kind="owned",whose="foreign"} 3
-    /// value{kind="owned",whose="mine"} 1
-    /// value{kind="ref",whose="foreign"} 2
+    /// ## HELP requests requests
+    /// ## TYPE requests counter
+    /// requests{tenant="acme"} 1
     ///     "#
     ///     .trim(),
     /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn render_text_filtered<F>(
+        &self,
+        predicate: F,
+    ) -> prometheus::Result<String>
+    where
+        F: FnMut(&str, &[(&str, &str)]) -> bool,
+    {
+        render_text_filtered(self.registry(), &self.storage.merged, predicate)
+    }
+
+    /// Merges the provided sibling [`prometheus::Registry`] into the metrics
+    /// rendered by this [`Recorder`], so the components insisting on their
+    /// own [`prometheus::Registry`] can still be served from the single
+    /// endpoint.
+    ///
+    /// The metric families of the merged [`prometheus::Registry`] are
+    /// gathered on each render call (like the
+    /// [`Recorder::render_text_filtered()`]), and appended to the ones of this
+    /// [`Recorder`]. The merged metric families duplicating the already
+    /// gathered ones are detected: they fail the fallible render calls, and
+    /// are dropped by the infallible ones (like the
+    /// [`Recorder::render_openmetrics()`]).
+    ///
+    /// The merged [`prometheus::Registry`] is kept once this [`Recorder`] is
+    /// frozen.
     ///
-    /// metrics::gauge!(
-    ///     "value", "whose" => "mine", "kind" => "owned",
-    /// ).increment(2.0);
-    /// metrics::gauge!(
-    ///     "value", "whose" => "mine", "kind" => "ref",
-    /// ).decrement(2.0);
-    /// metrics::gauge!(
-    ///     "value", "kind" => "owned", "whose" => "foreign",
-    /// ).increment(2.0);
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] is the one of this
+    /// [`Recorder`], or has been merged already.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    /// let sibling = prometheus::Registry::new();
+    /// recorder.merge_registry(&sibling)?;
+    /// assert!(recorder.merge_registry(&sibling).is_err());
+    ///
+    /// let jobs = prometheus::IntGauge::new("jobs", "Number of jobs.")?;
+    /// sibling.register(Box::new(jobs.clone()))?;
+    /// jobs.set(3);
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests").increment(1);
+    /// });
     ///
-    /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&prometheus::default_registry().gather())?;
     /// assert_eq!(
-    ///     report.trim(),
+    ///     recorder.render_text_filtered(|_, _| true)?.trim(),
     ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value gauge
-    /// value{kind="owned",whose="foreign"} 5
-    /// value{kind="owned",whose="mine"} 3
-    /// value{kind="ref",whose="foreign"} 2
-    /// value{kind="ref",whose="mine"} -2
+    /// ## HELP jobs Number of jobs.
+    /// ## TYPE jobs gauge
+    /// jobs 3
+    /// ## HELP requests requests
+    /// ## TYPE requests counter
+    /// requests 1
     ///     "#
     ///     .trim(),
     /// );
+    ///
+    /// // Duplicated metric family is detected.
+    /// let requests = prometheus::IntCounter::new("requests", "Duplicate.")?;
+    /// sibling.register(Box::new(requests))?;
+    /// assert!(recorder.render_text_filtered(|_, _| true).is_err());
     /// # Ok::<_, prometheus::Error>(())
     /// ```
-    pub fn register_metric<M>(&self, metric: M)
-    where
-        M: metric::Bundled + prometheus::core::Collector,
-        <M as metric::Bundled>::Bundle:
-            prometheus::core::Collector + Clone + 'static,
-        storage::Mutable: storage::Get<
-            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
-        >,
-    {
-        self.try_register_metric(metric).unwrap_or_else(|e| {
-            panic!("failed to register `prometheus` metric: {e}")
-        });
-    }
-}
-
-#[warn(clippy::missing_trait_methods)]
-impl<S> metrics::Recorder for Recorder<S>
-where
-    S: failure::Strategy,
-{
-    fn describe_counter(
+    pub fn merge_registry(
         &self,
-        key: metrics::KeyName,
-        _: Option<metrics::Unit>,
-        description: metrics::SharedString,
-    ) {
-        self.storage.describe::<prometheus::IntCounter>(
-            key.as_str(),
-            description.into_owned(),
-        );
+        registry: &prometheus::Registry,
+    ) -> prometheus::Result<()> {
+        self.storage.merged.push(self.registry(), registry)
     }
 
-    fn describe_gauge(
-        &self,
-        key: metrics::KeyName,
-        _: Option<metrics::Unit>,
-        description: metrics::SharedString,
-    ) {
-        self.storage.describe::<prometheus::Gauge>(
-            key.as_str(),
-            description.into_owned(),
-        );
+    /// Renders the metrics of this [`Recorder`] in the [OpenMetrics] text
+    /// format, emitting the [`metrics::Unit`]s declared via [`metrics`]
+    /// describe macros as `# UNIT` lines, and appending the unit suffix to
+    /// the metric family names lacking it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!(
+    ///         "sent",
+    ///         metrics::Unit::Bytes,
+    ///         "Number of sent bytes.",
+    ///     );
+    ///     metrics::counter!("sent").increment(42);
+    /// });
+    ///
+    /// assert_eq!(
+    ///     recorder.render_openmetrics(),
+    ///     "\
+    /// ## TYPE sent_bytes counter
+    /// ## UNIT sent_bytes bytes
+    /// ## HELP sent_bytes Number of sent bytes.
+    /// sent_bytes_total 42
+    /// ## EOF
+    /// ",
+    /// );
+    /// ```
+    ///
+    /// Declaring a unit disagreeing with the unit suffix of the metric name is
+    /// a [`prometheus::Error`] passed to the configured [`failure::Strategy`]:
+    /// ```rust,should_panic
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_failure_strategy(strategy::Panic)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     // panics: `latency_seconds` metric has `seconds` unit suffix, but
+    ///     //         `milliseconds` unit is declared
+    ///     metrics::describe_histogram!(
+    ///         "latency_seconds",
+    ///         metrics::Unit::Milliseconds,
+    ///         "Latency.",
+    ///     );
+    /// });
+    /// # #[cfg(feature = "never-panic")]
+    /// # panic!("suppressed by `never-panic` feature");
+    /// ```
+    ///
+    /// [OpenMetrics]: https://openmetrics.io
+    #[must_use]
+    pub fn render_openmetrics(&self) -> String {
+        let mut families = self.registry().gather();
+        // Duplicated metric families are dropped, as cannot be reported here.
+        drop(self.storage.merged.gather_into(&mut families));
+        openmetrics::encode(&families, &self.storage.units())
     }
 
-    fn describe_histogram(
-        &self,
-        key: metrics::KeyName,
-        _: Option<metrics::Unit>,
-        description: metrics::SharedString,
-    ) {
-        self.storage.describe::<prometheus::Histogram>(
-            key.as_str(),
-            description.into_owned(),
-        );
+    /// Renders the metrics of this [`Recorder`] in the Prometheus text format,
+    /// surviving panics of buggy [`prometheus::core::Collector`]s registered
+    /// in the underlying [`prometheus::Registry`] directly.
+    ///
+    /// If gathering the [`prometheus::Registry`] panics, only the metrics
+    /// registered via this [`Recorder`] are rendered (each collected
+    /// separately, skipping the panicking ones), and the
+    /// `metrics_prometheus_gather_panics_total` counter is incremented and
+    /// registered in the [`prometheus::Registry`] (if not yet).
+    ///
+    /// # Errors
+    ///
+    /// With a [`PartialRender`] containing the rendered metrics, if any panic
+    /// happens, or the gathered metrics cannot be encoded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use prometheus::{core, proto};
+    ///
+    /// struct Buggy(core::Desc);
+    ///
+    /// impl core::Collector for Buggy {
+    ///     fn desc(&self) -> Vec<&core::Desc> {
+    ///         vec![&self.0]
+    ///     }
+    ///
+    ///     fn collect(&self) -> Vec<proto::MetricFamily> {
+    ///         panic!("buggy collector")
+    ///     }
+    /// }
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests").increment(1);
+    /// });
+    /// assert!(recorder.try_render_text().is_ok());
+    ///
+    /// let desc = core::Desc::new("buggy".into(), "help".into(), vec![], {
+    ///     std::collections::HashMap::new()
+    /// })?;
+    /// recorder.registry().register(Box::new(Buggy(desc)))?;
+    ///
+    /// let partial = recorder.try_render_text().unwrap_err();
+    /// assert_eq!(partial.errors.len(), 1);
+    /// assert!(partial.text.contains("requests 1"));
+    /// let panics = "metrics_prometheus_gather_panics_total 1";
+    /// assert!(partial.text.contains(panics));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn try_render_text(&self) -> Result<String, PartialRender> {
+        try_render_text(&self.storage)
     }
 
-    fn register_counter(
-        &self,
-        key: &metrics::Key,
-        _: &metrics::Metadata<'_>,
-    ) -> metrics::Counter {
-        self.metrics
-            .get_or_create_counter(key, |counter| {
-                counter.as_ref().map(|c| Arc::clone(c).into()).or_else(|e| {
-                    match self.failure_strategy.decide(e) {
-                        failure::Action::NoOp => Ok(metrics::Counter::noop()),
-                        // PANIC: We cannot panic inside this closure, because
-                        //        this may lead to poisoning `RwLock`s inside
-                        //        `metrics_util::registry::Registry`.
-                        failure::Action::Panic => Err(e.to_string()),
-                    }
-                })
-            })
-            .unwrap_or_else(|e| {
-                panic!(
-                    "failed to register `prometheus::IntCounter` metric: {e}"
-                )
-            })
+    /// Returns a [`cached::Renderer`] serving the metrics of this [`Recorder`]
+    /// in the Prometheus text format, and caching the rendered text body for
+    /// the provided `ttl`.
+    ///
+    /// Useful for heavy [`prometheus::Registry`]s scraped by multiple
+    /// Prometheus servers, as re-encoding them on each scrape wastes CPU.
+    /// Concurrent scrapes are single-flighted, so only one of them gathers
+    /// the metrics once the cached text body expires.
+    ///
+    /// The age of the cached text body is measured with the [`Clock`]
+    /// configured via the [`Builder::with_clock()`] method.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use metrics_prometheus::clock;
+    ///
+    /// let clock = clock::Mock::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_clock(clock.clone())
+    ///     .build();
+    /// let renderer = recorder.cached_renderer(Duration::from_secs(5));
+    ///
+    /// let counter = metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests")
+    /// });
+    /// counter.increment(1);
+    /// assert!(renderer.render()?.contains("requests 1"));
+    ///
+    /// // The cached text body is served until it expires.
+    /// counter.increment(1);
+    /// assert!(renderer.render()?.contains("requests 1"));
+    ///
+    /// clock.advance(Duration::from_secs(5));
+    /// assert!(renderer.render()?.contains("requests 2"));
+    /// # Ok::<_, metrics_prometheus::recorder::PartialRender>(())
+    /// ```
+    ///
+    /// [`Clock`]: clock::Clock
+    #[must_use]
+    pub fn cached_renderer(&self, ttl: Duration) -> cached::Renderer {
+        cached::Renderer::new(self.storage.clone(), ttl)
     }
 
-    fn register_gauge(
+    /// Applies the provided documented [`catalog::Entries`] of a central
+    /// metrics catalog to this [`Recorder`], updating [`help` description]s of
+    /// its metrics, and reporting the ones missing in the catalog.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::{catalog, storage::pending::Kind};
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests").increment(1);
+    ///     metrics::gauge!("legacy_queue").set(1.0);
+    ///     metrics::gauge!("undocumented").set(1.0);
+    /// });
+    ///
+    /// let entries = catalog::Entries::from([
+    ///     (
+    ///         "requests".into(),
+    ///         catalog::Entry {
+    ///             kind: Some(Kind::Counter),
+    ///             help: "Number of handled requests.".into(),
+    ///             ..catalog::Entry::default()
+    ///         },
+    ///     ),
+    ///     (
+    ///         "legacy_queue".into(),
+    ///         catalog::Entry {
+    ///             help: "Size of the legacy queue.".into(),
+    ///             stability: catalog::Stability::Deprecated,
+    ///             ..catalog::Entry::default()
+    ///         },
+    ///     ),
+    ///     (
+    ///         "latency".into(),
+    ///         catalog::Entry {
+    ///             help: "Latency of requests.".into(),
+    ///             ..catalog::Entry::default()
+    ///         },
+    ///     ),
+    /// ]);
+    /// let applied = recorder.apply_catalog(&entries);
+    /// assert_eq!(applied.unknown, ["undocumented"]);
+    /// assert_eq!(applied.unapplied, ["latency"]);
+    /// assert_eq!(applied.deprecated, ["legacy_queue"]);
+    ///
+    /// let families = recorder.registry().gather();
+    /// let requests = families.iter().find(|f| f.get_name() == "requests");
+    /// assert_eq!(
+    ///     requests.map(|f| f.get_help()),
+    ///     Some("Number of handled requests."),
+    /// );
+    /// ```
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub fn apply_catalog(
         &self,
-        key: &metrics::Key,
-        _: &metrics::Metadata<'_>,
-    ) -> metrics::Gauge {
-        self.metrics
-            .get_or_create_gauge(key, |gauge| {
-                gauge.as_ref().map(|c| Arc::clone(c).into()).or_else(|e| {
-                    match self.failure_strategy.decide(e) {
-                        failure::Action::NoOp => Ok(metrics::Gauge::noop()),
-                        // PANIC: We cannot panic inside this closure, because
-                        //        this may lead to poisoning `RwLock`s inside
-                        //        `metrics_util::registry::Registry`.
-                        failure::Action::Panic => Err(e.to_string()),
-                    }
-                })
-            })
-            .unwrap_or_else(|e| {
-                panic!("failed to register `prometheus::Gauge` metric: {e}")
-            })
+        entries: &catalog::Entries,
+    ) -> catalog::Applied {
+        catalog::apply(&self.storage, entries)
     }
 
-    fn register_histogram(
-        &self,
-        key: &metrics::Key,
-        _: &metrics::Metadata<'_>,
-    ) -> metrics::Histogram {
-        self.metrics
-            .get_or_create_histogram(key, |histogram| {
-                histogram.as_ref().map(|c| Arc::clone(c).into()).or_else(|e| {
-                    match self.failure_strategy.decide(e) {
-                        failure::Action::NoOp => Ok(metrics::Histogram::noop()),
-                        // PANIC: We cannot panic inside this closure, because
-                        //        this may lead to poisoning `RwLock`s inside
-                        //        `metrics_util::registry::Registry`.
-                        failure::Action::Panic => Err(e.to_string()),
-                    }
-                })
-            })
-            .unwrap_or_else(|e| {
-                panic!("failed to register `prometheus::Histogram` metric: {e}")
-            })
+    /// Spawns a background [`catalog::Watcher`] polling the central metrics
+    /// catalog file at the provided `path` with the provided `interval`, and
+    /// [applying][0] its contents (parsed with the provided `parse` function)
+    /// to this [`Recorder`] on every change.
+    ///
+    /// Unreadable or unparsable contents are skipped, keeping the previously
+    /// applied [`help` description]s. Watching stops once the returned
+    /// [`catalog::Watcher`] is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::{fs, thread, time::Duration};
+    ///
+    /// use metrics_prometheus::{catalog, storage::pending::Kind};
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests").increment(1);
+    /// });
+    ///
+    /// let path = std::env::temp_dir().join("metrics_prometheus_catalog.txt");
+    /// fs::write(&path, "requests: Number of handled requests.").unwrap();
+    /// let parse = |contents: &str| {
+    ///     let entries = contents.lines().filter_map(|l| {
+    ///         let (name, help) = l.split_once(": ")?;
+    ///         let entry = catalog::Entry {
+    ///             kind: Some(Kind::Counter),
+    ///             help: help.into(),
+    ///             ..catalog::Entry::default()
+    ///         };
+    ///         Some((name.to_owned(), entry))
+    ///     });
+    ///     Ok::<_, ()>(entries.collect())
+    /// };
+    /// let interval = Duration::from_millis(10);
+    /// let watcher = recorder.watch_catalog(&path, interval, parse);
+    ///
+    /// let help = || recorder.registry().gather()[0].get_help().to_owned();
+    /// while help() != "Number of handled requests." {
+    ///     thread::sleep(Duration::from_millis(10));
+    /// }
+    ///
+    /// fs::write(&path, "requests: Handled requests.").unwrap();
+    /// while help() != "Handled requests." {
+    ///     thread::sleep(Duration::from_millis(10));
+    /// }
+    /// # drop(watcher);
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    /// [0]: Recorder::apply_catalog
+    pub fn watch_catalog<F, E>(
+        &self,
+        path: impl Into<PathBuf>,
+        interval: Duration,
+        parse: F,
+    ) -> catalog::Watcher
+    where
+        F: Fn(&str) -> Result<catalog::Entries, E> + Send + 'static,
+    {
+        catalog::Watcher::spawn(
+            self.storage.clone(),
+            path.into(),
+            interval,
+            parse,
+        )
     }
-}
 
-/// Builder for building a [`Recorder`].
-#[derive(Debug)]
-#[must_use]
-pub struct Builder<
-    FailureStrategy = PanicInDebugNoOpInRelease,
-    Layers = layer::Stack,
-> {
-    /// [`storage::Mutable`] registering metrics in its
-    /// [`prometheus::Registry`].
-    storage: storage::Mutable,
+    /// Returns the [`tasks::Shutdown`] of the background tasks spawned for this
+    /// [`Recorder`] (like the [`catalog::Watcher`]s), allowing to stop them
+    /// cleanly on the application termination.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// #
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// let path = std::env::temp_dir().join("metrics_prometheus_tasks.txt");
+    /// let parse = |_: &str| Ok::<_, ()>(Default::default());
+    /// let _watcher =
+    ///     recorder.watch_catalog(&path, Duration::from_secs(3600), parse);
+    ///
+    /// // Doesn't wait for the whole interval.
+    /// recorder.tasks().join();
+    /// ```
+    #[must_use]
+    pub const fn tasks(&self) -> &tasks::Shutdown {
+        &self.storage.tasks
+    }
 
-    /// [`failure::Strategy`] of the built [`Recorder`] to apply when a
-    /// [`prometheus::Error`] is encountered inside its [`metrics::Recorder`]
-    /// methods.
-    failure_strategy: FailureStrategy,
+    /// Returns [`storage::Counts`] of the metric families registered via this
+    /// [`Recorder`].
+    ///
+    /// Cheap enough for admin endpoints and tests asserting on the registry
+    /// size, as doesn't gather any metrics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(prometheus::Registry::new())
+    ///     .build();
+    /// assert!(recorder.metric_counts().is_empty());
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests", "op" => "read").increment(1);
+    ///     metrics::counter!("requests", "op" => "write").increment(1);
+    ///     metrics::gauge!("queue").set(3.0);
+    /// });
+    ///
+    /// let counts = recorder.metric_counts();
+    /// assert_eq!(counts.counters, 1);
+    /// assert_eq!(counts.gauges, 1);
+    /// assert_eq!(counts.histograms, 0);
+    /// assert_eq!(counts.len(), 2);
+    /// ```
+    #[must_use]
+    pub fn metric_counts(&self) -> storage::Counts {
+        self.storage.metric_counts()
+    }
 
-    /// [`metrics::Layer`]s to wrap the built [`Recorder`] with upon its
-    /// installation with the [`metrics::set_global_recorder()`].
+    /// Returns a JSON catalog of the metric families registered via this
+    /// [`Recorder`], describing their names, kinds, [`help` description]s,
+    /// units, label names and histogram buckets (but no values).
     ///
-    /// [`metrics::Layer`]: Layer
-    layers: Layers,
-}
+    /// Intended to be served at some `/metrics/catalog` endpoint for
+    /// documentation portals. Built from the storage directly, so doesn't
+    /// gather or encode any metrics.
+    ///
+    /// See the [`catalog::Snapshot::to_json()`] method for the document shape.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!(
+    ///         "requests",
+    ///         metrics::Unit::Count,
+    ///         "Number of \"requests\".",
+    ///     );
+    ///     metrics::counter!("requests", "path" => "/").increment(1);
+    ///     metrics::gauge!("queue").set(3.0);
+    /// });
+    ///
+    /// assert_eq!(
+    ///     recorder.catalog_json(),
+    ///     concat!(
+    ///         r#"{"families":["#,
+    ///         r#"{"name":"queue","kind":"gauge","help":"queue","#,
+    ///         r#""unit":null,"labels":[],"buckets":[]},"#,
+    ///         r#"{"name":"requests","kind":"counter","#,
+    ///         r#""help":"Number of \"requests\".","unit":"count","#,
+    ///         r#""labels":["path"],"buckets":[]}"#,
+    ///         "]}",
+    ///     ),
+    /// );
+    /// ```
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[must_use]
+    pub fn catalog_json(&self) -> String {
+        self.storage.catalog().to_json(&self.storage.units())
+    }
 
-impl<S, L> Builder<S, L> {
-    /// Sets the provided [`prometheus::Registry`] to be used by the built
-    /// [`Recorder`].
+    /// Returns [`storage::Counts`] of the metric families having a
+    /// [`help` description] in this [`Recorder`], but never registered
+    /// (orphan descriptions).
     ///
-    /// When not specified, the [`prometheus::default_registry()`] is used by
-    /// default.
+    /// See the [`Builder::with_orphan_descriptions()`] method for handling
+    /// them.
     ///
-    /// # Warning
+    /// # Example
     ///
-    /// Any [`prometheus`] metrics, already registered in the provided
-    /// [`prometheus::Registry`], cannot be used via the built
-    /// [`metrics::Recorder`] (and, so, [`metrics`] crate interfaces), and
-    /// trying to use them will inevitably cause a [`prometheus::Error`] being
-    /// emitted.
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!("forgotten", "Never registered.");
+    ///     metrics::describe_gauge!("queue", "Registered.");
+    ///     metrics::gauge!("queue").set(1.0);
+    /// });
+    ///
+    /// let counts = recorder.orphan_counts();
+    /// assert_eq!(counts.counters, 1);
+    /// assert_eq!(counts.len(), 1);
+    /// ```
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[must_use]
+    pub fn orphan_counts(&self) -> storage::Counts {
+        self.storage.orphan_counts()
+    }
+
+    /// Unregisters all the [`prometheus`] metrics registered via this
+    /// [`Recorder`] (including the pre-registered ones) from its
+    /// [`prometheus::Registry`], restoring it to its prior state.
+    ///
+    /// Useful for not leaking metrics into the process-global
+    /// [`prometheus::default_registry()`] across test runs or dynamic reload
+    /// cycles.
+    ///
+    /// The already obtained [`metrics`] handles keep working, but their values
+    /// are not exported anymore. The metrics registered afterwards are
+    /// registered anew.
     ///
     /// # Example
     ///
     /// ```rust
-    /// let custom = prometheus::Registry::new_custom(Some("my".into()), None)?;
+    /// let recorder = metrics_prometheus::install();
     ///
-    /// metrics_prometheus::Recorder::builder()
-    ///     .with_registry(&custom)
-    ///     .build_and_install();
+    /// metrics::counter!("count").increment(1);
+    /// assert_eq!(recorder.registry().gather().len(), 1);
+    ///
+    /// recorder.shutdown();
+    /// assert_eq!(recorder.registry().gather().len(), 0);
     ///
     /// metrics::counter!("count").increment(1);
     ///
-    /// let report =
-    ///     prometheus::TextEncoder::new().encode_to_string(&custom.gather())?;
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
-    /// ## HELP my_count count
-    /// ## TYPE my_count counter
-    /// my_count 1
+    /// ## HELP count count
+    /// ## TYPE count counter
+    /// count 1
     ///     "#
     ///     .trim(),
     /// );
     /// # Ok::<_, prometheus::Error>(())
     /// ```
-    // TODO: Try remove on Rust 1.84 upgrade.
-    #[expect( // anonymous lifetimes in `impl Trait` are unstable
-        single_use_lifetimes,
-        reason = "anonymous lifetimes in `impl Trait` are unstable"
-    )]
-    pub fn with_registry<'r>(
-        mut self,
-        registry: impl IntoCow<'r, prometheus::Registry>,
-    ) -> Self {
-        self.storage.prometheus = registry.into_cow().into_owned();
-        self
+    pub fn shutdown(&self) {
+        self.storage.unregister_all();
+        self.metrics.clear();
     }
 
-    /// Sets the provided [`failure::Strategy`] to be used by the built
-    /// [`Recorder`].
+    /// Sets the provided `annotation` of the counter identified by the
+    /// provided [`metrics::Key`].
     ///
-    /// [`prometheus::Registry`] has far more stricter semantics than the ones
-    /// implied by a [`metrics::Recorder`]. That's why incorrect usage of
-    /// [`prometheus`] metrics via [`metrics`] crate will inevitably lead to a
-    /// [`prometheus::Registry`] returning a [`prometheus::Error`] instead of a
-    /// registering the metric. The returned [`prometheus::Error`] can be either
-    /// turned into a panic, or just silently ignored, making the [`Recorder`]
-    /// to return a no-op metric instead (see [`metrics::Counter::noop()`] for
-    /// example).
+    /// Annotations are used for introspection only (like in an admin UI), and
+    /// are not exposed in Prometheus exposition, which uses the family-level
+    /// [`help` description] instead. See the [`Recorder::annotations()`]
+    /// method for an example.
     ///
-    /// The default [`failure::Strategy`] is [`PanicInDebugNoOpInRelease`]. See
-    /// [`failure::strategy`] module for other available [`failure::Strategy`]s,
-    /// or provide your own one by implementing the [`failure::Strategy`] trait.
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub fn annotate_counter(
+        &self,
+        key: &metrics::Key,
+        annotation: impl Into<String>,
+    ) {
+        self.storage.annotate::<prometheus::IntCounter>(key, annotation.into());
+    }
+
+    /// Sets the provided `annotation` of the gauge identified by the provided
+    /// [`metrics::Key`].
+    ///
+    /// See the [`Recorder::annotate_counter()`] method for details.
+    pub fn annotate_gauge(
+        &self,
+        key: &metrics::Key,
+        annotation: impl Into<String>,
+    ) {
+        self.storage.annotate::<prometheus::Gauge>(key, annotation.into());
+    }
+
+    /// Sets the provided `annotation` of the histogram identified by the
+    /// provided [`metrics::Key`].
+    ///
+    /// See the [`Recorder::annotate_counter()`] method for details.
+    pub fn annotate_histogram(
+        &self,
+        key: &metrics::Key,
+        annotation: impl Into<String>,
+    ) {
+        self.storage.annotate::<prometheus::Histogram>(key, annotation.into());
+    }
+
+    /// Returns all the annotations of the metrics in the family identified by
+    /// the provided `name`, keyed by their [`annotation::Labels`].
     ///
     /// # Example
     ///
     /// ```rust
-    /// use metrics_prometheus::failure::strategy;
+    /// use metrics::Key;
     ///
-    /// metrics_prometheus::Recorder::builder()
-    ///     .with_failure_strategy(strategy::NoOp)
-    ///     .build_and_install();
+    /// let recorder = metrics_prometheus::install();
     ///
-    /// metrics::counter!("invalid.name").increment(1);
+    /// metrics::counter!("requests", "route" => "/users").increment(1);
+    /// metrics::counter!("requests", "route" => "/admin").increment(1);
+    /// recorder.annotate_counter(
+    ///     &Key::from_parts("requests", &[("route", "/admin")]),
+    ///     "Owned by the security team.",
+    /// );
     ///
-    /// let stats = prometheus::default_registry().gather();
-    /// assert_eq!(stats.len(), 0);
+    /// let annotations = recorder.annotations("requests");
+    /// assert_eq!(annotations.len(), 1);
+    /// assert_eq!(
+    ///     annotations[&vec![("route".into(), "/admin".into())]],
+    ///     "Owned by the security team.",
+    /// );
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP requests requests
+    /// ## TYPE requests counter
+    /// requests{route="/admin"} 1
+    /// requests{route="/users"} 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
     /// ```
-    pub fn with_failure_strategy<F>(self, strategy: F) -> Builder<F, L>
-    where
-        F: failure::Strategy,
-    {
-        Builder {
-            storage: self.storage,
-            failure_strategy: strategy,
-            layers: self.layers,
+    ///
+    /// [`annotation::Labels`]: metric::annotation::Labels
+    #[must_use]
+    pub fn annotations(
+        &self,
+        name: &str,
+    ) -> BTreeMap<metric::annotation::Labels, String> {
+        let mut all = BTreeMap::new();
+        for annotations in [
+            self.storage.annotations::<prometheus::IntCounter>(name),
+            self.storage.annotations::<prometheus::Gauge>(name),
+            self.storage.annotations::<prometheus::Histogram>(name),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            all.extend(annotations.all());
         }
+        all
     }
 
-    /// Tries to register the provided [`prometheus`] `metric` in the underlying
-    /// [`prometheus::Registry`] in the way making it usable via the created
-    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    /// Returns the [`Origins`] of the metric family identified by the provided
+    /// `name` (when and where it was first described and registered), if it's
+    /// known.
     ///
-    /// Accepts only the following [`prometheus`] metrics:
-    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
-    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
-    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    /// The caller is identified by the [`metrics::Metadata::target()`] and
+    /// [`metrics::Metadata::module_path()`] of the registration only, as
+    /// [`metrics`] macros hide its source location behind dynamic dispatch.
+    /// Descriptions capture no caller at all, as [`metrics`] provides no
+    /// [`metrics::Metadata`] for them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::install();
+    ///
+    /// metrics::describe_counter!("jobs", "Number of jobs.");
+    /// metrics::counter!("jobs").increment(1);
+    ///
+    /// let origins = recorder.origins("jobs").unwrap();
+    /// let described = origins.described().unwrap();
+    /// let registered = origins.registered().unwrap();
+    /// assert!(described.at() <= registered.at());
+    /// assert_eq!(registered.target(), Some(module_path!()));
+    /// assert_eq!(registered.module_path(), Some(module_path!()));
+    /// assert_eq!(described.module_path(), None);
+    ///
+    /// assert!(recorder.origins("unknown").is_none());
+    /// ```
+    ///
+    /// [`Origins`]: metric::origin::Origins
+    #[cfg(feature = "origins")]
+    #[must_use]
+    pub fn origins(&self, name: &str) -> Option<metric::origin::Origins> {
+        self.storage
+            .origins::<prometheus::IntCounter>(name)
+            .or_else(|| self.storage.origins::<prometheus::Gauge>(name))
+            .or_else(|| self.storage.origins::<prometheus::Histogram>(name))
+    }
+
+    /// Sets the provided `callback` refreshing the value of the gauge
+    /// identified by the provided `name` and `labels` right before it's
+    /// gathered, replacing the previous one (if any).
+    ///
+    /// The gauge is registered, if it isn't yet. It still may be updated as
+    /// usual, but the `callback` overwrites its value on every gathering.
     ///
     /// # Errors
     ///
-    /// If the underlying [`prometheus::Registry`] fails to register the
-    /// provided `metric`.
+    /// If the gauge cannot be registered (like having invalid name or label
+    /// names different from the already registered ones).
     ///
     /// # Example
     ///
     /// ```rust
-    /// let gauge = prometheus::Gauge::new("value", "help")?;
+    /// use std::sync::{Arc, Mutex};
     ///
-    /// metrics_prometheus::Recorder::builder()
-    ///     .try_with_metric(gauge.clone())?
-    ///     .build_and_install();
+    /// let recorder = metrics_prometheus::install();
     ///
-    /// gauge.inc();
+    /// let cache = Arc::new(Mutex::new(vec![1, 2]));
+    /// let entries = Arc::clone(&cache);
+    /// recorder.set_collect_callback("cache_entries", &[("cache", "users")], {
+    ///     move || entries.lock().unwrap().len() as f64
+    /// })?;
+    /// cache.lock().unwrap().push(3);
     ///
     /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    ///     .encode_to_string(&recorder.registry().gather())?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value gauge
-    /// value 1
+    /// ## HELP cache_entries cache_entries
+    /// ## TYPE cache_entries gauge
+    /// cache_entries{cache="users"} 3
     ///     "#
     ///     .trim(),
     /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn set_collect_callback<F>(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        callback: F,
+    ) -> prometheus::Result<()>
+    where
+        F: Fn() -> f64 + Send + Sync + 'static,
+    {
+        self.storage.set_collect_callback(
+            &callback_key(name, labels),
+            Arc::new(callback),
+        )
+    }
+
+    /// Registers a [`StateSet`] with the provided `name` and `states`,
+    /// being in the first of them.
     ///
-    /// metrics::gauge!("value").increment(1.0);
+    /// Implements the [OpenMetrics `StateSet`][0] pattern as a gauge family
+    /// labeled with its own `name`, having exactly one gauge set to `1` (the
+    /// current state), while the others are `0`. The gauges are refreshed
+    /// right before being gathered (see the
+    /// [`Recorder::set_collect_callback()`] method), so are never updated
+    /// directly.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided `states` are empty or contain duplicates.
+    /// - If the gauges cannot be registered (like having invalid name or
+    ///   label names different from the already registered ones).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// let state = recorder.register_state_set(
+    ///     "service_state",
+    ///     ["starting", "ready", "degraded"],
+    /// )?;
+    /// assert_eq!(state.state(), "starting");
+    ///
+    /// state.set_state("ready")?;
+    /// assert!(state.set_state("unknown").is_err());
     ///
     /// let report = prometheus::TextEncoder::new()
-    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    ///     .encode_to_string(&recorder.registry().gather())?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value gauge
-    /// value 2
+    /// ## HELP service_state service_state
+    /// ## TYPE service_state gauge
+    /// service_state{service_state="degraded"} 0
+    /// service_state{service_state="ready"} 1
+    /// service_state{service_state="starting"} 0
     ///     "#
     ///     .trim(),
     /// );
     /// # Ok::<_, prometheus::Error>(())
     /// ```
-    pub fn try_with_metric<M>(self, metric: M) -> prometheus::Result<Self>
+    ///
+    /// [`StateSet`]: state_set::StateSet
+    /// [0]: https://prometheus.io/docs/specs/om/open_metrics_spec/#stateset
+    pub fn register_state_set<I>(
+        &self,
+        name: &str,
+        states: I,
+    ) -> prometheus::Result<state_set::StateSet>
     where
-        M: metric::Bundled + prometheus::core::Collector,
-        <M as metric::Bundled>::Bundle:
-            prometheus::core::Collector + Clone + 'static,
-        storage::Mutable: storage::Get<
-            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
-        >,
+        I: IntoIterator,
+        I::Item: Into<String>,
     {
-        self.storage.register_external(metric)?;
-        Ok(self)
+        let set = state_set::StateSet::new(
+            name,
+            states.into_iter().map(Into::into).collect(),
+        )?;
+        for (idx, state) in set.states().iter().enumerate() {
+            self.set_collect_callback(
+                name,
+                &[(name, state.as_str())],
+                set.callback(idx),
+            )?;
+        }
+        Ok(set)
     }
 
-    /// Registers the provided [`prometheus`] `metric` in the underlying
-    /// [`prometheus::Registry`] in the way making it usable via the created
-    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    /// Registers an [`Info`] with the provided `name` and `labels` payload.
+    ///
+    /// Implements the [OpenMetrics `Info`][0] pattern as a single always-`1`
+    /// gauge, carrying its payload in the labels. The payload (including its
+    /// label names) may be replaced later via the [`Info::set()`] method.
+    ///
+    /// The gauge is registered in the [`prometheus::Registry`] of this
+    /// [`Recorder`] directly, so is not affected by the
+    /// [`Recorder::shutdown()`].
+    ///
+    /// # Errors
+    ///
+    /// If the gauge cannot be created (like having invalid name or label
+    /// names) or registered in the [`prometheus::Registry`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// let info = recorder.register_info(
+    ///     "runtime_info",
+    ///     [("rustc", "1.81.0"), ("profile", "debug")],
+    /// )?;
+    /// info.set([("rustc", "1.81.0"), ("profile", "release")])?;
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP runtime_info runtime_info
+    /// ## TYPE runtime_info gauge
+    /// runtime_info{profile="release",rustc="1.81.0"} 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// // Label names may change as well.
+    /// info.set([("rustc", "1.82.0"), ("target", "x86_64")])?;
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP runtime_info runtime_info
+    /// ## TYPE runtime_info gauge
+    /// runtime_info{rustc="1.82.0",target="x86_64"} 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// // Invalid payload is rejected, keeping the previous one.
+    /// assert!(info.set([("rustc", "1"), ("rustc", "2")]).is_err());
+    /// assert!(info.set([("0-invalid", "1")]).is_err());
+    /// assert_eq!(info.labels().len(), 2);
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Info`]: info::Info
+    /// [`Info::set()`]: info::Info::set
+    /// [0]: https://prometheus.io/docs/specs/om/open_metrics_spec/#info
+    pub fn register_info<I, K, V>(
+        &self,
+        name: &str,
+        labels: I,
+    ) -> prometheus::Result<info::Info>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        info::Info::register(
+            self.registry(),
+            name,
+            labels.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+        )
+    }
+
+    /// Merges the provided pre-aggregated [`BucketCounts`] snapshot into the
+    /// histogram identified by the provided `name` and `labels`.
+    ///
+    /// Intended for importing histogram data aggregated by other systems (like
+    /// client-side aggregation). Snapshots are summed up, so all the ones
+    /// merged into the same histogram must have the same bucket bounds.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided `name` or `labels` are rejected by the
+    ///   [`config::Config`].
+    /// - If the provided [`BucketCounts`] are invalid, or have bucket bounds
+    ///   different from the previously merged ones.
+    /// - If the histogram is registered already with different label names.
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   histogram.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::metric::raw::BucketCounts;
+    ///
+    /// let registry = prometheus::Registry::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&registry)
+    ///     .build();
+    ///
+    /// let buckets = vec![(0.1, 1), (1.0, 3)];
+    /// let counts = BucketCounts { buckets, sum: 2.5, count: 4 };
+    /// recorder.record_histogram_raw("latency", &[("op", "read")], &counts)?;
+    /// recorder.record_histogram_raw("latency", &[("op", "read")], &counts)?;
+    ///
+    /// let encoder = prometheus::TextEncoder::new();
+    /// let report = encoder.encode_to_string(&registry.gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP latency latency
+    /// ## TYPE latency histogram
+    /// latency_bucket{op="read",le="0.1"} 2
+    /// latency_bucket{op="read",le="1"} 6
+    /// latency_bucket{op="read",le="+Inf"} 8
+    /// latency_sum{op="read"} 5
+    /// latency_count{op="read"} 8
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`BucketCounts`]: metric::raw::BucketCounts
+    pub fn record_histogram_raw(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        counts: &metric::raw::BucketCounts,
+    ) -> prometheus::Result<()> {
+        let key = metrics::Key::from_parts(
+            name.to_owned(),
+            labels
+                .iter()
+                .map(|(n, v)| {
+                    metrics::Label::new((*n).to_owned(), (*v).to_owned())
+                })
+                .collect::<Vec<_>>(),
+        );
+        let key = self.storage.config.load().validated(&key)?;
+        let mut pairs = key
+            .labels()
+            .map(|l| (l.key().to_owned(), l.value().to_owned()))
+            .collect::<Vec<_>>();
+        pairs.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        let (names, values): (Vec<_>, _) = pairs.into_iter().unzip();
+        self.storage.raw_histogram(key.name(), &names)?.merge(values, counts)
+    }
+
+    /// Registers a gauge with the provided `name`, exposing the increase of the
+    /// `source` counter over the provided sliding time `window` (like
+    /// "requests in the last 5 minutes").
+    ///
+    /// Handy for environments where Prometheus recording rules are not
+    /// available. Snapshots of the `source` counter are taken on each
+    /// collection (so the `window` slides with scrapes), and kept in a ring
+    /// buffer for the `window` duration. See the [`metric::windowed::Rate`]
+    /// for details.
+    ///
+    /// The `source` counter may be registered after calling this method.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided `name` is invalid.
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   gauge (like when a metric with the same `name` is registered already).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// let registry = prometheus::Registry::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&registry)
+    ///     .build();
+    ///
+    /// let window = Duration::from_secs(300);
+    /// recorder.windowed_rate("requests_last_5m", "requests_total", window)?;
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests_total", "op" => "read").increment(3);
+    /// });
+    ///
+    /// let encoder = prometheus::TextEncoder::new();
+    /// let report = encoder.encode_to_string(&registry.gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP requests_last_5m Increase of `requests_total` over 300s.
+    /// ## TYPE requests_last_5m gauge
+    /// requests_last_5m{op="read"} 3
+    /// ## HELP requests_total requests_total
+    /// ## TYPE requests_total counter
+    /// requests_total{op="read"} 3
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn windowed_rate(
+        &self,
+        name: &str,
+        source: &str,
+        window: Duration,
+    ) -> prometheus::Result<()> {
+        self.storage.windowed_rate(name, source, window)
+    }
+
+    /// Resolves the provided [`metrics::Key`] of a metric registered by the
+    /// crate of the provided [`metrics::Metadata::target()`] into the
+    /// [`prometheus`] metric family and its child, the same way this
+    /// [`Recorder`] does on registering it: prefixing its name, routing its
+    /// deprecated name, and applying the [`config::Config`] (validation,
+    /// dropping empty labels, stripping control characters, length limits and
+    /// default labels).
+    ///
+    /// Unlike the [`storage::resolve()`] function, maps names and labels
+    /// identically to this [`Recorder`], so is preferred for custom exporters
+    /// and debug tooling. Doesn't register anything, nor counts usages of the
+    /// deprecated names.
+    ///
+    /// Returns [`None`] if the metric is skipped by this [`Recorder`] (being
+    /// denied or mirrored).
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`metrics::Key`] is rejected by the
+    /// [`config::Config`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::deprecation;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_deprecated("hits", "requests", deprecation::Policy::Count)
+    ///     .with_empty_labels_dropped(true)
+    ///     .with_strict_validation(true)
+    ///     .build();
+    ///
+    /// let labels = [("path", "/"), ("op", "")];
+    /// let key = metrics::Key::from_parts("hits", &labels);
+    /// let metadata =
+    ///     metrics::Metadata::new(module_path!(), metrics::Level::INFO, None);
+    /// let resolved = recorder.resolve(&key, &metadata)?.unwrap();
+    /// assert_eq!(resolved.family, "requests");
+    /// assert_eq!(resolved.label_names().collect::<Vec<_>>(), ["path"]);
+    /// assert!(recorder.deprecated_usages().is_empty());
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn resolve(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> prometheus::Result<Option<storage::Resolved>> {
+        let key = self.storage.prefixed(key, metadata.target());
+        let name = self.storage.replace_deprecated(key.name_shared());
+        let key = if name.as_str() == key.name() {
+            key
+        } else {
+            Cow::Owned(metrics::Key::from_parts(
+                name,
+                key.labels().cloned().collect::<Vec<_>>(),
+            ))
+        };
+        if self.is_skipped(key.name()) {
+            return Ok(None);
+        }
+        self.storage.resolve(&key).map(Some)
+    }
+    /// Indicates whether the metric identified by the provided `name` is
+    /// skipped (being a silent no-op) by this [`Recorder`], as it's denied,
+    /// or is owned by the authoritative [`Recorder`] this one mirrors.
+    fn is_skipped(&self, name: &str) -> bool {
+        self.storage.is_mirrored(name) || self.storage.is_denied(name)
+    }
+
+    /// Returns a mirror of this [`Recorder`], sharing all its metrics, but
+    /// skipping the ones this [`Recorder`] is authoritative for (see the
+    /// [`Builder::with_authoritative()`] method).
+    ///
+    /// Useful for fanning out the same [`Recorder`] in several places (like
+    /// a [`metrics_util::layers::Fanout`]), without double-counting the
+    /// authoritative metrics.
+    ///
+    /// See the [`Builder::with_authoritative()`] method for an example.
+    #[must_use]
+    pub fn mirror(&self) -> Self
+    where
+        S: Clone,
+    {
+        let mut mirror = self.clone();
+        mirror.storage.mirror = true;
+        mirror
+    }
+
+    /// Returns a [`typed::CounterVec`] handle of the counter family with the
+    /// provided `name` and exactly `N` `labels`, enforcing the number of label
+    /// values at compile time.
+    ///
+    /// The handle resolves the same counters as the [`metrics::counter!`]
+    /// macro does for the same name and labels.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// let requests =
+    ///     recorder.counter_vec::<2>("requests_total", ["method", "status"]);
+    /// requests.inc(["GET", "200"]);
+    /// requests.inc_by(["GET", "200"], 2);
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!(
+    ///         "requests_total", "method" => "GET", "status" => "200",
+    ///     )
+    ///     .increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// let line = r#"requests_total{method="GET",status="200"} 4"#;
+    /// assert!(report.contains(line));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// Wrong number of label values doesn't compile:
+    /// ```rust,compile_fail
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// let requests =
+    ///     recorder.counter_vec::<2>("requests_total", ["method", "status"]);
+    /// requests.inc(["GET"]);
+    /// ```
+    #[must_use]
+    pub fn counter_vec<const N: usize>(
+        &self,
+        name: impl Into<metrics::KeyName>,
+        labels: [&'static str; N],
+    ) -> typed::CounterVec<N, S>
+    where
+        S: Clone,
+    {
+        typed::CounterVec::new(
+            self.clone(),
+            name.into(),
+            labels.map(metrics::SharedString::from),
+        )
+    }
+
+    /// Returns the [`relabel::Rules`] applied at gather time to the metrics
+    /// registered via this [`Recorder`], allowing to replace them at runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::relabel;
+    ///
+    /// let registry = prometheus::Registry::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&registry)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests", "path" => "/a").increment(1);
+    ///     metrics::counter!("requests", "path" => "/b").increment(1);
+    /// });
+    ///
+    /// recorder
+    ///     .relabel_rules()
+    ///     .set(vec![relabel::Rule::drop(["path"], "/b")?]);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&registry.gather())?;
+    /// assert!(report.contains(r#"requests{path="/a"} 1"#));
+    /// assert!(!report.contains(r#"path="/b""#));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    #[cfg(feature = "relabel")]
+    #[must_use]
+    pub const fn relabel_rules(&self) -> &relabel::Rules {
+        &self.storage.relabel
+    }
+
+    /// Returns the [`config::Reloadable`] configuration of the metrics
+    /// registered on the fly via this [`Recorder`].
+    ///
+    /// Cheaply cloneable, so may be kept at hand for updating the
+    /// configuration after this [`Recorder`] is installed globally.
+    #[must_use]
+    pub const fn config(&self) -> &config::Reloadable {
+        &self.storage.config
+    }
+
+    /// Replaces the [`config::Config`] of the metrics registered on the fly
+    /// via this [`Recorder`] with the provided one.
+    ///
+    /// Sanitization rules, default labels and cardinality limits take effect
+    /// for the metrics registered after the update, while the
+    /// [`config::Config::denied`] ones become no-op once their handles are
+    /// obtained anew.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::{failure::strategy, Matcher};
+    ///
+    /// let registry = prometheus::Registry::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&registry)
+    ///     .with_metric_budget(10, 10)
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build();
+    ///
+    /// let mut config = (*recorder.config().get()).clone();
+    /// config.budget.max_children = 2;
+    /// config.denied.push(Matcher::Prefix("debug_".into()));
+    /// config.default_labels.push(("region".into(), "eu".into()));
+    /// recorder.update_config(config);
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests", "path" => "/a").increment(1);
+    ///     metrics::counter!("requests", "path" => "/b").increment(1);
+    ///     // Exceeds the tightened budget, so is rejected.
+    ///     metrics::counter!("requests", "path" => "/c").increment(1);
+    ///     // Denied, so is silently no-op.
+    ///     metrics::counter!("debug_hits").increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&registry.gather())?;
+    /// assert!(report.contains(r#"requests{path="/a",region="eu"} 1"#));
+    /// assert!(report.contains(r#"requests{path="/b",region="eu"} 1"#));
+    /// assert!(!report.contains(r#"path="/c""#));
+    /// assert!(!report.contains("debug_hits"));
+    /// assert!(report.contains(
+    ///     r#"metrics_prometheus_budget_limit{limit="children"} 2"#,
+    /// ));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn update_config(&self, config: config::Config) {
+        self.storage.config.set(config);
+        if let Some(budget) = &self.storage.budget {
+            budget.refresh();
+        }
+    }
+
+    /// Returns the numbers of usages (registrations) of the deprecated metric
+    /// names via this [`Recorder`], by these names.
+    ///
+    /// Empty, unless any names are deprecated via the
+    /// [`Builder::with_deprecated()`] method.
+    ///
+    /// See the [`Builder::with_deprecated()`] method for an example.
+    #[must_use]
+    pub fn deprecated_usages(&self) -> BTreeMap<String, u64> {
+        self.storage.deprecated_usages()
+    }
+
+    /// Tries to register the provided [`prometheus`] `metric` in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via this
+    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// Accepts only the following [`prometheus`] metrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    ///
+    /// If a [`prometheus`] metric with the identical schema (name, help,
+    /// constant and variable labels) is registered already, then it's reused
+    /// instead, and the provided `metric` is not registered at all.
+    ///
+    /// # Errors
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the
+    /// provided `metric`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::install();
+    ///
+    /// let counter = prometheus::IntCounterVec::new(
+    ///     prometheus::opts!("value", "help"),
+    ///     &["whose", "kind"],
+    /// )?;
+    ///
+    /// recorder.try_register_metric(counter.clone())?;
+    ///
+    /// counter.with_label_values(&["mine", "owned"]).inc();
+    /// counter.with_label_values(&["foreign", "ref"]).inc_by(2);
+    /// counter.with_label_values(&["foreign", "owned"]).inc_by(3);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value counter
+    /// value{kind="owned",whose="foreign"} 3
+    /// value{kind="owned",whose="mine"} 1
+    /// value{kind="ref",whose="foreign"} 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// metrics::counter!(
+    ///     "value", "whose" => "mine", "kind" => "owned",
+    /// ).increment(1);
+    /// metrics::counter!(
+    ///     "value", "whose" => "mine", "kind" => "ref",
+    /// ).increment(1);
+    /// metrics::counter!(
+    ///     "value", "kind" => "owned", "whose" => "foreign",
+    /// ).increment(1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value counter
+    /// value{kind="owned",whose="foreign"} 4
+    /// value{kind="owned",whose="mine"} 2
+    /// value{kind="ref",whose="foreign"} 2
+    /// value{kind="ref",whose="mine"} 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// // Registering a metric with the identical schema reuses the existing
+    /// // one.
+    /// recorder.try_register_metric(prometheus::IntCounterVec::new(
+    ///     prometheus::opts!("value", "help"),
+    ///     &["whose", "kind"],
+    /// )?)?;
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn try_register_metric<M>(&self, metric: M) -> prometheus::Result<()>
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        self.storage.register_external(metric)
+    }
+
+    /// Registers the provided [`prometheus`] `metric` in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via this
+    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// Accepts only the following [`prometheus`] metrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    ///
+    /// # Panics
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the
+    /// provided `metric` (logged instead, if the `never-panic` feature is
+    /// enabled).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::install();
+    ///
+    /// let gauge = prometheus::GaugeVec::new(
+    ///     prometheus::opts!("value", "help"),
+    ///     &["whose", "kind"],
+    /// )?;
+    ///
+    /// recorder.register_metric(gauge.clone());
+    ///
+    /// gauge.with_label_values(&["mine", "owned"]).inc();
+    /// gauge.with_label_values(&["foreign", "ref"]).set(2.0);
+    /// gauge.with_label_values(&["foreign", "owned"]).set(3.0);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value gauge
+    /// value{kind="owned",whose="foreign"} 3
+    /// value{kind="owned",whose="mine"} 1
+    /// value{kind="ref",whose="foreign"} 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// metrics::gauge!(
+    ///     "value", "whose" => "mine", "kind" => "owned",
+    /// ).increment(2.0);
+    /// metrics::gauge!(
+    ///     "value", "whose" => "mine", "kind" => "ref",
+    /// ).decrement(2.0);
+    /// metrics::gauge!(
+    ///     "value", "kind" => "owned", "whose" => "foreign",
+    /// ).increment(2.0);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value gauge
+    /// value{kind="owned",whose="foreign"} 5
+    /// value{kind="owned",whose="mine"} 3
+    /// value{kind="ref",whose="foreign"} 2
+    /// value{kind="ref",whose="mine"} -2
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn register_metric<M>(&self, metric: M)
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        if let Err(e) = self.try_register_metric(metric) {
+            failure::panic_or_log(&format!(
+                "failed to register `prometheus` metric: {e}",
+            ));
+        }
+    }
+
+    /// Returns the [`Instant`] the metric identified by the provided `name`
+    /// and `labels` (in any order) was updated last time via this
+    /// [`Recorder`].
+    ///
+    /// [`None`] is returned if the metric has never been updated, allowing to
+    /// distinguish "never written" metrics from the "written, but with the
+    /// same value" ones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::install();
+    ///
+    /// let counter = metrics::counter!("count", "kind" => "owned");
+    /// assert!(recorder.last_updated("count", &[("kind", "owned")]).is_none());
+    ///
+    /// counter.increment(1);
+    /// assert!(recorder.last_updated("count", &[("kind", "owned")]).is_some());
+    /// ```
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn last_updated(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+    ) -> Option<Instant> {
+        self.storage.timestamps.get(name, labels)
+    }
+}
+
+impl<S: failure::Strategy> Recorder<S> {
+    /// Starts a new [`Batch`] of metrics changes, applied to this [`Recorder`]
+    /// all at once on [`Batch::commit()`], or discarded on [`Drop`].
+    ///
+    /// See the [`Batch`] for an example.
+    ///
+    /// [`Batch`]: batch::Batch
+    /// [`Batch::commit()`]: batch::Batch::commit
+    pub const fn batch(&self) -> batch::Batch<'_, S> {
+        batch::Batch::new(self)
+    }
+
+    /// Returns a counter identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its registration, regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// Unlike the [`metrics::counter!`] macro, never returns a silent no-op
+    /// counter on failure, so critical-path metrics may assert their validity
+    /// at startup. The returned counter is the same one the
+    /// [`metrics::counter!`] macro resolves to: the provided
+    /// [`metrics::Key`] is normalized the same way (routed if deprecated, and
+    /// prefixed as registered by this crate's `target`).
+    ///
+    /// # Errors
+    ///
+    /// If the counter cannot be registered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::{deprecation, failure::strategy};
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .with_deprecated("hits", "requests", deprecation::Policy::Count)
+    ///     .build();
+    ///
+    /// let key = metrics::Key::from_name("requests");
+    /// recorder.checked_counter(&key)?.increment(1);
+    ///
+    /// // Deprecated names are routed to their replacements.
+    /// let key = metrics::Key::from_name("hits");
+    /// recorder.checked_counter(&key)?.increment(1);
+    /// assert_eq!(recorder.deprecated_usages()["hits"], 1);
+    ///
+    /// // `prometheus` doesn't allow different label names in the same family.
+    /// let key = metrics::Key::from_parts("requests", &[("path", "/")]);
+    /// assert!(recorder.checked_counter(&key).is_err());
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains("\nrequests 2"));
+    /// assert!(!report.contains("\nhits"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn checked_counter(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Counter> {
+        let Some(key) = self.normalized(key, METADATA.target()) else {
+            return Ok(metrics::Counter::noop());
+        };
+        let key = &*key;
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().counter(key))
+        {
+            return res;
+        }
+        self.storage.flush_descriptions();
+        let err = self.metrics.get_or_create_counter(key, |counter| {
+            counter.as_ref().err().map(failure::clone_error)
+        });
+        err.map_or_else(
+            || Ok(self.register_normalized_counter(key, &METADATA)),
+            Err,
+        )
+    }
+
+    /// Returns a gauge identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its registration, regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the gauge cannot be registered.
+    pub fn checked_gauge(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Gauge> {
+        let Some(key) = self.normalized(key, METADATA.target()) else {
+            return Ok(metrics::Gauge::noop());
+        };
+        let key = &*key;
+        if let Some(res) =
+            self.storage.custom(key.name()).and_then(|b| b.as_ref().gauge(key))
+        {
+            return res;
+        }
+        self.storage.flush_descriptions();
+        let err = self.metrics.get_or_create_gauge(key, |gauge| {
+            gauge.as_ref().err().map(failure::clone_error)
+        });
+        err.map_or_else(
+            || Ok(self.register_normalized_gauge(key, &METADATA)),
+            Err,
+        )
+    }
+
+    /// Returns a histogram identified by the provided [`metrics::Key`], or the
+    /// [`prometheus::Error`] preventing its registration, regardless of the
+    /// configured [`failure::Strategy`].
+    ///
+    /// See the [`Recorder::checked_counter()`] method for details.
+    ///
+    /// # Errors
+    ///
+    /// If the histogram cannot be registered.
+    pub fn checked_histogram(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<metrics::Histogram> {
+        let Some(key) = self.normalized(key, METADATA.target()) else {
+            return Ok(metrics::Histogram::noop());
+        };
+        let key = &*key;
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().histogram(key))
+        {
+            return res;
+        }
+        self.storage.flush_descriptions();
+        let err = self.storage.adaptive_histogram(key).map_or_else(
+            || {
+                self.metrics.get_or_create_histogram(key, |histogram| {
+                    histogram.as_ref().err().map(failure::clone_error)
+                })
+            },
+            Result::err,
+        );
+        err.map_or_else(
+            || Ok(self.register_normalized_histogram(key, &METADATA)),
+            Err,
+        )
+    }
+
+    /// Forgets the metrics evicted by the [`budget::Tracker`] (if any) in the
+    /// [`metrics::Registry`], so they're registered anew on the next use.
+    ///
+    /// [`metrics::Registry`]: metrics_util::registry::Registry
+    fn forget_evicted(&self) {
+        if let Some(budget) = &self.storage.budget {
+            for key in budget.take_evicted() {
+                // The kind of the evicted metric is unknown here, while the
+                // same name cannot be used by metrics of different kinds.
+                _ = self.metrics.delete_counter(&key)
+                    || self.metrics.delete_gauge(&key)
+                    || self.metrics.delete_histogram(&key);
+                self.storage.forget_batched(&key);
+                self.storage.forget_striped(&key);
+            }
+        }
+    }
+
+    /// Applies the configured [`storage::Orphans`] policy to the
+    /// [`help` description] of the `M`etric identified by the provided `name`,
+    /// returning whether it should be described.
+    ///
+    /// # Panics
+    ///
+    /// If the description is rejected, and the [`failure::Strategy`] decides
+    /// so.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    fn adopt_description<M>(&self, name: &str) -> bool
+    where
+        M: metric::Bundled,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        self.storage.adopt_description::<M>(name).map_or_else(
+            |e| {
+                let key = metrics::Key::from_name(name.to_owned());
+                match self.failure_strategy.resolve_for(&key, &e) {
+                    failure::Action::NoOp => false,
+                    failure::Action::Panic => panic!("{e}"),
+                }
+            },
+            |()| true,
+        )
+    }
+
+    /// Describes the `M`etric family of the provided [`pending::Kind`]
+    /// identified by the provided `name` with the provided `description` and
+    /// declared [`metrics::Unit`] (if any).
+    ///
+    /// With [lazy descriptions] enabled, the whole description (including its
+    /// [`metrics::Unit`] and the [`storage::Orphans`] policy) is applied
+    /// lazily, so describing never takes any locks.
+    ///
+    /// # Panics
+    ///
+    /// If the description or its [`metrics::Unit`] is rejected, and the
+    /// [`failure::Strategy`] decides so.
+    ///
+    /// [lazy descriptions]: Builder::with_lazy_descriptions
+    /// [`pending::Kind`]: storage::pending::Kind
+    fn describe<M>(
+        &self,
+        kind: storage::pending::Kind,
+        name: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) where
+        M: metric::Bundled,
+        <M as metric::Bundled>::Bundle: Clone,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        let name = self.storage.replace_deprecated(name);
+        trace::described(kind.as_str(), name.as_str());
+        let pending = storage::pending::Description {
+            kind,
+            unit: unit.filter(|u| self.is_unit_accepted(&name, *u)),
+            name,
+            description,
+            #[cfg(feature = "origins")]
+            origin: metric::origin::Origin::capture(None),
+        };
+        if let Err(pending) = self.storage.describe_lazily(pending) {
+            self.describe_eagerly::<M>(pending);
+        }
+    }
+
+    /// Applies the provided [`pending::Description`] to the `M`etric family
+    /// right away, respecting the [`storage::Orphans`] policy.
+    ///
+    /// # Panics
+    ///
+    /// If the [`pending::Description`] is rejected, and the
+    /// [`failure::Strategy`] decides so.
+    ///
+    /// [`pending::Description`]: storage::pending::Description
+    fn describe_eagerly<M>(&self, pending: storage::pending::Description)
+    where
+        M: metric::Bundled,
+        <M as metric::Bundled>::Bundle: Clone,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        if !self.adopt_description::<M>(pending.name.as_str()) {
+            return;
+        }
+        #[cfg(feature = "origins")]
+        self.storage.originate_description::<M>(pending.name.as_str(), || {
+            pending.origin
+        });
+        if let Some(unit) = pending.unit {
+            self.storage.record_unit(pending.name.as_str().into(), unit);
+        }
+        self.storage.describe::<M>(pending.name.as_str(), pending.description);
+    }
+
+    /// Checks whether the provided declared [`metrics::Unit`] of the metric
+    /// family identified by the provided `name` is accepted, not conflicting
+    /// with the suffix of its `name`.
+    ///
+    /// # Panics
+    ///
+    /// If the `name` has a suffix of another [`metrics::Unit`], and the
+    /// [`failure::Strategy`] decides so.
+    fn is_unit_accepted(
+        &self,
+        name: &metrics::KeyName,
+        unit: metrics::Unit,
+    ) -> bool {
+        let Err(e) = openmetrics::check_suffix(name.as_str(), unit) else {
+            return true;
+        };
+        let key = metrics::Key::from_name(name.clone());
+        match self.failure_strategy.resolve_for(&key, &e) {
+            failure::Action::NoOp => false,
+            failure::Action::Panic => panic!("{e}"),
+        }
+    }
+
+    /// Makes the provided [`metrics::Histogram`] to track its extreme values
+    /// in a [`metric::extrema::Family`], if it's configured for the provided
+    /// [`metrics::Key`].
+    fn with_extrema(
+        &self,
+        key: &metrics::Key,
+        histogram: metrics::Histogram,
+    ) -> metrics::Histogram {
+        match self.storage.extrema(key) {
+            None => histogram,
+            Some(Ok(child)) => metrics::Histogram::from_arc(Arc::new(
+                metric::extrema::Histogram::new(histogram, child),
+            )),
+            Some(Err(e)) => match self.failure_strategy.resolve_for(key, &e) {
+                failure::Action::NoOp => histogram,
+                failure::Action::Panic => panic!(
+                    "failed to register `metric::extrema::Family` gauges: {e}",
+                ),
+            },
+        }
+    }
+
+    /// Makes the provided [`metrics::Histogram`] to count its observations
+    /// exceeding thresholds in a [`metric::threshold::Family`], if it's
+    /// configured for the provided [`metrics::Key`].
+    fn with_over_threshold(
+        &self,
+        key: &metrics::Key,
+        histogram: metrics::Histogram,
+    ) -> metrics::Histogram {
+        match self.storage.over_threshold(key) {
+            None => histogram,
+            Some(Ok(child)) => metrics::Histogram::from_arc(Arc::new(
+                metric::threshold::Histogram::new(histogram, child),
+            )),
+            Some(Err(e)) => match self.failure_strategy.resolve_for(key, &e) {
+                failure::Action::NoOp => histogram,
+                failure::Action::Panic => panic!(
+                    "failed to register `metric::threshold::Family` counters: \
+                     {e}",
+                ),
+            },
+        }
+    }
+
+    /// Registers the counter identified by the provided [`metrics::Key`],
+    /// already [normalized] by the provided `metadata` target (and not
+    /// skipped).
+    ///
+    /// [normalized]: Recorder::normalized
+    #[cfg_attr(
+        not(feature = "origins"),
+        expect( // intentional
+            unused_variables,
+            reason = "`metadata` is used for capturing origins only"
+        )
+    )]
+    fn register_normalized_counter(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Counter {
+        self.storage.flush_descriptions();
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().counter(key))
+        {
+            return res.unwrap_or_else(|e| {
+                match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Counter::noop(),
+                    failure::Action::Panic => panic!(
+                        "failed to register custom `prometheus` metric: {e}",
+                    ),
+                }
+            });
+        }
+        let res = self.metrics.get_or_create_counter(key, |counter| {
+            counter.as_ref().map(|c| self.counter(key, c)).or_else(|e| {
+                match self.failure_strategy.resolve_for(key, e) {
+                    failure::Action::NoOp => Ok(metrics::Counter::noop()),
+                    // PANIC: We cannot panic inside this closure, because
+                    //        this may lead to poisoning `RwLock`s inside
+                    //        `metrics_util::registry::Registry`.
+                    failure::Action::Panic => Err(counter.clone()),
+                }
+            })
+        });
+        #[cfg(feature = "origins")]
+        self.storage.originate_registration::<prometheus::IntCounter>(
+            key.name(),
+            || metric::origin::Origin::capture(Some(metadata)),
+        );
+        self.forget_evicted();
+        res.unwrap_or_else(|failed| fail("prometheus::IntCounter", &failed))
+    }
+
+    /// Registers the gauge identified by the provided [`metrics::Key`],
+    /// already [normalized] by the provided `metadata` target (and not
+    /// skipped).
+    ///
+    /// [normalized]: Recorder::normalized
+    #[cfg_attr(
+        not(feature = "origins"),
+        expect( // intentional
+            unused_variables,
+            reason = "`metadata` is used for capturing origins only"
+        )
+    )]
+    fn register_normalized_gauge(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Gauge {
+        self.storage.flush_descriptions();
+        if let Some(res) =
+            self.storage.custom(key.name()).and_then(|b| b.as_ref().gauge(key))
+        {
+            return res.unwrap_or_else(|e| {
+                match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Gauge::noop(),
+                    failure::Action::Panic => panic!(
+                        "failed to register custom `prometheus` metric: {e}",
+                    ),
+                }
+            });
+        }
+        let res = self.metrics.get_or_create_gauge(key, |gauge| {
+            gauge.as_ref().map(|c| Arc::clone(c).into()).or_else(|e| {
+                match self.failure_strategy.resolve_for(key, e) {
+                    failure::Action::NoOp => Ok(metrics::Gauge::noop()),
+                    // PANIC: We cannot panic inside this closure, because
+                    //        this may lead to poisoning `RwLock`s inside
+                    //        `metrics_util::registry::Registry`.
+                    failure::Action::Panic => Err(gauge.clone()),
+                }
+            })
+        });
+        #[cfg(feature = "origins")]
+        self.storage
+            .originate_registration::<prometheus::Gauge>(key.name(), || {
+                metric::origin::Origin::capture(Some(metadata))
+            });
+        self.forget_evicted();
+        let gauge =
+            res.unwrap_or_else(|failed| fail("prometheus::Gauge", &failed));
+        self.storage.striped(key, gauge)
+    }
+
+    /// Registers the histogram identified by the provided [`metrics::Key`],
+    /// already [normalized] by the provided `metadata` target (and not
+    /// skipped).
+    ///
+    /// [normalized]: Recorder::normalized
+    #[cfg_attr(
+        not(feature = "origins"),
+        expect( // intentional
+            unused_variables,
+            reason = "`metadata` is used for capturing origins only"
+        )
+    )]
+    fn register_normalized_histogram(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Histogram {
+        self.storage.flush_descriptions();
+        if let Some(res) = self
+            .storage
+            .custom(key.name())
+            .and_then(|b| b.as_ref().histogram(key))
+        {
+            return res.unwrap_or_else(|e| {
+                match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Histogram::noop(),
+                    failure::Action::Panic => panic!(
+                        "failed to register custom `prometheus` metric: {e}",
+                    ),
+                }
+            });
+        }
+        if let Some(res) = self.storage.adaptive_histogram(key) {
+            let histogram = res.map_or_else(
+                |e| match self.failure_strategy.resolve_for(key, &e) {
+                    failure::Action::NoOp => metrics::Histogram::noop(),
+                    failure::Action::Panic => panic!(
+                        "failed to register adaptive `prometheus::Histogram` \
+                         metric: {e}",
+                    ),
+                },
+                metrics::Histogram::from_arc,
+            );
+            let histogram = self.with_over_threshold(key, histogram);
+            self.with_quantiles(key);
+            return self.with_extrema(key, histogram);
+        }
+
+        let res = self.metrics.get_or_create_histogram(key, |histogram| {
+            histogram.as_ref().map(|h| Some(Arc::clone(h))).or_else(|e| {
+                match self.failure_strategy.resolve_for(key, e) {
+                    failure::Action::NoOp => Ok(None),
+                    // PANIC: We cannot panic inside this closure, because
+                    //        this may lead to poisoning `RwLock`s inside
+                    //        `metrics_util::registry::Registry`.
+                    failure::Action::Panic => Err(histogram.clone()),
+                }
+            })
+        });
+        #[cfg(feature = "origins")]
+        self.storage.originate_registration::<prometheus::Histogram>(
+            key.name(),
+            || metric::origin::Origin::capture(Some(metadata)),
+        );
+        self.forget_evicted();
+        let histogram = res
+            .unwrap_or_else(|failed| fail("prometheus::Histogram", &failed))
+            .map_or_else(metrics::Histogram::noop, |h| {
+                self.storage.batched(key, h)
+            });
+        let histogram = self.with_over_threshold(key, histogram);
+        self.with_quantiles(key);
+        self.with_extrema(key, histogram)
+    }
+
+    /// Normalizes the provided [`metrics::Key`] of a metric registered by the
+    /// provided `target` (as reported by [`metrics::Metadata::target()`]):
+    /// prefixes its name according to the [`target::Prefix`] rules, and
+    /// routes it to its replacement, if its name is deprecated.
+    ///
+    /// Shared by all the registration paths, so they resolve the same
+    /// [`metrics::Key`] into the same metric.
+    ///
+    /// Returns [`None`] if the metric should be skipped (being a silent
+    /// no-op), as its name is denied, or is owned by the authoritative
+    /// [`Recorder`] this one mirrors.
+    fn normalized<'k>(
+        &self,
+        key: &'k metrics::Key,
+        target: &str,
+    ) -> Option<Cow<'k, metrics::Key>> {
+        let key = self.route_deprecated(self.storage.prefixed(key, target));
+        (!self.is_skipped(key.name())).then_some(key)
+    }
+
+    /// Routes the provided [`metrics::Key`] to its replacement, if its name is
+    /// deprecated.
+    fn route_deprecated<'k>(
+        &self,
+        key: Cow<'k, metrics::Key>,
+    ) -> Cow<'k, metrics::Key> {
+        let Some((routed, res)) = self.storage.route_deprecated(&key) else {
+            return key;
+        };
+        if let Err(e) = res {
+            match self.failure_strategy.resolve_for(&key, &e) {
+                failure::Action::NoOp => {}
+                failure::Action::Panic => {
+                    panic!("failed to track deprecated metric: {e}")
+                }
+            }
+        }
+        Cow::Owned(routed)
+    }
+
+    /// Accompanies the histogram identified by the provided [`metrics::Key`]
+    /// with a [`metric::quantile::Family`] of gauges, if it's configured for
+    /// it.
+    fn with_quantiles(&self, key: &metrics::Key) {
+        if let Some(Err(e)) = self.storage.register_quantiles(key) {
+            match self.failure_strategy.resolve_for(key, &e) {
+                failure::Action::NoOp => {}
+                failure::Action::Panic => panic!(
+                    "failed to register `metric::quantile::Family` gauges: {e}",
+                ),
+            }
+        }
+    }
+
+    /// Converts the provided [`prometheus::IntCounter`] into a
+    /// [`metrics::Counter`], reconciling its declared
+    /// [`metric::ingestion::Mode`] or guarding it against regressions, if
+    /// required.
+    fn counter(
+        &self,
+        key: &metrics::Key,
+        counter: &Arc<metric::Metric<prometheus::IntCounter>>,
+    ) -> metrics::Counter {
+        if let Some(mode) = self.storage.ingestion.find(key.name()) {
+            return metrics::Counter::from_arc(Arc::new(
+                metric::ingestion::Counter::new(
+                    Arc::clone(counter),
+                    key,
+                    *mode,
+                    &self.storage.totals,
+                    &self.failure_strategy,
+                ),
+            ));
+        }
+        if self.storage.monotonic {
+            metrics::Counter::from_arc(Arc::new(
+                metric::monotonic::Counter::guard(
+                    Arc::clone(counter),
+                    key.name(),
+                    &self.failure_strategy,
+                ),
+            ))
+        } else {
+            Arc::clone(counter).into()
+        }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl<S> metrics::Recorder for Recorder<S>
+where
+    S: failure::Strategy,
+{
+    fn describe_counter(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.describe::<prometheus::IntCounter>(
+            storage::pending::Kind::Counter,
+            key,
+            unit,
+            description,
+        );
+    }
+
+    fn describe_gauge(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.describe::<prometheus::Gauge>(
+            storage::pending::Kind::Gauge,
+            key,
+            unit,
+            description,
+        );
+    }
+
+    fn describe_histogram(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.describe::<prometheus::Histogram>(
+            storage::pending::Kind::Histogram,
+            key,
+            unit,
+            description,
+        );
+    }
+
+    fn register_counter(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Counter {
+        let Some(key) = self.normalized(key, metadata.target()) else {
+            return metrics::Counter::noop();
+        };
+        self.register_normalized_counter(&key, metadata)
+    }
+
+    fn register_gauge(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Gauge {
+        let Some(key) = self.normalized(key, metadata.target()) else {
+            return metrics::Gauge::noop();
+        };
+        self.register_normalized_gauge(&key, metadata)
+    }
+
+    fn register_histogram(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Histogram {
+        let Some(key) = self.normalized(key, metadata.target()) else {
+            return metrics::Histogram::noop();
+        };
+        self.register_normalized_histogram(&key, metadata)
+    }
+}
+
+/// Panics on the provided [`metric::Fallible`] having failed to be registered
+/// as a `kind` metric.
+///
+/// The contained [`prometheus::Error`] is formatted here only, so the
+/// registration path doesn't allocate anything for its error handling.
+#[cold]
+#[track_caller]
+fn fail<M>(kind: &str, failed: &metric::Fallible<M>) -> ! {
+    match failed.as_ref() {
+        Err(e) => panic!("failed to register `{kind}` metric: {e}"),
+        Ok(_) => panic!("failed to register `{kind}` metric"),
+    }
+}
+
+/// Builds a [`metrics::Key`] of the gauge identified by the provided `name`
+/// and `labels`, whose collect callback is set.
+fn callback_key(name: &str, labels: &[(&str, &str)]) -> metrics::Key {
+    metrics::Key::from_parts(
+        name.to_owned(),
+        labels
+            .iter()
+            .map(|(k, v)| metrics::Label::new((*k).to_owned(), (*v).to_owned()))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Builder for building a [`Recorder`].
+#[derive(Debug)]
+#[must_use]
+pub struct Builder<
+    FailureStrategy = PanicInDebugNoOpInRelease,
+    Layers = layer::Stack,
+> {
+    /// [`storage::Mutable`] registering metrics in its
+    /// [`prometheus::Registry`].
+    storage: storage::Mutable,
+
+    /// [`failure::Strategy`] of the built [`Recorder`] to apply when a
+    /// [`prometheus::Error`] is encountered inside its [`metrics::Recorder`]
+    /// methods.
+    failure_strategy: FailureStrategy,
+
+    /// [`metrics::Layer`]s to wrap the built [`Recorder`] with upon its
+    /// installation with the [`metrics::set_global_recorder()`].
+    ///
+    /// [`metrics::Layer`]: Layer
+    layers: Layers,
+}
+
+impl<S, L> Builder<S, L> {
+    /// Sets the provided [`prometheus::Registry`] to be used by the built
+    /// [`Recorder`].
+    ///
+    /// When not specified, the [`prometheus::default_registry()`] is used by
+    /// default.
+    ///
+    /// # Warning
+    ///
+    /// Any [`prometheus`] metrics, already registered in the provided
+    /// [`prometheus::Registry`], cannot be used via the built
+    /// [`metrics::Recorder`] (and, so, [`metrics`] crate interfaces), and
+    /// trying to use them will inevitably cause a [`prometheus::Error`] being
+    /// emitted.
+    ///
+    /// # Panics
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register the
+    /// [`prometheus`] metrics registered in this [`Builder`] already (see the
+    /// [`Builder::try_with_registry()`] for details).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let custom = prometheus::Registry::new_custom(Some("my".into()), None)?;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&custom)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("count").increment(1);
+    ///
+    /// let report =
+    ///     prometheus::TextEncoder::new().encode_to_string(&custom.gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP my_count count
+    /// ## TYPE my_count counter
+    /// my_count 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    // TODO: Try remove on Rust 1.84 upgrade.
+    #[expect( // anonymous lifetimes in `impl Trait` are unstable
+        single_use_lifetimes,
+        reason = "anonymous lifetimes in `impl Trait` are unstable"
+    )]
+    pub fn with_registry<'r>(
+        self,
+        registry: impl IntoCow<'r, prometheus::Registry>,
+    ) -> Self {
+        self.try_with_registry(registry).unwrap_or_else(|e| {
+            panic!("failed to register `prometheus` metric: {e}")
+        })
+    }
+
+    /// Tries to set the provided [`prometheus::Registry`] to be used by the
+    /// built [`Recorder`].
+    ///
+    /// The [`prometheus`] metrics registered in this [`Builder`] already (via
+    /// [`Builder::with_metric()`], for example) are moved into the provided
+    /// [`prometheus::Registry`], so the order of [`Builder`] calls doesn't
+    /// matter.
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register any of the
+    /// [`prometheus`] metrics registered in this [`Builder`] already. In such
+    /// case, nothing is moved and the previous [`prometheus::Registry`] is
+    /// kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let counter = prometheus::IntCounter::new("value", "help")?;
+    /// let custom = prometheus::Registry::new();
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_metric(counter.clone())
+    ///     .try_with_registry(&custom)?
+    ///     .build();
+    ///
+    /// counter.inc();
+    ///
+    /// assert_eq!(custom.gather().len(), 1);
+    /// assert!(prometheus::default_registry().gather().is_empty());
+    ///
+    /// let taken = prometheus::Registry::new();
+    /// taken.register(Box::new(counter.clone()))?;
+    /// let res = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_metric(counter)
+    ///     .try_with_registry(&taken);
+    /// assert!(res.is_err());
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    // TODO: Try remove on Rust 1.84 upgrade.
+    #[expect( // anonymous lifetimes in `impl Trait` are unstable
+        single_use_lifetimes,
+        reason = "anonymous lifetimes in `impl Trait` are unstable"
+    )]
+    pub fn try_with_registry<'r>(
+        mut self,
+        registry: impl IntoCow<'r, prometheus::Registry>,
+    ) -> prometheus::Result<Self> {
+        self.storage.move_to(Arc::new(registry.into_cow().into_owned()))?;
+        Ok(self)
+    }
+
+    /// Makes the built [`Recorder`] to use a brand-new [`prometheus::Registry`]
+    /// of its own, isolated from the [`prometheus::default_registry()`] and
+    /// any other [`Recorder`].
+    ///
+    /// Useful in tests and benchmarks, preventing pollution across them via the
+    /// process-global [`prometheus::default_registry()`] (being used by
+    /// default otherwise). The created [`prometheus::Registry`] is accessible
+    /// via the [`Recorder::registry()`] method.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("isolated").increment(1);
+    /// });
+    ///
+    /// assert_eq!(recorder.registry().gather().len(), 1);
+    /// assert!(prometheus::default_registry().gather().is_empty());
+    /// ```
+    pub fn with_isolated_registry(self) -> Self {
+        self.with_registry(prometheus::Registry::new())
+    }
+
+    /// Sets the provided [`tasks::Shutdown`] to be used by the background
+    /// tasks spawned for the built [`Recorder`].
+    ///
+    /// Allows sharing a single [`tasks::Shutdown`] between several
+    /// [`Recorder`]s and the application's own background tasks. When not
+    /// specified, a new one is created (accessible via the
+    /// [`Recorder::tasks()`] method).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::tasks::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_shutdown(shutdown.clone())
+    ///     .build();
+    ///
+    /// shutdown.trigger();
+    /// assert!(recorder.tasks().is_triggered());
+    /// ```
+    pub fn with_shutdown(mut self, shutdown: tasks::Shutdown) -> Self {
+        self.storage.tasks = shutdown;
+        self
+    }
+
+    /// Makes the built [`Recorder`] to use the process-global
+    /// [`prometheus::default_registry()`].
+    ///
+    /// This is the default, so this method only makes it explicit (or reverts
+    /// a [`prometheus::Registry`] set previously). To avoid interference with
+    /// other crates and tests via the process-global one, use the
+    /// [`Builder::with_isolated_registry()`] instead.
+    ///
+    /// # Panics
+    ///
+    /// If the [`prometheus::default_registry()`] fails to register the
+    /// [`prometheus`] metrics registered in this [`Builder`] already (see the
+    /// [`Builder::try_with_registry()`] for details).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("count").increment(1);
+    ///
+    /// let families = prometheus::default_registry().gather();
+    /// assert_eq!(families[0].get_name(), "count");
+    /// ```
+    pub fn with_default_registry(self) -> Self {
+        self.with_registry(prometheus::default_registry())
+    }
+
+    /// Sets the provided [`Clock`] to be used as the time source by the
+    /// time-based features of the built [`Recorder`] (like
+    /// [`Recorder::windowed_rate()`], [`Builder::with_rate_limit()`] and last
+    /// update timestamps).
+    ///
+    /// When not specified, the [`clock::System`] is used by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use metrics::counter;
+    /// use metrics_prometheus::clock;
+    ///
+    /// let clock = clock::Mock::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_clock(clock.clone())
+    ///     .build();
+    /// recorder.windowed_rate("req_5m", "req", Duration::from_secs(300))?;
+    ///
+    /// let req = metrics::with_local_recorder(&recorder, || counter!("req"));
+    /// req.increment(3);
+    /// clock.advance(Duration::from_secs(200));
+    /// _ = recorder.registry().gather();
+    /// req.increment(2);
+    /// clock.advance(Duration::from_secs(300));
+    ///
+    /// let encoder = prometheus::TextEncoder::new();
+    /// let report = encoder.encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains("req_5m 2\n"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Clock`]: clock::Clock
+    pub fn with_clock<C: clock::Clock + 'static>(mut self, clock: C) -> Self {
+        let clock: clock::Shared = Arc::new(clock);
+        #[cfg(feature = "timestamps")]
+        {
+            self.storage.timestamps =
+                self.storage.timestamps.clone().with_clock(Arc::clone(&clock));
+        }
+        self.storage.clock = clock;
+        self
+    }
+
+    /// Sets the provided [`failure::Strategy`] to be used by the built
+    /// [`Recorder`].
+    ///
+    /// [`prometheus::Registry`] has far more stricter semantics than the ones
+    /// implied by a [`metrics::Recorder`]. That's why incorrect usage of
+    /// [`prometheus`] metrics via [`metrics`] crate will inevitably lead to a
+    /// [`prometheus::Registry`] returning a [`prometheus::Error`] instead of a
+    /// registering the metric. The returned [`prometheus::Error`] can be either
+    /// turned into a panic, or just silently ignored, making the [`Recorder`]
+    /// to return a no-op metric instead (see [`metrics::Counter::noop()`] for
+    /// example).
+    ///
+    /// The default [`failure::Strategy`] is [`PanicInDebugNoOpInRelease`]. See
+    /// [`failure::strategy`] module for other available [`failure::Strategy`]s,
+    /// or provide your own one by implementing the [`failure::Strategy`] trait.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("invalid.name").increment(1);
+    ///
+    /// let stats = prometheus::default_registry().gather();
+    /// assert_eq!(stats.len(), 0);
+    /// ```
+    pub fn with_failure_strategy<F>(self, strategy: F) -> Builder<F, L>
+    where
+        F: failure::Strategy,
+    {
+        Builder {
+            storage: self.storage,
+            failure_strategy: strategy,
+            layers: self.layers,
+        }
+    }
+
+    /// Overrides the current [`failure::Strategy`] of the built [`Recorder`]
+    /// with the provided one for the metrics matched by the provided
+    /// [`Matcher`], so specific critical metrics may panic on misuse, while
+    /// the rest of them are ignored silently, for example.
+    ///
+    /// Overrides set later take precedence over the ones set earlier. Setting
+    /// the [`Builder::with_failure_strategy()`] afterwards discards all the
+    /// overrides.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::panic;
+    ///
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .with_failure_strategy_for("payments", strategy::Panic)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests").increment(1);
+    ///     metrics::counter!("payments").increment(1);
+    ///
+    ///     // `prometheus` doesn't allow different label names in the same
+    ///     // family, so this is silently ignored...
+    ///     metrics::counter!("requests", "path" => "/").increment(1);
+    ///     // ...while this panics.
+    ///     let res = panic::catch_unwind(|| {
+    ///         metrics::counter!("payments", "bank" => "x").increment(1);
+    ///     });
+    ///     # #[cfg(not(feature = "never-panic"))]
+    ///     assert!(res.is_err());
+    /// });
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_failure_strategy_for<F>(
+        self,
+        matcher: impl Into<crate::Matcher>,
+        strategy: F,
+    ) -> Builder<failure::strategy::PerMetric<S>, L>
+    where
+        F: failure::Strategy + 'static,
+    {
+        Builder {
+            storage: self.storage,
+            failure_strategy: failure::strategy::PerMetric::new(
+                self.failure_strategy,
+            )
+            .with_override(matcher, strategy),
+            layers: self.layers,
+        }
+    }
+
+    /// Tries to register the provided [`prometheus`] `metric` in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via the created
+    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// Accepts only the following [`prometheus`] metrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    ///
+    /// If a [`prometheus`] metric with the identical schema (name, help,
+    /// constant and variable labels) is registered already, then it's reused
+    /// instead, and the provided `metric` is not registered at all.
+    ///
+    /// # Errors
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the
+    /// provided `metric`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let gauge = prometheus::Gauge::new("value", "help")?;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .try_with_metric(gauge.clone())?
+    ///     .build_and_install();
+    ///
+    /// gauge.inc();
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value gauge
+    /// value 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// metrics::gauge!("value").increment(1.0);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value gauge
+    /// value 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn try_with_metric<M>(self, metric: M) -> prometheus::Result<Self>
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        self.storage.register_external(metric)?;
+        Ok(self)
+    }
+
+    /// Registers the provided [`prometheus`] `metric` in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via the created
+    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// Accepts only the following [`prometheus`] metrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    ///
+    /// # Panics
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the
+    /// provided `metric`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let counter = prometheus::IntCounter::new("value", "help")?;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_metric(counter.clone())
+    ///     .build_and_install();
+    ///
+    /// counter.inc();
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value counter
+    /// value 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// metrics::counter!("value").increment(1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value counter
+    /// value 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn with_metric<M>(self, metric: M) -> Self
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        self.try_with_metric(metric).unwrap_or_else(|e| {
+            panic!("failed to register `prometheus` metric: {e}")
+        })
+    }
+
+    /// Tries to register the provided [`metric::CustomBundle`] in the
+    /// underlying [`prometheus::Registry`] in the way making it usable via the
+    /// created [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// See the [`metric::CustomBundle`] for an example.
+    ///
+    /// # Errors
+    ///
+    /// - If any of the `bundle` metrics is registered as a
+    ///   [`metric::CustomBundle`] already.
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   provided `bundle`.
+    pub fn try_with_custom_bundle<B>(
+        self,
+        bundle: B,
+    ) -> prometheus::Result<Self>
+    where
+        B: metric::CustomBundle + 'static,
+    {
+        self.storage.register_custom(bundle)?;
+        Ok(self)
+    }
+
+    /// Registers the provided [`metric::CustomBundle`] in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via the created
+    /// [`Recorder`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// The [`metrics`] of the same names as the `bundle` metrics are resolved
+    /// via it, rather than registered as the usual [`prometheus`] metrics.
+    ///
+    /// See the [`metric::CustomBundle`] for an example.
+    ///
+    /// # Panics
+    ///
+    /// - If any of the `bundle` metrics is registered as a
+    ///   [`metric::CustomBundle`] already.
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   provided `bundle`.
+    pub fn with_custom_bundle<B>(self, bundle: B) -> Self
+    where
+        B: metric::CustomBundle + 'static,
+    {
+        self.try_with_custom_bundle(bundle).unwrap_or_else(|e| {
+            panic!("failed to register custom `prometheus` metric: {e}")
+        })
+    }
+
+    /// Tries to register the provided [`prometheus`] `metric` in the same way
+    /// as the [`Builder::try_with_metric()`] does, additionally returning a
+    /// typed [`metric::Handle`] to it.
+    ///
+    /// The returned [`metric::Handle`] may be stashed and used directly on hot
+    /// paths, bypassing any lookups of [`metrics`] macros, while the same
+    /// metric remains usable via [`metrics`] crate interfaces too. If a
+    /// [`prometheus`] metric with the identical schema is registered already,
+    /// then the returned [`metric::Handle`] refers to that existing one.
+    ///
+    /// # Errors
+    ///
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   provided `metric`.
+    /// - If the reused [`prometheus`] metric is of another type than the
+    ///   provided `metric`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (builder, gauge) = metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .try_with_metric_handle(prometheus::Gauge::new("value", "help")?)?;
+    /// builder.build_and_install();
+    ///
+    /// gauge.inc();
+    /// metrics::gauge!("value").increment(1.0);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP value help
+    /// ## TYPE value gauge
+    /// value 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn try_with_metric_handle<M>(
+        self,
+        metric: M,
+    ) -> prometheus::Result<(Self, metric::Handle<M>)>
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        storage::Mutable: storage::Get<
+            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
+        >,
+    {
+        let handle = self.storage.register_external_handle(metric)?;
+        Ok((self, handle))
+    }
+
+    /// Tries to register a [`prometheus::IntCounterVec`] with the provided
+    /// `name`, variable `labels` and `help` description in the same way as
+    /// the [`Builder::try_with_metric()`] does.
+    ///
+    /// Use the [`Builder::try_with_counter_handle()`] method to obtain a typed
+    /// [`metric::Handle`] to the registered [`prometheus::IntCounterVec`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`prometheus::IntCounterVec`] cannot be created or registered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .try_with_counter("requests", &["method"], "Total requests.")?
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("requests", "method" => "GET").increment(1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP requests Total requests.
+    /// ## TYPE requests counter
+    /// requests{method="GET"} 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn try_with_counter(
+        self,
+        name: &str,
+        labels: &[&str],
+        help: &str,
+    ) -> prometheus::Result<Self> {
+        self.try_with_counter_handle(name, labels, help).map(|(b, _)| b)
+    }
+
+    /// Tries to register a [`prometheus::IntCounterVec`] in the same way as
+    /// the [`Builder::try_with_counter()`] does, additionally returning a
+    /// typed [`metric::Handle`] to it (see the
+    /// [`Builder::try_with_metric_handle()`] method for details).
+    ///
+    /// # Errors
+    ///
+    /// If the [`prometheus::IntCounterVec`] cannot be created or registered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (builder, requests) = metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .try_with_counter_handle("requests", &["method"], "Requests.")?;
+    /// builder.build_and_install();
+    ///
+    /// let get = requests.with_label_values(&["GET"]);
+    /// get.inc();
+    /// metrics::counter!("requests", "method" => "GET").increment(1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP requests Requests.
+    /// ## TYPE requests counter
+    /// requests{method="GET"} 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn try_with_counter_handle(
+        self,
+        name: &str,
+        labels: &[&str],
+        help: &str,
+    ) -> prometheus::Result<(Self, metric::Handle<prometheus::IntCounterVec>)>
+    {
+        self.try_with_metric_handle(prometheus::IntCounterVec::new(
+            prometheus::Opts::new(name, help),
+            labels,
+        )?)
+    }
+
+    /// Tries to register all the metric families of the provided
+    /// [`catalog::Snapshot`] (usually, persisted from a [`FrozenRecorder`] on
+    /// a previous run) in the same way as the [`Builder::try_with_metric()`]
+    /// does.
+    ///
+    /// See the [`catalog::Snapshot`] for an example.
+    ///
+    /// # Errors
+    ///
+    /// - If the [`catalog::Snapshot`] contains a metric family of an
+    ///   unsupported kind.
+    /// - If any of the metric families cannot be created or registered.
+    ///
+    /// [`FrozenRecorder`]: crate::FrozenRecorder
+    pub fn try_with_catalog(
+        mut self,
+        snapshot: &catalog::Snapshot,
+    ) -> prometheus::Result<Self> {
+        use prometheus::proto::MetricType as T;
+
+        for family in &snapshot.families {
+            let catalog::Family { schema, help } = family;
+            let help = if help.is_empty() { &schema.name } else { help };
+            let opts = prometheus::Opts::new(&schema.name, help);
+            let labels = schema
+                .label_names
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            self = match (schema.kind, labels.is_empty()) {
+                (T::COUNTER, true) => self
+                    .try_with_metric(prometheus::IntCounter::with_opts(opts)?),
+                (T::COUNTER, false) => self.try_with_metric(
+                    prometheus::IntCounterVec::new(opts, &labels)?,
+                ),
+                (T::GAUGE, true) => {
+                    self.try_with_metric(prometheus::Gauge::with_opts(opts)?)
+                }
+                (T::GAUGE, false) => self
+                    .try_with_metric(prometheus::GaugeVec::new(opts, &labels)?),
+                (T::HISTOGRAM, no_labels) => {
+                    let mut opts = prometheus::HistogramOpts::from(opts);
+                    if !schema.buckets.is_empty() {
+                        opts = opts.buckets(schema.buckets.clone());
+                    }
+                    let buckets = opts.buckets.as_slice().into();
+                    self = if no_labels {
+                        self.try_with_metric(prometheus::Histogram::with_opts(
+                            opts,
+                        )?)
+                    } else {
+                        self.try_with_metric(prometheus::HistogramVec::new(
+                            opts, &labels,
+                        )?)
+                    }?;
+                    self.storage.record_buckets(&schema.name, buckets);
+                    Ok(self)
+                }
+                (T::SUMMARY | T::UNTYPED, _) => {
+                    return Err(prometheus::Error::Msg(format!(
+                        "`{}` metric is of unsupported kind",
+                        schema.name,
+                    )));
+                }
+            }?;
+        }
+        Ok(self)
+    }
+
+    /// Makes the histograms matched by the provided [`Matcher`] to be
+    /// registered on the fly as [`metric::adaptive::Histogram`]s, tuning their
+    /// buckets automatically according to the provided
+    /// [`metric::adaptive::Config`].
+    ///
+    /// Such a histogram starts with a wide provisional buckets set, collects
+    /// the configured number of warm-up observations, and then re-registers
+    /// itself with log-spaced buckets covering the observed range (the
+    /// observations recorded during the warm-up are replayed into the tuned
+    /// histogram). Useful when the distribution of the observed values is not
+    /// known upfront.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::metric::adaptive;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_adaptive_histogram(
+    ///         "latency",
+    ///         adaptive::Config::new().with_warm_up(4).with_buckets(3),
+    ///     )
+    ///     .build_and_install();
+    ///
+    /// for v in [1.0, 10.0, 100.0, 1000.0] {
+    ///     metrics::histogram!("latency").record(v);
+    /// }
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP latency latency
+    /// ## TYPE latency histogram
+    /// latency_bucket{le="1"} 1
+    /// latency_bucket{le="31.6"} 2
+    /// latency_bucket{le="1000"} 4
+    /// latency_bucket{le="+Inf"} 4
+    /// latency_sum 1111
+    /// latency_count 4
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_adaptive_histogram(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        config: metric::adaptive::Config,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.adaptive).push(matcher, config);
+        self
+    }
+
+    /// Makes the built [`Recorder`] to guard the [`prometheus::IntCounter`]s
+    /// against regressions.
+    ///
+    /// Once enabled, the following is treated as a [`prometheus::Error`] and
+    /// is passed to the configured [`failure::Strategy`], instead of being
+    /// silently ignored (or exporting a decreasing counter, breaking `rate()`
+    /// queries, if it's [resetting]):
+    /// - setting an [`absolute`] value of a counter lower than its current
+    ///   one (the value is not set if no panic happens);
+    /// - using a registered counter as a gauge or a histogram.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_monotonic_counters()
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("count").absolute(5);
+    /// // Ignored, as `count` cannot regress.
+    /// metrics::counter!("count").absolute(3);
+    /// // Ignored, as `count` cannot be used as a gauge.
+    /// metrics::gauge!("count").set(1.0);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP count count
+    /// ## TYPE count counter
+    /// count 5
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// ```rust,should_panic
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_monotonic_counters()
+    ///     .with_failure_strategy(strategy::Panic)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("count").absolute(5);
+    /// // panics: `count` counter cannot regress from 5 to 3
+    /// metrics::counter!("count").absolute(3);
+    /// # #[cfg(feature = "never-panic")]
+    /// # panic!("suppressed by `never-panic` feature");
+    /// ```
+    ///
+    /// [`absolute`]: metrics::Counter::absolute
+    /// [resetting]: Builder::with_resetting_counters
+    pub const fn with_monotonic_counters(mut self) -> Self {
+        self.storage.monotonic = true;
+        self
+    }
+
+    /// Makes the [`prometheus::IntCounter`]s matched by the provided
+    /// [`Matcher`] to set their [`absolute`] values by resetting.
+    ///
+    /// By default, setting an [`absolute`] value increments a counter by the
+    /// delta from its current value (never decreasing it), so the counter is
+    /// never observed reset to zero in the middle of the operation. Resetting
+    /// counters, instead, may decrease, at the cost of such scrape-visible
+    /// dips, and races between concurrent [`absolute`] operations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_resetting_counters("resetting")
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("resetting").absolute(5);
+    /// metrics::counter!("resetting").absolute(3);
+    /// metrics::counter!("usual").absolute(5);
+    /// // Ignored, as the counter never decreases.
+    /// metrics::counter!("usual").absolute(3);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP resetting resetting
+    /// ## TYPE resetting counter
+    /// resetting 3
+    /// ## HELP usual usual
+    /// ## TYPE usual counter
+    /// usual 5
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`absolute`]: metrics::Counter::absolute
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_resetting_counters(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.resetting).push(matcher, ());
+        self
+    }
+
+    /// Makes the built [`Recorder`] to validate metrics strictly before
+    /// registering them, if `enabled`.
+    ///
+    /// Once enabled, empty metric names, label names and label values are
+    /// rejected with a precise [`prometheus::Error`] passed to the configured
+    /// [`failure::Strategy`], instead of producing confusing errors
+    /// downstream. Labels with empty values may be dropped instead of being
+    /// rejected via the [`Builder::with_empty_labels_dropped()`] method.
+    ///
+    /// Applies to the metrics registered on the fly, and the ones registered
+    /// via the [`Builder::with_metric()`] method (their constant labels).
+    ///
+    /// # Example
+    ///
+    /// ```rust,should_panic
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_strict_validation(true)
+    ///     .with_failure_strategy(strategy::Panic)
+    ///     .build_and_install();
+    ///
+    /// // panics: value of `op` label of `count` metric cannot be empty
+    /// metrics::counter!("count", "op" => "").increment(1);
+    /// # #[cfg(feature = "never-panic")]
+    /// # panic!("suppressed by `never-panic` feature");
+    /// ```
+    pub fn with_strict_validation(self, enabled: bool) -> Self {
+        self.storage.config.update(|c| c.strict = enabled);
+        self
+    }
+
+    /// Makes the [strict validation] of the built [`Recorder`] to drop labels
+    /// with empty values, instead of rejecting them, if `enabled`.
+    ///
+    /// No-op, unless the [strict validation] is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_strict_validation(true)
+    ///     .with_empty_labels_dropped(true)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("count", "op" => "").increment(1);
+    /// metrics::counter!("count").increment(1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP count count
+    /// ## TYPE count counter
+    /// count 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    ///
+    /// // Frozen metrics are resolved the same way.
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_strict_validation(true)
+    ///     .with_empty_labels_dropped(true)
+    ///     .build_freezable();
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("count").increment(1);
+    /// });
+    /// recorder.freeze();
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("count", "op" => "").increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains("count 2"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [strict validation]: Builder::with_strict_validation
+    pub fn with_empty_labels_dropped(self, enabled: bool) -> Self {
+        self.storage.config.update(|c| c.drop_empty_labels = enabled);
+        self
+    }
+
+    /// Limits the lengths (in bytes) of metric names, label names and label
+    /// values registered on the fly by the built [`Recorder`], handling the
+    /// overlong ones according to the provided [`storage::Overlong`] policy.
+    ///
+    /// Keeps exposition payloads bounded, when instrumented code interpolates
+    /// user input into metric names or labels. Truncation is UTF-8-safe, never
+    /// splitting a multi-byte character, and applies to label values only:
+    /// overlong metric names and label names are always rejected, as
+    /// truncating them would merge different metric families.
+    ///
+    /// With the [`storage::Overlong::Reject`] policy, a precise
+    /// [`prometheus::Error`] is passed to the configured
+    /// [`failure::Strategy`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::{
+    ///     failure::strategy,
+    ///     storage::{Lengths, Overlong},
+    /// };
+    ///
+    /// let lengths = Lengths { max_label_value: 3, ..Lengths::UNLIMITED };
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_length_limits(lengths, Overlong::Truncate)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     // Truncated to "Jö", not splitting the two-byte "ö".
+    ///     metrics::counter!("requests", "user" => "Jöran").increment(1);
+    ///     metrics::counter!("requests", "user" => "Jörgen").increment(1);
+    ///     metrics::counter!("requests", "user" => "Bob").increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"requests{user="Jö"} 2"#));
+    /// assert!(report.contains(r#"requests{user="Bob"} 1"#));
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_length_limits(lengths, Overlong::Reject)
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build();
+    ///
+    /// let key = metrics::Key::from_parts("requests", &[("user", "Jöran")]);
+    /// assert_eq!(
+    ///     recorder.checked_counter(&key).unwrap_err().to_string(),
+    ///     "Error: value of `user` label of `requests` metric exceeds 3 bytes",
+    /// );
+    ///
+    /// let lengths = Lengths { max_name: 8, ..Lengths::UNLIMITED };
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_length_limits(lengths, Overlong::Truncate)
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build();
+    ///
+    /// // Never truncated into the same `requests` family.
+    /// let key = metrics::Key::from_name("requests_total");
+    /// assert_eq!(
+    ///     recorder.checked_counter(&key).unwrap_err().to_string(),
+    ///     "Error: name of `requests_total` metric exceeds 8 bytes",
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn with_length_limits(
+        self,
+        lengths: storage::Lengths,
+        policy: storage::Overlong,
+    ) -> Self {
+        self.storage.config.update(|c| {
+            c.lengths = lengths;
+            c.overlong = policy;
+        });
+        self
+    }
+
+    /// Makes the [`FrozenRecorder`] built (or [`.freeze()`]d) out of this
+    /// [`Builder`] to track accesses to the metrics unknown to it (not
+    /// registered before freezing), which are silently no-op otherwise.
+    ///
+    /// Up to the provided `max_names` distinct names are tracked, while the
+    /// accesses to the other ones are counted under the
+    /// [`budget::OVERFLOW_LABEL_VALUE`]. If `exported`, the tracked accesses
+    /// are reported via the `metrics_prometheus_frozen_unknown_total` counter
+    /// (labeled with the `name` of the unknown metric), registered along with
+    /// the first unknown metric accessed.
+    ///
+    /// See the [`FrozenRecorder::unknown_keys()`] method for an example.
+    ///
+    /// [`FrozenRecorder`]: crate::FrozenRecorder
+    /// [`FrozenRecorder::unknown_keys()`]: frozen::Recorder::unknown_keys
+    /// [`.freeze()`]: freezable::Recorder::freeze()
+    pub fn with_unknown_keys_tracked(
+        mut self,
+        max_names: usize,
+        exported: bool,
+    ) -> Self {
+        self.storage.unknown =
+            Some(Arc::new(storage::unknown::Tracker::new(max_names, exported)));
+        self
+    }
+
+    /// Deprecates the provided `old` metric name in favor of the provided `new`
+    /// one, so the metrics registered or described with the `old` name are
+    /// transparently routed to the `new` family (keeping their labels).
+    ///
+    /// Usages of the `old` name are surfaced according to the provided
+    /// [`deprecation::Policy`], and counted by the
+    /// [`Recorder::deprecated_usages()`] method, helping to enforce a cleanup
+    /// of the metrics catalog.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::deprecation;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_deprecated(
+    ///         "requests",
+    ///         "requests_total",
+    ///         deprecation::Policy::Count,
+    ///     )
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests", "code" => "200").increment(2);
+    ///     metrics::counter!("requests_total", "code" => "200").increment(1);
+    /// });
+    ///
+    /// assert_eq!(recorder.deprecated_usages()["requests"], 1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"requests_total{code="200"} 3"#));
+    /// assert!(report.contains(
+    ///     "metrics_prometheus_deprecated_usages_total{\
+    ///        name=\"requests\",replacement=\"requests_total\"} 1",
+    /// ));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn with_deprecated(
+        mut self,
+        old: impl Into<String>,
+        new: impl Into<String>,
+        policy: deprecation::Policy,
+    ) -> Self {
+        self.storage.deprecated = Some(Arc::new(deprecation::Tracker::with(
+            self.storage.deprecated.as_deref(),
+            old.into(),
+            new.into(),
+            policy,
+        )));
+        self
+    }
+
+    /// Makes the built [`Recorder`] to prefix the names of the metrics
+    /// registered by the crates matching the provided [`Matcher`] (as reported
+    /// by [`metrics::Metadata::target()`]) with the provided
+    /// [`target::Prefix`].
+    ///
+    /// Keeps metrics of third-party libraries from colliding with the ones of
+    /// an application in a shared [`prometheus::Registry`]. The first rule
+    /// matching the crate wins, so [`target::Prefix::None`] may exempt some
+    /// crates from a broader rule. Names already starting with the prefix are
+    /// left as is.
+    ///
+    /// # Limitations
+    ///
+    /// [`metrics::Recorder::describe_counter()`] (and others) receive no
+    /// [`metrics::Metadata`], so the describing crate is unknown, and
+    /// [`help` description]s are __never__ prefixed. The description given
+    /// for an unprefixed name describes the unprefixed family only (like the
+    /// application's one), so the prefixed families should be described with
+    /// their prefixed names explicitly.
+    ///
+    /// Metrics resolved via the [`Recorder::checked_counter()`] (and others)
+    /// or a [`Batch`] are prefixed as registered by this crate (its target
+    /// being `metrics_prometheus`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics::Recorder as _;
+    /// use metrics_prometheus::{target, Matcher};
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_target_prefix("hyper", target::Prefix::CrateName)
+    ///     .with_target_prefix(Matcher::Prefix("tower".into()), "middleware_")
+    ///     .build();
+    ///
+    /// let key = metrics::Key::from_name("requests_total");
+    /// let from =
+    ///     |target| metrics::Metadata::new(target, metrics::Level::INFO, None);
+    /// recorder.register_counter(&key, &from("hyper::proto::h1")).increment(1);
+    /// // Resolved prefix and prefixed `metrics::Key` are cached for the crate.
+    /// recorder.register_counter(&key, &from("hyper::client")).increment(1);
+    /// recorder.register_counter(&key, &from("tower::util")).increment(2);
+    /// recorder.register_counter(&key, &from("my_app::server")).increment(3);
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!("requests_total", "App requests.");
+    ///     metrics::describe_counter!("hyper_requests_total", "Hyper reqs.");
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains("hyper_requests_total 2"));
+    /// assert!(report.contains("middleware_requests_total 2"));
+    /// assert!(report.contains("\nrequests_total 3"));
+    /// assert!(report.contains("# HELP requests_total App requests."));
+    /// assert!(report.contains("# HELP hyper_requests_total Hyper reqs."));
+    /// assert!(report.contains(
+    ///     "# HELP middleware_requests_total middleware_requests_total",
+    /// ));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Batch`]: batch::Batch
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_target_prefix(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        prefix: impl Into<target::Prefix>,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.target_prefixes)
+            .push(matcher, prefix.into());
+        self
+    }
+
+    /// Makes the built [`Recorder`] to handle orphan [`help` description]s
+    /// (describing metrics which have never been registered) according to
+    /// the provided [`storage::Orphans`] policy.
+    ///
+    /// By default, orphan descriptions are kept until their metrics are
+    /// registered (maybe forever). The current ones are counted by the
+    /// [`Recorder::orphan_counts()`] method.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// use metrics_prometheus::{clock, failure::strategy, storage::Orphans};
+    ///
+    /// let clock = Arc::new(clock::Mock::new());
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_clock(Arc::clone(&clock))
+    ///     .with_orphan_descriptions(Orphans::Expire(Duration::from_secs(60)))
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!("forgotten", "Never registered.");
+    /// });
+    /// assert_eq!(recorder.orphan_counts().counters, 1);
+    ///
+    /// clock.advance(Duration::from_secs(60));
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_gauge!("queue", "Registered later.");
+    /// });
+    /// assert_eq!(recorder.orphan_counts().counters, 0);
+    /// assert_eq!(recorder.orphan_counts().gauges, 1);
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .with_orphan_descriptions(Orphans::Reject)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!("forgotten", "Never registered.");
+    /// });
+    /// assert!(recorder.orphan_counts().is_empty());
+    /// ```
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub const fn with_orphan_descriptions(
+        mut self,
+        policy: storage::Orphans,
+    ) -> Self {
+        self.storage.orphans = policy;
+        self
+    }
+
+    /// Makes the built [`Recorder`] to create histograms on the fly with the
+    /// provided `buckets`, instead of the [`prometheus::DEFAULT_BUCKETS`]
+    /// (being request latencies in seconds, and so, inappropriate for many
+    /// domains, like batch jobs durations or message sizes).
+    ///
+    /// Histograms registered with their own buckets (like via the
+    /// [`Builder::with_metric()`] or [`Builder::with_adaptive_histogram()`])
+    /// are not affected. See the [`buckets`] module for curated presets.
+    ///
+    /// If the provided `buckets` are not strictly increasing, then every
+    /// histogram fails to be created on the fly, and the error is passed to
+    /// the [`failure::Strategy`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::buckets;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_default_histogram_buckets(buckets::size_bytes())
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::histogram!("message_size_bytes").record(2000.0);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"message_size_bytes_bucket{le="1024"} 0"#));
+    /// assert!(report.contains(r#"message_size_bytes_bucket{le="4096"} 1"#));
+    /// assert!(!report.contains(r#"le="0.005""#));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`buckets`]: crate::buckets
+    pub fn with_default_histogram_buckets(
+        mut self,
+        buckets: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        self.storage.default_buckets = Some(buckets.into_iter().collect());
+        self
+    }
+
+    /// Makes the built [`Recorder`] to create the histograms matching the
+    /// provided [`Matcher`] on the fly in a __count-only__ mode: with the
+    /// single `+Inf` bucket, maintaining their `_count` and `_sum` only.
+    ///
+    /// Skips the bucket bookkeeping (saving memory and atomic increments) for
+    /// the metrics recorded via [`metrics::histogram!`] macro, but only
+    /// needing their rate or average (like large families labeled by many
+    /// values). Takes precedence over the
+    /// [`Builder::with_default_histogram_buckets()`].
+    ///
+    /// Histograms registered with their own buckets (like via the
+    /// [`Builder::with_metric()`] or [`Builder::with_adaptive_histogram()`])
+    /// are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_count_only_histograms("payload_bytes")
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::histogram!("payload_bytes").record(2.0);
+    ///     metrics::histogram!("payload_bytes").record(3.0);
+    ///     metrics::histogram!("latency").record(0.5);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"payload_bytes_bucket{le="+Inf"} 2"#));
+    /// assert!(!report.contains(r#"payload_bytes_bucket{le="0.005"}"#));
+    /// assert!(report.contains("payload_bytes_sum 5"));
+    /// assert!(report.contains("payload_bytes_count 2"));
+    /// assert!(report.contains(r#"latency_bucket{le="0.005"} 0"#));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_count_only_histograms(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.count_only).push(matcher, ());
+        self
+    }
+
+    /// Makes the built [`Recorder`] to accompany the histograms matching the
+    /// provided [`Matcher`] with `*_min` and `*_max` gauges, exposing the
+    /// minimum and maximum observed values, reset according to the provided
+    /// [`metric::extrema::Reset`].
+    ///
+    /// [`prometheus::Histogram`]s cannot answer the extreme observed values,
+    /// so this is useful where Prometheus recording rules are not available.
+    ///
+    /// The extreme values are tracked for the metrics registered by the built
+    /// [`Recorder`] only (not after it's frozen).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::metric::extrema;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_histogram_extrema("latency", extrema::Reset::OnScrape)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     let latency = metrics::histogram!("latency", "op" => "read");
+    ///     latency.record(0.5);
+    ///     latency.record(0.1);
+    ///     latency.record(2.5);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"latency_min{op="read"} 0.1"#));
+    /// assert!(report.contains(r#"latency_max{op="read"} 2.5"#));
+    ///
+    /// // Reset on the previous scrape.
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(!report.contains("latency_min"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_histogram_extrema(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        reset: metric::extrema::Reset,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.extrema).push(matcher, reset);
+        self
+    }
+
+    /// Makes the built [`Recorder`] to start the counters matching the
+    /// provided [`Matcher`] at the provided `initial` value, rather than `0`.
+    ///
+    /// The `initial` value is applied once a counter is registered (on its
+    /// first use), so the dashboards show complete series from the process
+    /// start, even before the first recording.
+    ///
+    /// The initial values are applied to the metrics registered by the built
+    /// [`Recorder`] only (not after it's frozen).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_initial_counter_value("restarts", 1)
+    ///     .build();
+    ///
+    /// let restarts = metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("restarts")
+    /// });
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains("restarts 1"));
+    ///
+    /// restarts.increment(1);
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains("restarts 2"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_initial_counter_value(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        initial: u64,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.initial_counters)
+            .push(matcher, initial);
+        self
+    }
+
+    /// Makes the built [`Recorder`] to start the gauges matching the provided
+    /// [`Matcher`] at the provided `initial` value, rather than `0`.
+    ///
+    /// The `initial` value is applied once a gauge is registered (on its first
+    /// use), so the dashboards show complete series from the process start,
+    /// even before the first recording.
+    ///
+    /// The initial values are applied to the metrics registered by the built
+    /// [`Recorder`] only (not after it's frozen).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_initial_gauge_value("queue_depth", -1.0)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     _ = metrics::gauge!("queue_depth", "queue" => "mail");
+    /// });
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"queue_depth{queue="mail"} -1"#));
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::gauge!("queue_depth", "queue" => "mail").set(3.0);
+    /// });
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"queue_depth{queue="mail"} 3"#));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_initial_gauge_value(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        initial: f64,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.initial_gauges).push(matcher, initial);
+        self
+    }
+
+    /// Makes the built [`Recorder`] to accompany the histograms matching the
+    /// provided [`Matcher`] with `*_over_threshold_total` counters, counting
+    /// the observations exceeding each of the provided `thresholds` (labeled
+    /// with `le` of the threshold).
+    ///
+    /// The counters are computed at record time, so enable simple SLO burn
+    /// alerts without applying `histogram_quantile()` to huge sets of
+    /// buckets.
+    ///
+    /// The thresholds are counted for the histograms registered by the built
+    /// [`Recorder`] (and keep being counted once it's frozen).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_histogram_thresholds("latency", [0.5, 1.0])
+    ///     .build_freezable();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     let latency = metrics::histogram!("latency", "op" => "read");
+    ///     latency.record(0.1);
+    ///     latency.record(0.7);
+    /// });
+    /// recorder.freeze();
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::histogram!("latency", "op" => "read").record(2.5);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(
+    ///     r#"latency_over_threshold_total{op="read",le="0.5"} 2"#,
+    /// ));
+    /// assert!(report.contains(
+    ///     r#"latency_over_threshold_total{op="read",le="1"} 1"#,
+    /// ));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_histogram_thresholds(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        thresholds: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.thresholds)
+            .push(matcher, thresholds.into_iter().collect());
+        self
+    }
+
+    /// Makes the built [`Recorder`] to accompany the histograms matching the
+    /// provided [`Matcher`] with `*_quantile` gauges, exposing the provided
+    /// `quantiles` (labeled with `quantile`) of their observations.
+    ///
+    /// The quantiles are computed at gather time out of the histogram buckets
+    /// via linear interpolation (the same way `histogram_quantile()` does),
+    /// so are __approximations__ only, as precise as the buckets are. Useful
+    /// for constrained scrapers not capable of computing them on their own.
+    ///
+    /// The quantiles are exposed for the metrics registered by the built
+    /// [`Recorder`] only (not after it's frozen).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_default_histogram_buckets([1.0, 2.0, 4.0])
+    ///     .with_histogram_quantiles("latency_seconds", [0.5, 0.75])
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     let latency = metrics::histogram!("latency_seconds", "op" => "rd");
+    ///     latency.record(0.5);
+    ///     latency.record(1.5);
+    ///     latency.record(3.0);
+    ///     latency.record(3.5);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(
+    ///     r#"latency_seconds_quantile{op="rd",quantile="0.5"} 2"#,
+    /// ));
+    /// assert!(report.contains(
+    ///     r#"latency_seconds_quantile{op="rd",quantile="0.75"} 3"#,
+    /// ));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_histogram_quantiles(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        quantiles: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.quantiles)
+            .push(matcher, quantiles.into_iter().collect());
+        self
+    }
+
+    /// Makes the built [`Recorder`] to batch the observations of the
+    /// histograms matching the provided [`Matcher`] in per-thread
+    /// [`prometheus::local::LocalHistogram`]s of the provided `capacity` (in
+    /// observations), flushed into the underlying [`prometheus::Histogram`]s
+    /// on overflow, on thread exit and at gather.
+    ///
+    /// Drastically reduces the number of atomic operations on the hot path of
+    /// extremely hot histograms (a flush increments every bucket once), at the
+    /// cost of an uncontended lock of the current thread's buffer, which is
+    /// contended by a gather only (draining the buffers of all the threads,
+    /// including the idle ones).
+    ///
+    /// The observations are batched for the metrics registered by the built
+    /// [`Recorder`] only (not after it's frozen), excluding the
+    /// [adaptive histograms][0] (retuning their buckets).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_histogram_batching("latency", 64)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     let latency = metrics::histogram!("latency");
+    ///     for _ in 0..1000 {
+    ///         latency.record(0.5);
+    ///     }
+    ///     latency.record(2.5);
+    /// });
+    /// // Buffers of the exited threads are flushed on their exit.
+    /// let cloned = recorder.clone();
+    /// std::thread::spawn(move || {
+    ///     metrics::with_local_recorder(&cloned, || {
+    ///         metrics::histogram!("latency").record(0.5);
+    ///     });
+    /// })
+    /// .join()
+    /// .unwrap();
+    /// // Buffers of the idle threads are flushed at gather.
+    /// let (recorded_tx, recorded_rx) = std::sync::mpsc::channel();
+    /// let (gathered_tx, gathered_rx) = std::sync::mpsc::channel::<()>();
+    /// let cloned = recorder.clone();
+    /// let idle = std::thread::spawn(move || {
+    ///     metrics::with_local_recorder(&cloned, || {
+    ///         metrics::histogram!("latency").record(0.5);
+    ///     });
+    ///     recorded_tx.send(()).unwrap();
+    ///     _ = gathered_rx.recv();
+    /// });
+    /// recorded_rx.recv()?;
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"latency_bucket{le="0.5"} 1002"#));
+    /// assert!(report.contains(r#"latency_bucket{le="5"} 1003"#));
+    /// assert!(report.contains("latency_count 1003"));
+    /// assert!(report.contains("latency_sum 503.5"));
+    /// # drop(gathered_tx);
+    /// # idle.join().unwrap();
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    /// [0]: Builder::with_adaptive_histogram
+    pub fn with_histogram_batching(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        capacity: usize,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.batching).push(matcher, capacity);
+        self
+    }
+
+    /// Makes the built [`Recorder`] to spread the increments and decrements of
+    /// the gauges matching the provided [`Matcher`] over the provided number
+    /// of `shards`, summed into the underlying [`prometheus::Gauge`]s at
+    /// gather.
+    ///
+    /// Reduces the contention on a single atomic of extremely hot gauges
+    /// (like in-flight requests changed by hundreds of tasks). Setting such a
+    /// gauge discards the not gathered yet increments and decrements.
+    ///
+    /// The gauges are striped for the metrics registered by the built
+    /// [`Recorder`] only (not after it's frozen).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_gauge_striping("requests_in_flight", 8)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     let in_flight = metrics::gauge!("requests_in_flight");
+    ///     for _ in 0..10 {
+    ///         in_flight.increment(1.0);
+    ///     }
+    ///     in_flight.decrement(3.0);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains("requests_in_flight 7"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_gauge_striping(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        shards: usize,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.striping).push(matcher, shards);
+        self
+    }
+
+    /// Makes the built [`Recorder`] to strip control characters (like
+    /// newlines or tabs) from label values before registering metrics, if
+    /// `enabled`.
+    ///
+    /// Label values are always [escaped] properly in the exposition (so any
+    /// value is represented faithfully), however, some naive parsers may
+    /// still choke on the escaped control characters. This normalization
+    /// merges the label values differing in control characters only.
+    ///
+    /// Control characters are stripped before the [strict validation], so
+    /// the label values consisting of them only are treated as empty ones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_strict_validation(true)
+    ///     .with_empty_labels_dropped(true)
+    ///     .with_label_values_normalized(true)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests", "path" => "/a\n\"b\"\\").increment(1);
+    ///     metrics::counter!("requests", "path" => "/a\"b\"\\").increment(1);
+    ///     metrics::counter!("errors", "path" => "\r\n").increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert!(report.contains(r#"requests{path="/a\"b\"\\"} 2"#));
+    /// assert!(report.contains("errors 1"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [escaped]: https://prometheus.io/docs/instrumenting/exposition_formats
+    /// [strict validation]: Builder::with_strict_validation
+    pub fn with_label_values_normalized(self, enabled: bool) -> Self {
+        self.storage.config.update(|c| c.strip_control_chars = enabled);
+        self
+    }
+
+    /// Declares the [`metric::ingestion::Mode`] the counters matched by the
+    /// provided [`Matcher`] are fed with by the instrumented code.
+    ///
+    /// Useful when different libraries feed counters in different styles:
+    /// - a [`metric::ingestion::Mode::Delta`] counter accepts
+    ///   [`metrics::Counter::increment()`] calls only;
+    /// - a [`metric::ingestion::Mode::Absolute`] counter accepts
+    ///   [`metrics::Counter::absolute()`] calls only, converting the fed
+    ///   totals into deltas, so the exported counter stays monotonic even if
+    ///   the upstream one is reset.
+    ///
+    /// Feeding a counter in the mismatched style is treated as a
+    /// [`prometheus::Error`] and is passed to the configured
+    /// [`failure::Strategy`] (the value is not recorded if no panic happens).
+    ///
+    /// Takes precedence over the [`Builder::with_monotonic_counters()`] for
+    /// the matched counters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::{failure::strategy, metric::ingestion};
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_counter_ingestion("bytes", ingestion::Mode::Absolute)
+    ///     .with_counter_ingestion("requests", ingestion::Mode::Delta)
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("bytes").absolute(10);
+    /// metrics::counter!("bytes").absolute(15);
+    /// // Upstream counter is reset, so its whole total is counted.
+    /// metrics::counter!("bytes").absolute(3);
+    /// // Mismatched style, so is ignored.
+    /// metrics::counter!("bytes").increment(100);
+    ///
+    /// metrics::counter!("requests").increment(2);
+    /// // Mismatched style, so is ignored.
+    /// metrics::counter!("requests").absolute(100);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP bytes bytes
+    /// ## TYPE bytes counter
+    /// bytes 18
+    /// ## HELP requests requests
+    /// ## TYPE requests counter
+    /// requests 2
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_counter_ingestion(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+        mode: metric::ingestion::Mode,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.ingestion).push(matcher, mode);
+        self
+    }
+
+    /// Marks the metric families matched by the provided [`Matcher`] as owned
+    /// by the built [`Recorder`], so its mirrors (see the
+    /// [`Recorder::mirror()`] method) skip them.
+    ///
+    /// Prevents double-counting when the same [`Recorder`] is fanned out in
+    /// several places, recording the same metrics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_util::layers::FanoutBuilder;
+    ///
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_authoritative("requests")
+    ///     .build();
+    /// let fanout = FanoutBuilder::default()
+    ///     .add_recorder(recorder.clone())
+    ///     .add_recorder(recorder.mirror())
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&fanout, || {
+    ///     metrics::counter!("requests").increment(1);
+    ///     metrics::counter!("other").increment(1);
+    /// });
+    ///
+    /// // Checked metrics are skipped by mirrors too.
+    /// let key = metrics::Key::from_name("requests");
+    /// recorder.mirror().checked_counter(&key)?.increment(1);
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP other other
+    /// ## TYPE other counter
+    /// other 2
+    /// ## HELP requests requests
+    /// ## TYPE requests counter
+    /// requests 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// [`Matcher`]: crate::Matcher
+    pub fn with_authoritative(
+        mut self,
+        matcher: impl Into<crate::Matcher>,
+    ) -> Self {
+        Arc::make_mut(&mut self.storage.authoritative).push(matcher, ());
+        self
+    }
+
+    /// Reserves the provided metric name `prefix` for the metric families
+    /// declared upfront (via [`Builder::with_metric()`], for example).
+    ///
+    /// Registering on the fly a metric under the reserved `prefix`, which is
+    /// not declared, or has label names different from the declared ones, is
+    /// treated as a [`prometheus::Error`] and is passed to the configured
+    /// [`failure::Strategy`]. This protects curated metric namespaces from
+    /// collisions with other libraries using [`metrics`] crate.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// let requests = prometheus::IntCounterVec::new(
+    ///     prometheus::Opts::new("mylib_requests", "help"),
+    ///     &["path"],
+    /// )?;
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_metric(requests)
+    ///     .reserve_prefix("mylib_")
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("mylib_requests", "path" => "/").increment(1);
+    ///     // Ignored, as not declared.
+    ///     metrics::counter!("mylib_errors").increment(1);
+    ///     // Ignored, as label names don't match the declared ones.
+    ///     metrics::counter!("mylib_requests", "method" => "GET").increment(1);
+    ///     metrics::counter!("other_requests").increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP mylib_requests help
+    /// ## TYPE mylib_requests counter
+    /// mylib_requests{path="/"} 1
+    /// ## HELP other_requests other_requests
+    /// ## TYPE other_requests counter
+    /// other_requests 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn reserve_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        Arc::make_mut(&mut self.storage.reserved)
+            .push(crate::Matcher::Prefix(prefix.clone()), prefix);
+        self
+    }
+
+    /// Makes the built [`Recorder`] to apply [`help` description]s lazily.
+    ///
+    /// By default, describing a not yet registered metric takes a write lock,
+    /// which may contend with the concurrent registrations. Once enabled, the
+    /// descriptions are pushed into a lock-free queue instead, and applied on
+    /// the next registration of a metric or gathering of the metrics, so
+    /// describing never blocks.
+    ///
+    /// The declared [`metrics::Unit`]s and the configured orphans policy (see
+    /// [`Builder::with_orphan_descriptions()`]) are applied lazily as well, so
+    /// a rejected orphan description is dropped silently once applied.
+    ///
+    /// Should be called before registering any metrics via this [`Builder`],
+    /// as gathering of the already registered ones doesn't apply the pending
+    /// descriptions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_lazy_descriptions()
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("count").increment(1);
+    /// metrics::describe_counter!("count", "Lazily described.");
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&prometheus::default_registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP count Lazily described.
+    /// ## TYPE count counter
+    /// count 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    ///
+    /// Declared [`metrics::Unit`]s are applied along with the descriptions:
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_lazy_descriptions()
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::describe_counter!(
+    ///         "sent",
+    ///         metrics::Unit::Bytes,
+    ///         "Number of sent bytes.",
+    ///     );
+    ///     metrics::counter!("sent").increment(42);
+    /// });
     ///
-    /// Accepts only the following [`prometheus`] metrics:
-    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
-    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
-    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    /// assert_eq!(
+    ///     recorder.render_openmetrics(),
+    ///     "\
+    /// ## TYPE sent_bytes counter
+    /// ## UNIT sent_bytes bytes
+    /// ## HELP sent_bytes Number of sent bytes.
+    /// sent_bytes_total 42
+    /// ## EOF
+    /// ",
+    /// );
+    /// ```
     ///
-    /// # Panics
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub fn with_lazy_descriptions(mut self) -> Self {
+        self.storage.pending = Some(storage::pending::Queue::default());
+        self
+    }
+
+    /// Appends the provided [`relabel::Rule`] to the ones applied at gather
+    /// time to the metrics registered via the built [`Recorder`].
     ///
-    /// If the underlying [`prometheus::Registry`] fails to register the
-    /// provided `metric`.
+    /// Allows renaming labels or dropping series without touching the
+    /// instrumentation. The [`relabel::Rule`]s may be replaced at runtime via
+    /// the [`Recorder::relabel_rules()`] method.
     ///
     /// # Example
     ///
     /// ```rust
-    /// let counter = prometheus::IntCounter::new("value", "help")?;
+    /// use metrics_prometheus::relabel::Rule;
     ///
     /// metrics_prometheus::Recorder::builder()
-    ///     .with_metric(counter.clone())
+    ///     .with_default_registry()
+    ///     .with_relabel_rule(Rule::replace(
+    ///         ["path"],
+    ///         "/user/.*",
+    ///         "path",
+    ///         "/user",
+    ///     )?)
+    ///     .with_relabel_rule(Rule::labelmap("svc_(.+)", "$1")?)
+    ///     .with_relabel_rule(Rule::drop(["__name__"], "debug_.*")?)
     ///     .build_and_install();
     ///
-    /// counter.inc();
+    /// metrics::counter!("requests", "path" => "/user/42").increment(1);
+    /// metrics::gauge!("workers", "svc_name" => "api").set(3.0);
+    /// metrics::gauge!("debug_info").set(1.0);
     ///
     /// let report = prometheus::TextEncoder::new()
     ///     .encode_to_string(&prometheus::default_registry().gather())?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value counter
-    /// value 1
+    /// ## HELP requests requests
+    /// ## TYPE requests counter
+    /// requests{path="/user"} 1
+    /// ## HELP workers workers
+    /// ## TYPE workers gauge
+    /// workers{name="api",svc_name="api"} 3
     ///     "#
     ///     .trim(),
     /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    #[cfg(feature = "relabel")]
+    pub fn with_relabel_rule(self, rule: relabel::Rule) -> Self {
+        self.storage.relabel.push(rule);
+        self
+    }
+
+    /// Limits the number of metrics registered on the fly by the built
+    /// [`Recorder`] with the provided maximum number of metric families
+    /// (distinct metric names) and metrics (distinct metric names along with
+    /// their label values).
+    ///
+    /// Exceeding the limits is resolved according to the configured
+    /// [`budget::Policy`] (see [`Builder::with_metric_budget_policy()`]), which
+    /// is [`budget::Policy::Reject`] by default, passing a
+    /// [`prometheus::Error`] to the [`failure::Strategy`].
+    ///
+    /// The budget utilization is reported via `metrics_prometheus_budget_used`
+    /// and `metrics_prometheus_budget_limit` gauges, registered along with the
+    /// first metric registered on the fly. [`prometheus`] metrics registered
+    /// explicitly (via [`Builder::with_metric()`], for example) are not
+    /// limited, while [adaptive histograms] are limited, but never evicted.
     ///
-    /// metrics::counter!("value").increment(1);
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::failure::strategy;
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
+    ///     .with_metric_budget(2, 3)
+    ///     .with_failure_strategy(strategy::NoOp)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("requests", "path" => "/a").increment(1);
+    /// // This one fails (having different label names), so doesn't use up the
+    /// // budget.
+    /// metrics::counter!("requests", "method" => "GET").increment(1);
+    /// metrics::counter!("requests", "path" => "/b").increment(1);
+    /// metrics::gauge!("queue").set(1.0);
+    /// // These ones exceed the budget, so are rejected.
+    /// metrics::counter!("requests", "path" => "/c").increment(1);
+    /// metrics::gauge!("another").set(1.0);
     ///
     /// let report = prometheus::TextEncoder::new()
     ///     .encode_to_string(&prometheus::default_registry().gather())?;
     /// assert_eq!(
     ///     report.trim(),
     ///     r#"
-    /// ## HELP value help
-    /// ## TYPE value counter
-    /// value 2
+    /// ## HELP metrics_prometheus_budget_limit Limit of the metrics budget.
+    /// ## TYPE metrics_prometheus_budget_limit gauge
+    /// metrics_prometheus_budget_limit{limit="children"} 3
+    /// metrics_prometheus_budget_limit{limit="families"} 2
+    /// ## HELP metrics_prometheus_budget_used Used part of the metrics budget.
+    /// ## TYPE metrics_prometheus_budget_used gauge
+    /// metrics_prometheus_budget_used{limit="children"} 3
+    /// metrics_prometheus_budget_used{limit="families"} 2
+    /// ## HELP queue queue
+    /// ## TYPE queue gauge
+    /// queue 1
+    /// ## HELP requests requests
+    /// ## TYPE requests counter
+    /// requests{path="/a"} 1
+    /// requests{path="/b"} 1
     ///     "#
     ///     .trim(),
     /// );
     /// # Ok::<_, prometheus::Error>(())
     /// ```
-    pub fn with_metric<M>(self, metric: M) -> Self
-    where
-        M: metric::Bundled + prometheus::core::Collector,
-        <M as metric::Bundled>::Bundle:
-            prometheus::core::Collector + Clone + 'static,
-        storage::Mutable: storage::Get<
-            storage::mutable::Collection<<M as metric::Bundled>::Bundle>,
-        >,
-    {
-        self.try_with_metric(metric).unwrap_or_else(|e| {
-            panic!("failed to register `prometheus` metric: {e}")
-        })
+    ///
+    /// [adaptive histograms]: Builder::with_adaptive_histogram
+    pub fn with_metric_budget(
+        mut self,
+        max_families: usize,
+        max_children: usize,
+    ) -> Self {
+        self.storage.config.update(|c| {
+            c.budget = budget::Budget { max_families, max_children };
+        });
+        self.track_budget();
+        self
+    }
+
+    /// Sets the [`budget::Policy`] to apply when the budget configured via
+    /// [`Builder::with_metric_budget()`] is exceeded.
+    ///
+    /// Has no effect unless [`Builder::with_metric_budget()`] is called (in any
+    /// order).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::budget;
+    ///
+    /// let custom = prometheus::Registry::new();
+    ///
+    /// metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&custom)
+    ///     .with_metric_budget_policy(budget::Policy::Overflow)
+    ///     .with_metric_budget(1, 2)
+    ///     .build_and_install();
+    ///
+    /// metrics::counter!("requests", "path" => "/a").increment(1);
+    /// metrics::counter!("requests", "path" => "/b").increment(1);
+    /// metrics::counter!("requests", "path" => "/c").increment(1);
+    /// metrics::counter!("requests", "path" => "/d").increment(1);
+    ///
+    /// let report =
+    ///     prometheus::TextEncoder::new().encode_to_string(&custom.gather())?;
+    /// assert!(report.contains(r#"requests{path="/a"} 1"#));
+    /// assert!(report.contains(r#"requests{path="/b"} 1"#));
+    /// assert!(report.contains(r#"requests{path="__overflow__"} 2"#));
+    ///
+    /// // No budget is tracked without `Builder::with_metric_budget()`.
+    /// let unlimited = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_metric_budget_policy(budget::Policy::Overflow)
+    ///     .build();
+    /// metrics::with_local_recorder(&unlimited, || {
+    ///     metrics::counter!("requests").increment(1);
+    /// });
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&unlimited.registry().gather())?;
+    /// assert!(!report.contains("metrics_prometheus_budget"));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn with_metric_budget_policy(mut self, policy: budget::Policy) -> Self {
+        self.storage.budget_policy = policy;
+        if self.storage.budget.take().is_some() {
+            self.track_budget();
+        }
+        self
+    }
+
+    /// Enables tracking of the budget of the metrics registered on the fly by
+    /// the built [`Recorder`], unless enabled already.
+    fn track_budget(&mut self) {
+        if self.storage.budget.is_none() {
+            self.storage.budget = Some(Arc::new(budget::Tracker::new(
+                self.storage.config.clone(),
+                self.storage.budget_policy,
+                #[cfg(feature = "timestamps")]
+                self.storage.timestamps.clone(),
+            )));
+        }
+    }
+
+    /// Replaces the whole [`config::Config`] of the metrics registered on the
+    /// fly by the built [`Recorder`] with the provided one, overriding the
+    /// ones set via the [`Builder::with_strict_validation()`],
+    /// [`Builder::with_length_limits()`] and similar methods before.
+    ///
+    /// The [`config::Config`] may be replaced later at runtime via the
+    /// [`Recorder::update_config()`] method. The budget tracking is enabled
+    /// if the provided [`config::Config::budget`] limits anything.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::config::Config;
+    ///
+    /// let registry = prometheus::Registry::new();
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&registry)
+    ///     .with_config(Config {
+    ///         default_labels: vec![("service".into(), "api".into())],
+    ///         ..Config::default()
+    ///     })
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     metrics::counter!("requests").increment(1);
+    ///     metrics::counter!("requests", "service" => "web").increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&registry.gather())?;
+    /// assert!(report.contains(r#"requests{service="api"} 1"#));
+    /// assert!(report.contains(r#"requests{service="web"} 1"#));
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn with_config(mut self, config: config::Config) -> Self {
+        let limited = config.budget != budget::Budget::UNLIMITED;
+        self.storage.config.set(config);
+        if limited {
+            self.track_budget();
+        }
+        self
     }
 
     /// Builds a [`Recorder`] out of this [`Builder`] and returns it being
@@ -838,8 +4965,10 @@ impl<S, L> Builder<S, L> {
         L: Layer<frozen::Recorder<S>>,
     {
         let Self { storage, failure_strategy, layers } = self;
-        let rec =
-            frozen::Recorder { storage: (&storage).into(), failure_strategy };
+        let rec = frozen::Recorder {
+            storage: storage.into_immutable(),
+            failure_strategy,
+        };
         layers.layer(rec)
     }
 
@@ -1069,10 +5198,13 @@ impl<S, L> Builder<S, L> {
             metrics::Recorder + Sync + 'static,
     {
         let Self { storage, failure_strategy, layers } = self;
-        let rec =
-            frozen::Recorder { storage: (&storage).into(), failure_strategy };
+        let registry = Arc::clone(&storage.prometheus);
+        let rec = frozen::Recorder {
+            storage: storage.into_immutable(),
+            failure_strategy,
+        };
         metrics::set_global_recorder(layers.layer(rec))?;
-        Ok(storage.prometheus)
+        Ok(Arc::unwrap_or_clone(registry))
     }
 
     /// Builds a [`Recorder`] out of this [`Builder`] and installs it with the
@@ -1214,6 +5346,103 @@ impl<S, L> Builder<S, L> {
         })
     }
 
+    /// Builds a [`Recorder`] out of this [`Builder`] and tries to install it
+    /// with the [`metrics::set_global_recorder()`], calling the provided
+    /// `fallback` if it fails.
+    ///
+    /// Unlike the [`Builder::try_build_and_install()`] method, the built
+    /// [`Recorder`] is returned regardless of the installation result, while
+    /// the `fallback` receives the [`metrics::SetRecorderError`] holding the
+    /// built (and layered) [`metrics::Recorder`], so it may be logged and
+    /// installed in another way (like locally), instead of being lost.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let registry = prometheus::Registry::new();
+    /// metrics_prometheus::install();
+    ///
+    /// let mut local = None;
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_registry(&registry)
+    ///     .build_and_install_with_fallback(|e| local = Some(e.into_inner()));
+    /// let local = local.expect("global recorder is installed already");
+    ///
+    /// metrics::with_local_recorder(&local, || {
+    ///     metrics::counter!("count").increment(1);
+    /// });
+    ///
+    /// let report = prometheus::TextEncoder::new()
+    ///     .encode_to_string(&recorder.registry().gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP count count
+    /// ## TYPE count counter
+    /// count 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn build_and_install_with_fallback<F>(self, fallback: F) -> Recorder<S>
+    where
+        S: failure::Strategy + Clone,
+        L: Layer<Recorder<S>>,
+        <L as Layer<Recorder<S>>>::Output: metrics::Recorder + Sync + 'static,
+        F: FnOnce(metrics::SetRecorderError<L::Output>),
+    {
+        let Self { storage, failure_strategy, layers } = self;
+        let rec = Recorder {
+            metrics: Arc::new(metrics_util::registry::Registry::new(
+                storage.clone(),
+            )),
+            storage,
+            failure_strategy,
+        };
+        if let Err(e) = metrics::set_global_recorder(layers.layer(rec.clone()))
+        {
+            fallback(e);
+        }
+        rec
+    }
+
+    /// Builds a [`FreezableRecorder`] out of this [`Builder`] and tries to
+    /// install it with the [`metrics::set_global_recorder()`], calling the
+    /// provided `fallback` if it fails.
+    ///
+    /// Unlike the [`Builder::try_build_freezable_and_install()`] method, the
+    /// built [`FreezableRecorder`] is returned regardless of the installation
+    /// result. See the [`Builder::build_and_install_with_fallback()`] method
+    /// for details.
+    ///
+    /// [`FreezableRecorder`]: Freezable
+    pub fn build_freezable_and_install_with_fallback<F>(
+        self,
+        fallback: F,
+    ) -> freezable::Recorder<S>
+    where
+        S: failure::Strategy + Clone,
+        L: Layer<freezable::Recorder<S>>,
+        <L as Layer<freezable::Recorder<S>>>::Output:
+            metrics::Recorder + Sync + 'static,
+        F: FnOnce(metrics::SetRecorderError<L::Output>),
+    {
+        let Self { storage, failure_strategy, layers } = self;
+        let rec = freezable::Recorder::wrap(Recorder {
+            metrics: Arc::new(metrics_util::registry::Registry::new(
+                storage.clone(),
+            )),
+            storage,
+            failure_strategy,
+        });
+        if let Err(e) = metrics::set_global_recorder(layers.layer(rec.clone()))
+        {
+            fallback(e);
+        }
+        rec
+    }
+
     /// Builds a [`FrozenRecorder`] out of this [`Builder`] and installs it with
     /// the [`metrics::set_global_recorder()`].
     ///
@@ -1282,6 +5511,85 @@ impl<S, L> Builder<S, L> {
             )
         })
     }
+
+    /// Builds a [`Recorder`] out of this [`Builder`] and installs it with the
+    /// [`metrics::set_default_local_recorder()`], so it's used by the
+    /// [`metrics`] macros on the current thread only, until the returned
+    /// [`metrics::LocalRecorderGuard`] is dropped.
+    ///
+    /// Allows a binary embedding multiple isolated subsystems to give each one
+    /// its own [`Recorder`] (and [`prometheus::Registry`]) without touching the
+    /// global one, which is restored once the guard is dropped. Nested guards
+    /// must be dropped in the reverse order of their creation.
+    ///
+    /// Similarly to the [`metrics::set_global_recorder()`], the installed
+    /// (layered) [`metrics::Recorder`] is leaked to outlive the guard, so this
+    /// method is intended for long-living scopes (like a subsystem's thread),
+    /// rather than for being called repeatedly. For short scopes, use the
+    /// [`metrics::with_local_recorder()`] with a [`Builder::build()`] result
+    /// instead.
+    ///
+    /// # Async tasks
+    ///
+    /// The installed [`Recorder`] is bound to the current thread, not to an
+    /// async task. The returned guard is `!Send`, so it cannot be held across
+    /// an `.await` point in a task spawned onto a multi-threaded runtime. On
+    /// a single-threaded runtime, however, it affects all the tasks polled on
+    /// this thread while it's alive, and a task migrated to another thread
+    /// stops using it. So, for async code, either install it for the whole
+    /// single-threaded runtime of a subsystem, or wrap the synchronous parts
+    /// recording metrics into the [`metrics::with_local_recorder()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::thread;
+    ///
+    /// let custom = prometheus::Registry::new();
+    ///
+    /// thread::scope(|s| {
+    ///     _ = s.spawn(|| {
+    ///         let (_rec, _guard) = metrics_prometheus::Recorder::builder()
+    ///             .with_registry(&custom)
+    ///             .install_local();
+    ///
+    ///         metrics::counter!("subsystem_jobs").increment(1);
+    ///     });
+    ///     _ = s.spawn(|| metrics::counter!("other_jobs").increment(1));
+    /// });
+    ///
+    /// let report =
+    ///     prometheus::TextEncoder::new().encode_to_string(&custom.gather())?;
+    /// assert_eq!(
+    ///     report.trim(),
+    ///     r#"
+    /// ## HELP subsystem_jobs subsystem_jobs
+    /// ## TYPE subsystem_jobs counter
+    /// subsystem_jobs 1
+    ///     "#
+    ///     .trim(),
+    /// );
+    /// # Ok::<_, prometheus::Error>(())
+    /// ```
+    pub fn install_local(
+        self,
+    ) -> (Recorder<S>, metrics::LocalRecorderGuard<'static>)
+    where
+        S: failure::Strategy + Clone,
+        L: Layer<Recorder<S>>,
+        <L as Layer<Recorder<S>>>::Output: metrics::Recorder + 'static,
+    {
+        let Self { storage, failure_strategy, layers } = self;
+        let rec = Recorder {
+            metrics: Arc::new(metrics_util::registry::Registry::new(
+                storage.clone(),
+            )),
+            storage,
+            failure_strategy,
+        };
+        let installed = Box::leak(Box::new(layers.layer(rec.clone())));
+        (rec, metrics::set_default_local_recorder(installed))
+    }
 }
 
 impl<S, H, T> Builder<S, layer::Stack<H, T>> {
@@ -1294,6 +5602,7 @@ impl<S, H, T> Builder<S, layer::Stack<H, T>> {
     /// use metrics_util::layers::FilterLayer;
     ///
     /// metrics_prometheus::Recorder::builder()
+    ///     .with_default_registry()
     ///     .with_layer(FilterLayer::from_patterns(["ignored"]))
     ///     .with_layer(FilterLayer::from_patterns(["skipped"]))
     ///     .build_and_install();
@@ -1331,6 +5640,229 @@ impl<S, H, T> Builder<S, layer::Stack<H, T>> {
             layers: self.layers.push(layer),
         }
     }
+
+    /// Adds the provided [`metrics::Layer`] to wrap the built [`Recorder`]
+    /// upon its installation with the [`metrics::set_global_recorder()`], only
+    /// if the provided `condition` is `true`.
+    ///
+    /// Unlike the [`Builder::with_layer()`] used conditionally, keeps the same
+    /// [`Builder`] type regardless of the `condition`, so allows toggling
+    /// [`metrics::Layer`]s by configuration without duplicating the code.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_util::layers::FilterLayer;
+    ///
+    /// let registry = prometheus::Registry::new();
+    /// let filtered = |enabled| {
+    ///     let filter = FilterLayer::from_patterns(["ignored"]);
+    ///     metrics_prometheus::Recorder::builder()
+    ///         .with_registry(registry.clone())
+    ///         .with_layer_when(enabled, filter)
+    ///         .build()
+    /// };
+    ///
+    /// metrics::with_local_recorder(&filtered(true), || {
+    ///     metrics::counter!("ignored_counter").increment(1);
+    /// });
+    /// assert!(registry.gather().is_empty());
+    ///
+    /// metrics::with_local_recorder(&filtered(false), || {
+    ///     metrics::counter!("ignored_counter").increment(1);
+    /// });
+    /// assert_eq!(registry.gather().len(), 1);
+    /// ```
+    ///
+    /// [`metrics::Layer`]: Layer
+    pub fn with_layer_when<L>(
+        self,
+        condition: bool,
+        layer: L,
+    ) -> Builder<S, layer::Stack<layer::Optional<L>, layer::Stack<H, T>>>
+    where
+        L: Layer<<layer::Stack<H, T> as Layer<Recorder<S>>>::Output>,
+        layer::Stack<H, T>: Layer<Recorder<S>>,
+    {
+        self.with_layer(if condition {
+            layer::Either::Left(layer)
+        } else {
+            layer::Either::Right(layer::Identity)
+        })
+    }
+
+    /// Adds a [`rate_limited::Limit`] layer to wrap the built [`Recorder`],
+    /// dropping recordings of a [`metrics::Key`] beyond the provided number of
+    /// operations per second.
+    ///
+    /// Intended for debugging runaway instrumentation, protecting the process
+    /// from pathological hot loops hammering a metric. To inspect the number of
+    /// dropped recordings or to be notified about exceeding the limit, use the
+    /// [`Builder::with_layer()`] with a [`rate_limited::Limit`] kept at hand
+    /// instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let recorder = metrics_prometheus::Recorder::builder()
+    ///     .with_isolated_registry()
+    ///     .with_rate_limit(1_000)
+    ///     .build();
+    ///
+    /// metrics::with_local_recorder(&recorder, || {
+    ///     for _ in 0..10_000 {
+    ///         metrics::counter!("count").increment(1);
+    ///     }
+    /// });
+    ///
+    /// let dropped = recorder.limit().dropped();
+    /// assert!(dropped > 0);
+    /// let count = recorder.inner().registry().gather()[0].get_metric()[0]
+    ///     .get_counter()
+    ///     .get_value();
+    /// assert_eq!(count as u64, 10_000 - dropped);
+    /// ```
+    pub fn with_rate_limit(
+        self,
+        ops_per_sec: u32,
+    ) -> Builder<S, layer::Stack<rate_limited::Limit, layer::Stack<H, T>>>
+    where
+        layer::Stack<H, T>: Layer<Recorder<S>>,
+    {
+        let clock = Arc::clone(&self.storage.clock);
+        self.with_layer(rate_limited::Limit::new(ops_per_sec).with_clock(clock))
+    }
+}
+
+/// Renders the metrics gathered from the provided [`prometheus::Registry`] in
+/// the Prometheus text format, keeping only the series matching the provided
+/// `predicate`.
+///
+/// # Errors
+///
+/// If the gathered metrics cannot be encoded.
+fn render_text_filtered<F>(
+    registry: &prometheus::Registry,
+    merged: &storage::Merged,
+    mut predicate: F,
+) -> prometheus::Result<String>
+where
+    F: FnMut(&str, &[(&str, &str)]) -> bool,
+{
+    let mut families = registry.gather();
+    let duplicates = merged.gather_into(&mut families);
+    if !duplicates.is_empty() {
+        return Err(storage::duplicated(&duplicates));
+    }
+    for family in &mut families {
+        let name = family.get_name().to_owned();
+        let metrics = family.take_metric().into_iter().filter(|m| {
+            let labels = m
+                .get_label()
+                .iter()
+                .map(|l| (l.get_name(), l.get_value()))
+                .collect::<Vec<_>>();
+            predicate(&name, &labels)
+        });
+        family.set_metric(metrics.collect());
+    }
+    families.retain(|f| !f.get_metric().is_empty());
+    prometheus::TextEncoder::new().encode_to_string(&families)
+}
+
+/// Renders the metrics of the provided [`storage::Mutable`] in the Prometheus
+/// text format, surviving panics of [`prometheus::core::Collector`]s.
+///
+/// # Errors
+///
+/// With a [`PartialRender`], if any panic happens, or the gathered metrics
+/// cannot be encoded.
+fn try_render_text(
+    storage: &storage::Mutable,
+) -> Result<String, PartialRender> {
+    /// Extracts a message out of the provided panic `payload`.
+    fn message(payload: &(dyn Any + Send)) -> &str {
+        payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic")
+    }
+
+    let registry = &storage.prometheus;
+    let mut errors = vec![];
+    let families = match panic::catch_unwind(AssertUnwindSafe(|| {
+        registry.gather()
+    })) {
+        Ok(families) => families,
+        Err(payload) => {
+            storage.gather_panics.inc();
+            // Fails only if registered already.
+            drop(registry.register(Box::new(storage.gather_panics.clone())));
+            errors.push(format!(
+                "gathering `prometheus::Registry` panicked: {}",
+                message(&*payload),
+            ));
+
+            let mut families =
+                prometheus::core::Collector::collect(&storage.gather_panics);
+            for collector in storage.collectors() {
+                match panic::catch_unwind(AssertUnwindSafe(|| {
+                    collector.collect()
+                })) {
+                    Ok(collected) => families.extend(collected),
+                    Err(p) => errors.push(format!(
+                        "collecting `{}` panicked: {}",
+                        collector
+                            .desc()
+                            .first()
+                            .map_or("", |d| d.fq_name.as_str()),
+                        message(&*p),
+                    )),
+                }
+            }
+            families.retain(|f| !f.get_metric().is_empty());
+            families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+            families
+        }
+    };
+    let mut families = families;
+    let duplicates = storage.merged.gather_into(&mut families);
+    if !duplicates.is_empty() {
+        errors.push(storage::duplicated(&duplicates).to_string());
+    }
+    let text = prometheus::TextEncoder::new()
+        .encode_to_string(&families)
+        .unwrap_or_else(|e| {
+            errors.push(format!("encoding metrics failed: {e}"));
+            String::new()
+        });
+    if errors.is_empty() {
+        Ok(text)
+    } else {
+        Err(PartialRender { text, errors })
+    }
+}
+
+/// Partially rendered metrics in the Prometheus text format, returned by the
+/// [`Recorder::try_render_text()`] method when some of them failed to be
+/// rendered.
+///
+/// If gathering the whole [`prometheus::Registry`] has failed, the rendered
+/// metrics omit its prefix and common labels (if any).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PartialRender {
+    /// Metrics rendered successfully.
+    pub text: String,
+
+    /// Summaries of the encountered errors.
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for PartialRender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.errors.join("; "))
+    }
 }
 
 /// Ad hoc polymorphism for accepting either a reference or an owned function
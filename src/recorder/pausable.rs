@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`metrics::Recorder`] allowing to pause and resume recording at runtime.
+
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use super::Layer;
+
+/// Shared switch pausing and resuming recording of a [`PausableRecorder`].
+///
+/// Cheaply cloneable, so may be kept at hand after the [`PausableRecorder`]
+/// is installed globally. Being used as a [`metrics::Layer`], wraps a
+/// [`metrics::Recorder`] into a [`PausableRecorder`] controlled by this
+/// [`Switch`].
+///
+/// [`metrics::Layer`]: Layer
+/// [`PausableRecorder`]: Recorder
+#[derive(Clone, Debug, Default)]
+pub struct Switch(Arc<ArcSwap<bool>>);
+
+impl Switch {
+    /// Pauses recording, making all the handles (both already obtained and new
+    /// ones) no-op.
+    pub fn pause(&self) {
+        self.0.store(Arc::new(true));
+    }
+
+    /// Resumes recording, previously paused with the [`Switch::pause()`].
+    pub fn resume(&self) {
+        self.0.store(Arc::new(false));
+    }
+
+    /// Indicates whether recording is paused currently.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        **self.0.load()
+    }
+}
+
+impl<R> Layer<R> for Switch {
+    type Output = Recorder<R>;
+
+    fn layer(&self, inner: R) -> Self::Output {
+        Recorder::new(inner, self.clone())
+    }
+}
+
+/// [`metrics::Recorder`] wrapping another one and allowing to pause and resume
+/// recording via its [`Switch`] at runtime, without uninstalling it.
+///
+/// While paused, all the handles (both already obtained and new ones) are
+/// no-op, so the recording overhead is reduced to a single atomic load.
+/// Useful for load tests and incident response.
+///
+/// Descriptions and registrations are passed through to the wrapped
+/// [`metrics::Recorder`] regardless of the [`Switch`], so the handles obtained
+/// while paused start recording once resumed.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::recorder::pausable;
+///
+/// let switch = pausable::Switch::default();
+/// metrics_prometheus::Recorder::builder()
+///     .with_default_registry()
+///     .with_layer(switch.clone())
+///     .build_and_install();
+///
+/// let counter = metrics::counter!("count");
+/// counter.increment(1);
+/// switch.pause();
+/// counter.increment(10);
+/// metrics::counter!("count").increment(100);
+/// switch.resume();
+/// counter.increment(1);
+///
+/// let report = prometheus::TextEncoder::new()
+///     .encode_to_string(&prometheus::default_registry().gather())?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP count count
+/// ## TYPE count counter
+/// count 2
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Recorder<R> {
+    /// Wrapped [`metrics::Recorder`].
+    inner: R,
+
+    /// [`Switch`] pausing and resuming recording.
+    switch: Switch,
+}
+
+impl<R> Recorder<R> {
+    /// Wraps the provided [`metrics::Recorder`] to be paused and resumed with
+    /// the provided [`Switch`].
+    #[must_use]
+    pub const fn new(inner: R, switch: Switch) -> Self {
+        Self { inner, switch }
+    }
+
+    /// Returns the [`Switch`] pausing and resuming this [`PausableRecorder`].
+    ///
+    /// [`PausableRecorder`]: Recorder
+    #[must_use]
+    pub const fn switch(&self) -> &Switch {
+        &self.switch
+    }
+
+    /// Returns the wrapped [`metrics::Recorder`].
+    #[must_use]
+    pub const fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Pauses recording, making all the handles (both already obtained and new
+    /// ones) no-op.
+    pub fn pause(&self) {
+        self.switch.pause();
+    }
+
+    /// Resumes recording, previously paused with the [`Recorder::pause()`].
+    pub fn resume(&self) {
+        self.switch.resume();
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl<R: metrics::Recorder> metrics::Recorder for Recorder<R> {
+    fn describe_counter(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.inner.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.inner.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.inner.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Counter {
+        metrics::Counter::from_arc(Arc::new(Handle {
+            inner: self.inner.register_counter(key, metadata),
+            switch: self.switch.clone(),
+        }))
+    }
+
+    fn register_gauge(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Gauge {
+        metrics::Gauge::from_arc(Arc::new(Handle {
+            inner: self.inner.register_gauge(key, metadata),
+            switch: self.switch.clone(),
+        }))
+    }
+
+    fn register_histogram(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Histogram {
+        metrics::Histogram::from_arc(Arc::new(Handle {
+            inner: self.inner.register_histogram(key, metadata),
+            switch: self.switch.clone(),
+        }))
+    }
+}
+
+/// Handle of a [`metrics`] metric, being no-op while its [`Switch`] is paused.
+#[derive(Debug)]
+struct Handle<M> {
+    /// Wrapped handle of a [`metrics`] metric.
+    inner: M,
+
+    /// [`Switch`] pausing and resuming the wrapped handle.
+    switch: Switch,
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::CounterFn for Handle<metrics::Counter> {
+    fn increment(&self, value: u64) {
+        if !self.switch.is_paused() {
+            self.inner.increment(value);
+        }
+    }
+
+    fn absolute(&self, value: u64) {
+        if !self.switch.is_paused() {
+            self.inner.absolute(value);
+        }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::GaugeFn for Handle<metrics::Gauge> {
+    fn increment(&self, value: f64) {
+        if !self.switch.is_paused() {
+            self.inner.increment(value);
+        }
+    }
+
+    fn decrement(&self, value: f64) {
+        if !self.switch.is_paused() {
+            self.inner.decrement(value);
+        }
+    }
+
+    fn set(&self, value: f64) {
+        if !self.switch.is_paused() {
+            self.inner.set(value);
+        }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::HistogramFn for Handle<metrics::Histogram> {
+    fn record(&self, value: f64) {
+        if !self.switch.is_paused() {
+            self.inner.record(value);
+        }
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        if !self.switch.is_paused() {
+            self.inner.record_many(value, count);
+        }
+    }
+}
@@ -0,0 +1,429 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`metrics::Recorder`] enforcing per-key rate limiting of recordings.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
+};
+
+use crate::clock::{self, Clock as _};
+
+use super::Layer;
+
+/// Callback invoked once per [`metrics::Key`] when its rate limit is exceeded
+/// for the first time.
+pub type OnExceeded = Arc<dyn Fn(&metrics::Key) + Send + Sync>;
+
+/// Per-key rate limit of recordings of a [`RateLimitedRecorder`].
+///
+/// Cheaply cloneable, so may be kept at hand after the [`RateLimitedRecorder`]
+/// is installed globally (to inspect the number of dropped recordings). Being
+/// used as a [`metrics::Layer`], wraps a [`metrics::Recorder`] into a
+/// [`RateLimitedRecorder`] enforcing this [`Limit`].
+///
+/// [`metrics::Layer`]: Layer
+/// [`RateLimitedRecorder`]: Recorder
+#[derive(Clone)]
+pub struct Limit {
+    /// Maximum number of recordings per second allowed for a single
+    /// [`metrics::Key`].
+    ops_per_sec: u32,
+
+    /// [`OnExceeded`] callback to invoke once per [`metrics::Key`] exceeding
+    /// this [`Limit`].
+    on_exceeded: Option<OnExceeded>,
+
+    /// State shared between all the clones of this [`Limit`].
+    state: Arc<State>,
+}
+
+/// State of a [`Limit`], shared between all its clones.
+#[derive(Debug)]
+struct State {
+    /// [`Clock`] the seconds of the [`Window`]s are measured with.
+    ///
+    /// [`Clock`]: clock::Clock
+    clock: clock::Shared,
+
+    /// [`Instant`] the seconds of the [`Window`]s are counted from.
+    started: Instant,
+
+    /// [`Window`]s of the recordings per [`metrics::Key`].
+    windows: RwLock<HashMap<metrics::Key, Arc<Window>>>,
+
+    /// Total number of the dropped recordings.
+    dropped: AtomicU64,
+}
+
+impl Limit {
+    /// Creates a new [`Limit`] allowing the provided number of recordings per
+    /// second for a single [`metrics::Key`].
+    #[must_use]
+    pub fn new(ops_per_sec: u32) -> Self {
+        Self {
+            ops_per_sec,
+            on_exceeded: None,
+            state: Arc::new(State::new(Arc::new(clock::System))),
+        }
+    }
+
+    /// Makes this [`Limit`] to measure seconds with the provided [`Clock`].
+    ///
+    /// Resets the state of this [`Limit`], so it's not shared with its clones
+    /// made before anymore.
+    ///
+    /// [`Clock`]: clock::Clock
+    #[must_use]
+    pub fn with_clock(mut self, clock: clock::Shared) -> Self {
+        self.state = Arc::new(State::new(clock));
+        self
+    }
+
+    /// Sets the provided callback to be invoked once per [`metrics::Key`] when
+    /// its rate limit is exceeded for the first time (like logging a warning
+    /// about a runaway instrumentation).
+    #[must_use]
+    pub fn on_exceeded<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&metrics::Key) + Send + Sync + 'static,
+    {
+        self.on_exceeded = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns the maximum number of recordings per second allowed by this
+    /// [`Limit`] for a single [`metrics::Key`].
+    #[must_use]
+    pub const fn ops_per_sec(&self) -> u32 {
+        self.ops_per_sec
+    }
+
+    /// Returns the total number of recordings dropped due to exceeding this
+    /// [`Limit`].
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.state.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the [`Window`] of the recordings of the provided
+    /// [`metrics::Key`], creating it if it doesn't exist yet.
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn window(&self, key: &metrics::Key) -> Arc<Window> {
+        if let Some(w) = self.state.windows.read().unwrap().get(key) {
+            return Arc::clone(w);
+        }
+        Arc::clone(
+            self.state
+                .windows
+                .write()
+                .unwrap()
+                .entry(key.clone())
+                .or_insert_with(|| Arc::new(Window::new(key.clone()))),
+        )
+    }
+
+    /// Checks whether a recording in the provided [`Window`] is allowed by
+    /// this [`Limit`], accounting it.
+    fn admit(&self, window: &Window) -> bool {
+        #[expect( // intentional
+            clippy::as_conversions,
+            clippy::cast_possible_truncation,
+            reason = "wrapping is OK, as only equality of seconds matters"
+        )]
+        let now = self
+            .state
+            .clock
+            .now()
+            .saturating_duration_since(self.state.started)
+            .as_secs() as u32;
+        if window.admit(now, self.ops_per_sec) {
+            return true;
+        }
+        _ = self.state.dropped.fetch_add(1, Ordering::Relaxed);
+        if !window.reported.swap(true, Ordering::Relaxed) {
+            if let Some(callback) = &self.on_exceeded {
+                callback(&window.key);
+            }
+        }
+        false
+    }
+}
+
+impl State {
+    /// Creates a new empty [`State`] measuring seconds with the provided
+    /// [`Clock`].
+    ///
+    /// [`Clock`]: clock::Clock
+    fn new(clock: clock::Shared) -> Self {
+        Self {
+            started: clock.now(),
+            clock,
+            windows: RwLock::default(),
+            dropped: AtomicU64::new(0),
+        }
+    }
+}
+
+impl fmt::Debug for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Limit")
+            .field("ops_per_sec", &self.ops_per_sec)
+            .field("dropped", &self.dropped())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R> Layer<R> for Limit {
+    type Output = Recorder<R>;
+
+    fn layer(&self, inner: R) -> Self::Output {
+        Recorder::new(inner, self.clone())
+    }
+}
+
+/// Counting window of recordings of a single [`metrics::Key`].
+#[derive(Debug)]
+struct Window {
+    /// [`metrics::Key`] this [`Window`] counts recordings of.
+    key: metrics::Key,
+
+    /// Current second (high 32 bits) and the number of recordings in it (low
+    /// 32 bits).
+    state: AtomicU64,
+
+    /// Indicator whether exceeding the [`Limit`] has been reported already.
+    reported: AtomicBool,
+}
+
+impl Window {
+    /// Creates a new empty [`Window`] for the provided [`metrics::Key`].
+    const fn new(key: metrics::Key) -> Self {
+        Self { key, state: AtomicU64::new(0), reported: AtomicBool::new(false) }
+    }
+
+    /// Accounts a recording in the provided second, if the provided `limit`
+    /// of recordings per second is not reached yet.
+    fn admit(&self, now: u32, limit: u32) -> bool {
+        let now = u64::from(now) << 32;
+        self.state
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |state| {
+                if state & !u64::from(u32::MAX) != now {
+                    return (limit > 0).then_some(now | 1);
+                }
+                (state & u64::from(u32::MAX) < u64::from(limit))
+                    .then_some(state + 1)
+            })
+            .is_ok()
+    }
+}
+
+/// [`metrics::Recorder`] wrapping another one and dropping recordings of a
+/// [`metrics::Key`] beyond its [`Limit`] of operations per second.
+///
+/// Intended for debugging runaway instrumentation, protecting the process
+/// from pathological hot loops hammering a metric. Descriptions and
+/// registrations are passed through to the wrapped [`metrics::Recorder`]
+/// regardless of the [`Limit`].
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::recorder::rate_limited;
+///
+/// let limit = rate_limited::Limit::new(1_000).on_exceeded(|key| {
+///     eprintln!("metric `{}` is recorded too often", key.name());
+/// });
+/// metrics_prometheus::Recorder::builder()
+///     .with_default_registry()
+///     .with_layer(limit.clone())
+///     .build_and_install();
+///
+/// for _ in 0..10_000 {
+///     metrics::counter!("count").increment(1);
+/// }
+///
+/// assert!(limit.dropped() > 0);
+/// let count = prometheus::default_registry().gather()[0].get_metric()[0]
+///     .get_counter()
+///     .get_value();
+/// assert_eq!(count as u64, 10_000 - limit.dropped());
+/// ```
+#[derive(Debug)]
+pub struct Recorder<R> {
+    /// Wrapped [`metrics::Recorder`].
+    inner: R,
+
+    /// [`Limit`] of recordings per [`metrics::Key`].
+    limit: Limit,
+}
+
+impl<R> Recorder<R> {
+    /// Wraps the provided [`metrics::Recorder`] to enforce the provided
+    /// [`Limit`].
+    #[must_use]
+    pub const fn new(inner: R, limit: Limit) -> Self {
+        Self { inner, limit }
+    }
+
+    /// Returns the [`Limit`] enforced by this [`RateLimitedRecorder`].
+    ///
+    /// [`RateLimitedRecorder`]: Recorder
+    #[must_use]
+    pub const fn limit(&self) -> &Limit {
+        &self.limit
+    }
+
+    /// Returns the wrapped [`metrics::Recorder`].
+    #[must_use]
+    pub const fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Wraps the provided handle of a [`metrics`] metric into a [`Handle`]
+    /// enforcing the [`Limit`] of the provided [`metrics::Key`].
+    fn handle<M>(&self, key: &metrics::Key, inner: M) -> Arc<Handle<M>> {
+        Arc::new(Handle {
+            inner,
+            window: self.limit.window(key),
+            limit: self.limit.clone(),
+        })
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl<R: metrics::Recorder> metrics::Recorder for Recorder<R> {
+    fn describe_counter(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.inner.describe_counter(key, unit, description);
+    }
+
+    fn describe_gauge(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.inner.describe_gauge(key, unit, description);
+    }
+
+    fn describe_histogram(
+        &self,
+        key: metrics::KeyName,
+        unit: Option<metrics::Unit>,
+        description: metrics::SharedString,
+    ) {
+        self.inner.describe_histogram(key, unit, description);
+    }
+
+    fn register_counter(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Counter {
+        let inner = self.inner.register_counter(key, metadata);
+        metrics::Counter::from_arc(self.handle(key, inner))
+    }
+
+    fn register_gauge(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Gauge {
+        let inner = self.inner.register_gauge(key, metadata);
+        metrics::Gauge::from_arc(self.handle(key, inner))
+    }
+
+    fn register_histogram(
+        &self,
+        key: &metrics::Key,
+        metadata: &metrics::Metadata<'_>,
+    ) -> metrics::Histogram {
+        let inner = self.inner.register_histogram(key, metadata);
+        metrics::Histogram::from_arc(self.handle(key, inner))
+    }
+}
+
+/// Handle of a [`metrics`] metric, dropping recordings beyond its [`Limit`].
+#[derive(Debug)]
+struct Handle<M> {
+    /// Wrapped handle of a [`metrics`] metric.
+    inner: M,
+
+    /// [`Window`] of the recordings of the wrapped handle.
+    window: Arc<Window>,
+
+    /// [`Limit`] of the recordings of the wrapped handle.
+    limit: Limit,
+}
+
+impl<M> Handle<M> {
+    /// Checks whether a recording is allowed by the [`Limit`] of this
+    /// [`Handle`].
+    fn admit(&self) -> bool {
+        self.limit.admit(&self.window)
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::CounterFn for Handle<metrics::Counter> {
+    fn increment(&self, value: u64) {
+        if self.admit() {
+            self.inner.increment(value);
+        }
+    }
+
+    fn absolute(&self, value: u64) {
+        if self.admit() {
+            self.inner.absolute(value);
+        }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::GaugeFn for Handle<metrics::Gauge> {
+    fn increment(&self, value: f64) {
+        if self.admit() {
+            self.inner.increment(value);
+        }
+    }
+
+    fn decrement(&self, value: f64) {
+        if self.admit() {
+            self.inner.decrement(value);
+        }
+    }
+
+    fn set(&self, value: f64) {
+        if self.admit() {
+            self.inner.set(value);
+        }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl metrics::HistogramFn for Handle<metrics::Histogram> {
+    fn record(&self, value: f64) {
+        if self.admit() {
+            self.inner.record(value);
+        }
+    }
+
+    fn record_many(&self, value: f64, count: usize) {
+        if self.admit() {
+            self.inner.record_many(value, count);
+        }
+    }
+}
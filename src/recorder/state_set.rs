@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [OpenMetrics `StateSet`][0] handles, representing a single logical gauge
+//! being in exactly one of its possible states.
+//!
+//! [0]: https://prometheus.io/docs/specs/om/open_metrics_spec/#stateset
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Handle of a [OpenMetrics `StateSet`][0], returned by the
+/// [`Recorder::register_state_set()`] method.
+///
+/// Exposed as a gauge family labeled with its own name, having exactly one
+/// gauge set to `1` (the current state), while the others are `0`.
+///
+/// [`Recorder::register_state_set()`]: super::Recorder::register_state_set
+/// [0]: https://prometheus.io/docs/specs/om/open_metrics_spec/#stateset
+#[derive(Clone, Debug)]
+pub struct StateSet {
+    /// Name of the gauge family of this [`StateSet`].
+    name: String,
+
+    /// All the possible states of this [`StateSet`].
+    states: Arc<[String]>,
+
+    /// Index of the current state in the [`StateSet::states`].
+    current: Arc<AtomicUsize>,
+}
+
+impl StateSet {
+    /// Creates a new [`StateSet`] with the provided `name` and `states`, being
+    /// in the first of them.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `states` are empty or contain duplicates.
+    pub(super) fn new(
+        name: &str,
+        states: Vec<String>,
+    ) -> prometheus::Result<Self> {
+        if states.is_empty() {
+            return Err(prometheus::Error::Msg(format!(
+                "`{name}` state set has no states",
+            )));
+        }
+        for (i, state) in states.iter().enumerate() {
+            if states[..i].contains(state) {
+                return Err(prometheus::Error::Msg(format!(
+                    "`{name}` state set has duplicated `{state}` state",
+                )));
+            }
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            states: states.into(),
+            current: Arc::default(),
+        })
+    }
+
+    /// Returns the name of the gauge family of this [`StateSet`].
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns all the possible states of this [`StateSet`].
+    #[must_use]
+    pub fn states(&self) -> &[String] {
+        &self.states
+    }
+
+    /// Returns the current state of this [`StateSet`].
+    #[must_use]
+    pub fn state(&self) -> &str {
+        &self.states[self.current.load(Ordering::Relaxed)]
+    }
+
+    /// Switches this [`StateSet`] into the provided `state`, unsetting the
+    /// previous one.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `state` is not one of the [`StateSet::states()`].
+    pub fn set_state(&self, state: &str) -> prometheus::Result<()> {
+        let idx =
+            self.states.iter().position(|s| s == state).ok_or_else(|| {
+                prometheus::Error::Msg(format!(
+                    "`{state}` is not a state of `{}` state set",
+                    self.name,
+                ))
+            })?;
+        self.current.store(idx, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns a callback reporting the value of the gauge representing the
+    /// state at the provided `idx` in the [`StateSet::states`].
+    pub(super) fn callback(
+        &self,
+        idx: usize,
+    ) -> impl Fn() -> f64 + Send + Sync + 'static {
+        let current = Arc::clone(&self.current);
+        move || f64::from(u8::from(current.load(Ordering::Relaxed) == idx))
+    }
+}
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Typed handles of metric families enforcing their label arity at compile
+//! time.
+
+use metrics::Recorder as _;
+
+use crate::failure;
+
+use super::Recorder;
+
+/// [`metrics::Metadata`] of all the metrics resolved via typed or checked
+/// handles.
+pub(super) static METADATA: metrics::Metadata<'static> = metrics::Metadata::new(
+    module_path!(),
+    metrics::Level::INFO,
+    Some(module_path!()),
+);
+
+/// Typed handle of a counter family with exactly `N` labels, returned by the
+/// [`Recorder::counter_vec()`] method.
+///
+/// Resolves its counters via the same [`Recorder`] storage entries the
+/// [`metrics::counter!`] macro resolves to, so both may be used
+/// interchangeably.
+#[derive(Clone, Debug)]
+pub struct CounterVec<const N: usize, FailureStrategy> {
+    /// [`Recorder`] resolving the counters of this [`CounterVec`].
+    recorder: Recorder<FailureStrategy>,
+
+    /// Name of the counter family.
+    name: metrics::KeyName,
+
+    /// Names of the labels of the counter family.
+    labels: [metrics::SharedString; N],
+}
+
+impl<const N: usize, S> CounterVec<N, S> {
+    /// Creates a new [`CounterVec`] resolving the counters of the family with
+    /// the provided `name` and `labels` via the provided [`Recorder`].
+    pub(super) const fn new(
+        recorder: Recorder<S>,
+        name: metrics::KeyName,
+        labels: [metrics::SharedString; N],
+    ) -> Self {
+        Self { recorder, name, labels }
+    }
+
+    /// Returns the name of the counter family of this [`CounterVec`].
+    #[must_use]
+    pub const fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the names of the labels of the counter family of this
+    /// [`CounterVec`].
+    #[must_use]
+    pub fn label_names(&self) -> [&str; N] {
+        self.labels.each_ref().map(AsRef::as_ref)
+    }
+}
+
+impl<const N: usize, S> CounterVec<N, S>
+where
+    S: failure::Strategy,
+{
+    /// Resolves the [`metrics::Counter`] with the provided label `values`
+    /// (in the order of the [label names][0]).
+    ///
+    /// [0]: CounterVec::label_names
+    pub fn with_label_values<V>(&self, values: [V; N]) -> metrics::Counter
+    where
+        V: Into<metrics::SharedString>,
+    {
+        let labels = self
+            .labels
+            .iter()
+            .zip(values)
+            .map(|(name, value)| metrics::Label::new(name.clone(), value))
+            .collect::<Vec<_>>();
+        let key = metrics::Key::from_parts(self.name.clone(), labels);
+        self.recorder.register_counter(&key, &METADATA)
+    }
+
+    /// Increments the counter with the provided label `values` by `1`.
+    pub fn inc<V>(&self, values: [V; N])
+    where
+        V: Into<metrics::SharedString>,
+    {
+        self.with_label_values(values).increment(1);
+    }
+
+    /// Increments the counter with the provided label `values` by the
+    /// provided `value`.
+    pub fn inc_by<V>(&self, values: [V; N], value: u64)
+    where
+        V: Into<metrics::SharedString>,
+    {
+        self.with_label_values(values).increment(value);
+    }
+}
@@ -0,0 +1,351 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Relabeling of [`prometheus`] metrics at gather time.
+//!
+//! Supports a subset of Prometheus `relabel_config` actions: `replace`,
+//! `keep`, `drop` and `labelmap`. Regular expressions are anchored on both
+//! ends, and the metric name is available as the `__name__` label, the same
+//! way as in Prometheus. After applying all the [`Rule`]s, the labels starting
+//! with `__` and the ones having empty values are removed.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use arc_swap::ArcSwap;
+use regex::Regex;
+
+/// Name of the label holding a metric name during relabeling.
+pub const METRIC_NAME_LABEL: &str = "__name__";
+
+/// Single relabeling rule.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    /// [`Action`] performed by this [`Rule`].
+    action: Action,
+
+    /// Anchored [`Regex`] matched against the source value.
+    regex: Regex,
+}
+
+/// Action performed by a [`Rule`].
+#[derive(Clone, Debug)]
+enum Action {
+    /// Replaces the `target_label` value with the `replacement`, if the
+    /// [`Source`] value matches.
+    Replace {
+        /// [`Source`] of the value to match.
+        source: Source,
+
+        /// Label to write the `replacement` into.
+        target_label: String,
+
+        /// Replacement, possibly referring to the capture groups (like `$1`).
+        replacement: String,
+    },
+
+    /// Keeps the metric only if the [`Source`] value matches.
+    Keep(Source),
+
+    /// Drops the metric if the [`Source`] value matches.
+    Drop(Source),
+
+    /// Copies the values of the matching labels into the labels named by the
+    /// `replacement`.
+    LabelMap {
+        /// Replacement of the label name, possibly referring to the capture
+        /// groups (like `$1`).
+        replacement: String,
+    },
+}
+
+/// Source value of a [`Rule`], being the concatenated values of labels.
+#[derive(Clone, Debug)]
+struct Source {
+    /// Names of the labels to concatenate the values of.
+    labels: Vec<String>,
+
+    /// Separator of the concatenated values.
+    separator: String,
+}
+
+impl Source {
+    /// Creates a new [`Source`] out of the provided label names, concatenated
+    /// with the default `;` separator.
+    fn new<L: Into<String>>(labels: impl IntoIterator<Item = L>) -> Self {
+        Self {
+            labels: labels.into_iter().map(Into::into).collect(),
+            separator: ";".into(),
+        }
+    }
+
+    /// Returns the concatenated value of this [`Source`] in the provided
+    /// `labels`.
+    fn value(&self, labels: &BTreeMap<String, String>) -> String {
+        self.labels
+            .iter()
+            .map(|l| labels.get(l).map_or("", String::as_str))
+            .collect::<Vec<_>>()
+            .join(&self.separator)
+    }
+}
+
+impl Rule {
+    /// Creates a new `replace` [`Rule`], writing the `replacement` (possibly
+    /// referring to the `regex` capture groups, like `$1`) into the
+    /// `target_label`, if the concatenated values of the `source_labels`
+    /// match the `regex`.
+    ///
+    /// Empty `replacement` result removes the `target_label`.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `regex` is invalid.
+    pub fn replace<L: Into<String>>(
+        source_labels: impl IntoIterator<Item = L>,
+        regex: &str,
+        target_label: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> prometheus::Result<Self> {
+        Ok(Self {
+            action: Action::Replace {
+                source: Source::new(source_labels),
+                target_label: target_label.into(),
+                replacement: replacement.into(),
+            },
+            regex: anchored(regex)?,
+        })
+    }
+
+    /// Creates a new `keep` [`Rule`], dropping the metrics whose
+    /// concatenated values of the `source_labels` don't match the `regex`.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `regex` is invalid.
+    pub fn keep<L: Into<String>>(
+        source_labels: impl IntoIterator<Item = L>,
+        regex: &str,
+    ) -> prometheus::Result<Self> {
+        Ok(Self {
+            action: Action::Keep(Source::new(source_labels)),
+            regex: anchored(regex)?,
+        })
+    }
+
+    /// Creates a new `drop` [`Rule`], dropping the metrics whose
+    /// concatenated values of the `source_labels` match the `regex`.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `regex` is invalid.
+    pub fn drop<L: Into<String>>(
+        source_labels: impl IntoIterator<Item = L>,
+        regex: &str,
+    ) -> prometheus::Result<Self> {
+        Ok(Self {
+            action: Action::Drop(Source::new(source_labels)),
+            regex: anchored(regex)?,
+        })
+    }
+
+    /// Creates a new `labelmap` [`Rule`], copying the values of the labels
+    /// whose names match the `regex` into the labels named by the
+    /// `replacement` (possibly referring to the `regex` capture groups, like
+    /// `$1`).
+    ///
+    /// # Errors
+    ///
+    /// If the provided `regex` is invalid.
+    pub fn labelmap(
+        regex: &str,
+        replacement: impl Into<String>,
+    ) -> prometheus::Result<Self> {
+        Ok(Self {
+            action: Action::LabelMap { replacement: replacement.into() },
+            regex: anchored(regex)?,
+        })
+    }
+
+    /// Sets the `separator` to concatenate the values of the source labels
+    /// with (`;` by default).
+    ///
+    /// No-op for a `labelmap` [`Rule`].
+    #[must_use]
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        match &mut self.action {
+            Action::Replace { source, .. }
+            | Action::Keep(source)
+            | Action::Drop(source) => source.separator = separator.into(),
+            Action::LabelMap { .. } => {}
+        }
+        self
+    }
+
+    /// Applies this [`Rule`] to the provided `labels` of a metric.
+    ///
+    /// Returns `false` if the metric should be dropped.
+    fn apply(&self, labels: &mut BTreeMap<String, String>) -> bool {
+        match &self.action {
+            Action::Replace { source, target_label, replacement } => {
+                let value = source.value(labels);
+                if let Some(caps) = self.regex.captures(&value) {
+                    let mut res = String::new();
+                    caps.expand(replacement, &mut res);
+                    if res.is_empty() {
+                        drop(labels.remove(target_label));
+                    } else {
+                        drop(labels.insert(target_label.clone(), res));
+                    }
+                }
+                true
+            }
+            Action::Keep(source) => self.regex.is_match(&source.value(labels)),
+            Action::Drop(source) => !self.regex.is_match(&source.value(labels)),
+            Action::LabelMap { replacement } => {
+                let mapped = labels
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        let caps = self.regex.captures(name)?;
+                        let mut res = String::new();
+                        caps.expand(replacement, &mut res);
+                        Some((res, value.clone()))
+                    })
+                    .collect::<Vec<_>>();
+                labels.extend(mapped);
+                true
+            }
+        }
+    }
+}
+
+/// Compiles the provided `regex` anchored on both ends.
+fn anchored(regex: &str) -> prometheus::Result<Regex> {
+    Regex::new(&format!("^(?:{regex})$")).map_err(|e| {
+        prometheus::Error::Msg(format!(
+            "invalid relabeling regex `{regex}`: {e}"
+        ))
+    })
+}
+
+/// Shared list of relabeling [`Rule`]s, applied in order, which may be
+/// replaced at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct Rules(Arc<ArcSwap<Vec<Rule>>>);
+
+impl Rules {
+    /// Returns the current relabeling [`Rule`]s.
+    #[must_use]
+    pub fn get(&self) -> Arc<Vec<Rule>> {
+        self.0.load_full()
+    }
+
+    /// Replaces the current relabeling [`Rule`]s with the provided ones,
+    /// taking effect on the next gathering.
+    pub fn set(&self, rules: Vec<Rule>) {
+        self.0.store(Arc::new(rules));
+    }
+
+    /// Appends the provided relabeling [`Rule`] to the current ones.
+    ///
+    /// Not atomic in regard to the concurrent [`Rules::set()`] calls, so is
+    /// intended to be used while building only.
+    pub(crate) fn push(&self, rule: Rule) {
+        let mut rules = Vec::clone(&self.0.load());
+        rules.push(rule);
+        self.set(rules);
+    }
+
+    /// Applies the current relabeling [`Rule`]s to the provided gathered
+    /// [`prometheus::proto::MetricFamily`]s.
+    #[must_use]
+    pub fn apply(
+        &self,
+        families: Vec<prometheus::proto::MetricFamily>,
+    ) -> Vec<prometheus::proto::MetricFamily> {
+        let rules = self.0.load();
+        if rules.is_empty() {
+            return families;
+        }
+
+        let mut out = Vec::<prometheus::proto::MetricFamily>::new();
+        for mut family in families {
+            for mut metric in family.take_metric() {
+                let mut labels = metric
+                    .take_label()
+                    .into_iter()
+                    .map(|l| {
+                        (l.get_name().to_owned(), l.get_value().to_owned())
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                drop(labels.insert(
+                    METRIC_NAME_LABEL.into(),
+                    family.get_name().into(),
+                ));
+                if !rules.iter().all(|rule| rule.apply(&mut labels)) {
+                    continue;
+                }
+                let Some(name) =
+                    labels.remove(METRIC_NAME_LABEL).filter(|n| !n.is_empty())
+                else {
+                    continue;
+                };
+                metric.set_label(
+                    labels
+                        .into_iter()
+                        .filter(|(n, v)| !n.starts_with("__") && !v.is_empty())
+                        .map(|(n, v)| {
+                            let mut label =
+                                prometheus::proto::LabelPair::default();
+                            label.set_name(n);
+                            label.set_value(v);
+                            label
+                        })
+                        .collect(),
+                );
+
+                if let Some(f) = out.iter_mut().find(|f| f.get_name() == name) {
+                    f.mut_metric().push(metric);
+                } else {
+                    let mut f = family.clone();
+                    f.set_name(name);
+                    f.mut_metric().push(metric);
+                    out.push(f);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// [`prometheus::core::Collector`] applying relabeling [`Rules`] to the
+/// metrics collected by the wrapped one.
+#[derive(Clone, Debug)]
+pub struct Collector<C> {
+    /// Wrapped [`prometheus::core::Collector`].
+    collector: C,
+
+    /// Relabeling [`Rules`] to apply.
+    rules: Rules,
+}
+
+impl<C> Collector<C> {
+    /// Wraps the provided [`prometheus::core::Collector`] to apply the
+    /// provided relabeling [`Rules`] to its metrics.
+    #[must_use]
+    pub const fn new(collector: C, rules: Rules) -> Self {
+        Self { collector, rules }
+    }
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl<C> prometheus::core::Collector for Collector<C>
+where
+    C: prometheus::core::Collector,
+{
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        self.collector.desc()
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.rules.apply(self.collector.collect())
+    }
+}
@@ -1,10 +1,21 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! Immutable storage of [`metric::Describable`].
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
 
 use sealed::sealed;
 
-use crate::{metric, Metric};
+#[cfg(feature = "relabel")]
+use crate::relabel;
+use crate::{
+    catalog, config, deprecation, matcher, metric, recorder::IntoCow, target,
+    Metric,
+};
 
 use super::KeyName;
 
@@ -14,6 +25,19 @@ use super::KeyName;
 /// [`Describable`]: metric::Describable
 pub type Collection<M> = HashMap<KeyName, metric::Describable<M>>;
 
+/// Memoized single [`prometheus`] `M`etrics resolved out of a [`Collection`],
+/// identified by their [`metrics::Key`]s.
+///
+/// Caches only the successfully resolved [`Metric`]s (up to the
+/// [`MEMO_CAPACITY`]), so resolving the same [`metrics::Key`] again is a
+/// single lookup, while the failing or excessive [`metrics::Key`]s don't grow
+/// it.
+pub type Memo<M> = RwLock<HashMap<metrics::Key, Metric<M>>>;
+
+/// Maximum number of [`Metric`]s memoized in a single [`Memo`], after which the
+/// new [`metrics::Key`]s are resolved on every call.
+pub const MEMO_CAPACITY: usize = 10_000;
+
 /// Snapshot of a [`mutable::Storage`], that is not capable of registering
 /// metrics in a [`prometheus::Registry`] on the fly.
 ///
@@ -30,6 +54,10 @@ pub type Collection<M> = HashMap<KeyName, metric::Describable<M>>;
 /// [`help` description]: prometheus::proto::MetricFamily::get_help
 #[derive(Debug)]
 pub struct Storage {
+    /// [`prometheus::Registry`] the metrics of this immutable [`Storage`] are
+    /// registered in.
+    pub(crate) prometheus: Arc<prometheus::Registry>,
+
     /// [`Collection`] of [`prometheus::IntCounter`] metrics registered in this
     /// immutable [`Storage`].
     counters: Collection<metric::PrometheusIntCounter>,
@@ -41,6 +69,80 @@ pub struct Storage {
     /// [`Collection`] of [`prometheus::Histogram`] metrics registered in this
     /// immutable [`Storage`].
     histograms: Collection<metric::PrometheusHistogram>,
+
+    /// [`metric::adaptive::Histogram`]s registered in this immutable
+    /// [`Storage`].
+    adaptive_histograms: Collection<metric::adaptive::Histogram>,
+
+    /// [`metric::CustomBundle`]s registered in this immutable [`Storage`], by
+    /// the names of all their metrics.
+    custom: HashMap<KeyName, metric::custom::Shared>,
+
+    /// [`metric::threshold::Family`] counters accompanying the histograms of
+    /// this immutable [`Storage`], by the names of the histograms.
+    threshold_families: HashMap<KeyName, metric::threshold::Family>,
+
+    /// [`Memo`] of the [`prometheus::IntCounter`]s resolved out of the
+    /// `counters` [`Collection`].
+    counters_memo: Memo<prometheus::IntCounter>,
+
+    /// [`Memo`] of the [`prometheus::Gauge`]s resolved out of the `gauges`
+    /// [`Collection`].
+    gauges_memo: Memo<prometheus::Gauge>,
+
+    /// [`Memo`] of the [`prometheus::Histogram`]s resolved out of the
+    /// `histograms` [`Collection`].
+    histograms_memo: Memo<prometheus::Histogram>,
+
+    /// Sibling [`prometheus::Registry`]s merged into the rendered metrics of
+    /// this immutable [`Storage`].
+    pub(crate) merged: super::Merged,
+
+    /// Indicator whether [`prometheus::IntCounter`]s are guarded against
+    /// regressions and being used as non-monotonic metrics.
+    pub(crate) monotonic: bool,
+
+    /// [`matcher::Rules`] of the declared [`metric::ingestion::Mode`]s of the
+    /// [`prometheus::IntCounter`]s.
+    pub(crate) ingestion: Arc<matcher::Rules<metric::ingestion::Mode>>,
+
+    /// [`metric::ingestion::Totals`] of the [`prometheus::IntCounter`]s fed in
+    /// the [`metric::ingestion::Mode::Absolute`].
+    pub(crate) totals: metric::ingestion::Totals,
+
+    /// [`matcher::Rules`] of the [`target::Prefix`]es of the metric names, by
+    /// the names of the crates registering them.
+    pub(crate) target_prefixes: Arc<target::Prefixes>,
+
+    /// [`matcher::Rules`] of the [`prometheus::IntCounter`]s setting their
+    /// absolute values by resetting.
+    ///
+    /// [`Metric::with_resetting_absolute()`] is applied to them.
+    pub(crate) resetting: Arc<matcher::Rules<()>>,
+
+    /// [`config::Reloadable`] configuration of the metrics accessed via this
+    /// immutable [`Storage`].
+    pub(crate) config: config::Reloadable,
+
+    /// [`unknown::Tracker`] of the metrics accessed via this immutable
+    /// [`Storage`], but not stored in it, if enabled.
+    ///
+    /// [`unknown::Tracker`]: super::unknown::Tracker
+    unknown: Option<Arc<super::unknown::Tracker>>,
+
+    /// [`deprecation::Tracker`] routing the deprecated metric names to their
+    /// replacements, if any.
+    deprecated: Option<Arc<deprecation::Tracker>>,
+
+    /// [`relabel::Rules`] applied to the metrics registered in this
+    /// immutable [`Storage`] at gather time.
+    #[cfg(feature = "relabel")]
+    pub(crate) relabel: relabel::Rules,
+
+    /// [`metric::timestamp::Timestamps`] of the metrics registered in this
+    /// immutable [`Storage`].
+    #[cfg(feature = "timestamps")]
+    pub(crate) timestamps: metric::timestamp::Timestamps,
 }
 
 #[sealed]
@@ -50,6 +152,15 @@ impl super::Get<Collection<metric::PrometheusIntCounter>> for Storage {
     }
 }
 
+#[sealed]
+impl super::GetMut<Collection<metric::PrometheusIntCounter>> for Storage {
+    fn collection_mut(
+        &mut self,
+    ) -> &mut Collection<metric::PrometheusIntCounter> {
+        &mut self.counters
+    }
+}
+
 #[sealed]
 impl super::Get<Collection<metric::PrometheusGauge>> for Storage {
     fn collection(&self) -> &Collection<metric::PrometheusGauge> {
@@ -57,6 +168,13 @@ impl super::Get<Collection<metric::PrometheusGauge>> for Storage {
     }
 }
 
+#[sealed]
+impl super::GetMut<Collection<metric::PrometheusGauge>> for Storage {
+    fn collection_mut(&mut self) -> &mut Collection<metric::PrometheusGauge> {
+        &mut self.gauges
+    }
+}
+
 #[sealed]
 impl super::Get<Collection<metric::PrometheusHistogram>> for Storage {
     fn collection(&self) -> &Collection<metric::PrometheusHistogram> {
@@ -64,6 +182,36 @@ impl super::Get<Collection<metric::PrometheusHistogram>> for Storage {
     }
 }
 
+#[sealed]
+impl super::GetMut<Collection<metric::PrometheusHistogram>> for Storage {
+    fn collection_mut(
+        &mut self,
+    ) -> &mut Collection<metric::PrometheusHistogram> {
+        &mut self.histograms
+    }
+}
+
+#[sealed]
+impl super::Get<Memo<prometheus::IntCounter>> for Storage {
+    fn collection(&self) -> &Memo<prometheus::IntCounter> {
+        &self.counters_memo
+    }
+}
+
+#[sealed]
+impl super::Get<Memo<prometheus::Gauge>> for Storage {
+    fn collection(&self) -> &Memo<prometheus::Gauge> {
+        &self.gauges_memo
+    }
+}
+
+#[sealed]
+impl super::Get<Memo<prometheus::Histogram>> for Storage {
+    fn collection(&self) -> &Memo<prometheus::Histogram> {
+        &self.histograms_memo
+    }
+}
+
 impl Storage {
     /// Changes the [`help` description] of the [`prometheus`] `M`etric
     /// identified by its `name`. No-op if this immutable [`Storage`] doesn't
@@ -79,7 +227,7 @@ impl Storage {
     /// [`metrics::Recorder::describe_histogram()`] implementations.
     ///
     /// [`help` description]: prometheus::proto::MetricFamily::get_help
-    pub fn describe<M>(&self, name: &str, description: String)
+    pub fn describe<M>(&self, name: &str, description: metrics::SharedString)
     where
         M: metric::Bundled,
         <M as metric::Bundled>::Bundle: metric::Bundle,
@@ -89,7 +237,143 @@ impl Storage {
 
         if let Some(bundle) = self.collection().get(name) {
             bundle.description.store(Arc::new(description));
-        };
+        }
+    }
+
+    /// Changes the [`help` description] of the
+    /// [`metric::adaptive::Histogram`] identified by its `name`. No-op if this
+    /// immutable [`Storage`] doesn't contain it.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub fn describe_adaptive_histogram(
+        &self,
+        name: &str,
+        description: metrics::SharedString,
+    ) {
+        if let Some(histogram) = self.adaptive_histograms.get(name) {
+            histogram.description.store(Arc::new(description));
+        }
+    }
+
+    /// Applies the provided [`pending::Description`] to the metric family it
+    /// describes, if it's stored in this immutable [`Storage`].
+    ///
+    /// [`pending::Description`]: super::pending::Description
+    pub(crate) fn describe_pending(
+        &self,
+        pending: super::pending::Description,
+    ) {
+        use super::pending::Kind;
+
+        let super::pending::Description { kind, name, description, .. } =
+            pending;
+        let name = self.replace_deprecated(name);
+        match kind {
+            Kind::Counter => self
+                .describe::<prometheus::IntCounter>(name.as_str(), description),
+            Kind::Gauge => {
+                self.describe::<prometheus::Gauge>(name.as_str(), description);
+            }
+            Kind::Histogram => {
+                self.describe_adaptive_histogram(
+                    name.as_str(),
+                    description.clone(),
+                );
+                self.describe::<prometheus::Histogram>(
+                    name.as_str(),
+                    description,
+                );
+            }
+        }
+    }
+
+    /// Returns the [`metric::CustomBundle`] registered in this immutable
+    /// [`Storage`] for the metric identified by its `name`, if any.
+    #[must_use]
+    pub fn custom(&self, name: &str) -> Option<&metric::custom::Shared> {
+        self.custom.get(name)
+    }
+
+    /// Sets the provided [`Callback`] refreshing the value of the
+    /// [`prometheus::Gauge`] identified by the provided [`metrics::Key`] right
+    /// before it's gathered, replacing the previous one (if any).
+    ///
+    /// # Errors
+    ///
+    /// - If the [`prometheus::Gauge`] family is not stored in this immutable
+    ///   [`Storage`] (wasn't registered before freezing).
+    /// - If the [`prometheus::Gauge`] cannot be resolved out of its family.
+    ///
+    /// [`Callback`]: metric::callback::Callback
+    pub fn set_collect_callback(
+        &self,
+        key: &metrics::Key,
+        callback: metric::callback::Callback,
+    ) -> prometheus::Result<()> {
+        let gauge =
+            self.get_metric::<prometheus::Gauge>(key).ok_or_else(|| {
+                prometheus::Error::Msg(format!(
+                "`{}` gauge is not registered before freezing, so cannot have \
+                 a collect callback",
+                key.name(),
+            ))
+            })??;
+        let key = self.config.load().validated(key)?;
+        if let Some(family) = self.gauges.get(key.name()) {
+            family.callbacks.set(&key, gauge.as_ref().clone(), callback);
+        }
+        Ok(())
+    }
+
+    /// Returns a [`metric::threshold::Child`] counting the observations
+    /// exceeding the thresholds of the histogram identified by the provided
+    /// [`metrics::Key`], if it's accompanied by a [`metric::threshold::Family`]
+    /// in this immutable [`Storage`].
+    #[must_use]
+    pub fn over_threshold(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<Arc<metric::threshold::Child>> {
+        self.threshold_families.get(key.name()).map(|f| f.child(key))
+    }
+
+    /// Returns a [`metric::adaptive::Child`] stored in this immutable
+    /// [`Storage`] and identified by the provided [`metrics::Key`].
+    ///
+    /// Intended to be used in [`metrics::Recorder::register_histogram()`]
+    /// implementations.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided [`metrics::Key`] is rejected by the
+    ///   [`config::Config`].
+    /// - If the identified [`metric::adaptive::Histogram`] doesn't comply with
+    ///   the labeling of the provided [`metrics::Key`].
+    #[must_use]
+    pub fn adaptive_histogram(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<prometheus::Result<Arc<metric::adaptive::Child>>> {
+        let histogram = self.adaptive_histograms.get(key.name())?;
+        Some(
+            self.config
+                .load()
+                .validated(key)
+                .and_then(|key| histogram.metric.child(&key)),
+        )
+    }
+
+    /// Returns a [`prometheus::Error`] if the provided `name` identifies a
+    /// [`prometheus::IntCounter`] stored in this immutable [`Storage`], while
+    /// its regressions are guarded.
+    ///
+    /// Intended to be used in [`metrics::Recorder::register_gauge()`] and
+    /// [`metrics::Recorder::register_histogram()`] implementations, for
+    /// detecting counters being used as non-monotonic metrics.
+    #[must_use]
+    pub fn counter_misuse(&self, name: &str) -> Option<prometheus::Error> {
+        (self.monotonic && self.counters.contains_key(name))
+            .then(|| metric::monotonic::misuse(name))
     }
 
     /// Returns a [`prometheus`] `M`etric stored in this immutable [`Storage`]
@@ -104,66 +388,727 @@ impl Storage {
     /// [`metrics::Recorder::register_gauge()`] and
     /// [`metrics::Recorder::register_histogram()`] implementations.
     ///
+    /// The provided [`metrics::Key`] is validated and sanitized according to
+    /// the [`config::Config`] the same way the [`mutable::Storage`] does, and
+    /// the [`config::Config::default_labels`] declared by the identified
+    /// [`prometheus`] `M`etric family, but missing in it, are added to it
+    /// beforehand.
+    ///
+    /// The resolved [`Metric`] is [`Memo`]ized per [`metrics::Key`] (up to the
+    /// [`MEMO_CAPACITY`]), so the labels validation and the child
+    /// [`prometheus`] metric creation happen only once for every
+    /// [`metrics::Key`]. Errors are not memoized.
+    ///
     /// # Errors
     ///
-    /// If the identified [`prometheus`] `M`etric doesn't comply with the
-    /// labeling of the provided [`metrics::Key`].
+    /// - If the provided [`metrics::Key`] is rejected by the
+    ///   [`config::Config`].
+    /// - If the identified [`prometheus`] `M`etric doesn't comply with the
+    ///   labeling of the provided [`metrics::Key`].
+    ///
+    /// [`mutable::Storage`]: super::mutable::Storage
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
     pub fn get_metric<M>(
         &self,
         key: &metrics::Key,
     ) -> Option<Result<Metric<M>, prometheus::Error>>
     where
         M: metric::Bundled,
-        <M as metric::Bundled>::Bundle: metric::Bundle<Single = M>,
-        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+        <M as metric::Bundled>::Bundle:
+            metric::Bundle<Single = M> + prometheus::core::Collector,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>
+            + super::Get<Memo<M>>,
     {
         use super::Get as _;
         use metric::Bundle as _;
 
-        self.collection().get(key.name()).map(|bundle| {
-            bundle.metric.get_single_metric(key).map(Metric::wrap)
-        })
+        let collection: &Collection<_> = self.collection();
+        let bundle = collection.get(key.name())?;
+
+        let config = self.config.load();
+        let key = match config.validated(key) {
+            Ok(key) => key,
+            Err(e) => return Some(Err(e)),
+        };
+        let key = config.defaulted(&key, || {
+            Some(
+                prometheus::core::Collector::desc(&bundle.metric)
+                    .first()
+                    .map(|d| d.variable_labels.clone())
+                    .unwrap_or_default(),
+            )
+        });
+        let key = &*key;
+
+        let memo: &Memo<M> = self.collection();
+        if let Some(memoized) = memo.read().unwrap().get(key) {
+            return Some(Ok(memoized.clone()));
+        }
+
+        let resetting = self.resetting.find(key.name()).is_some();
+        let metric = bundle.metric.get_single_metric(key).map(|m| {
+            let m = Metric::wrap(m);
+            if resetting {
+                m.with_resetting_absolute()
+            } else if <M as metric::Bundled>::ABSOLUTE {
+                // Memoized `Metric`s are cloned, so must share the shadow.
+                m.with_shadow()
+            } else {
+                m
+            }
+        });
+        #[cfg(feature = "timestamps")]
+        let metric = metric
+            .map(|m| m.with_last_updated(self.timestamps.get_or_create(key)));
+        let metric = match metric {
+            Ok(m) => m,
+            Err(e) => return Some(Err(e)),
+        };
+        let mut memo = memo.write().unwrap();
+        if memo.len() >= MEMO_CAPACITY && !memo.contains_key(key) {
+            return Some(Ok(metric));
+        }
+        Some(Ok(memo.entry(key.clone()).or_insert(metric).clone()))
     }
-}
 
-#[expect( // intentional
-    clippy::fallible_impl_from,
-    reason = "`RwLock` usage is fully panic-safe inside, so the `impl` is \
-              infallible, in fact"
-)]
-impl From<&super::mutable::Storage> for Storage {
-    /// Creates a new immutable [`Storage`] by [draining] the referred
-    /// [`mutable::Storage`] and leaving it empty.
+    /// Creates a new immutable [`Storage`] by moving only the metric families
+    /// identified by the provided `names` out of the referred
+    /// [`mutable::Storage`], leaving the rest of them there.
+    ///
+    /// [`mutable::Storage`]: super::mutable::Storage
+    // TODO: Try remove on Rust 1.84 upgrade.
+    #[expect( // anonymous lifetimes in `impl Trait` are unstable
+        single_use_lifetimes,
+        reason = "anonymous lifetimes in `impl Trait` are unstable"
+    )]
+    #[must_use]
+    pub fn from_names<'n>(
+        mutable: &super::mutable::Storage,
+        names: impl IntoIterator<Item = &'n str>,
+    ) -> Self {
+        let names = names.into_iter().collect::<HashSet<_>>();
+        Self::drained(mutable, |name| names.contains(name))
+    }
+
+    /// Creates a new immutable [`Storage`] by moving the metric families
+    /// with the names satisfying the provided `matches` predicate out of the
+    /// referred [`mutable::Storage`].
     ///
     /// [`mutable::Storage`]: super::mutable::Storage
-    /// [draining]: HashMap::drain
     #[expect( // intentional
         clippy::unwrap_used,
         reason = "`RwLock` usage is fully panic-safe here"
     )]
-    fn from(mutable: &super::mutable::Storage) -> Self {
+    pub(crate) fn drained(
+        mutable: &super::mutable::Storage,
+        matches: impl Fn(&str) -> bool,
+    ) -> Self {
+        mutable.flush_descriptions();
         Self {
-            counters: mutable
-                .counters
-                .write()
-                .unwrap()
-                .drain()
+            prometheus: Arc::clone(&mutable.prometheus),
+            counters: take(&mut mutable.counters.write().unwrap(), &matches)
                 .filter_map(|(name, bundle)| Some((name, bundle.transpose()?)))
                 .collect(),
-            gauges: mutable
-                .gauges
-                .write()
-                .unwrap()
-                .drain()
+            gauges: take(&mut mutable.gauges.write().unwrap(), &matches)
                 .filter_map(|(name, bundle)| Some((name, bundle.transpose()?)))
                 .collect(),
-            histograms: mutable
-                .histograms
-                .write()
-                .unwrap()
-                .drain()
-                .filter_map(|(name, bundle)| Some((name, bundle.transpose()?)))
+            histograms: take(
+                &mut mutable.histograms.write().unwrap(),
+                &matches,
+            )
+            .filter_map(|(name, bundle)| Some((name, bundle.transpose()?)))
+            .collect(),
+            adaptive_histograms: take(
+                &mut mutable.adaptive_histograms.write().unwrap(),
+                &matches,
+            )
+            .collect(),
+            custom: take(&mut mutable.custom.write().unwrap(), &matches)
                 .collect(),
+            threshold_families: take(
+                &mut mutable.threshold_families.write().unwrap(),
+                &matches,
+            )
+            .collect(),
+            counters_memo: Memo::default(),
+            gauges_memo: Memo::default(),
+            histograms_memo: Memo::default(),
+            merged: mutable.merged.clone(),
+            monotonic: mutable.monotonic,
+            ingestion: Arc::clone(&mutable.ingestion),
+            totals: mutable.totals.clone(),
+            target_prefixes: Arc::clone(&mutable.target_prefixes),
+            resetting: Arc::clone(&mutable.resetting),
+            config: mutable.config.clone(),
+            unknown: mutable.unknown.clone(),
+            deprecated: mutable.deprecated.clone(),
+            #[cfg(feature = "relabel")]
+            relabel: mutable.relabel.clone(),
+            #[cfg(feature = "timestamps")]
+            timestamps: mutable.timestamps.clone(),
+        }
+    }
+
+    /// Creates a new immutable [`Storage`] containing the metrics of both this
+    /// and the `other` immutable [`Storage`]s (the `other` ones take
+    /// precedence on names collision).
+    ///
+    /// The configuration (like [`prometheus::Registry`]) is taken from this
+    /// immutable [`Storage`].
+    #[must_use]
+    pub fn merged(&self, other: &Self) -> Self {
+        Self {
+            prometheus: Arc::clone(&self.prometheus),
+            counters: merge(&self.counters, &other.counters),
+            gauges: merge(&self.gauges, &other.gauges),
+            histograms: merge(&self.histograms, &other.histograms),
+            adaptive_histograms: merge(
+                &self.adaptive_histograms,
+                &other.adaptive_histograms,
+            ),
+            custom: merge(&self.custom, &other.custom),
+            threshold_families: merge(
+                &self.threshold_families,
+                &other.threshold_families,
+            ),
+            counters_memo: Memo::default(),
+            gauges_memo: Memo::default(),
+            histograms_memo: Memo::default(),
+            merged: self.merged.clone(),
+            monotonic: self.monotonic,
+            ingestion: Arc::clone(&self.ingestion),
+            totals: self.totals.clone(),
+            target_prefixes: Arc::clone(&self.target_prefixes),
+            resetting: Arc::clone(&self.resetting),
+            config: self.config.clone(),
+            unknown: self.unknown.clone(),
+            deprecated: self.deprecated.clone(),
+            #[cfg(feature = "relabel")]
+            relabel: self.relabel.clone(),
+            #[cfg(feature = "timestamps")]
+            timestamps: self.timestamps.clone(),
         }
     }
+
+    /// Indicates whether the metric family identified by the provided `name`
+    /// is denied by the current [`config::Config`], so should be silently
+    /// no-op.
+    #[must_use]
+    pub fn is_denied(&self, name: &str) -> bool {
+        self.config.load().is_denied(name)
+    }
+
+    /// Routes the provided [`metrics::Key`] to its replacement, if its name is
+    /// deprecated, tracking this usage according to its
+    /// [`deprecation::Policy`].
+    ///
+    /// Returns [`None`] if the name of the provided [`metrics::Key`] is not
+    /// deprecated.
+    ///
+    /// # Errors
+    ///
+    /// If the counter of the deprecated metrics usages cannot be registered in
+    /// the underlying [`prometheus::Registry`]. The replacement
+    /// [`metrics::Key`] is still returned in such case.
+    pub(crate) fn route_deprecated(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<(metrics::Key, prometheus::Result<()>)> {
+        self.deprecated.as_ref()?.route(key, &self.prometheus)
+    }
+
+    /// Returns the replacement of the provided metric `name`, if it's
+    /// deprecated, or the provided `name` as is otherwise.
+    pub(crate) fn replace_deprecated(
+        &self,
+        name: metrics::KeyName,
+    ) -> metrics::KeyName {
+        self.deprecated
+            .as_ref()
+            .and_then(|d| d.replacement(name.as_str()))
+            .map_or(name, |n| n.to_owned().into())
+    }
+
+    /// Returns the numbers of usages of the deprecated metric names, by these
+    /// names.
+    #[must_use]
+    pub fn deprecated_usages(&self) -> BTreeMap<String, u64> {
+        self.deprecated.as_ref().map(|d| d.usages()).unwrap_or_default()
+    }
+
+    /// Prefixes the name of the provided [`metrics::Key`] according to the
+    /// [`target::Prefix`] rule matching the crate of the provided `target`
+    /// (as reported by [`metrics::Metadata::target()`]), if any.
+    pub(crate) fn prefixed<'k>(
+        &self,
+        key: &'k metrics::Key,
+        target: &str,
+    ) -> Cow<'k, metrics::Key> {
+        self.target_prefixes.prefixed(key, target)
+    }
+
+    /// Indicates whether a metric family identified by the provided `name` is
+    /// stored in this immutable [`Storage`].
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.counters.contains_key(name)
+            || self.gauges.contains_key(name)
+            || self.histograms.contains_key(name)
+            || self.adaptive_histograms.contains_key(name)
+            || self.custom.contains_key(name)
+    }
+
+    /// Tracks an access to the metric family identified by the provided
+    /// `name`, if it's not stored in this immutable [`Storage`], and tracking
+    /// of unknown metrics is enabled.
+    ///
+    /// # Errors
+    ///
+    /// If the counter of the tracked unknown metrics cannot be registered in
+    /// the [`prometheus::Registry`].
+    pub(crate) fn track_unknown(&self, name: &str) -> prometheus::Result<()> {
+        match &self.unknown {
+            Some(unknown) if !self.contains(name) => {
+                unknown.track(name, &self.prometheus)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the numbers of accesses to the metrics not stored in this
+    /// immutable [`Storage`], by their names, if tracking of unknown metrics
+    /// is enabled.
+    #[must_use]
+    pub fn unknown_keys(&self) -> BTreeMap<String, u64> {
+        self.unknown.as_ref().map(|u| u.counts()).unwrap_or_default()
+    }
+
+    /// Returns the [`catalog::Snapshot`] of the [`prometheus::IntCounter`],
+    /// [`prometheus::Gauge`] and [`prometheus::Histogram`] metric families
+    /// stored in this immutable [`Storage`].
+    ///
+    /// [`metric::adaptive::Histogram`]s and [`metric::CustomBundle`]s are not
+    /// included.
+    #[must_use]
+    pub fn catalog(&self) -> catalog::Snapshot {
+        fn families<S, V>(
+            collection: &Collection<metric::bundle::Either<S, V>>,
+            kind: prometheus::proto::MetricType,
+            out: &mut Vec<catalog::Family>,
+        ) where
+            metric::bundle::Either<S, V>: prometheus::core::Collector,
+        {
+            out.extend(collection.values().map(|entry| {
+                catalog::Family::of_bundle(
+                    &entry.metric,
+                    &entry.description.load(),
+                    kind,
+                    entry.buckets.as_deref(),
+                )
+            }));
+        }
+
+        use prometheus::proto::MetricType as T;
+
+        let mut out = vec![];
+        families(&self.counters, T::COUNTER, &mut out);
+        families(&self.gauges, T::GAUGE, &mut out);
+        families(&self.histograms, T::HISTOGRAM, &mut out);
+        out.sort_by(|a, b| a.schema.name.cmp(&b.schema.name));
+        catalog::Snapshot { families: out }
+    }
+
+    /// Returns [`Counts`] of the metric families stored in this immutable
+    /// [`Storage`].
+    ///
+    /// [`Counts`]: super::Counts
+    #[must_use]
+    pub fn metric_counts(&self) -> super::Counts {
+        super::Counts {
+            counters: self.counters.len(),
+            gauges: self.gauges.len(),
+            histograms: self.histograms.len() + self.adaptive_histograms.len(),
+        }
+    }
+
+    /// Unregisters all the [`prometheus`] metrics of this immutable [`Storage`]
+    /// from its [`prometheus::Registry`].
+    ///
+    /// The metrics remain usable, but are not exported anymore.
+    #[expect( // intentional
+        clippy::iter_over_hash_type,
+        reason = "order of unregistering doesn't matter"
+    )]
+    pub fn unregister_all(&self) {
+        unregister(&self.prometheus, &self.counters);
+        unregister(&self.prometheus, &self.gauges);
+        unregister(&self.prometheus, &self.histograms);
+        unregister(&self.prometheus, &self.adaptive_histograms);
+        // Bundles are stored under multiple names, so unregistering the same
+        // one repeatedly just fails silently.
+        for bundle in self.custom.values() {
+            drop(self.prometheus.unregister(Box::new(bundle.clone())));
+        }
+        for family in self.threshold_families.values() {
+            drop(self.prometheus.unregister(Box::new(family.clone())));
+        }
+        if let Some(unknown) = &self.unknown {
+            unknown.unregister(&self.prometheus);
+        }
+        if let Some(deprecated) = &self.deprecated {
+            deprecated.unregister(&self.prometheus);
+        }
+    }
+
+    /// Returns all the [`prometheus`] metrics of this immutable [`Storage`],
+    /// prepared for registering in a [`prometheus::Registry`] with the
+    /// configured relabeling rules applied, if any.
+    #[expect( // intentional
+        clippy::iter_over_hash_type,
+        reason = "order of collecting doesn't matter"
+    )]
+    fn collectors(&self) -> Vec<Arc<dyn prometheus::core::Collector>> {
+        fn bundles<M>(
+            collection: &Collection<M>,
+            out: &mut Vec<Arc<dyn prometheus::core::Collector>>,
+        ) where
+            M: prometheus::core::Collector + Clone + 'static,
+        {
+            for entry in collection.values() {
+                out.push(Arc::new(entry.clone()));
+            }
+        }
+
+        let mut out = Vec::<Arc<dyn prometheus::core::Collector>>::new();
+        bundles(&self.counters, &mut out);
+        bundles(&self.gauges, &mut out);
+        bundles(&self.histograms, &mut out);
+        bundles(&self.adaptive_histograms, &mut out);
+        // Bundles are stored under multiple names, so should be deduplicated.
+        let mut seen = HashSet::new();
+        for bundle in self.custom.values() {
+            let name = prometheus::core::Collector::desc(bundle)
+                .first()
+                .map(|d| d.fq_name.clone());
+            if seen.insert(name) {
+                out.push(Arc::new(bundle.clone()));
+            }
+        }
+        for family in self.threshold_families.values() {
+            out.push(Arc::new(family.clone()));
+        }
+        #[cfg(feature = "relabel")]
+        let out = out
+            .into_iter()
+            .map(|c| -> Arc<dyn prometheus::core::Collector> {
+                Arc::new(relabel::Collector::new(
+                    super::Shared(c),
+                    self.relabel.clone(),
+                ))
+            })
+            .collect();
+        out
+    }
+}
+
+/// Unregisters all the metrics of the provided [`Collection`] from the provided
+/// [`prometheus::Registry`].
+#[expect( // intentional
+    clippy::iter_over_hash_type,
+    reason = "order of unregistering doesn't matter"
+)]
+fn unregister<M>(registry: &prometheus::Registry, collection: &Collection<M>)
+where
+    M: prometheus::core::Collector + Clone + 'static,
+{
+    for entry in collection.values() {
+        drop(registry.unregister(Box::new(entry.metric.clone())));
+    }
+}
+
+/// **Deprecated**: use the [`mutable::Storage::into_immutable()`] method
+/// instead, as draining the referred [`mutable::Storage`] leaves it empty
+/// while its metrics are still registered in the [`prometheus::Registry`], so
+/// its subsequent use may re-register duplicates.
+///
+/// [`mutable::Storage`]: super::mutable::Storage
+/// [`mutable::Storage::into_immutable()`]:
+///     super::mutable::Storage::into_immutable
+impl From<&super::mutable::Storage> for Storage {
+    /// Creates a new immutable [`Storage`] by [draining] the referred
+    /// [`mutable::Storage`] and leaving it empty.
+    ///
+    /// [`mutable::Storage`]: super::mutable::Storage
+    /// [draining]: HashMap::drain
+    fn from(mutable: &super::mutable::Storage) -> Self {
+        Self::drained(mutable, |_| true)
+    }
+}
+
+/// Merges the provided [`HashMap`]s into a new one (the entries of `b` take
+/// precedence on keys collision).
+fn merge<V: Clone>(
+    a: &HashMap<KeyName, V>,
+    b: &HashMap<KeyName, V>,
+) -> HashMap<KeyName, V> {
+    a.iter().chain(b).map(|(n, v)| (n.clone(), v.clone())).collect()
+}
+
+/// Takes the entries with the keys satisfying the provided `matches` predicate
+/// out of the provided [`HashMap`].
+fn take<V>(
+    map: &mut HashMap<KeyName, V>,
+    matches: &impl Fn(&str) -> bool,
+) -> impl Iterator<Item = (KeyName, V)> {
+    let names = map.keys().filter(|n| matches(n)).cloned().collect::<Vec<_>>();
+    names
+        .into_iter()
+        .filter_map(|n| map.remove_entry(&n))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Builder of an immutable [`Storage`] directly out of [`prometheus`] metrics,
+/// without registering them on the fly via a [`mutable::Storage`].
+///
+/// Useful for embedding a [`FrozenRecorder`] in constrained environments,
+/// where the set of metrics is known upfront.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::{
+///     failure::strategy, storage::immutable, FrozenRecorder,
+/// };
+///
+/// let registry = prometheus::Registry::new();
+/// let storage = immutable::Builder::new()
+///     .with_registry(&registry)
+///     .try_with_metric(prometheus::IntCounter::new("requests", "help")?)?
+///     .try_with_metric(prometheus::GaugeVec::new(
+///         prometheus::Opts::new("queue", "help"),
+///         &["name"],
+///     )?)?
+///     .build();
+/// let recorder = FrozenRecorder::from_storage(storage, strategy::NoOp);
+///
+/// metrics::with_local_recorder(&recorder, || {
+///     metrics::counter!("requests").increment(1);
+///     metrics::gauge!("queue", "name" => "jobs").set(3.0);
+///     // This is ignored, as is not registered upfront.
+///     metrics::gauge!("another").set(1.0);
+/// });
+///
+/// let report =
+///     prometheus::TextEncoder::new().encode_to_string(&registry.gather())?;
+/// assert_eq!(
+///     report.trim(),
+///     r#"
+/// ## HELP queue help
+/// ## TYPE queue gauge
+/// queue{name="jobs"} 3
+/// ## HELP requests help
+/// ## TYPE requests counter
+/// requests 1
+///     "#
+///     .trim(),
+/// );
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`FrozenRecorder`]: crate::FrozenRecorder
+/// [`mutable::Storage`]: super::Mutable
+#[derive(Debug)]
+pub struct Builder {
+    /// Immutable [`Storage`] being built.
+    storage: Storage,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builder {
+    /// Starts building a new immutable [`Storage`] on top of a new empty
+    /// [`prometheus::Registry`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            storage: Storage {
+                prometheus: Arc::new(prometheus::Registry::new()),
+                counters: Collection::default(),
+                gauges: Collection::default(),
+                histograms: Collection::default(),
+                adaptive_histograms: Collection::default(),
+                custom: HashMap::new(),
+                threshold_families: HashMap::new(),
+                counters_memo: Memo::default(),
+                gauges_memo: Memo::default(),
+                histograms_memo: Memo::default(),
+                merged: super::Merged::default(),
+                monotonic: false,
+                ingestion: Arc::default(),
+                totals: metric::ingestion::Totals::default(),
+                target_prefixes: Arc::default(),
+                resetting: Arc::default(),
+                config: config::Reloadable::default(),
+                unknown: None,
+                deprecated: None,
+                #[cfg(feature = "relabel")]
+                relabel: relabel::Rules::default(),
+                #[cfg(feature = "timestamps")]
+                timestamps: metric::timestamp::Timestamps::default(),
+            },
+        }
+    }
+
+    /// Sets the provided [`prometheus::Registry`] to register the metrics of
+    /// the built immutable [`Storage`] in.
+    ///
+    /// The metrics registered already are moved into the provided
+    /// [`prometheus::Registry`].
+    ///
+    /// # Panics
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register the metrics
+    /// registered already.
+    #[expect( // intentional
+        single_use_lifetimes,
+        reason = "anonymous lifetimes in `impl Trait` are unstable"
+    )]
+    #[must_use]
+    pub fn with_registry<'r>(
+        self,
+        registry: impl IntoCow<'r, prometheus::Registry>,
+    ) -> Self {
+        self.try_with_registry(registry).unwrap_or_else(|e| {
+            panic!("failed to register `prometheus` metric: {e}")
+        })
+    }
+
+    /// Tries to set the provided [`prometheus::Registry`] to register the
+    /// metrics of the built immutable [`Storage`] in.
+    ///
+    /// The metrics registered already are moved into the provided
+    /// [`prometheus::Registry`].
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register the metrics
+    /// registered already. In such case, nothing is moved and the previous
+    /// [`prometheus::Registry`] is kept.
+    #[expect( // intentional
+        single_use_lifetimes,
+        reason = "anonymous lifetimes in `impl Trait` are unstable"
+    )]
+    pub fn try_with_registry<'r>(
+        mut self,
+        registry: impl IntoCow<'r, prometheus::Registry>,
+    ) -> prometheus::Result<Self> {
+        let registry = Arc::new(registry.into_cow().into_owned());
+        if !super::is_same_registry(&self.storage.prometheus, &registry) {
+            super::move_collectors(
+                &self.storage.collectors(),
+                &self.storage.prometheus,
+                &registry,
+            )?;
+        }
+        self.storage.prometheus = registry;
+        Ok(self)
+    }
+
+    /// Tries to register the provided [`prometheus`] `metric` in the
+    /// underlying [`prometheus::Registry`] in the way making it usable via the
+    /// built immutable [`Storage`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// Accepts only the following [`prometheus`] metrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    ///
+    /// If a [`prometheus`] metric with the identical schema is registered
+    /// already, then it's reused instead, and the provided `metric` is not
+    /// registered at all.
+    ///
+    /// # Errors
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the
+    /// provided `metric`.
+    pub fn try_with_metric<M>(mut self, metric: M) -> prometheus::Result<Self>
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        Storage: super::GetMut<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        use super::GetMut as _;
+        use prometheus::core::Collector as _;
+
+        let name = metric
+            .desc()
+            .first()
+            .map(|d| d.fq_name.clone())
+            .unwrap_or_default();
+        let bundle = metric.into_bundle();
+
+        let prometheus = Arc::clone(&self.storage.prometheus);
+        #[cfg(feature = "relabel")]
+        let rules = self.storage.relabel.clone();
+        let collection = self.storage.collection_mut();
+        if collection.get(&name).is_some_and(|existing| {
+            super::mutable::is_same_schema(
+                &existing.metric.desc(),
+                &bundle.desc(),
+            )
+        }) {
+            return Ok(self);
+        }
+        let entry = metric::Describable::wrap(bundle);
+        let collector = entry.clone();
+        #[cfg(feature = "relabel")]
+        let collector = relabel::Collector::new(collector, rules);
+        prometheus.register(Box::new(collector))?;
+        drop(collection.insert(name, entry));
+
+        Ok(self)
+    }
+
+    /// Registers the provided [`prometheus`] `metric` in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via the built
+    /// immutable [`Storage`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// Accepts only the following [`prometheus`] metrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    ///
+    /// # Panics
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the
+    /// provided `metric`.
+    #[must_use]
+    pub fn with_metric<M>(self, metric: M) -> Self
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        Storage: super::GetMut<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        self.try_with_metric(metric).unwrap_or_else(|e| {
+            panic!("failed to register `prometheus` metric: {e}")
+        })
+    }
+
+    /// Builds the immutable [`Storage`] out of this [`Builder`].
+    #[must_use]
+    pub fn build(self) -> Storage {
+        self.storage
+    }
 }
@@ -1,10 +1,29 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! [`metrics::registry::Storage`] implementations.
 //!
+//! Both storages hold [`prometheus`] metrics directly and expose them via
+//! their API, so no other backend (like the [`prometheus-client`] crate) may
+//! be used in place of the [`prometheus`] crate.
+//!
 //! [`metrics::registry::Storage`]: metrics_util::registry::Storage
+//! [`prometheus-client`]: https://docs.rs/prometheus-client
 
 pub mod immutable;
 pub mod mutable;
+pub mod pending;
+pub(crate) mod unknown;
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
+use arc_swap::ArcSwap;
 use sealed::sealed;
 
 #[doc(inline)]
@@ -15,6 +34,191 @@ pub use self::{immutable::Storage as Immutable, mutable::Storage as Mutable};
 /// [`metric::Bundle`]: crate::metric::Bundle
 pub type KeyName = String;
 
+/// Numbers of metric families stored in a storage, per their kind.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Counts {
+    /// Number of stored [`prometheus::IntCounter`] families.
+    pub counters: usize,
+
+    /// Number of stored [`prometheus::Gauge`] families.
+    pub gauges: usize,
+
+    /// Number of stored [`prometheus::Histogram`] families (including the
+    /// adaptive and raw ones).
+    pub histograms: usize,
+}
+
+impl Counts {
+    /// Returns the total number of metric families in these [`Counts`].
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.counters + self.gauges + self.histograms
+    }
+
+    /// Indicates whether these [`Counts`] have no metric families.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// [`prometheus`] metric a [`metrics::Key`] is resolved into by storages,
+/// returned by the [`resolve()`] function.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Resolved {
+    /// Name of the [`prometheus`] metric family.
+    pub family: String,
+
+    /// Labels (names along with values) identifying the child metric in the
+    /// family, sorted by their names, in the same order the variable labels
+    /// of the family are declared.
+    pub child_labels: Vec<(String, String)>,
+}
+
+impl Resolved {
+    /// Returns the names of the variable labels the family of this
+    /// [`Resolved`] metric is declared with.
+    pub fn label_names(&self) -> impl Iterator<Item = &str> {
+        self.child_labels.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the values of the variable labels identifying the child metric
+    /// of this [`Resolved`] one, in the order of the
+    /// [`Resolved::label_names()`].
+    pub fn label_values(&self) -> impl Iterator<Item = &str> {
+        self.child_labels.iter().map(|(_, value)| value.as_str())
+    }
+}
+
+/// Resolves the provided [`metrics::Key`] into the [`prometheus`] metric
+/// family and its child, the same way storages do on registering it.
+///
+/// Useful for custom exporters and debug tooling built on top of this crate,
+/// so they map names and labels identically, without re-implementing it.
+///
+/// Doesn't apply any sanitization, prefixing, deprecation routing or
+/// [`config::Config`] of a [`Recorder`], so maps the provided
+/// [`metrics::Key`] as is. Use the [`Recorder::resolve()`] method to resolve
+/// it exactly the way a [`Recorder`] does.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::storage;
+///
+/// let key = metrics::Key::from_parts(
+///     "requests",
+///     &[("path", "/users"), ("method", "GET")],
+/// );
+/// let resolved = storage::resolve(&key);
+/// assert_eq!(resolved.family, "requests");
+/// assert_eq!(resolved.label_names().collect::<Vec<_>>(), ["method", "path"]);
+/// assert_eq!(resolved.label_values().collect::<Vec<_>>(), ["GET", "/users"]);
+///
+/// let recorder = metrics_prometheus::Recorder::builder()
+///     .with_isolated_registry()
+///     .build();
+/// recorder.checked_counter(&key)?.increment(1);
+/// let family = &recorder.registry().gather()[0];
+/// assert_eq!(family.get_name(), resolved.family);
+/// let labels = family.get_metric()[0]
+///     .get_label()
+///     .iter()
+///     .map(|l| (l.get_name().to_owned(), l.get_value().to_owned()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(labels, resolved.child_labels);
+/// # Ok::<_, prometheus::Error>(())
+/// ```
+///
+/// [`config::Config`]: crate::config::Config
+/// [`Recorder`]: crate::Recorder
+/// [`Recorder::resolve()`]: crate::Recorder::resolve
+#[must_use]
+pub fn resolve(key: &metrics::Key) -> Resolved {
+    let mut child_labels = key
+        .labels()
+        .map(|l| (l.key().to_owned(), l.value().to_owned()))
+        .collect::<Vec<_>>();
+    // Sorted the same way as the variable labels of a family are declared by
+    // `metric::label_names()`.
+    child_labels.sort_by(|a, b| a.0.cmp(&b.0));
+    Resolved { family: key.name().to_owned(), child_labels }
+}
+
+/// Policy of handling orphan [`help` description]s, describing metrics which
+/// have never been registered.
+///
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Orphans {
+    /// Orphan descriptions are kept until their metrics are registered (maybe
+    /// forever).
+    #[default]
+    Keep,
+
+    /// Orphan descriptions are dropped once their metrics stay unregistered
+    /// for the provided [`Duration`] since being described.
+    Expire(Duration),
+
+    /// Describing metrics not registered yet is rejected, and reported via the
+    /// [`failure::Strategy`].
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    Reject,
+}
+
+/// Maximum lengths (in bytes) of metric names, label names and label values
+/// registered on the fly.
+///
+/// Keeps exposition payloads bounded, when instrumented code interpolates
+/// user input into metric names or labels.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Lengths {
+    /// Maximum length of a metric name.
+    pub max_name: usize,
+
+    /// Maximum length of a label name.
+    pub max_label_name: usize,
+
+    /// Maximum length of a label value.
+    pub max_label_value: usize,
+}
+
+impl Lengths {
+    /// [`Lengths`] not limiting anything.
+    pub const UNLIMITED: Self = Self {
+        max_name: usize::MAX,
+        max_label_name: usize::MAX,
+        max_label_value: usize::MAX,
+    };
+}
+
+impl Default for Lengths {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// Policy of handling metric names, label names and label values exceeding
+/// the configured [`Lengths`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Overlong {
+    /// Overlong metrics are rejected, and reported via the
+    /// [`failure::Strategy`].
+    ///
+    /// [`failure::Strategy`]: crate::failure::Strategy
+    #[default]
+    Reject,
+
+    /// Overlong label values are truncated to the configured [`Lengths`] (at
+    /// the nearest UTF-8 character boundary), so the metrics differing in
+    /// their truncated parts only are merged.
+    ///
+    /// Overlong metric names and label names are still rejected, as
+    /// truncating them would merge different metric families.
+    Truncate,
+}
+
 /// Retrieving a `Collection` of [`metric::Bundle`]s from a storage.
 ///
 /// [`metric::Bundle`]: crate::metric::Bundle
@@ -26,3 +230,165 @@ pub trait Get<Collection> {
     #[must_use]
     fn collection(&self) -> &Collection;
 }
+
+/// Retrieving a mutable `Collection` of [`metric::Bundle`]s from a storage.
+///
+/// [`metric::Bundle`]: crate::metric::Bundle
+#[sealed]
+pub trait GetMut<Collection> {
+    /// Returns a mutable `Collection` of [`metric::Bundle`]s stored in this
+    /// storage.
+    ///
+    /// [`metric::Bundle`]: crate::metric::Bundle
+    #[must_use]
+    fn collection_mut(&mut self) -> &mut Collection;
+}
+
+/// Sibling [`prometheus::Registry`]s, whose metric families are appended to
+/// the ones of a storage when rendering them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Merged(Arc<ArcSwap<Vec<prometheus::Registry>>>);
+
+impl Merged {
+    /// Adds the provided `registry` to these [`Merged`] ones.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `registry` is the `own` one, or has been merged
+    /// already.
+    pub(crate) fn push(
+        &self,
+        own: &prometheus::Registry,
+        registry: &prometheus::Registry,
+    ) -> prometheus::Result<()> {
+        if is_same_registry(own, registry)
+            || self.0.load().iter().any(|r| is_same_registry(r, registry))
+        {
+            return Err(prometheus::Error::Msg(
+                "`prometheus::Registry` is merged already".into(),
+            ));
+        }
+        drop(self.0.rcu(|merged| {
+            let mut merged = Vec::clone(merged);
+            merged.push(registry.clone());
+            merged
+        }));
+        Ok(())
+    }
+
+    /// Gathers the metric families of these [`Merged`]
+    /// [`prometheus::Registry`]s into the provided `families`, keeping them
+    /// sorted by names.
+    ///
+    /// Returns the names of the gathered metric families being duplicates of
+    /// the already present ones, which are dropped.
+    pub(crate) fn gather_into(
+        &self,
+        families: &mut Vec<prometheus::proto::MetricFamily>,
+    ) -> Vec<String> {
+        let merged = self.0.load();
+        if merged.is_empty() {
+            return vec![];
+        }
+        let mut names = families
+            .iter()
+            .map(|f| f.get_name().to_owned())
+            .collect::<HashSet<_>>();
+        let mut duplicates = vec![];
+        for family in merged.iter().flat_map(prometheus::Registry::gather) {
+            if names.insert(family.get_name().to_owned()) {
+                families.push(family);
+            } else {
+                duplicates.push(family.get_name().to_owned());
+            }
+        }
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+        duplicates
+    }
+}
+
+/// Returns a [`prometheus::Error`] about the provided `duplicates` of metric
+/// families, as returned by the [`Merged::gather_into()`] method.
+pub(crate) fn duplicated(duplicates: &[String]) -> prometheus::Error {
+    prometheus::Error::Msg(format!(
+        "metric families are exposed by multiple merged \
+         `prometheus::Registry`s: {}",
+        duplicates.join(", "),
+    ))
+}
+
+/// Shared [`prometheus::core::Collector`], allowing to register the same one in
+/// multiple [`prometheus::Registry`]s.
+struct Shared(Arc<dyn prometheus::core::Collector>);
+
+#[warn(clippy::missing_trait_methods)]
+impl prometheus::core::Collector for Shared {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        self.0.desc()
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.0.collect()
+    }
+}
+
+/// Moves the provided [`prometheus::core::Collector`]s `from` one
+/// [`prometheus::Registry`] `to` another one.
+///
+/// # Errors
+///
+/// If the `to` [`prometheus::Registry`] fails to register any of the provided
+/// [`prometheus::core::Collector`]s. In such case, nothing is moved.
+fn move_collectors(
+    collectors: &[Arc<dyn prometheus::core::Collector>],
+    from: &prometheus::Registry,
+    to: &prometheus::Registry,
+) -> prometheus::Result<()> {
+    for (i, c) in collectors.iter().enumerate() {
+        if let Err(e) = to.register(Box::new(Shared(Arc::clone(c)))) {
+            for registered in collectors.iter().take(i) {
+                drop(to.unregister(Box::new(Shared(Arc::clone(registered)))));
+            }
+            return Err(e);
+        }
+    }
+    for c in collectors {
+        drop(from.unregister(Box::new(Shared(Arc::clone(c)))));
+    }
+    Ok(())
+}
+
+/// Checks whether the provided [`prometheus::Registry`]s are the same one (or
+/// clones of the same one).
+///
+/// [`prometheus::Registry`] exposes no identity, so this is detected by
+/// registering a unique probe metric in both of them.
+fn is_same_registry(
+    a: &prometheus::Registry,
+    b: &prometheus::Registry,
+) -> bool {
+    /// Counter of the created probe metrics, making each of them unique.
+    static PROBES: AtomicU64 = AtomicU64::new(0);
+
+    let Ok(probe) = prometheus::IntGauge::with_opts(
+        prometheus::Opts::new(
+            "metrics_prometheus_registry_probe",
+            "Probe of `prometheus::Registry` identity.",
+        )
+        .const_label("id", PROBES.fetch_add(1, Ordering::Relaxed).to_string()),
+    ) else {
+        return false;
+    };
+    if a.register(Box::new(probe.clone())).is_err() {
+        return false;
+    }
+    let same = matches!(
+        b.register(Box::new(probe.clone())),
+        Err(prometheus::Error::AlreadyReg),
+    );
+    if !same {
+        drop(b.unregister(Box::new(probe.clone())));
+    }
+    drop(a.unregister(Box::new(probe)));
+    same
+}
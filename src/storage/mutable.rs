@@ -1,17 +1,30 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
 //! Mutable [`metrics::registry::Storage`] backed by a [`prometheus::Registry`].
 //!
 //! [`metrics::registry::Storage`]: metrics_util::registry::Storage
 
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock, TryLockError,
+    },
+    time::{Duration, Instant},
 };
 
 use sealed::sealed;
 
-use crate::{metric, Metric};
+#[cfg(feature = "relabel")]
+use crate::relabel;
+use crate::{
+    budget, catalog, clock, config, deprecation, matcher, metric, openmetrics,
+    sync::{RwLock, RwLockWriteGuard},
+    target, tasks, trace, Metric,
+};
 
-use super::KeyName;
+use super::{pending, KeyName};
 
 /// Thread-safe [`HashMap`] a [`Collection`] is built upon.
 // TODO: Remove `Arc` here by implementing `metrics_util::registry::Storage` for
@@ -46,7 +59,7 @@ pub type Collection<M> = Map<KeyName, metric::Describable<Option<M>>>;
 #[derive(Clone, Debug)]
 pub struct Storage {
     /// [`prometheus::Registry`] backing this mutable [`Storage`].
-    pub(crate) prometheus: prometheus::Registry,
+    pub(crate) prometheus: Arc<prometheus::Registry>,
 
     /// [`Collection`] of [`prometheus::IntCounter`] metrics registered in this
     /// mutable [`Storage`].
@@ -59,6 +72,196 @@ pub struct Storage {
     /// [`Collection`] of [`prometheus::Histogram`] metrics registered in this
     /// mutable [`Storage`].
     pub(super) histograms: Collection<metric::PrometheusHistogram>,
+
+    /// Buckets of the [`prometheus::Histogram`]s created lazily by this
+    /// mutable [`Storage`], instead of the [`prometheus::DEFAULT_BUCKETS`].
+    pub(crate) default_buckets: Option<Arc<[f64]>>,
+
+    /// [`matcher::Rules`] of the [`prometheus::Histogram`]s created lazily by
+    /// this mutable [`Storage`] with the single `+Inf` bucket, maintaining
+    /// their `_count` and `_sum` only.
+    pub(crate) count_only: Arc<matcher::Rules<()>>,
+
+    /// [`matcher::Rules`] of the histograms to be registered as
+    /// [`metric::adaptive::Histogram`]s.
+    pub(crate) adaptive: Arc<matcher::Rules<metric::adaptive::Config>>,
+
+    /// [`metric::adaptive::Histogram`]s registered in this mutable
+    /// [`Storage`].
+    pub(super) adaptive_histograms:
+        Map<KeyName, metric::Describable<metric::adaptive::Histogram>>,
+
+    /// [`metric::raw::Histogram`]s registered in this mutable [`Storage`].
+    pub(super) raw_histograms:
+        Map<KeyName, metric::Describable<metric::raw::Histogram>>,
+
+    /// [`metric::CustomBundle`]s registered in this mutable [`Storage`], by
+    /// the names of all their metrics.
+    pub(super) custom: Map<KeyName, metric::custom::Shared>,
+
+    /// Indicator whether any [`metric::CustomBundle`] has ever been registered
+    /// in this mutable [`Storage`], so the registration of metrics skips
+    /// looking them up otherwise.
+    has_custom: Arc<AtomicBool>,
+
+    /// [`metric::windowed::Rate`]s registered in this mutable [`Storage`].
+    pub(super) windowed: Map<KeyName, metric::windowed::Rate>,
+
+    /// [`tasks::Shutdown`] of the background tasks spawned for this mutable
+    /// [`Storage`].
+    pub(crate) tasks: tasks::Shutdown,
+
+    /// [`metrics::Unit`]s of the metric families, declared via
+    /// [`metrics::Recorder`] describe methods.
+    pub(crate) units: Map<KeyName, metrics::Unit>,
+
+    /// [`matcher::Rules`] of the histograms to be accompanied with
+    /// [`metric::extrema::Family`] gauges.
+    pub(crate) extrema: Arc<matcher::Rules<metric::extrema::Reset>>,
+
+    /// [`metric::extrema::Family`] gauges registered in this mutable
+    /// [`Storage`], by the names of their histograms.
+    pub(super) extrema_families: Map<KeyName, metric::extrema::Family>,
+
+    /// [`matcher::Rules`] of the histograms to be accompanied with
+    /// [`metric::threshold::Family`] counters, with their thresholds.
+    pub(crate) thresholds: Arc<matcher::Rules<Vec<f64>>>,
+
+    /// [`metric::threshold::Family`] counters registered in this mutable
+    /// [`Storage`], by the names of their histograms.
+    pub(super) threshold_families: Map<KeyName, metric::threshold::Family>,
+
+    /// [`matcher::Rules`] of the histograms to be accompanied with
+    /// [`metric::quantile::Family`] gauges, with their quantiles.
+    pub(crate) quantiles: Arc<matcher::Rules<Vec<f64>>>,
+
+    /// [`metric::quantile::Family`] gauges registered in this mutable
+    /// [`Storage`], by the names of their histograms.
+    pub(super) quantile_families: Map<KeyName, metric::quantile::Family>,
+
+    /// [`matcher::Rules`] of the histograms to be [`metric::batched`], with
+    /// the capacities of their thread-local buffers.
+    pub(crate) batching: Arc<matcher::Rules<usize>>,
+
+    /// [`metric::batched::Family`]s of the histograms registered in this
+    /// mutable [`Storage`], by their names.
+    pub(super) batched: Map<KeyName, metric::batched::Family>,
+
+    /// [`matcher::Rules`] of the gauges to be [`metric::striped`], with the
+    /// numbers of their shards.
+    pub(crate) striping: Arc<matcher::Rules<usize>>,
+
+    /// [`metric::striped::Family`]s of the gauges registered in this mutable
+    /// [`Storage`], by their names.
+    pub(super) striped: Map<KeyName, metric::striped::Family>,
+
+    /// Sibling [`prometheus::Registry`]s merged into the rendered metrics of
+    /// this mutable [`Storage`].
+    pub(crate) merged: super::Merged,
+
+    /// Indicator whether [`prometheus::IntCounter`]s are guarded against
+    /// regressions and being used as non-monotonic metrics.
+    pub(crate) monotonic: bool,
+
+    /// [`config::Reloadable`] configuration of the metrics registered on the
+    /// fly.
+    pub(crate) config: config::Reloadable,
+
+    /// Policy of handling orphan [`help` description]s, describing metrics
+    /// which have never been registered.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub(crate) orphans: super::Orphans,
+
+    /// Moments the metrics were first described at, by their names.
+    ///
+    /// Tracked for the [`Orphans::Expire`] policy only.
+    ///
+    /// [`Orphans::Expire`]: super::Orphans::Expire
+    pub(super) described_at: Map<KeyName, Instant>,
+
+    /// [`matcher::Rules`] of the declared [`metric::ingestion::Mode`]s of the
+    /// [`prometheus::IntCounter`]s.
+    pub(crate) ingestion: Arc<matcher::Rules<metric::ingestion::Mode>>,
+
+    /// [`metric::ingestion::Totals`] of the [`prometheus::IntCounter`]s fed in
+    /// the [`metric::ingestion::Mode::Absolute`].
+    pub(crate) totals: metric::ingestion::Totals,
+
+    /// [`matcher::Rules`] of the [`target::Prefix`]es of the metric names, by
+    /// the names of the crates registering them.
+    pub(crate) target_prefixes: Arc<target::Prefixes>,
+
+    /// [`matcher::Rules`] of the [`prometheus::IntCounter`]s setting their
+    /// absolute values by resetting.
+    ///
+    /// [`Metric::with_resetting_absolute()`] is applied to them.
+    pub(crate) resetting: Arc<matcher::Rules<()>>,
+
+    /// [`matcher::Rules`] of the [`prometheus::IntCounter`]s starting at the
+    /// declared initial values, rather than `0`.
+    pub(crate) initial_counters: Arc<matcher::Rules<u64>>,
+
+    /// [`matcher::Rules`] of the [`prometheus::Gauge`]s starting at the
+    /// declared initial values, rather than `0`.
+    pub(crate) initial_gauges: Arc<matcher::Rules<f64>>,
+
+    /// [`matcher::Rules`] of the metric families this mutable [`Storage`] is
+    /// authoritative for, so its mirrors skip them.
+    pub(crate) authoritative: Arc<matcher::Rules<()>>,
+
+    /// [`matcher::Rules`] of the reserved metric name prefixes, under which
+    /// only the declared (registered upfront) metric families may be used.
+    pub(crate) reserved: Arc<matcher::Rules<String>>,
+
+    /// Indicator whether this mutable [`Storage`] is a mirror, skipping the
+    /// metric families matched by the [`Storage::authoritative`] rules.
+    pub(crate) mirror: bool,
+
+    /// [`pending::Queue`] of the [`pending::Description`]s to be applied
+    /// lazily, if enabled.
+    pub(crate) pending: Option<pending::Queue>,
+
+    /// [`budget::Tracker`] enforcing the [`budget::Budget`] on the metrics
+    /// registered on the fly, if any.
+    pub(crate) budget: Option<Arc<budget::Tracker>>,
+
+    /// [`budget::Policy`] of the [`Storage::budget`] tracker, applied once
+    /// it's enabled.
+    pub(crate) budget_policy: budget::Policy,
+
+    /// [`unknown::Tracker`] of the metrics unknown to the [`FrozenRecorder`]
+    /// built out of this mutable [`Storage`], if enabled.
+    ///
+    /// [`FrozenRecorder`]: crate::FrozenRecorder
+    /// [`unknown::Tracker`]: super::unknown::Tracker
+    pub(crate) unknown: Option<Arc<super::unknown::Tracker>>,
+
+    /// [`deprecation::Tracker`] routing the deprecated metric names to their
+    /// replacements, if any.
+    pub(crate) deprecated: Option<Arc<deprecation::Tracker>>,
+
+    /// [`relabel::Rules`] applied to the metrics registered in this mutable
+    /// [`Storage`] at gather time.
+    #[cfg(feature = "relabel")]
+    pub(crate) relabel: relabel::Rules,
+
+    /// [`metric::timestamp::Timestamps`] of the metrics registered in this
+    /// mutable [`Storage`].
+    #[cfg(feature = "timestamps")]
+    pub(crate) timestamps: metric::timestamp::Timestamps,
+
+    /// [`Clock`] used by the time-based features of this mutable [`Storage`].
+    ///
+    /// [`Clock`]: clock::Clock
+    pub(crate) clock: clock::Shared,
+
+    /// [`prometheus::IntCounter`] of panics happened while gathering the
+    /// underlying [`prometheus::Registry`] by the
+    /// [`Recorder::try_render_text()`].
+    ///
+    /// [`Recorder::try_render_text()`]: crate::Recorder::try_render_text
+    pub(crate) gather_panics: prometheus::IntCounter,
 }
 
 #[sealed]
@@ -83,12 +286,65 @@ impl super::Get<Collection<metric::PrometheusHistogram>> for Storage {
 }
 
 impl Default for Storage {
+    #[expect( // intentional
+        clippy::expect_used,
+        reason = "counter is created with the valid constant options"
+    )]
     fn default() -> Self {
         Self {
-            prometheus: prometheus::default_registry().clone(),
+            prometheus: Arc::new(prometheus::default_registry().clone()),
             counters: Collection::default(),
             gauges: Collection::default(),
             histograms: Collection::default(),
+            default_buckets: None,
+            count_only: Arc::default(),
+            adaptive: Arc::default(),
+            adaptive_histograms: Map::default(),
+            raw_histograms: Map::default(),
+            custom: Map::default(),
+            has_custom: Arc::default(),
+            windowed: Map::default(),
+            units: Map::default(),
+            tasks: tasks::Shutdown::new(),
+            extrema: Arc::default(),
+            extrema_families: Map::default(),
+            thresholds: Arc::default(),
+            threshold_families: Map::default(),
+            quantiles: Arc::default(),
+            quantile_families: Map::default(),
+            batching: Arc::default(),
+            batched: Map::default(),
+            striping: Arc::default(),
+            striped: Map::default(),
+            merged: super::Merged::default(),
+            monotonic: false,
+            config: config::Reloadable::default(),
+            orphans: super::Orphans::default(),
+            described_at: Map::default(),
+            ingestion: Arc::default(),
+            totals: metric::ingestion::Totals::default(),
+            target_prefixes: Arc::default(),
+            resetting: Arc::default(),
+            initial_counters: Arc::default(),
+            initial_gauges: Arc::default(),
+            authoritative: Arc::default(),
+            reserved: Arc::default(),
+            mirror: false,
+            pending: None,
+            budget: None,
+            budget_policy: budget::Policy::default(),
+            unknown: None,
+            deprecated: None,
+            #[cfg(feature = "relabel")]
+            relabel: relabel::Rules::default(),
+            #[cfg(feature = "timestamps")]
+            timestamps: metric::timestamp::Timestamps::default(),
+            clock: Arc::new(clock::System),
+            gather_panics: prometheus::IntCounter::new(
+                "metrics_prometheus_gather_panics_total",
+                "Number of panics happened while gathering metrics.",
+            )
+            .expect("valid counter options"),
         }
     }
 }
@@ -107,12 +363,7 @@ impl Storage {
     /// [`metrics::Recorder::describe_histogram()`] implementations.
     ///
     /// [`help` description]: prometheus::proto::MetricFamily::get_help
-    #[expect( // intentional
-        clippy::missing_panics_doc,
-        clippy::unwrap_used,
-        reason = "`RwLock` usage is fully panic-safe here"
-    )]
-    pub fn describe<M>(&self, name: &str, description: String)
+    pub fn describe<M>(&self, name: &str, description: metrics::SharedString)
     where
         M: metric::Bundled,
         <M as metric::Bundled>::Bundle: Clone,
@@ -120,189 +371,2523 @@ impl Storage {
     {
         use super::Get as _;
 
-        let read_storage = self.collection().read().unwrap();
-        if let Some(metric) = read_storage.get(name) {
-            metric.description.store(Arc::new(description));
-        } else {
-            drop(read_storage);
-            // We do intentionally hold here the `write_storage` lock till
-            // the end of the scope, to perform all the operations atomically.
-            let mut write_storage = self.collection().write().unwrap();
+        describe(self.collection(), name, description);
+    }
 
-            if let Some(metric) = write_storage.get(name) {
-                metric.description.store(Arc::new(description));
-            } else {
-                drop(write_storage.insert(
-                    name.into(),
-                    metric::Describable::only_description(description),
-                ));
-            }
+    /// Sets the provided `annotation` of the [`prometheus`] `M`etric
+    /// identified by the provided [`metrics::Key`], used for introspection
+    /// only (see the [`metric::annotation`] module).
+    ///
+    /// If the [`prometheus`] `M`etric isn't registered yet, then the
+    /// `annotation` is kept until it is.
+    ///
+    /// Accepts only the following [`prometheus`] `M`etrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    pub fn annotate<M>(&self, key: &metrics::Key, annotation: String)
+    where
+        M: metric::Bundled,
+        <M as metric::Bundled>::Bundle: Clone,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        use super::Get as _;
+
+        annotations(self.collection(), key.name()).set(key, annotation);
+    }
+
+    /// Records the provided configured `buckets` of the [`prometheus`]
+    /// histogram family identified by the provided `name`, so its schema is
+    /// known even without any children being collected.
+    ///
+    /// Does nothing if the histogram family isn't registered yet.
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub(crate) fn record_buckets(&self, name: &str, buckets: Arc<[f64]>) {
+        if let Some(entry) = self.histograms.write().unwrap().get_mut(name) {
+            entry.buckets = Some(buckets);
         }
     }
 
-    /// Initializes a new [`prometheus`] `M`etric (or reuses the existing one)
-    /// in the underlying [`prometheus::Registry`], satisfying the labeling of
-    /// the provided [`metrics::Key`] according to
-    /// [`metrics::registry::Storage`] interface semantics, and returns it for
-    /// use in a [`metrics::Registry`].
+    /// Sets the provided [`Callback`] refreshing the value of the
+    /// [`prometheus::Gauge`] identified by the provided [`metrics::Key`] right
+    /// before it's gathered, replacing the previous one (if any).
+    ///
+    /// The [`prometheus::Gauge`] is registered, if it isn't yet.
     ///
     /// # Errors
     ///
-    /// If the underlying [`prometheus::Registry`] fails to register the newly
-    /// initialized [`prometheus`] `M`etric according to the provided
-    /// [`metrics::Key`].
+    /// If the [`prometheus::Gauge`] cannot be registered.
     ///
-    /// [`metrics::Registry`]: metrics_util::registry::Registry
-    /// [`metrics::registry::Storage`]: metrics_util::registry::Storage
+    /// [`Callback`]: metric::callback::Callback
     #[expect( // intentional
+        clippy::missing_panics_doc,
         clippy::unwrap_in_result,
         clippy::unwrap_used,
-        reason = "`RwLock` usage is fully panic-safe here (considering the \
-                  `prometheus::Registry::register()` does not)"
+        reason = "`RwLock` usage is fully panic-safe here"
     )]
+    pub fn set_collect_callback(
+        &self,
+        key: &metrics::Key,
+        callback: metric::callback::Callback,
+    ) -> prometheus::Result<()> {
+        let key = self.config.load().validated(key)?;
+        let gauge = self.register::<prometheus::Gauge>(&key)?;
+        let callbacks = self
+            .gauges
+            .read()
+            .unwrap()
+            .get(key.name())
+            .map(|m| m.callbacks.clone());
+        if let Some(callbacks) = callbacks {
+            callbacks.set(&key, (*gauge).as_ref().clone(), callback);
+        }
+        Ok(())
+    }
+
+    /// Returns the [`Annotations`] of the [`prometheus`] `M`etric family
+    /// identified by the provided `name`, if it's known to this mutable
+    /// [`Storage`].
+    ///
+    /// [`Annotations`]: metric::annotation::Annotations
     #[expect( // intentional
-        clippy::significant_drop_tightening,
-        reason = "write lock on `storage` is intentionally held till the end \
-                  of the scope, to perform all the operations atomically"
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
     )]
-    fn register<'k, M>(
+    #[must_use]
+    pub fn annotations<M>(
         &self,
-        key: &'k metrics::Key,
-    ) -> prometheus::Result<Arc<Metric<M>>>
+        name: &str,
+    ) -> Option<metric::annotation::Annotations>
     where
-        M: metric::Bundled + prometheus::core::Metric + Clone,
-        <M as metric::Bundled>::Bundle: metric::Bundle<Single = M>
-            + prometheus::core::Collector
-            + Clone
-            + TryFrom<&'k metrics::Key, Error = prometheus::Error>
-            + 'static,
+        M: metric::Bundled,
         Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
     {
         use super::Get as _;
-        use metric::Bundle as _;
 
-        let name = key.name();
-
-        let mut bundle_opt = self
-            .collection()
+        self.collection()
             .read()
             .unwrap()
             .get(name)
-            .and_then(|m| m.metric.clone());
-
-        let bundle = if let Some(bundle) = bundle_opt {
-            bundle
-        } else {
-            // We do intentionally hold here the write lock on `storage` till
-            // the end of the scope, to perform all the operations atomically.
-            let mut storage = self.collection().write().unwrap();
-
-            bundle_opt = storage.get(name).and_then(|m| m.metric.clone());
-            if let Some(bundle) = bundle_opt {
-                bundle
-            } else {
-                let bundle: <M as metric::Bundled>::Bundle = key.try_into()?;
-
-                // This way we reuse existing `description` if it has been set
-                // before metric registration.
-                let entry = storage.entry(name.into()).or_default();
-                // We should register in `prometheus::Registry` before storing
-                // in our `Collection`. This way `metrics::Recorder`
-                // implementations using this `storage::Mutable` will be able to
-                // retry registration in `prometheus::Registry`.
-                // TODO: Re-register?
-                self.prometheus.register(Box::new(
-                    entry.clone().map(|_| bundle.clone()),
-                ))?;
-                entry.metric = Some(bundle.clone());
+            .map(|m| m.annotations.clone())
+    }
 
-                bundle
-            }
-        };
+    /// Returns the [`Origins`] of the [`prometheus`] `M`etric family
+    /// identified by the provided `name`, if it's known to this mutable
+    /// [`Storage`].
+    ///
+    /// [`Origins`]: metric::origin::Origins
+    #[cfg(feature = "origins")]
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn origins<M>(&self, name: &str) -> Option<metric::origin::Origins>
+    where
+        M: metric::Bundled,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        use super::Get as _;
 
-        bundle.get_single_metric(key).map(Metric::wrap).map(Arc::new)
+        self.collection().read().unwrap().get(name).map(|m| m.origins.clone())
     }
 
-    /// Registers the provided [`prometheus`] `metric` in the underlying
-    /// [`prometheus::Registry`] in the way making it usable via this
-    /// [`metrics::registry::Storage`] (and, so, [`metrics`] crate interfaces).
+    /// Captures the [`Origin`] of the first description of the [`prometheus`]
+    /// `M`etric family identified by the provided `name`, if it isn't
+    /// captured yet.
     ///
-    /// Accepts only the following [`prometheus`] metrics:
-    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
-    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
-    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    /// If the [`prometheus`] `M`etric isn't described or registered yet, then
+    /// the [`Origin`] is kept until it is.
     ///
-    /// # Errors
+    /// Intended to be used in [`metrics::Recorder::describe_counter()`],
+    /// [`metrics::Recorder::describe_gauge()`] and
+    /// [`metrics::Recorder::describe_histogram()`] implementations.
     ///
-    /// If the underlying [`prometheus::Registry`] fails to register the
-    /// provided `metric`.
+    /// [`Origin`]: metric::origin::Origin
+    #[cfg(feature = "origins")]
+    pub fn originate_description<M>(
+        &self,
+        name: &str,
+        origin: impl FnOnce() -> metric::origin::Origin,
+    ) where
+        M: metric::Bundled,
+        <M as metric::Bundled>::Bundle: Clone,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        use super::Get as _;
+
+        origins(self.collection(), name).describe(origin);
+    }
+
+    /// Captures the [`Origin`] of the first registration of the
+    /// [`prometheus`] `M`etric family identified by the provided `name`, if
+    /// it's registered and its [`Origin`] isn't captured yet.
     ///
-    /// [`metrics::registry::Storage`]: metrics_util::registry::Storage
+    /// Intended to be used in [`metrics::Recorder::register_counter()`],
+    /// [`metrics::Recorder::register_gauge()`] and
+    /// [`metrics::Recorder::register_histogram()`] implementations.
+    ///
+    /// [`Origin`]: metric::origin::Origin
+    #[cfg(feature = "origins")]
     #[expect( // intentional
         clippy::missing_panics_doc,
-        clippy::unwrap_in_result,
         clippy::unwrap_used,
-        reason = "`RwLock` usage is fully panic-safe here (considering the \
-                  `prometheus::Registry::register()` does not)"
-    )]
-    #[expect( // intentional
-        clippy::significant_drop_tightening,
-        reason = "write lock on `storage` is intentionally held till the end \
-                  of the scope, to perform the registration in \
-                  `prometheus::Registry` exclusively"
+        reason = "`RwLock` usage is fully panic-safe here"
     )]
-    pub fn register_external<M>(&self, metric: M) -> prometheus::Result<()>
-    where
-        M: metric::Bundled + prometheus::core::Collector,
-        <M as metric::Bundled>::Bundle:
-            prometheus::core::Collector + Clone + 'static,
+    pub fn originate_registration<M>(
+        &self,
+        name: &str,
+        origin: impl FnOnce() -> metric::origin::Origin,
+    ) where
+        M: metric::Bundled,
         Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
     {
         use super::Get as _;
 
-        let name = metric
-            .desc()
-            .first()
-            .map(|d| d.fq_name.clone())
-            .unwrap_or_default();
-        let entry = metric::Describable::wrap(Some(metric.into_bundle()));
+        if let Some(m) = self.collection().read().unwrap().get(name) {
+            if m.metric.is_some() {
+                m.origins.register(origin);
+            }
+        }
+    }
 
-        // We do intentionally hold here the write lock on `storage` till
-        // the end of the scope, to perform the registration in
-        // `prometheus::Registry` exclusively.
-        let mut storage = self.collection().write().unwrap();
-        // We should register in `prometheus::Registry` before storing in our
-        // `Collection`. This way `metrics::Recorder` implementations using this
-        // `storage::Mutable` will be able to retry registration in
-        // `prometheus::Registry`.
-        // TODO: Re-register?
-        self.prometheus
-            .register(Box::new(entry.clone().map(Option::unwrap)))?;
-        drop(storage.insert(name, entry));
+    /// Pushes the provided [`pending::Description`] into the queue of this
+    /// mutable [`Storage`], if [lazy descriptions] are enabled, without
+    /// blocking.
+    ///
+    /// Returns the provided [`pending::Description`] back if
+    /// [lazy descriptions] are disabled.
+    ///
+    /// Intended to be used in [`metrics::Recorder::describe_counter()`],
+    /// [`metrics::Recorder::describe_gauge()`] and
+    /// [`metrics::Recorder::describe_histogram()`] implementations.
+    ///
+    /// # Errors
+    ///
+    /// If [lazy descriptions] are disabled, so the provided
+    /// [`pending::Description`] should be applied eagerly.
+    ///
+    /// [lazy descriptions]: crate::recorder::Builder::with_lazy_descriptions
+    pub fn describe_lazily(
+        &self,
+        description: pending::Description,
+    ) -> Result<(), pending::Description> {
+        match &self.pending {
+            Some(queue) => {
+                queue.push(description);
+                Ok(())
+            }
+            None => Err(description),
+        }
+    }
 
-        Ok(())
+    /// Indicates whether the metric identified by the provided `name` should
+    /// be skipped, as this mutable [`Storage`] is a mirror and the metric
+    /// family is owned by the authoritative one.
+    ///
+    /// Intended to be used in [`metrics::Recorder::register_counter()`],
+    /// [`metrics::Recorder::register_gauge()`] and
+    /// [`metrics::Recorder::register_histogram()`] implementations.
+    #[must_use]
+    pub fn is_mirrored(&self, name: &str) -> bool {
+        self.mirror && self.authoritative.find(name).is_some()
     }
-}
 
-impl metrics_util::registry::Storage<metrics::Key> for Storage {
-    // PANIC: We cannot panic inside `metrics_util::registry::Storage`
-    //        implementation, because it will poison locks used inside
-    //        `metrics_util::registry::Registry`. That's why we should pass
-    //        possible errors through it and deal with them inside
-    //        `metrics::Recorder` implementation.
-    type Counter = metric::Fallible<prometheus::IntCounter>;
-    type Gauge = metric::Fallible<prometheus::Gauge>;
-    type Histogram = metric::Fallible<prometheus::Histogram>;
+    /// Indicates whether the metric family identified by the provided `name`
+    /// is denied by the current [`config::Config`], so should be silently
+    /// no-op.
+    ///
+    /// Intended to be used in [`metrics::Recorder::register_counter()`],
+    /// [`metrics::Recorder::register_gauge()`] and
+    /// [`metrics::Recorder::register_histogram()`] implementations.
+    #[must_use]
+    pub fn is_denied(&self, name: &str) -> bool {
+        self.config.load().is_denied(name)
+    }
 
-    fn counter(&self, key: &metrics::Key) -> Self::Counter {
-        self.register::<prometheus::IntCounter>(key).into()
+    /// Routes the provided [`metrics::Key`] to its replacement, if its name is
+    /// deprecated, tracking this usage according to its
+    /// [`deprecation::Policy`].
+    ///
+    /// Returns [`None`] if the name of the provided [`metrics::Key`] is not
+    /// deprecated.
+    ///
+    /// # Errors
+    ///
+    /// If the counter of the deprecated metrics usages cannot be registered in
+    /// the underlying [`prometheus::Registry`]. The replacement
+    /// [`metrics::Key`] is still returned in such case.
+    pub(crate) fn route_deprecated(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<(metrics::Key, prometheus::Result<()>)> {
+        self.deprecated.as_ref()?.route(key, &self.prometheus)
     }
 
-    fn gauge(&self, key: &metrics::Key) -> Self::Gauge {
-        self.register::<prometheus::Gauge>(key).into()
+    /// Returns the replacement of the provided metric `name`, if it's
+    /// deprecated, or the provided `name` as is otherwise.
+    pub(crate) fn replace_deprecated(
+        &self,
+        name: metrics::KeyName,
+    ) -> metrics::KeyName {
+        self.deprecated
+            .as_ref()
+            .and_then(|d| d.replacement(name.as_str()))
+            .map_or(name, |n| n.to_owned().into())
+    }
+
+    /// Returns the numbers of usages of the deprecated metric names, by these
+    /// names.
+    #[must_use]
+    pub fn deprecated_usages(&self) -> BTreeMap<String, u64> {
+        self.deprecated.as_ref().map(|d| d.usages()).unwrap_or_default()
+    }
+
+    /// Prefixes the name of the provided [`metrics::Key`] according to the
+    /// [`target::Prefix`] rule matching the crate of the provided `target`
+    /// (as reported by [`metrics::Metadata::target()`]), if any.
+    pub(crate) fn prefixed<'k>(
+        &self,
+        key: &'k metrics::Key,
+        target: &str,
+    ) -> Cow<'k, metrics::Key> {
+        self.target_prefixes.prefixed(key, target)
+    }
+
+    /// Resolves the provided [`metrics::Key`] into the [`prometheus`] metric
+    /// family and its child, the same way this mutable [`Storage`] does on
+    /// registering it: applying its [`config::Config`] (validation, dropping
+    /// empty labels, stripping control characters, length limits and default
+    /// labels).
+    ///
+    /// Neither prefixes nor routes deprecated names (see the
+    /// [`Recorder::resolve()`] method doing so).
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`metrics::Key`] is rejected by the
+    /// [`config::Config`].
+    ///
+    /// [`Recorder::resolve()`]: crate::Recorder::resolve
+    pub fn resolve(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<super::Resolved> {
+        let key = self.config.load().validated(key)?;
+        let key = self
+            .config
+            .load()
+            .defaulted(&key, || self.declared_labels(key.name()));
+        Ok(super::resolve(&key))
+    }
+
+    /// Returns the label names the registered metric family identified by the
+    /// provided `name` is declared with, if any.
+    fn declared_labels(&self, name: &str) -> Option<Vec<String>> {
+        /// Returns the label names of the metric family identified by the
+        /// provided `name` in the provided [`Collection`], if any.
+        #[expect( // intentional
+            clippy::unwrap_used,
+            reason = "`RwLock` usage is fully panic-safe here"
+        )]
+        fn declared<B>(
+            collection: &Collection<B>,
+            name: &str,
+        ) -> Option<Vec<String>>
+        where
+            B: prometheus::core::Collector,
+        {
+            collection.read().unwrap().get(name)?.metric.as_ref().map(|b| {
+                prometheus::core::Collector::desc(b)
+                    .first()
+                    .map(|d| d.variable_labels.clone())
+                    .unwrap_or_default()
+            })
+        }
+
+        declared(&self.counters, name)
+            .or_else(|| declared(&self.gauges, name))
+            .or_else(|| declared(&self.histograms, name))
+    }
+
+    /// Applies all the [`pending::Description`]s pushed via the
+    /// [`Storage::describe_lazily()`] method.
+    ///
+    /// No-op if there are no [`pending::Description`]s.
+    pub fn flush_descriptions(&self) {
+        let Some(flusher) = self.flusher() else {
+            return;
+        };
+        if flusher.flush() {
+            if let super::Orphans::Expire(ttl) = self.orphans {
+                self.expire_orphans(self.clock.now(), ttl);
+            }
+        }
+    }
+
+    /// Returns [`Counts`] of the metric families registered in this mutable
+    /// [`Storage`] (not counting the ones having a [`help` description]
+    /// only).
+    ///
+    /// [`Counts`]: super::Counts
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn metric_counts(&self) -> super::Counts {
+        fn registered<M>(collection: &Collection<M>) -> usize {
+            collection
+                .read()
+                .unwrap()
+                .values()
+                .filter(|m| m.metric.is_some())
+                .count()
+        }
+
+        super::Counts {
+            counters: registered(&self.counters),
+            gauges: registered(&self.gauges),
+            histograms: registered(&self.histograms)
+                + self.adaptive_histograms.read().unwrap().len()
+                + self.raw_histograms.read().unwrap().len(),
+        }
+    }
+
+    /// Returns the [`catalog::Snapshot`] of the metric families registered in
+    /// this mutable [`Storage`] (not counting the ones having a
+    /// [`help` description] only).
+    ///
+    /// Adaptive, raw and custom histograms are not included, as have no fixed
+    /// bucket layout.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn catalog(&self) -> catalog::Snapshot {
+        fn families<S, V>(
+            collection: &Collection<metric::bundle::Either<S, V>>,
+            kind: prometheus::proto::MetricType,
+            out: &mut Vec<catalog::Family>,
+        ) where
+            metric::bundle::Either<S, V>: prometheus::core::Collector,
+        {
+            out.extend(collection.read().unwrap().values().filter_map(
+                |entry| {
+                    let metric = entry.metric.as_ref()?;
+                    Some(catalog::Family::of_bundle(
+                        metric,
+                        &entry.description.load(),
+                        kind,
+                        entry.buckets.as_deref(),
+                    ))
+                },
+            ));
+        }
+
+        use prometheus::proto::MetricType as T;
+
+        let mut out = vec![];
+        families(&self.counters, T::COUNTER, &mut out);
+        families(&self.gauges, T::GAUGE, &mut out);
+        families(&self.histograms, T::HISTOGRAM, &mut out);
+        out.sort_by(|a, b| a.schema.name.cmp(&b.schema.name));
+        catalog::Snapshot { families: out }
+    }
+
+    /// Returns sorted names of the metric families registered in this mutable
+    /// [`Storage`] (not counting the ones having a [`help` description]
+    /// only).
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub(crate) fn registered_names(&self) -> Vec<KeyName> {
+        fn registered<M>(collection: &Collection<M>) -> Vec<KeyName> {
+            collection
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|(_, m)| m.metric.is_some())
+                .map(|(name, _)| name.clone())
+                .collect()
+        }
+
+        let mut names = registered(&self.counters);
+        names.extend(registered(&self.gauges));
+        names.extend(registered(&self.histograms));
+        names.extend(self.adaptive_histograms.read().unwrap().keys().cloned());
+        names.extend(self.raw_histograms.read().unwrap().keys().cloned());
+        names.extend(self.custom.read().unwrap().keys().cloned());
+        names.extend(self.windowed.read().unwrap().keys().cloned());
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Returns sorted names of the metrics having a [`help` description] in
+    /// this mutable [`Storage`], but never registered.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[must_use]
+    pub fn described_only(&self) -> Vec<KeyName> {
+        let [counters, gauges, histograms] = self.orphans();
+        let mut names = counters;
+        names.extend(gauges);
+        names.extend(histograms);
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Returns [`Counts`] of the metric families having a
+    /// [`help` description] in this mutable [`Storage`], but never
+    /// registered.
+    ///
+    /// [`Counts`]: super::Counts
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[must_use]
+    pub fn orphan_counts(&self) -> super::Counts {
+        let [counters, gauges, histograms] = self.orphans();
+        super::Counts {
+            counters: counters.len(),
+            gauges: gauges.len(),
+            histograms: histograms.len(),
+        }
+    }
+
+    /// Returns names of the counters, gauges and histograms having a
+    /// [`help` description] in this mutable [`Storage`], but never
+    /// registered.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn orphans(&self) -> [Vec<KeyName>; 3] {
+        self.flush_descriptions();
+
+        let adaptive = self.adaptive_histograms.read().unwrap();
+        let raw = self.raw_histograms.read().unwrap();
+        let custom = self.custom.read().unwrap();
+        let mut orphans = [
+            described_only(&self.counters),
+            described_only(&self.gauges),
+            described_only(&self.histograms)
+                .into_iter()
+                .filter(|n| !adaptive.contains_key(n) && !raw.contains_key(n))
+                .collect(),
+        ];
+        for names in &mut orphans {
+            names.retain(|n| !custom.contains_key(n));
+        }
+        drop((adaptive, raw, custom));
+        orphans
+    }
+
+    /// Applies the [`Orphans`] policy to the [`help` description] of the
+    /// [`prometheus`] `M`etric identified by the provided `name`, before it's
+    /// described.
+    ///
+    /// For the [`Orphans::Expire`] policy, tracks the moment the metric is
+    /// described at, and drops the expired orphan descriptions.
+    ///
+    /// Intended to be used in [`metrics::Recorder::describe_counter()`],
+    /// [`metrics::Recorder::describe_gauge()`] and
+    /// [`metrics::Recorder::describe_histogram()`] implementations.
+    ///
+    /// # Errors
+    ///
+    /// For the [`Orphans::Reject`] policy, if the metric isn't registered yet.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    /// [`Orphans`]: super::Orphans
+    /// [`Orphans::Expire`]: super::Orphans::Expire
+    /// [`Orphans::Reject`]: super::Orphans::Reject
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn adopt_description<M>(&self, name: &str) -> prometheus::Result<()>
+    where
+        M: metric::Bundled,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        use super::Get as _;
+
+        match self.orphans {
+            super::Orphans::Keep => Ok(()),
+            super::Orphans::Reject => {
+                // Every lock is released before acquiring the next one, as
+                // the registration paths acquire them in the other order
+                // (like `adaptive_histograms` before `histograms`), and so,
+                // holding them together may lead to a deadlock.
+                let registered = is_registered(self.collection(), name)
+                    || contains(&self.adaptive_histograms, name)
+                    || contains(&self.raw_histograms, name)
+                    || contains(&self.custom, name);
+                if registered {
+                    Ok(())
+                } else {
+                    Err(prometheus::Error::Msg(format!(
+                        "`{name}` metric is described before being registered",
+                    )))
+                }
+            }
+            super::Orphans::Expire(ttl) => {
+                let now = self.clock.now();
+                _ = self
+                    .described_at
+                    .write()
+                    .unwrap()
+                    .entry(name.into())
+                    .or_insert(now);
+                self.expire_orphans(now, ttl);
+                Ok(())
+            }
+        }
+    }
+
+    /// Drops the orphan [`help` description]s described for longer than the
+    /// provided `ttl` by the provided `now` moment.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn expire_orphans(&self, now: Instant, ttl: Duration) {
+        let mut expired = vec![];
+        self.described_at.write().unwrap().retain(|name, at| {
+            let alive = now.saturating_duration_since(*at) < ttl;
+            if !alive {
+                expired.push(name.clone());
+            }
+            alive
+        });
+        if expired.is_empty() {
+            return;
+        }
+
+        let [counters, gauges, histograms] = self.orphans();
+        for name in expired {
+            if counters.contains(&name) {
+                forget_orphan(&self.counters, &name);
+            }
+            if gauges.contains(&name) {
+                forget_orphan(&self.gauges, &name);
+            }
+            if histograms.contains(&name) {
+                forget_orphan(&self.histograms, &name);
+            }
+        }
+    }
+
+    /// Returns sorted names of the metrics registered in this mutable
+    /// [`Storage`], but having no values recorded into them.
+    ///
+    /// As [`prometheus`] metrics don't track whether they were ever recorded,
+    /// the metric is considered so, if all its values are zero (so a gauge
+    /// explicitly set to zero is reported too).
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn unrecorded(&self) -> Vec<KeyName> {
+        let mut names = unrecorded(&self.counters);
+        names.extend(unrecorded(&self.gauges));
+        names.extend(unrecorded(&self.histograms));
+        names.extend(
+            self.adaptive_histograms
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|(_, h)| {
+                    is_unrecorded(&prometheus::core::Collector::collect(
+                        &h.metric,
+                    ))
+                })
+                .map(|(name, _)| name.clone()),
+        );
+        names.sort_unstable();
+        names
+    }
+
+    /// Unregisters all the [`prometheus`] metrics registered by this mutable
+    /// [`Storage`] from its [`prometheus::Registry`], and forgets them (while
+    /// keeping their [`help` description]s).
+    ///
+    /// The metrics registered afterwards are registered anew.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[expect( // intentional
+        clippy::iter_over_hash_type,
+        reason = "order of unregistering doesn't matter"
+    )]
+    pub fn unregister_all(&self) {
+        unregister(&self.prometheus, &self.counters);
+        unregister(&self.prometheus, &self.gauges);
+        unregister(&self.prometheus, &self.histograms);
+        for (_, histogram) in self.adaptive_histograms.write().unwrap().drain()
+        {
+            drop(self.prometheus.unregister(Box::new(histogram)));
+        }
+        for (_, histogram) in self.raw_histograms.write().unwrap().drain() {
+            drop(self.prometheus.unregister(Box::new(histogram)));
+        }
+        // Bundles are stored under multiple names, so unregistering the same
+        // one repeatedly just fails silently.
+        for (_, bundle) in self.custom.write().unwrap().drain() {
+            drop(self.prometheus.unregister(Box::new(bundle)));
+        }
+        for (_, rate) in self.windowed.write().unwrap().drain() {
+            drop(self.prometheus.unregister(Box::new(rate)));
+        }
+        for (_, family) in self.extrema_families.write().unwrap().drain() {
+            drop(self.prometheus.unregister(Box::new(family)));
+        }
+        for (_, family) in self.threshold_families.write().unwrap().drain() {
+            drop(self.prometheus.unregister(Box::new(family)));
+        }
+        for (_, family) in self.quantile_families.write().unwrap().drain() {
+            drop(self.prometheus.unregister(Box::new(family)));
+        }
+        self.batched.write().unwrap().clear();
+        self.striped.write().unwrap().clear();
+        if let Some(budget) = &self.budget {
+            budget.unregister(&self.prometheus);
+        }
+        if let Some(deprecated) = &self.deprecated {
+            deprecated.unregister(&self.prometheus);
+        }
+    }
+
+    /// Converts this mutable [`Storage`] into an [`Immutable`] one, moving all
+    /// its metrics (still registered in the same [`prometheus::Registry`]).
+    ///
+    /// Consuming this mutable [`Storage`] makes the ownership transfer
+    /// explicit, so it cannot be accidentally reused after being emptied
+    /// (which would re-register duplicates of the moved metrics).
+    ///
+    /// [`Immutable`]: super::Immutable
+    #[must_use]
+    pub fn into_immutable(self) -> super::Immutable {
+        super::Immutable::drained(&self, |_| true)
+    }
+
+    /// Moves all the [`prometheus`] metrics registered by this mutable
+    /// [`Storage`] into the provided [`prometheus::Registry`], unregistering
+    /// them from the current one, and makes the provided one the underlying
+    /// [`prometheus::Registry`].
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register any of the
+    /// metrics. In such case, nothing is moved and the current
+    /// [`prometheus::Registry`] is kept.
+    pub(crate) fn move_to(
+        &mut self,
+        registry: Arc<prometheus::Registry>,
+    ) -> prometheus::Result<()> {
+        if !super::is_same_registry(&self.prometheus, &registry) {
+            let collectors = self.collectors();
+            super::move_collectors(&collectors, &self.prometheus, &registry)?;
+            if let Some(budget) = &self.budget {
+                if let Err(e) = budget.move_to(&self.prometheus, &registry) {
+                    // Cannot fail, as they've been registered there before.
+                    drop(super::move_collectors(
+                        &collectors,
+                        &registry,
+                        &self.prometheus,
+                    ));
+                    return Err(e);
+                }
+            }
+        }
+        self.prometheus = registry;
+        Ok(())
+    }
+
+    /// Returns all the [`prometheus`] metrics registered by this mutable
+    /// [`Storage`] in the underlying [`prometheus::Registry`], prepared for
+    /// registering as the [`Storage::collector()`] does.
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[expect( // intentional
+        clippy::iter_over_hash_type,
+        reason = "order of collecting doesn't matter"
+    )]
+    pub(crate) fn collectors(
+        &self,
+    ) -> Vec<Arc<dyn prometheus::core::Collector>> {
+        fn bundles<B>(
+            storage: &Storage,
+            collection: &Collection<B>,
+            out: &mut Vec<Arc<dyn prometheus::core::Collector>>,
+        ) where
+            B: prometheus::core::Collector + Clone + 'static,
+        {
+            for entry in collection.read().unwrap().values() {
+                if let Some(bundle) = &entry.metric {
+                    out.push(Arc::from(
+                        storage
+                            .collector(entry.clone().map(|_| bundle.clone())),
+                    ));
+                }
+            }
+        }
+
+        let mut out = Vec::<Arc<dyn prometheus::core::Collector>>::new();
+        bundles(self, &self.counters, &mut out);
+        bundles(self, &self.gauges, &mut out);
+        bundles(self, &self.histograms, &mut out);
+        for histogram in self.adaptive_histograms.read().unwrap().values() {
+            out.push(Arc::from(self.collector(histogram.clone())));
+        }
+        for histogram in self.raw_histograms.read().unwrap().values() {
+            out.push(Arc::from(self.collector(histogram.clone())));
+        }
+        // Bundles are stored under multiple names, so should be deduplicated.
+        let mut seen = HashSet::new();
+        for bundle in self.custom.read().unwrap().values() {
+            let name = prometheus::core::Collector::desc(bundle)
+                .first()
+                .map(|d| d.fq_name.clone());
+            if seen.insert(name) {
+                out.push(Arc::from(self.collector(bundle.clone())));
+            }
+        }
+        for rate in self.windowed.read().unwrap().values() {
+            out.push(Arc::from(self.collector(rate.clone())));
+        }
+        for family in self.extrema_families.read().unwrap().values() {
+            out.push(Arc::from(self.collector(family.clone())));
+        }
+        for family in self.threshold_families.read().unwrap().values() {
+            out.push(Arc::from(self.collector(family.clone())));
+        }
+        for family in self.quantile_families.read().unwrap().values() {
+            out.push(Arc::from(self.collector(family.clone())));
+        }
+        out
+    }
+
+    /// Returns a [`Flusher`] of the [`pending::Description`]s of this mutable
+    /// [`Storage`], if [lazy descriptions] are enabled.
+    ///
+    /// [lazy descriptions]: crate::recorder::Builder::with_lazy_descriptions
+    fn flusher(&self) -> Option<Flusher> {
+        self.pending.as_ref().map(|queue| Flusher {
+            queue: queue.clone(),
+            counters: Arc::clone(&self.counters),
+            gauges: Arc::clone(&self.gauges),
+            histograms: Arc::clone(&self.histograms),
+            adaptive_histograms: Arc::clone(&self.adaptive_histograms),
+            raw_histograms: Arc::clone(&self.raw_histograms),
+            custom: Arc::clone(&self.custom),
+            units: Arc::clone(&self.units),
+            orphans: self.orphans,
+            described_at: Arc::clone(&self.described_at),
+            clock: Arc::clone(&self.clock),
+        })
+    }
+
+    /// Initializes a new [`prometheus`] `M`etric (or reuses the existing one)
+    /// in the underlying [`prometheus::Registry`], satisfying the labeling of
+    /// the provided [`metrics::Key`] according to
+    /// [`metrics::registry::Storage`] interface semantics, and returns it for
+    /// use in a [`metrics::Registry`].
+    ///
+    /// The [`config::Config::default_labels`] missing in the provided
+    /// [`metrics::Key`] are added to it beforehand.
+    ///
+    /// # Errors
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the newly
+    /// initialized [`prometheus`] `M`etric according to the provided
+    /// [`metrics::Key`].
+    ///
+    /// [`metrics::Registry`]: metrics_util::registry::Registry
+    /// [`metrics::registry::Storage`]: metrics_util::registry::Storage
+    #[expect( // intentional
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    #[expect( // intentional
+        clippy::significant_drop_tightening,
+        reason = "write lock on `storage` is intentionally held till the end \
+                  of the scope, to perform all the operations atomically"
+    )]
+    fn register<M>(
+        &self,
+        key: &metrics::Key,
+    ) -> prometheus::Result<Arc<Metric<M>>>
+    where
+        M: metric::Bundled + prometheus::core::Metric + Clone,
+        <M as metric::Bundled>::Bundle: metric::Bundle<Single = M>
+            + prometheus::core::Collector
+            + Clone
+            + Lazy
+            + Send
+            + Sync
+            + 'static,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        use super::Get as _;
+        use metric::Bundle as _;
+
+        let mut bundle_opt = self
+            .collection()
+            .read()
+            .unwrap()
+            .get(key.name())
+            .and_then(|m| m.metric.clone());
+
+        let key = self.config.load().defaulted(key, || {
+            bundle_opt.as_ref().map(|b| {
+                prometheus::core::Collector::desc(b)
+                    .first()
+                    .map(|d| d.variable_labels.clone())
+                    .unwrap_or_default()
+            })
+        });
+        let key = &*key;
+        let name = key.name();
+
+        let bundle = if let Some(bundle) = bundle_opt {
+            bundle
+        } else {
+            if let Some(prefix) = self.reserved.find(name) {
+                return Err(reserved(key, prefix, "it's not declared"));
+            }
+            // Checked before acquiring the write lock, as may require locking
+            // other `Collection`s, and so, lead to a deadlock otherwise.
+            if self.monotonic && self.is_counter(name) {
+                return Err(metric::monotonic::misuse(name));
+            }
+
+            // We do intentionally hold here the write lock on `storage` till
+            // the end of the scope, to perform all the operations atomically.
+            let mut storage = self.collection().write().unwrap();
+
+            bundle_opt = storage.get(name).and_then(|m| m.metric.clone());
+            if let Some(bundle) = bundle_opt {
+                bundle
+            } else {
+                let bundle = <M as metric::Bundled>::Bundle::create(key, self)?;
+
+                if let Some(budget) = &self.budget {
+                    budget.register(&self.prometheus)?;
+                    budget.admit_family(name)?;
+                }
+
+                // This way we reuse existing `description` if it has been set
+                // before metric registration.
+                let entry = storage.entry(name.into()).or_default();
+                // We should register in `prometheus::Registry` before storing
+                // in our `Collection`. This way `metrics::Recorder`
+                // implementations using this `storage::Mutable` will be able to
+                // retry registration in `prometheus::Registry`. Once stored,
+                // it's re-registered by `Storage::move_to()` on a swap.
+                if let Err(e) = self.prometheus.register(
+                    self.collector(entry.clone().map(|_| bundle.clone())),
+                ) {
+                    if let Some(budget) = &self.budget {
+                        budget.release_family(name);
+                    }
+                    return Err(e);
+                }
+                entry.metric = Some(bundle.clone());
+                entry.buckets =
+                    <M as metric::Bundled>::Bundle::buckets(key, self);
+
+                bundle
+            }
+        };
+
+        self.check_reserved(key, &bundle)?;
+
+        let resolved = if let Some(budget) = &self.budget {
+            // Only labeled metrics may be evicted from their families.
+            let evict = key.labels().next().is_some().then(|| {
+                let (bundle, key) = (bundle.clone(), key.clone());
+                move || bundle.remove_single_metric(&key)
+            });
+            budget.admit_child(key, evict)?
+        } else {
+            Cow::Borrowed(key)
+        };
+
+        let mut metric = match bundle.get_single_metric(&resolved) {
+            Ok(m) => Metric::wrap(m),
+            Err(e) => {
+                // Failed resolutions must not use up the budget.
+                if let Some(budget) = &self.budget {
+                    budget.release_child(key);
+                }
+                return Err(e);
+            }
+        };
+        if self.resetting.find(name).is_some() {
+            metric = metric.with_resetting_absolute();
+        }
+        #[cfg(feature = "timestamps")]
+        let metric =
+            metric.with_last_updated(self.timestamps.get_or_create(key));
+        Ok(Arc::new(metric))
+    }
+
+    /// Checks whether the provided [`metrics::Key`] uses the label names
+    /// declared by the provided `family`, if the `family` falls under a
+    /// reserved prefix.
+    ///
+    /// # Errors
+    ///
+    /// If the label names of the provided [`metrics::Key`] don't match the
+    /// declared ones.
+    fn check_reserved(
+        &self,
+        key: &metrics::Key,
+        family: &impl prometheus::core::Collector,
+    ) -> prometheus::Result<()> {
+        let Some(prefix) = self.reserved.find(key.name()) else {
+            return Ok(());
+        };
+        let mut declared = family
+            .desc()
+            .first()
+            .map(|d| d.variable_labels.clone())
+            .unwrap_or_default();
+        declared.sort_unstable();
+        let mut used =
+            key.labels().map(metrics::Label::key).collect::<Vec<_>>();
+        used.sort_unstable();
+        if declared != used {
+            return Err(reserved(
+                key,
+                prefix,
+                &format!(
+                    "its label names don't match the declared ones: [{}]",
+                    declared.join(", "),
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Boxes the provided [`prometheus::core::Collector`] for registering in
+    /// the underlying [`prometheus::Registry`], applying the configured
+    /// relabeling rules to it, if any, and flushing the
+    /// [`pending::Description`]s on gathering, if [lazy descriptions] are
+    /// enabled.
+    ///
+    /// The [`metric::batched::Histogram`]s and [`metric::striped::Gauge`]s are
+    /// flushed on gathering too, if [histogram batching] or
+    /// [gauge striping] is enabled.
+    ///
+    /// [gauge striping]: crate::recorder::Builder::with_gauge_striping
+    /// [histogram batching]: crate::recorder::Builder::with_histogram_batching
+    /// [lazy descriptions]: crate::recorder::Builder::with_lazy_descriptions
+    fn collector<C>(&self, collector: C) -> Box<dyn prometheus::core::Collector>
+    where
+        C: prometheus::core::Collector + 'static,
+    {
+        #[cfg(feature = "relabel")]
+        let collector =
+            relabel::Collector::new(collector, self.relabel.clone());
+        let flusher = self.flusher();
+        let batched =
+            (!self.batching.is_empty()).then(|| Arc::clone(&self.batched));
+        let striped =
+            (!self.striping.is_empty()).then(|| Arc::clone(&self.striped));
+        if flusher.is_none() && batched.is_none() && striped.is_none() {
+            return Box::new(collector);
+        }
+        Box::new(Flushing { collector, flusher, batched, striped })
+    }
+
+    /// Checks whether a [`prometheus::IntCounter`] is registered under the
+    /// provided `name` in this mutable [`Storage`].
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn is_counter(&self, name: &str) -> bool {
+        self.counters
+            .read()
+            .unwrap()
+            .get(name)
+            .is_some_and(|c| c.metric.is_some())
+    }
+
+    /// Returns a [`metric::adaptive::Child`] identified by the provided
+    /// [`metrics::Key`], initializing a new [`metric::adaptive::Histogram`]
+    /// (or reusing the existing one) in the underlying
+    /// [`prometheus::Registry`].
+    ///
+    /// Returns [`None`] if the provided [`metrics::Key`] doesn't match any
+    /// rule configured for [`metric::adaptive::Histogram`]s, so a usual
+    /// [`prometheus::Histogram`] should be used instead.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided [`metrics::Key`] is rejected by the
+    ///   [`config::Config`], falls under a reserved prefix, or exceeds the
+    ///   [`Budget`].
+    /// - If the underlying [`prometheus::Registry`] fails to register the newly
+    ///   initialized [`metric::adaptive::Histogram`], or it cannot provide a
+    ///   [`metric::adaptive::Child`] for the provided [`metrics::Key`].
+    ///
+    /// [`Budget`]: budget::Budget
+    #[must_use]
+    pub fn adaptive_histogram(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<prometheus::Result<Arc<metric::adaptive::Child>>> {
+        let config = self.adaptive.find(key.name())?;
+        Some(self.register_adaptive(key, config))
+    }
+
+    /// Registers a [`metric::adaptive::Histogram`] with the provided
+    /// [`metric::adaptive::Config`] (or reuses the existing one), and returns
+    /// its [`metric::adaptive::Child`] identified by the provided
+    /// [`metrics::Key`].
+    ///
+    /// Applies the same admission checks as the [`Storage::register()`] does,
+    /// rolling back everything on failure.
+    #[expect( // intentional
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    fn register_adaptive(
+        &self,
+        key: &metrics::Key,
+        config: &metric::adaptive::Config,
+    ) -> prometheus::Result<Arc<metric::adaptive::Child>> {
+        let name = key.name();
+        if self.monotonic && self.is_counter(name) {
+            return Err(metric::monotonic::misuse(name));
+        }
+        let key = self.config.load().validated(key)?;
+        let key = &*key;
+
+        let existing =
+            self.adaptive_histograms.read().unwrap().get(name).cloned();
+        let family = if let Some(family) = existing {
+            family
+        } else {
+            if let Some(prefix) = self.reserved.find(name) {
+                return Err(reserved(key, prefix, "it's not declared"));
+            }
+            if let Some(budget) = &self.budget {
+                budget.register(&self.prometheus)?;
+            }
+
+            // This way we reuse existing `description` and annotations if
+            // they have been set before metric registration. The lock is
+            // released right away, as no other path holds `histograms` and
+            // `adaptive_histograms` locks together.
+            let mut histograms = self.histograms.write().unwrap();
+            let created = !histograms.contains_key(name);
+            let described = histograms.entry(name.into()).or_default().clone();
+            drop(histograms);
+            let rollback = |e| {
+                if created {
+                    forget_blank(&self.histograms, name, &described);
+                }
+                e
+            };
+
+            let mut storage = self.adaptive_histograms.write().unwrap();
+            if let Some(family) = storage.get(name) {
+                family.clone()
+            } else {
+                let histogram =
+                    match metric::adaptive::Histogram::new(key, config.clone())
+                    {
+                        Ok(h) => h,
+                        Err(e) => {
+                            drop(storage);
+                            return Err(rollback(e));
+                        }
+                    };
+                if let Some(budget) = &self.budget {
+                    if let Err(e) = budget.admit_family(name) {
+                        drop(storage);
+                        return Err(rollback(e));
+                    }
+                }
+                let family = described.clone().map(|_| histogram);
+                if let Err(e) =
+                    self.prometheus.register(self.collector(family.clone()))
+                {
+                    drop((storage, family));
+                    if let Some(budget) = &self.budget {
+                        budget.release_family(name);
+                    }
+                    return Err(rollback(e));
+                }
+                drop(storage.insert(name.into(), family.clone()));
+                drop(storage);
+                family
+            }
+        };
+
+        self.check_reserved(key, &family.metric)?;
+
+        let resolved = if let Some(budget) = &self.budget {
+            // Children of `adaptive::Histogram`s cannot be removed from their
+            // family, so are never evicted.
+            budget.admit_child(key, None::<fn() -> prometheus::Result<()>>)?
+        } else {
+            Cow::Borrowed(key)
+        };
+        family.metric.child(&resolved).inspect_err(|_| {
+            // Failed resolutions must not use up the budget.
+            if let Some(budget) = &self.budget {
+                budget.release_child(key);
+            }
+        })
+    }
+
+    /// Sets the provided [`metrics::Unit`] of the metric family with the
+    /// provided `name`, to be emitted in the [`openmetrics`] text format.
+    ///
+    /// # Errors
+    ///
+    /// If the provided `name` has a suffix of another [`metrics::Unit`].
+    pub fn set_unit(
+        &self,
+        name: KeyName,
+        unit: metrics::Unit,
+    ) -> prometheus::Result<()> {
+        openmetrics::check_suffix(name.as_str(), unit)?;
+        self.record_unit(name, unit);
+        Ok(())
+    }
+
+    /// Records the provided [`metrics::Unit`] of the metric family with the
+    /// provided `name`, already checked via the [`openmetrics::check_suffix()`]
+    /// function.
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub(crate) fn record_unit(&self, name: KeyName, unit: metrics::Unit) {
+        _ = self.units.write().unwrap().insert(name, unit);
+    }
+
+    /// Returns the [`metrics::Unit`]s of the metric families, declared via
+    /// [`metrics::Recorder`] describe methods, by their names.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn units(&self) -> BTreeMap<String, metrics::Unit> {
+        self.units
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, unit)| (name.as_str().to_owned(), *unit))
+            .collect()
+    }
+
+    /// Returns the [`metrics::Unit`] of the metric family with the provided
+    /// `name`, declared via [`metrics::Recorder`] describe methods, if any.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn unit(&self, name: &str) -> Option<metrics::Unit> {
+        self.units.read().unwrap().get(name).copied()
+    }
+
+    /// Returns a [`metric::extrema::Child`] tracking the extreme values of the
+    /// histogram identified by the provided [`metrics::Key`], initializing a
+    /// new [`metric::extrema::Family`] (or reusing the existing one) in the
+    /// underlying [`prometheus::Registry`].
+    ///
+    /// Returns [`None`] if the provided [`metrics::Key`] doesn't match any
+    /// rule configured for [`metric::extrema::Family`] gauges.
+    ///
+    /// # Errors
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the newly
+    /// initialized [`metric::extrema::Family`].
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    #[must_use]
+    pub fn extrema(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<prometheus::Result<Arc<metric::extrema::Child>>> {
+        let name = key.name();
+        let reset = *self.extrema.find(name)?;
+
+        let existing = self.extrema_families.read().unwrap().get(name).cloned();
+        let family = if let Some(family) = existing {
+            family
+        } else {
+            let mut storage = self.extrema_families.write().unwrap();
+            if let Some(family) = storage.get(name) {
+                family.clone()
+            } else {
+                let family = match metric::extrema::Family::new(
+                    name,
+                    reset,
+                    Arc::clone(&self.clock),
+                ) {
+                    Ok(f) => f,
+                    Err(e) => return Some(Err(e)),
+                };
+                if let Err(e) =
+                    self.prometheus.register(self.collector(family.clone()))
+                {
+                    return Some(Err(e));
+                }
+                drop(storage.insert(name.into(), family.clone()));
+                family
+            }
+        };
+
+        Some(Ok(family.child(key)))
+    }
+
+    /// Returns a [`metric::threshold::Child`] counting the observations
+    /// exceeding the thresholds of the histogram identified by the provided
+    /// [`metrics::Key`], initializing a new [`metric::threshold::Family`] (or
+    /// reusing the existing one) in the underlying [`prometheus::Registry`].
+    ///
+    /// Returns [`None`] if the provided [`metrics::Key`] doesn't match any
+    /// rule configured for [`metric::threshold::Family`] counters.
+    ///
+    /// # Errors
+    ///
+    /// If the newly initialized [`metric::threshold::Family`] is invalid, or
+    /// the underlying [`prometheus::Registry`] fails to register it.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    #[must_use]
+    pub fn over_threshold(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<prometheus::Result<Arc<metric::threshold::Child>>> {
+        let name = key.name();
+        let thresholds = self.thresholds.find(name)?;
+
+        let existing =
+            self.threshold_families.read().unwrap().get(name).cloned();
+        let family = if let Some(family) = existing {
+            family
+        } else {
+            let mut storage = self.threshold_families.write().unwrap();
+            if let Some(family) = storage.get(name) {
+                family.clone()
+            } else {
+                let family =
+                    match metric::threshold::Family::new(name, thresholds) {
+                        Ok(f) => f,
+                        Err(e) => return Some(Err(e)),
+                    };
+                if let Err(e) =
+                    self.prometheus.register(self.collector(family.clone()))
+                {
+                    return Some(Err(e));
+                }
+                drop(storage.insert(name.into(), family.clone()));
+                family
+            }
+        };
+
+        Some(Ok(family.child(key)))
+    }
+
+    /// Accompanies the histogram identified by the provided [`metrics::Key`]
+    /// with a [`metric::quantile::Family`] of gauges (unless it's accompanied
+    /// already), registering it in the underlying [`prometheus::Registry`].
+    ///
+    /// Returns [`None`] if the provided [`metrics::Key`] doesn't match any
+    /// rule configured for [`metric::quantile::Family`] gauges, or the
+    /// histogram is not registered in this mutable [`Storage`].
+    ///
+    /// # Errors
+    ///
+    /// If the newly initialized [`metric::quantile::Family`] is invalid, or
+    /// the underlying [`prometheus::Registry`] fails to register it.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    #[must_use]
+    pub fn register_quantiles(
+        &self,
+        key: &metrics::Key,
+    ) -> Option<prometheus::Result<()>> {
+        let name = key.name();
+        let quantiles = self.quantiles.find(name)?;
+        let registered =
+            self.quantile_families.read().unwrap().contains_key(name);
+        if registered {
+            return Some(Ok(()));
+        }
+
+        let adaptive =
+            self.adaptive_histograms.read().unwrap().get(name).cloned();
+        let histogram: Arc<dyn prometheus::core::Collector> = if let Some(h) =
+            adaptive
+        {
+            Arc::new(h.metric)
+        } else {
+            let bundle =
+                self.histograms.read().unwrap().get(name)?.metric.clone()?;
+            // Batched observations should be flushed before computing.
+            let batched =
+                (!self.batching.is_empty()).then(|| Arc::clone(&self.batched));
+            Arc::new(Flushing {
+                collector: bundle,
+                flusher: None,
+                batched,
+                striped: None,
+            })
+        };
+
+        let mut storage = self.quantile_families.write().unwrap();
+        if storage.contains_key(name) {
+            return Some(Ok(()));
+        }
+        let family =
+            match metric::quantile::Family::new(name, quantiles, histogram) {
+                Ok(f) => f,
+                Err(e) => return Some(Err(e)),
+            };
+        if let Err(e) = self.prometheus.register(self.collector(family.clone()))
+        {
+            return Some(Err(e));
+        }
+        drop(storage.insert(name.into(), family));
+        drop(storage);
+        Some(Ok(()))
+    }
+
+    /// Wraps the provided [`prometheus::Histogram`] [`Metric`] identified by
+    /// the provided [`metrics::Key`] into a [`metric::batched::Histogram`] (or
+    /// reuses the existing one), if the [`metrics::Key`] matches any rule
+    /// configured for [`metric::batched`] histograms.
+    ///
+    /// Returns the provided [`Metric`] as a [`metrics::Histogram`] otherwise.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn batched(
+        &self,
+        key: &metrics::Key,
+        histogram: Arc<Metric<prometheus::Histogram>>,
+    ) -> metrics::Histogram {
+        let name = key.name();
+        let Some(capacity) = self.batching.find(name) else {
+            return histogram.into();
+        };
+
+        let existing = self.batched.read().unwrap().get(name).cloned();
+        let family = existing.unwrap_or_else(|| {
+            self.batched
+                .write()
+                .unwrap()
+                .entry(name.into())
+                .or_insert_with(|| metric::batched::Family::new(*capacity))
+                .clone()
+        });
+        metrics::Histogram::from_arc(Arc::new(family.child(key, histogram)))
+    }
+
+    /// Flushes and forgets the [`metric::batched::Histogram`] identified by
+    /// the provided [`metrics::Key`], if any, so it's wrapped anew on the next
+    /// registration.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn forget_batched(&self, key: &metrics::Key) {
+        if let Some(family) = self.batched.read().unwrap().get(key.name()) {
+            family.forget(key);
+        }
+    }
+
+    /// Wraps the provided [`metrics::Gauge`] identified by the provided
+    /// [`metrics::Key`] into a [`metric::striped::Gauge`] (or reuses the
+    /// existing one), if the [`metrics::Key`] matches any rule configured for
+    /// [`metric::striped`] gauges.
+    ///
+    /// Returns the provided [`metrics::Gauge`] as is otherwise.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn striped(
+        &self,
+        key: &metrics::Key,
+        gauge: metrics::Gauge,
+    ) -> metrics::Gauge {
+        let name = key.name();
+        let Some(shards) = self.striping.find(name) else {
+            return gauge;
+        };
+
+        let existing = self.striped.read().unwrap().get(name).cloned();
+        let family = existing.unwrap_or_else(|| {
+            self.striped
+                .write()
+                .unwrap()
+                .entry(name.into())
+                .or_insert_with(|| metric::striped::Family::new(*shards))
+                .clone()
+        });
+        metrics::Gauge::from_arc(Arc::new(family.child(key, gauge)))
+    }
+
+    /// Flushes and forgets the [`metric::striped::Gauge`] identified by the
+    /// provided [`metrics::Key`], if any, so it's wrapped anew on the next
+    /// registration.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn forget_striped(&self, key: &metrics::Key) {
+        if let Some(family) = self.striped.read().unwrap().get(key.name()) {
+            family.forget(key);
+        }
+    }
+
+    /// Returns a [`metric::raw::Histogram`] family identified by the provided
+    /// `name` and variable `labels` names, initializing it (or reusing the
+    /// existing one) in the underlying [`prometheus::Registry`].
+    ///
+    /// # Errors
+    ///
+    /// - If the existing [`metric::raw::Histogram`] family has different
+    ///   variable labels.
+    /// - If the underlying [`prometheus::Registry`] fails to register the newly
+    ///   initialized [`metric::raw::Histogram`].
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    pub fn raw_histogram(
+        &self,
+        name: &str,
+        labels: &[String],
+    ) -> prometheus::Result<metric::raw::Histogram> {
+        let existing = self.raw_histograms.read().unwrap().get(name).cloned();
+        let family = if let Some(family) = existing {
+            family
+        } else {
+            let mut storage = self.raw_histograms.write().unwrap();
+            if let Some(family) = storage.get(name) {
+                family.clone()
+            } else {
+                let histogram =
+                    metric::raw::Histogram::new(name, labels.to_vec())?;
+                // This way we reuse existing `description` and annotations if
+                // they have been set before metric registration.
+                let family = self
+                    .histograms
+                    .write()
+                    .unwrap()
+                    .entry(name.into())
+                    .or_default()
+                    .clone()
+                    .map(|_| histogram);
+                self.prometheus.register(self.collector(family.clone()))?;
+                drop(storage.insert(name.into(), family.clone()));
+                family
+            }
+        };
+
+        if family.metric.labels() != labels {
+            return Err(prometheus::Error::Msg(format!(
+                "`{name}` raw histogram is registered already with labels \
+                 `{:?}`, but `{labels:?}` provided",
+                family.metric.labels(),
+            )));
+        }
+        Ok(family.metric)
+    }
+
+    /// Registers a [`metric::windowed::Rate`] gauge with the provided `name`
+    /// in the underlying [`prometheus::Registry`], exposing the increase of the
+    /// `source` [`prometheus::IntCounter`] (registered in this mutable
+    /// [`Storage`]) over the provided `window`.
+    ///
+    /// The `source` counter is resolved lazily on collection, so may be
+    /// registered afterwards. Once resolved, it keeps being used even after
+    /// this mutable [`Storage`] is frozen.
+    ///
+    /// # Errors
+    ///
+    /// - If the provided `name` is invalid.
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   [`metric::windowed::Rate`] (like when it's registered already).
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    pub fn windowed_rate(
+        &self,
+        name: &str,
+        source: &str,
+        window: Duration,
+    ) -> prometheus::Result<()> {
+        use prometheus::core::Collector as _;
+
+        let counters = Arc::clone(&self.counters);
+        let resolved = OnceLock::<metric::PrometheusIntCounter>::new();
+        let name_of_source = KeyName::from(source.to_owned());
+        // Never blocks on the `counters` lock, as is called while holding a
+        // lock of the `prometheus::Registry`.
+        let collect = move || {
+            if let Some(counter) = resolved.get() {
+                return Some(counter.collect());
+            }
+            let found = counters
+                .try_read()
+                .ok()?
+                .get(&name_of_source)
+                .and_then(|c| c.metric.clone());
+            Some(found.map_or_else(Vec::new, |c| {
+                resolved.get_or_init(|| c).collect()
+            }))
+        };
+
+        let rate = metric::windowed::Rate::new(
+            name,
+            format!("Increase of `{source}` over {window:?}."),
+            window,
+            Box::new(collect),
+            Arc::clone(&self.clock),
+        )?;
+        self.prometheus.register(self.collector(rate.clone()))?;
+        drop(self.windowed.write().unwrap().insert(name.into(), rate));
+        Ok(())
+    }
+
+    /// Registers the provided [`metric::CustomBundle`] in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via this
+    /// [`Storage`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// The [`metrics::Key`]s named as any of the `bundle` metrics are resolved
+    /// via it, rather than registering the usual [`prometheus`] metrics.
+    ///
+    /// # Errors
+    ///
+    /// - If any of the `bundle` metrics is registered as a
+    ///   [`metric::CustomBundle`] already.
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   provided `bundle`.
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    pub fn register_custom<B>(&self, bundle: B) -> prometheus::Result<()>
+    where
+        B: metric::CustomBundle + 'static,
+    {
+        use prometheus::core::Collector as _;
+
+        let bundle = metric::custom::Shared::new(bundle);
+        let names = bundle
+            .desc()
+            .into_iter()
+            .map(|d| KeyName::from(d.fq_name.clone()))
+            .collect::<Vec<_>>();
+
+        let mut storage = self.custom.write().unwrap();
+        if let Some(name) = names.iter().find(|n| storage.contains_key(*n)) {
+            return Err(prometheus::Error::Msg(format!(
+                "custom bundle for `{}` metric is registered already",
+                name.as_str(),
+            )));
+        }
+        self.prometheus.register(self.collector(bundle.clone()))?;
+        for name in names {
+            drop(storage.insert(name, bundle.clone()));
+        }
+        drop(storage);
+        self.has_custom.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns the [`metric::CustomBundle`] registered in this mutable
+    /// [`Storage`] for the metric identified by its `name`, if any.
+    ///
+    /// Doesn't lock anything, unless any [`metric::CustomBundle`] has ever
+    /// been registered in this mutable [`Storage`].
+    #[expect( // intentional
+        clippy::missing_panics_doc,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    #[must_use]
+    pub fn custom(&self, name: &str) -> Option<metric::custom::Shared> {
+        if !self.has_custom.load(Ordering::Acquire) {
+            return None;
+        }
+        self.custom.read().unwrap().get(name).cloned()
+    }
+
+    /// Registers the provided [`prometheus`] `metric` in the underlying
+    /// [`prometheus::Registry`] in the way making it usable via this
+    /// [`metrics::registry::Storage`] (and, so, [`metrics`] crate interfaces).
+    ///
+    /// Accepts only the following [`prometheus`] metrics:
+    /// - [`prometheus::IntCounter`], [`prometheus::IntCounterVec`]
+    /// - [`prometheus::Gauge`], [`prometheus::GaugeVec`]
+    /// - [`prometheus::Histogram`], [`prometheus::HistogramVec`]
+    ///
+    /// If a [`prometheus`] metric with the identical schema (name, help,
+    /// constant and variable labels) is registered already, then it's reused
+    /// instead, and the provided `metric` is not registered at all. This way,
+    /// only the already registered metric is exported, so any values recorded
+    /// directly into the provided `metric` won't appear in the
+    /// [`prometheus::Registry`].
+    ///
+    /// # Errors
+    ///
+    /// If the underlying [`prometheus::Registry`] fails to register the
+    /// provided `metric`.
+    ///
+    /// [`metrics::registry::Storage`]: metrics_util::registry::Storage
+    pub fn register_external<M>(&self, metric: M) -> prometheus::Result<()>
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        self.register_external_handle(metric).map(drop)
+    }
+
+    /// Registers the provided [`prometheus`] `metric` in the same way as the
+    /// [`Storage::register_external()`] does, returning a [`metric::Handle`]
+    /// to the actually registered [`prometheus`] metric (being the already
+    /// registered one, if reused).
+    ///
+    /// # Errors
+    ///
+    /// - If the underlying [`prometheus::Registry`] fails to register the
+    ///   provided `metric`.
+    /// - If the reused [`prometheus`] metric is of another type than the
+    ///   provided `metric` (like a [`prometheus::IntCounter`] instead of a
+    ///   [`prometheus::IntCounterVec`] without variable labels).
+    pub fn register_external_handle<M>(
+        &self,
+        metric: M,
+    ) -> prometheus::Result<metric::Handle<M>>
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        if self.config.load().strict {
+            validate_descs(&metric.desc())?;
+        }
+
+        let name = metric
+            .desc()
+            .first()
+            .map(|d| d.fq_name.clone())
+            .unwrap_or_default();
+        let res = self.register_external_bundle(&name, metric);
+        trace::registered_external(&name, &res);
+        res
+    }
+
+    /// Registers the provided [`prometheus`] `metric` identified by the
+    /// provided `name` in the way described for the
+    /// [`Storage::register_external_handle()`] method.
+    ///
+    /// # Errors
+    ///
+    /// See the [`Storage::register_external_handle()`] method.
+    #[expect( // intentional
+        clippy::unwrap_in_result,
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here (considering the \
+                  `prometheus::Registry::register()` does not)"
+    )]
+    #[expect( // intentional
+        clippy::significant_drop_tightening,
+        reason = "write lock on `storage` is intentionally held till the end \
+                  of the scope, to perform the registration in \
+                  `prometheus::Registry` exclusively"
+    )]
+    fn register_external_bundle<M>(
+        &self,
+        name: &str,
+        metric: M,
+    ) -> prometheus::Result<metric::Handle<M>>
+    where
+        M: metric::Bundled + prometheus::core::Collector,
+        <M as metric::Bundled>::Bundle:
+            prometheus::core::Collector + Clone + 'static,
+        Self: super::Get<Collection<<M as metric::Bundled>::Bundle>>,
+    {
+        use super::Get as _;
+        use prometheus::core::Collector as _;
+
+        let bundle = metric.clone().into_bundle();
+
+        // We do intentionally hold here the write lock on `storage` till
+        // the end of the scope, to perform the registration in
+        // `prometheus::Registry` exclusively.
+        let mut storage = self.collection().write().unwrap();
+        if let Some(existing) = storage
+            .get(name)
+            .and_then(|existing| existing.metric.as_ref())
+            .filter(|existing| is_same_schema(&existing.desc(), &bundle.desc()))
+        {
+            return M::from_bundle(existing)
+                .map(metric::Handle::wrap)
+                .ok_or_else(|| {
+                    prometheus::Error::Msg(format!(
+                        "`{name}` metric is registered already with another \
+                         type",
+                    ))
+                });
+        }
+        let entry = metric::Describable::wrap(Some(bundle));
+        // We should register in `prometheus::Registry` before storing in our
+        // `Collection`. This way `metrics::Recorder` implementations using this
+        // `storage::Mutable` will be able to retry registration in
+        // `prometheus::Registry`. Once stored, it's re-registered by
+        // `Storage::move_to()` on a swap.
+        self.prometheus
+            .register(self.collector(entry.clone().map(Option::unwrap)))?;
+        drop(storage.insert(name.into(), entry));
+
+        Ok(metric::Handle::wrap(metric))
+    }
+}
+
+/// Changes the [`help` description] of the metric identified by its `name` in
+/// the provided [`Collection`].
+///
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn describe<B: Clone>(
+    collection: &Collection<B>,
+    name: &str,
+    description: metrics::SharedString,
+) {
+    let read_storage = collection.read().unwrap();
+    if let Some(metric) = read_storage.get(name) {
+        metric.description.store(Arc::new(description));
+    } else {
+        drop(read_storage);
+        // We do intentionally hold here the `write_storage` lock till the end
+        // of the scope, to perform all the operations atomically.
+        let mut write_storage = collection.write().unwrap();
+        describe_locked(&mut write_storage, name, description);
+    }
+}
+
+/// Indicates whether the provided [`Map`] contains the provided `name`,
+/// releasing its lock right away.
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn contains<V>(map: &Map<KeyName, V>, name: &str) -> bool {
+    map.read().unwrap().contains_key(name)
+}
+
+/// Unregisters all the metrics of the provided [`Collection`] from the provided
+/// [`prometheus::Registry`], keeping their [`help` description]s only.
+///
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+#[expect( // intentional
+    clippy::iter_over_hash_type,
+    reason = "order of unregistering doesn't matter"
+)]
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn unregister<B>(registry: &prometheus::Registry, collection: &Collection<B>)
+where
+    B: prometheus::core::Collector + 'static,
+{
+    for entry in collection.write().unwrap().values_mut() {
+        if let Some(metric) = entry.metric.take() {
+            drop(registry.unregister(Box::new(metric)));
+        }
+    }
+}
+
+/// Creates a [`prometheus::Error`] describing the metric identified by the
+/// provided [`metrics::Key`] being rejected under the reserved `prefix` for
+/// the provided `reason`.
+fn reserved(
+    key: &metrics::Key,
+    prefix: &str,
+    reason: &str,
+) -> prometheus::Error {
+    prometheus::Error::Msg(format!(
+        "`{}` metric cannot be registered on the fly under the reserved \
+         `{prefix}` prefix, as {reason}",
+        key.name(),
+    ))
+}
+
+/// Returns the [`Annotations`] of the metric identified by its `name` in the
+/// provided [`Collection`], creating them if the metric isn't known yet.
+///
+/// [`Annotations`]: metric::annotation::Annotations
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn annotations<B>(
+    collection: &Collection<B>,
+    name: &str,
+) -> metric::annotation::Annotations {
+    if let Some(metric) = collection.read().unwrap().get(name) {
+        return metric.annotations.clone();
+    }
+    collection
+        .write()
+        .unwrap()
+        .entry(name.into())
+        .or_default()
+        .annotations
+        .clone()
+}
+
+/// Returns the [`Origins`] of the metric identified by its `name` in the
+/// provided [`Collection`], creating them if the metric isn't known yet.
+///
+/// [`Origins`]: metric::origin::Origins
+#[cfg(feature = "origins")]
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn origins<B>(
+    collection: &Collection<B>,
+    name: &str,
+) -> metric::origin::Origins {
+    if let Some(metric) = collection.read().unwrap().get(name) {
+        return metric.origins.clone();
+    }
+    collection.write().unwrap().entry(name.into()).or_default().origins.clone()
+}
+
+/// Changes the [`help` description] of the metric identified by its `name` in
+/// the provided locked [`Collection`].
+///
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+fn describe_locked<B>(
+    collection: &mut HashMap<KeyName, metric::Describable<Option<B>>>,
+    name: &str,
+    description: metrics::SharedString,
+) {
+    if let Some(metric) = collection.get(name) {
+        metric.description.store(Arc::new(description));
+    } else {
+        drop(collection.insert(
+            name.into(),
+            metric::Describable::only_description(description),
+        ));
+    }
+}
+
+/// Applies the provided [`pending::Description`] to the metric it describes in
+/// the provided locked [`Collection`].
+fn apply_locked<B>(
+    collection: &mut HashMap<KeyName, metric::Describable<Option<B>>>,
+    pending: pending::Description,
+) {
+    describe_locked(collection, pending.name.as_str(), pending.description);
+    #[cfg(feature = "origins")]
+    if let Some(metric) = collection.get(pending.name.as_str()) {
+        metric.origins.describe(|| pending.origin);
+    }
+}
+
+/// Indicates whether the metric identified by the provided `name` is
+/// registered in the provided [`Collection`], releasing its lock right away.
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn is_registered<B>(collection: &Collection<B>, name: &str) -> bool {
+    collection.read().unwrap().get(name).is_some_and(|m| m.metric.is_some())
+}
+
+/// Returns names of the metrics having a [`help` description] in the provided
+/// [`Collection`], but never registered.
+///
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn described_only<B>(collection: &Collection<B>) -> Vec<KeyName> {
+    collection
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(_, m)| m.metric.is_none() && !m.description.load().is_empty())
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Removes the orphan metric identified by its `name` from the provided
+/// [`Collection`], unless it has been registered meanwhile.
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn forget_orphan<B>(collection: &Collection<B>, name: &str) {
+    let mut collection = collection.write().unwrap();
+    if collection.get(name).is_some_and(|m| m.metric.is_none()) {
+        drop(collection.remove(name));
+    }
+}
+
+/// Removes the blank (neither registered, nor described) metric identified by
+/// its `name` from the provided [`Collection`], unless anyone else has taken
+/// it meanwhile, besides the provided `taken` one.
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn forget_blank<B, M>(
+    collection: &Collection<B>,
+    name: &str,
+    taken: &metric::Describable<M>,
+) {
+    let mut collection = collection.write().unwrap();
+    // Blank metric must be held only by the `collection` itself and the
+    // `taken` one.
+    let blank = collection.get(name).is_some_and(|m| {
+        m.metric.is_none()
+            && m.description.load().is_empty()
+            && Arc::strong_count(&m.description) <= 2
+            && Arc::ptr_eq(&m.description, &taken.description)
+    });
+    if blank {
+        drop(collection.remove(name));
+    }
+}
+
+/// Returns names of the metrics registered in the provided [`Collection`], but
+/// having no values recorded into them.
+#[expect( // intentional
+    clippy::unwrap_used,
+    reason = "`RwLock` usage is fully panic-safe here"
+)]
+fn unrecorded<B>(collection: &Collection<B>) -> Vec<KeyName>
+where
+    B: prometheus::core::Collector,
+{
+    collection
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(_, m)| {
+            m.metric.as_ref().is_some_and(|b| is_unrecorded(&b.collect()))
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Checks whether all the values of the provided collected
+/// [`prometheus::proto::MetricFamily`]s are zero.
+fn is_unrecorded(families: &[prometheus::proto::MetricFamily]) -> bool {
+    families.iter().flat_map(prometheus::proto::MetricFamily::get_metric).all(
+        |m| {
+            m.get_counter().get_value() == 0.0
+                && m.get_gauge().get_value() == 0.0
+                && m.get_histogram().get_sample_count() == 0
+                && m.get_summary().get_sample_count() == 0
+        },
+    )
+}
+
+/// Applier of the [`pending::Description`]s to the [`Collection`]s of a
+/// mutable [`Storage`].
+#[derive(Clone, Debug)]
+struct Flusher {
+    /// [`pending::Queue`] of the [`pending::Description`]s to apply.
+    queue: pending::Queue,
+
+    /// [`Collection`] of [`prometheus::IntCounter`] metrics.
+    counters: Collection<metric::PrometheusIntCounter>,
+
+    /// [`Collection`] of [`prometheus::Gauge`] metrics.
+    gauges: Collection<metric::PrometheusGauge>,
+
+    /// [`Collection`] of [`prometheus::Histogram`] metrics.
+    histograms: Collection<metric::PrometheusHistogram>,
+
+    /// [`metric::adaptive::Histogram`]s, considered registered by the
+    /// [`Orphans::Reject`] policy.
+    ///
+    /// [`Orphans::Reject`]: super::Orphans::Reject
+    adaptive_histograms:
+        Map<KeyName, metric::Describable<metric::adaptive::Histogram>>,
+
+    /// [`metric::raw::Histogram`]s, considered registered by the
+    /// [`Orphans::Reject`] policy.
+    ///
+    /// [`Orphans::Reject`]: super::Orphans::Reject
+    raw_histograms: Map<KeyName, metric::Describable<metric::raw::Histogram>>,
+
+    /// [`metric::CustomBundle`]s, considered registered by the
+    /// [`Orphans::Reject`] policy.
+    ///
+    /// [`Orphans::Reject`]: super::Orphans::Reject
+    custom: Map<KeyName, metric::custom::Shared>,
+
+    /// [`metrics::Unit`]s of the metric families to record the declared ones
+    /// into.
+    units: Map<KeyName, metrics::Unit>,
+
+    /// Policy of handling orphan [`help` description]s.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    orphans: super::Orphans,
+
+    /// Moments the metrics were first described at, by their names.
+    described_at: Map<KeyName, Instant>,
+
+    /// [`Clock`] the moments of the descriptions are taken from.
+    ///
+    /// [`Clock`]: clock::Clock
+    clock: clock::Shared,
+}
+
+impl Flusher {
+    /// Applies all the [`pending::Description`]s (along with their declared
+    /// [`metrics::Unit`]s and the [`Orphans`] policy), blocking on the locks,
+    /// if required.
+    ///
+    /// Returns whether there were any [`pending::Description`]s to apply.
+    ///
+    /// [`Orphans`]: super::Orphans
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn flush(&self) -> bool {
+        if self.queue.is_empty() {
+            return false;
+        }
+        for d in self.queue.take() {
+            if !self.adopt(d.kind, d.name.as_str()) {
+                continue;
+            }
+            if let Some(unit) = d.unit {
+                _ = self
+                    .units
+                    .write()
+                    .unwrap()
+                    .insert(d.name.as_str().into(), unit);
+            }
+            match d.kind {
+                pending::Kind::Counter => {
+                    apply_locked(&mut self.counters.write().unwrap(), d);
+                }
+                pending::Kind::Gauge => {
+                    apply_locked(&mut self.gauges.write().unwrap(), d);
+                }
+                pending::Kind::Histogram => {
+                    apply_locked(&mut self.histograms.write().unwrap(), d);
+                }
+            }
+        }
+        true
+    }
+
+    /// Applies the [`Orphans`] policy to the [`pending::Description`] of the
+    /// metric of the provided [`pending::Kind`] identified by the provided
+    /// `name`, returning whether it should be applied.
+    ///
+    /// Rejected [`pending::Description`]s are dropped silently, as there is
+    /// no caller to report them to anymore.
+    ///
+    /// [`Orphans`]: super::Orphans
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn adopt(&self, kind: pending::Kind, name: &str) -> bool {
+        match self.orphans {
+            super::Orphans::Keep => true,
+            super::Orphans::Reject => {
+                // Every lock is released before acquiring the next one (see
+                // the `Storage::adopt_description()` method for details).
+                let registered = match kind {
+                    pending::Kind::Counter => {
+                        is_registered(&self.counters, name)
+                    }
+                    pending::Kind::Gauge => is_registered(&self.gauges, name),
+                    pending::Kind::Histogram => {
+                        is_registered(&self.histograms, name)
+                    }
+                };
+                registered
+                    || contains(&self.adaptive_histograms, name)
+                    || contains(&self.raw_histograms, name)
+                    || contains(&self.custom, name)
+            }
+            super::Orphans::Expire(_) => {
+                let now = self.clock.now();
+                _ = self
+                    .described_at
+                    .write()
+                    .unwrap()
+                    .entry(name.into())
+                    .or_insert(now);
+                true
+            }
+        }
+    }
+
+    /// Applies all the [`pending::Description`]s, unless any of the
+    /// [`Collection`]s is locked already (leaving them pending in such case).
+    ///
+    /// Never blocks, so is safe to be called while holding a lock of a
+    /// [`prometheus::Registry`].
+    ///
+    /// Leaves them pending for the [`Flusher::flush()`] if the [`Orphans`]
+    /// policy requires inspecting other locks.
+    ///
+    /// [`Orphans`]: super::Orphans
+    fn try_flush(&self) {
+        if self.queue.is_empty()
+            || !matches!(self.orphans, super::Orphans::Keep)
+        {
+            return;
+        }
+        let Some(mut counters) = try_write(&self.counters) else { return };
+        let Some(mut gauges) = try_write(&self.gauges) else { return };
+        let Some(mut histograms) = try_write(&self.histograms) else {
+            return;
+        };
+        let Some(mut units) = try_write(&self.units) else { return };
+        for d in self.queue.take() {
+            if let Some(unit) = d.unit {
+                _ = units.insert(d.name.as_str().into(), unit);
+            }
+            match d.kind {
+                pending::Kind::Counter => apply_locked(&mut counters, d),
+                pending::Kind::Gauge => apply_locked(&mut gauges, d),
+                pending::Kind::Histogram => apply_locked(&mut histograms, d),
+            }
+        }
+    }
+}
+
+/// Tries to acquire the write lock of the provided [`Map`] without blocking.
+fn try_write<K, V>(
+    map: &Map<K, V>,
+) -> Option<RwLockWriteGuard<'_, HashMap<K, V>>> {
+    match map.try_write() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// [`prometheus::core::Collector`] applying the [`pending::Description`]s and
+/// flushing the [`metric::batched::Histogram`]s and [`metric::striped::Gauge`]s
+/// before collecting the metrics of the wrapped one.
+#[derive(Debug)]
+struct Flushing<C> {
+    /// Wrapped [`prometheus::core::Collector`].
+    collector: C,
+
+    /// [`Flusher`] of the [`pending::Description`]s, if any.
+    flusher: Option<Flusher>,
+
+    /// [`metric::batched::Family`]s to be flushed, if any.
+    batched: Option<Map<KeyName, metric::batched::Family>>,
+
+    /// [`metric::striped::Family`]s to be flushed, if any.
+    striped: Option<Map<KeyName, metric::striped::Family>>,
+}
+
+#[warn(clippy::missing_trait_methods)]
+impl<C> prometheus::core::Collector for Flushing<C>
+where
+    C: prometheus::core::Collector,
+{
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        self.collector.desc()
+    }
+
+    #[expect( // intentional
+        clippy::unwrap_used,
+        reason = "`RwLock` usage is fully panic-safe here"
+    )]
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        if let Some(batched) = &self.batched {
+            let families = batched.read().unwrap();
+            for desc in self.collector.desc() {
+                if let Some(family) = families.get(desc.fq_name.as_str()) {
+                    family.flush();
+                }
+            }
+        }
+        if let Some(striped) = &self.striped {
+            let families = striped.read().unwrap();
+            for desc in self.collector.desc() {
+                if let Some(family) = families.get(desc.fq_name.as_str()) {
+                    family.flush();
+                }
+            }
+        }
+        if let Some(flusher) = &self.flusher {
+            flusher.try_flush();
+        }
+        self.collector.collect()
+    }
+}
+
+/// Validates the provided [`prometheus::core::Desc`]s to have no empty names
+/// and constant label values.
+///
+/// # Errors
+///
+/// If any of the provided [`prometheus::core::Desc`]s has an empty name, or any
+/// of its constant labels has an empty value.
+fn validate_descs(descs: &[&prometheus::core::Desc]) -> prometheus::Result<()> {
+    for desc in descs {
+        let name = &desc.fq_name;
+        if name.is_empty() {
+            return Err(prometheus::Error::Msg(
+                "metric name cannot be empty".into(),
+            ));
+        }
+        if let Some(label) =
+            desc.const_label_pairs.iter().find(|l| l.get_value().is_empty())
+        {
+            return Err(prometheus::Error::Msg(format!(
+                "value of `{}` label of `{name}` metric cannot be empty",
+                label.get_name(),
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// [`metric::Bundle`] created lazily, on the first access to it.
+///
+/// [`metric::Bundle`]: crate::metric::Bundle
+trait Lazy: Sized {
+    /// Creates a new [`metric::Bundle`] identified by the provided
+    /// [`metrics::Key`], according to the configuration of the provided
+    /// mutable [`Storage`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`metric::Bundle`] cannot be created.
+    ///
+    /// [`metric::Bundle`]: crate::metric::Bundle
+    fn create(
+        key: &metrics::Key,
+        storage: &Storage,
+    ) -> prometheus::Result<Self>;
+
+    /// Returns the buckets (excluding the implicit `+Inf` one) the
+    /// [`metric::Bundle`] identified by the provided [`metrics::Key`] is
+    /// created with, if it's a histogram one.
+    ///
+    /// [`metric::Bundle`]: crate::metric::Bundle
+    fn buckets(_: &metrics::Key, _: &Storage) -> Option<Arc<[f64]>> {
+        None
+    }
+}
+
+impl Lazy for metric::PrometheusIntCounter {
+    fn create(key: &metrics::Key, _: &Storage) -> prometheus::Result<Self> {
+        key.try_into()
+    }
+}
+
+impl Lazy for metric::PrometheusGauge {
+    fn create(key: &metrics::Key, _: &Storage) -> prometheus::Result<Self> {
+        key.try_into()
+    }
+}
+
+impl Lazy for metric::PrometheusHistogram {
+    fn create(
+        key: &metrics::Key,
+        storage: &Storage,
+    ) -> prometheus::Result<Self> {
+        if storage.count_only.find(key.name()).is_some() {
+            // `prometheus` treats the trailing `+Inf` bucket as the implicit
+            // one, so no bucket bookkeeping is done at all.
+            return Self::with_buckets(key, vec![f64::INFINITY]);
+        }
+        storage.default_buckets.as_ref().map_or_else(
+            || key.try_into(),
+            |buckets| Self::with_buckets(key, buckets.to_vec()),
+        )
+    }
+
+    fn buckets(key: &metrics::Key, storage: &Storage) -> Option<Arc<[f64]>> {
+        if storage.count_only.find(key.name()).is_some() {
+            return Some(Arc::new([]));
+        }
+        Some(
+            storage.default_buckets.clone().unwrap_or_else(|| {
+                prometheus::DEFAULT_BUCKETS.as_slice().into()
+            }),
+        )
+    }
+}
+
+/// Checks whether the provided [`prometheus::core::Desc`]s describe the
+/// identical metrics schema (regardless of the variable labels order).
+pub(super) fn is_same_schema(
+    a: &[&prometheus::core::Desc],
+    b: &[&prometheus::core::Desc],
+) -> bool {
+    let sorted = |labels: &Vec<String>| {
+        let mut labels = labels.clone();
+        labels.sort_unstable();
+        labels
+    };
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            a.fq_name == b.fq_name
+                && a.help == b.help
+                && a.const_label_pairs == b.const_label_pairs
+                && sorted(&a.variable_labels) == sorted(&b.variable_labels)
+        })
+}
+
+impl metrics_util::registry::Storage<metrics::Key> for Storage {
+    // PANIC: We cannot panic inside `metrics_util::registry::Storage`
+    //        implementation, because it will poison locks used inside
+    //        `metrics_util::registry::Registry`. That's why we should pass
+    //        possible errors through it and deal with them inside
+    //        `metrics::Recorder` implementation.
+    type Counter = metric::Fallible<prometheus::IntCounter>;
+    type Gauge = metric::Fallible<prometheus::Gauge>;
+    type Histogram = metric::Fallible<prometheus::Histogram>;
+
+    fn counter(&self, key: &metrics::Key) -> Self::Counter {
+        let res = self
+            .config
+            .load()
+            .validated(key)
+            .and_then(|key| self.register::<prometheus::IntCounter>(&key));
+        if let (Ok(counter), Some(initial)) =
+            (&res, self.initial_counters.find(key.name()))
+        {
+            let counter: &prometheus::IntCounter = (**counter).as_ref();
+            // Only a never touched counter is initialized, as the same one may
+            // be resolved by multiple `metrics::Key`s (like ones with
+            // differently ordered labels).
+            if counter.get() == 0 {
+                counter.inc_by(*initial);
+            }
+        }
+        trace::registered("counter", key, &res);
+        res.into()
+    }
+
+    fn gauge(&self, key: &metrics::Key) -> Self::Gauge {
+        let res = self
+            .config
+            .load()
+            .validated(key)
+            .and_then(|key| self.register::<prometheus::Gauge>(&key));
+        if let (Ok(gauge), Some(initial)) =
+            (&res, self.initial_gauges.find(key.name()))
+        {
+            let gauge: &prometheus::Gauge = (**gauge).as_ref();
+            // Only a never touched gauge is initialized, as the same one may be
+            // resolved by multiple `metrics::Key`s (like ones with differently
+            // ordered labels).
+            if gauge.get().to_bits() == 0 {
+                gauge.set(*initial);
+            }
+        }
+        trace::registered("gauge", key, &res);
+        res.into()
     }
 
     fn histogram(&self, key: &metrics::Key) -> Self::Histogram {
-        self.register::<prometheus::Histogram>(key).into()
+        let res = self
+            .config
+            .load()
+            .validated(key)
+            .and_then(|key| self.register::<prometheus::Histogram>(&key));
+        trace::registered("histogram", key, &res);
+        res.into()
     }
 }
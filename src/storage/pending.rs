@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Lock-free queue of pending [`help` description]s of metrics.
+//!
+//! [`help` description]: prometheus::proto::MetricFamily::get_help
+
+use std::sync::Arc;
+
+use arc_swap::ArcSwapOption;
+
+#[cfg(feature = "origins")]
+use crate::metric::origin::Origin;
+
+/// Kind of a metric a pending [`Description`] belongs to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    /// [`prometheus::IntCounter`] metric.
+    Counter,
+
+    /// [`prometheus::Gauge`] metric.
+    Gauge,
+
+    /// [`prometheus::Histogram`] metric.
+    Histogram,
+}
+
+impl Kind {
+    /// Returns the name of this [`Kind`] of metrics.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+/// Pending [`help` description] of a metric.
+///
+/// [`help` description]: prometheus::proto::MetricFamily::get_help
+#[derive(Clone, Debug)]
+pub struct Description {
+    /// [`Kind`] of the described metric.
+    pub kind: Kind,
+
+    /// Name of the described metric.
+    pub name: metrics::KeyName,
+
+    /// [`help` description] itself.
+    ///
+    /// [`help` description]: prometheus::proto::MetricFamily::get_help
+    pub description: metrics::SharedString,
+
+    /// Declared [`metrics::Unit`] of the described metric, if any.
+    pub unit: Option<metrics::Unit>,
+
+    /// [`Origin`] of the description.
+    ///
+    /// [`Origin`]: crate::metric::origin::Origin
+    #[cfg(feature = "origins")]
+    pub origin: Origin,
+}
+
+/// Node of a [`Queue`].
+#[derive(Clone, Debug)]
+struct Node {
+    /// [`Description`] stored in this [`Node`].
+    description: Arc<Description>,
+
+    /// Previously pushed [`Node`], if any.
+    next: Option<Arc<Self>>,
+}
+
+/// Lock-free queue of pending [`Description`]s.
+///
+/// Pushing never blocks (being a single compare-and-swap in the uncontended
+/// case), so is suitable for latency-critical paths.
+#[derive(Clone, Debug, Default)]
+pub struct Queue(Arc<ArcSwapOption<Node>>);
+
+impl Queue {
+    /// Pushes the provided [`Description`] into this [`Queue`].
+    pub fn push(&self, description: Description) {
+        let description = Arc::new(description);
+        drop(self.0.rcu(|next| {
+            Some(Arc::new(Node {
+                description: Arc::clone(&description),
+                next: next.clone(),
+            }))
+        }));
+    }
+
+    /// Indicates whether this [`Queue`] has no pending [`Description`]s.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.load().is_none()
+    }
+
+    /// Takes all the pending [`Description`]s out of this [`Queue`], in the
+    /// order they were pushed.
+    #[must_use]
+    pub fn take(&self) -> Vec<Description> {
+        let mut out = Vec::new();
+        let mut node = self.0.swap(None);
+        while let Some(n) = node {
+            let n = Arc::unwrap_or_clone(n);
+            out.push(Arc::unwrap_or_clone(n.description));
+            node = n.next;
+        }
+        out.reverse();
+        out
+    }
+}
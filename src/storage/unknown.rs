@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracking of the unknown metrics accessed via a [`FrozenRecorder`].
+//!
+//! [`FrozenRecorder`]: crate::FrozenRecorder
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::budget::OVERFLOW_LABEL_VALUE;
+
+use super::KeyName;
+
+/// Mutable state of a [`Tracker`].
+#[derive(Debug, Default)]
+struct State {
+    /// Numbers of accesses to the tracked unknown metrics, by their names.
+    counts: HashMap<KeyName, u64>,
+
+    /// Number of accesses to the unknown metrics not fitting into the
+    /// [`Tracker::max_names`].
+    overflow: u64,
+}
+
+/// Tracker of the metrics unknown to a [`FrozenRecorder`] (not registered
+/// before it was built), which are silently dropped otherwise.
+///
+/// Tracks up to the [`Tracker::max_names`] distinct names, counting accesses
+/// to the other ones under the [`OVERFLOW_LABEL_VALUE`].
+///
+/// Optionally, reports the tracked accesses via the
+/// `metrics_prometheus_frozen_unknown_total` counter, labeled with the `name`
+/// of the unknown metric.
+///
+/// [`FrozenRecorder`]: crate::FrozenRecorder
+#[derive(Debug)]
+pub(crate) struct Tracker {
+    /// Maximum number of distinct names tracked by this [`Tracker`].
+    max_names: usize,
+
+    /// [`State`] of this [`Tracker`].
+    state: Mutex<State>,
+
+    /// Counter reporting the tracked accesses, if exported.
+    exported: Option<prometheus::IntCounterVec>,
+
+    /// Result of registering the counter of this [`Tracker`] in a
+    /// [`prometheus::Registry`], if registered.
+    registered: Mutex<Option<Result<(), String>>>,
+}
+
+impl Tracker {
+    /// Creates a new [`Tracker`] of up to the provided `max_names` distinct
+    /// names, optionally `exported` as a counter.
+    #[expect( // intentional
+        clippy::expect_used,
+        reason = "counter is created with the valid constant options"
+    )]
+    pub(crate) fn new(max_names: usize, exported: bool) -> Self {
+        let exported = exported.then(|| {
+            prometheus::IntCounterVec::new(
+                prometheus::Opts::new(
+                    "metrics_prometheus_frozen_unknown_total",
+                    "Number of accesses to metrics unknown to the frozen \
+                     recorder.",
+                ),
+                &["name"],
+            )
+            .expect("valid counter options")
+        });
+        Self {
+            max_names,
+            state: Mutex::default(),
+            exported,
+            registered: Mutex::default(),
+        }
+    }
+
+    /// Tracks an access to the unknown metric with the provided `name`,
+    /// registering the counter of this [`Tracker`] in the provided
+    /// [`prometheus::Registry`], unless registered already.
+    ///
+    /// # Errors
+    ///
+    /// If the provided [`prometheus::Registry`] fails to register the counter.
+    pub(crate) fn track(
+        &self,
+        name: &str,
+        registry: &prometheus::Registry,
+    ) -> prometheus::Result<()> {
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let label = if state.counts.contains_key(name)
+            || state.counts.len() < self.max_names
+        {
+            *state.counts.entry(name.into()).or_default() += 1;
+            name
+        } else {
+            state.overflow += 1;
+            OVERFLOW_LABEL_VALUE
+        };
+        drop(state);
+
+        let Some(counter) = &self.exported else {
+            return Ok(());
+        };
+        counter.with_label_values(&[label]).inc();
+
+        let mut registered =
+            self.registered.lock().unwrap_or_else(PoisonError::into_inner);
+        let res = registered
+            .get_or_insert_with(|| {
+                registry
+                    .register(Box::new(counter.clone()))
+                    .map_err(|e| e.to_string())
+            })
+            .clone();
+        drop(registered);
+        res.map_err(|e| {
+            prometheus::Error::Msg(format!(
+                "failed to register unknown metrics counter: {e}",
+            ))
+        })
+    }
+
+    /// Unregisters the counter of this [`Tracker`] from the provided
+    /// [`prometheus::Registry`], if registered.
+    pub(crate) fn unregister(&self, registry: &prometheus::Registry) {
+        let mut registered =
+            self.registered.lock().unwrap_or_else(PoisonError::into_inner);
+        if let (Some(Ok(())), Some(counter)) =
+            (registered.take(), &self.exported)
+        {
+            drop(registry.unregister(Box::new(counter.clone())));
+        }
+        drop(registered);
+    }
+
+    /// Returns the numbers of accesses to the tracked unknown metrics, by
+    /// their names.
+    ///
+    /// Accesses to the unknown metrics not fitting into the
+    /// [`Tracker::max_names`] are counted under the [`OVERFLOW_LABEL_VALUE`].
+    pub(crate) fn counts(&self) -> BTreeMap<String, u64> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut counts = state
+            .counts
+            .iter()
+            .map(|(name, count)| (name.clone(), *count))
+            .collect::<BTreeMap<_, _>>();
+        if state.overflow > 0 {
+            _ = counts.insert(OVERFLOW_LABEL_VALUE.into(), state.overflow);
+        }
+        drop(state);
+        counts
+    }
+}
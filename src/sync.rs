@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Synchronization primitives guarding [`storage`]s, substituted with the
+//! [`loom`] ones when building with `--cfg metrics_prometheus_loom`, so the
+//! lock ordering can be model-checked.
+//!
+//! [`loom`]: https://docs.rs/loom
+//! [`storage`]: crate::storage
+
+#[cfg(metrics_prometheus_loom)]
+pub(crate) use loom::sync::{RwLock, RwLockWriteGuard};
+#[cfg(not(metrics_prometheus_loom))]
+pub(crate) use std::sync::{RwLock, RwLockWriteGuard};
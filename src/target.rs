@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Prefixing of metric names according to the crates registering them (as
+//! reported by [`metrics::Metadata::target()`]).
+//!
+//! Keeps metrics of third-party libraries from colliding with the ones of an
+//! application in a shared [`prometheus::Registry`].
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use crate::matcher;
+
+/// Maximum number of the prefixed [`metrics::Key`]s cached by [`Prefixes`].
+///
+/// Keeps high-cardinality labels from growing the cache unboundedly, while the
+/// [`metrics::Key`]s not fitting into it are prefixed on every registration.
+const CACHE_CAPACITY: usize = 4096;
+
+/// Prefix of the metric names registered by the crates matched by a
+/// [`Matcher`].
+///
+/// [`Matcher`]: matcher::Matcher
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Prefix {
+    /// Name of the registering crate, followed by `_` (like `hyper_`).
+    CrateName,
+
+    /// Custom prefix (like `http_client_`).
+    Custom(String),
+
+    /// No prefix, exempting the matched crates from the following rules.
+    None,
+}
+
+impl Prefix {
+    /// Returns the prefix to be applied to the metrics registered by the
+    /// provided `krate`, if any.
+    #[must_use]
+    pub fn of<'p>(&'p self, krate: &str) -> Option<Cow<'p, str>> {
+        match self {
+            Self::CrateName => Some(format!("{krate}_").into()),
+            Self::Custom(prefix) => Some(prefix.as_str().into()),
+            Self::None => None,
+        }
+    }
+}
+
+impl From<&str> for Prefix {
+    /// Creates a [`Prefix::Custom`] out of the provided `prefix`.
+    fn from(prefix: &str) -> Self {
+        Self::Custom(prefix.into())
+    }
+}
+
+impl From<String> for Prefix {
+    /// Creates a [`Prefix::Custom`] out of the provided `prefix`.
+    fn from(prefix: String) -> Self {
+        Self::Custom(prefix)
+    }
+}
+
+/// Returns the name of the crate out of the provided `target` module path
+/// (like `hyper` out of `hyper::proto::h1`).
+#[must_use]
+pub fn crate_name(target: &str) -> &str {
+    target.split("::").next().unwrap_or_default()
+}
+
+/// [`matcher::Rules`] of [`Prefix`]es, by the names of the crates registering
+/// metrics, caching the resolved prefixes and the prefixed [`metrics::Key`]s.
+#[derive(Debug, Default)]
+pub(crate) struct Prefixes {
+    /// [`matcher::Rules`] of [`Prefix`]es.
+    rules: matcher::Rules<Prefix>,
+
+    /// [`Cache`] of the resolved prefixes and prefixed [`metrics::Key`]s.
+    cache: RwLock<Cache>,
+}
+
+// Manual implementation is required to start with an empty `Cache`.
+impl Clone for Prefixes {
+    fn clone(&self) -> Self {
+        Self { rules: self.rules.clone(), cache: RwLock::default() }
+    }
+}
+
+/// Cache of the [`Prefixes`] resolved for crates.
+#[derive(Debug, Default)]
+struct Cache {
+    /// [`Resolved`] prefixes, by the names of crates.
+    crates: HashMap<Box<str>, Resolved>,
+
+    /// Total number of the prefixed [`metrics::Key`]s cached in all the
+    /// [`Resolved`] prefixes.
+    keys: usize,
+}
+
+/// Prefix resolved for a single crate.
+#[derive(Debug)]
+struct Resolved {
+    /// Prefix applied to the metrics registered by the crate, if any.
+    prefix: Option<Arc<str>>,
+
+    /// Prefixed [`metrics::Key`]s, by the original ones.
+    keys: HashMap<metrics::Key, metrics::Key>,
+}
+
+impl Prefixes {
+    /// Appends a new rule, applying the provided [`Prefix`] to the metrics
+    /// registered by the crates matched by the provided [`Matcher`].
+    ///
+    /// [`Matcher`]: matcher::Matcher
+    pub(crate) fn push(
+        &mut self,
+        matcher: impl Into<matcher::Matcher>,
+        prefix: Prefix,
+    ) {
+        self.rules.push(matcher, prefix);
+        *self.cache.get_mut().unwrap_or_else(PoisonError::into_inner) =
+            Cache::default();
+    }
+
+    /// Prefixes the name of the provided [`metrics::Key`] according to the
+    /// first rule matching the crate of the provided `target`.
+    ///
+    /// Names already starting with the prefix are left as is.
+    ///
+    /// Both the prefix of the crate and the prefixed [`metrics::Key`] are
+    /// resolved once and cached (the latter up to [`CACHE_CAPACITY`]), so a
+    /// repeated registration doesn't allocate.
+    pub(crate) fn prefixed<'k>(
+        &self,
+        key: &'k metrics::Key,
+        target: &str,
+    ) -> Cow<'k, metrics::Key> {
+        if self.rules.is_empty() {
+            return Cow::Borrowed(key);
+        }
+        let krate = crate_name(target);
+
+        let prefix = match self.lookup(krate, key) {
+            Some(Lookup::Unprefixed) => return Cow::Borrowed(key),
+            Some(Lookup::Cached(prefixed)) => return Cow::Owned(prefixed),
+            Some(Lookup::Missed(prefix)) => prefix,
+            None => {
+                let Some(prefix) = self.resolve(krate) else {
+                    return Cow::Borrowed(key);
+                };
+                if key.name().starts_with(&*prefix) {
+                    return Cow::Borrowed(key);
+                }
+                prefix
+            }
+        };
+
+        // Retained, so cloning it out of the cache doesn't allocate.
+        let prefixed = metrics::Key::from_parts(
+            format!("{prefix}{}", key.name()),
+            key.labels().cloned().collect::<Vec<_>>(),
+        )
+        .to_retained();
+        self.remember(krate, key, &prefixed);
+        Cow::Owned(prefixed)
+    }
+
+    /// Looks up the cached [`Lookup`] of the provided [`metrics::Key`]
+    /// registered by the provided `krate`.
+    ///
+    /// Returns [`None`] if the prefix of the `krate` hasn't been resolved yet.
+    fn lookup(&self, krate: &str, key: &metrics::Key) -> Option<Lookup> {
+        let cache = self.cache.read().unwrap_or_else(PoisonError::into_inner);
+        let lookup =
+            cache.crates.get(krate).map(|resolved| match &resolved.prefix {
+                None => Lookup::Unprefixed,
+                Some(p) if key.name().starts_with(&**p) => Lookup::Unprefixed,
+                Some(p) => resolved.keys.get(key).map_or_else(
+                    || Lookup::Missed(Arc::clone(p)),
+                    |prefixed| Lookup::Cached(prefixed.clone()),
+                ),
+            });
+        drop(cache);
+        lookup
+    }
+
+    /// Resolves the prefix of the metrics registered by the provided `krate`
+    /// and caches it.
+    fn resolve(&self, krate: &str) -> Option<Arc<str>> {
+        let prefix = self
+            .rules
+            .find(krate)
+            .and_then(|p| p.of(krate))
+            .map(|p| Arc::<str>::from(&*p));
+        _ = self
+            .cache
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .crates
+            .entry(krate.into())
+            .or_insert_with(|| Resolved {
+                prefix: prefix.clone(),
+                keys: HashMap::new(),
+            });
+        prefix
+    }
+
+    /// Caches the `prefixed` [`metrics::Key`] of the provided one registered
+    /// by the provided `krate`, unless the [`CACHE_CAPACITY`] is reached.
+    fn remember(
+        &self,
+        krate: &str,
+        key: &metrics::Key,
+        prefixed: &metrics::Key,
+    ) {
+        let mut cache =
+            self.cache.write().unwrap_or_else(PoisonError::into_inner);
+        if cache.keys >= CACHE_CAPACITY {
+            return;
+        }
+        let Cache { crates, keys } = &mut *cache;
+        if let Some(resolved) = crates.get_mut(krate) {
+            if resolved.keys.insert(key.clone(), prefixed.clone()).is_none() {
+                *keys += 1;
+            }
+        }
+        drop(cache);
+    }
+}
+
+/// Result of looking up a [`metrics::Key`] in the [`Cache`] of [`Prefixes`].
+#[derive(Debug)]
+#[expect( // intentional
+    variant_size_differences,
+    reason = "short-lived, so boxing `metrics::Key` would only allocate"
+)]
+enum Lookup {
+    /// [`metrics::Key`] is left as is.
+    Unprefixed,
+
+    /// Prefixed [`metrics::Key`] is cached already.
+    Cached(metrics::Key),
+
+    /// Prefixed [`metrics::Key`] is not cached yet, while the prefix is.
+    Missed(Arc<str>),
+}
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Background tasks spawned by this crate, supporting cooperative
+//! cancellation and a clean [`Shutdown`].
+
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::failure;
+
+/// Handle shared by background tasks, signaling them to stop and awaiting
+/// their completion.
+///
+/// Once [triggered][0], every task is expected to perform its final work
+/// (like a last push or write) and return, so [`Shutdown::join()`] (or
+/// [`Shutdown::shutdown()`]) completes once all of them did.
+///
+/// Cheaply cloneable, with all the clones sharing the same tasks.
+///
+/// # Example
+///
+/// ```rust
+/// use std::{
+///     sync::{Arc, Mutex},
+///     time::Duration,
+/// };
+///
+/// use metrics_prometheus::tasks::Shutdown;
+///
+/// let shutdown = Shutdown::new();
+/// let flushed = Arc::new(Mutex::new(0));
+///
+/// let counter = Arc::clone(&flushed);
+/// shutdown.spawn(move |signal| {
+///     while signal.sleep(Duration::from_secs(3600)) {
+///         // Periodic work is never done here, as being interrupted.
+///     }
+///     // Final flush.
+///     *counter.lock().unwrap() += 1;
+/// });
+///
+/// shutdown.join();
+/// assert!(shutdown.is_triggered());
+/// assert_eq!(*flushed.lock().unwrap(), 1);
+/// ```
+///
+/// [0]: Shutdown::trigger
+#[derive(Clone, Default)]
+pub struct Shutdown(Arc<Inner>);
+
+/// Shared state of a [`Shutdown`].
+#[derive(Default)]
+struct Inner {
+    /// Indicator whether the [`Shutdown`] has been triggered.
+    triggered: Mutex<bool>,
+
+    /// [`Condvar`] notifying the sleeping tasks about the triggered
+    /// [`Shutdown`].
+    wakeup: Condvar,
+
+    /// [`JoinHandle`]s of the spawned tasks, not joined yet.
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Shutdown {
+    /// Creates a new not triggered [`Shutdown`] with no tasks.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns the provided background `task` in a separate thread, providing
+    /// it with a [`Signal`] of this [`Shutdown`].
+    ///
+    /// If this [`Shutdown`] has been triggered already, the `task` is still
+    /// spawned (to perform its final work), but its [`Signal`] is triggered
+    /// already.
+    ///
+    /// # Panics
+    ///
+    /// If the OS fails to spawn a thread (logged instead, if the `never-panic`
+    /// feature is enabled, so the `task` is never run).
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: FnOnce(Signal) + Send + 'static,
+    {
+        let signal = Signal(self.clone());
+        match thread::Builder::new()
+            .name("metrics-prometheus".into())
+            .spawn(move || task(signal))
+        {
+            Ok(handle) => self
+                .0
+                .tasks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(handle),
+            Err(e) => {
+                failure::panic_or_log(&format!("failed to spawn thread: {e}"));
+            }
+        }
+    }
+
+    /// Indicates whether this [`Shutdown`] has been triggered.
+    #[must_use]
+    pub fn is_triggered(&self) -> bool {
+        *self.0.triggered.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Triggers this [`Shutdown`], signaling all its tasks to stop, without
+    /// awaiting them.
+    pub fn trigger(&self) {
+        *self.0.triggered.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        self.0.wakeup.notify_all();
+    }
+
+    /// Triggers this [`Shutdown`] and blocks the current thread until all its
+    /// tasks complete.
+    ///
+    /// Panics of the tasks are not propagated.
+    pub fn join(&self) {
+        self.trigger();
+        loop {
+            let tasks = mem::take(
+                &mut *self
+                    .0
+                    .tasks
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+            if tasks.is_empty() {
+                break;
+            }
+            for task in tasks {
+                // Panics of the tasks are their own concern.
+                drop(task.join());
+            }
+        }
+    }
+
+    /// Triggers this [`Shutdown`] and returns a [`Future`] resolving once all
+    /// its tasks complete, without blocking an asynchronous runtime.
+    ///
+    /// Panics of the tasks are not propagated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use metrics_prometheus::tasks::Shutdown;
+    ///
+    /// let shutdown = Shutdown::new();
+    /// shutdown.spawn(|signal| while signal.sleep(Default::default()) {});
+    ///
+    /// tokio::runtime::Builder::new_current_thread()
+    ///     .build()?
+    ///     .block_on(shutdown.shutdown());
+    /// assert!(shutdown.is_triggered());
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn shutdown(&self) -> impl Future<Output = ()> + Send + 'static {
+        let joining = Joining::default();
+        let (this, state) = (self.clone(), Arc::clone(&joining.0));
+        drop(thread::spawn(move || {
+            this.join();
+            let mut state =
+                state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.0 = true;
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        }));
+        joining
+    }
+}
+
+impl fmt::Debug for Shutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("triggered", &self.is_triggered())
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`Future`] of all the tasks of a [`Shutdown`] being joined.
+#[derive(Default)]
+struct Joining(Arc<Mutex<(bool, Option<Waker>)>>);
+
+impl Future for Joining {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.0 {
+            return Poll::Ready(());
+        }
+        state.1 = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Cancellation signal of a background task, provided by a [`Shutdown`].
+#[derive(Clone, Debug)]
+pub struct Signal(Shutdown);
+
+impl Signal {
+    /// Indicates whether the [`Shutdown`] has been triggered, so the task
+    /// should perform its final work and stop.
+    #[must_use]
+    pub fn is_triggered(&self) -> bool {
+        self.0.is_triggered()
+    }
+
+    /// Sleeps for the provided `duration`, waking up early if the
+    /// [`Shutdown`] is triggered.
+    ///
+    /// Returns `false` if the [`Shutdown`] has been triggered, so the task
+    /// should perform its final work and stop.
+    #[must_use]
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let inner = &(self.0).0;
+        let triggered =
+            inner.triggered.lock().unwrap_or_else(PoisonError::into_inner);
+        let (triggered, _) = inner
+            .wakeup
+            .wait_timeout_while(triggered, duration, |t| !*t)
+            .unwrap_or_else(PoisonError::into_inner);
+        !*triggered
+    }
+}
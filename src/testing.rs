@@ -0,0 +1,345 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`proptest`] generators of [`metrics`] usage scenarios, allowing to check
+//! [`metrics::Recorder`] implementations for robustness.
+//!
+//! # Example
+//!
+//! ```rust
+//! use metrics_prometheus::{failure::strategy, testing, Recorder};
+//! use proptest::{prop_assert, proptest};
+//!
+//! proptest!(|(ops in testing::ops(8))| {
+//!     let registry = prometheus::Registry::new();
+//!     let recorder = Recorder::builder()
+//!         .with_registry(&registry)
+//!         .with_failure_strategy(strategy::NoOp)
+//!         .build();
+//!
+//!     metrics::with_local_recorder(&recorder, || {
+//!         for op in &ops {
+//!             op.perform();
+//!         }
+//!     });
+//!
+//!     let report = prometheus::TextEncoder::new()
+//!         .encode_to_string(&registry.gather())?;
+//!     prop_assert!(testing::validate_text(&report).is_ok());
+//! });
+//! # Ok::<_, prometheus::Error>(())
+//! ```
+
+use proptest::{
+    collection, option,
+    prelude::{any, prop_oneof, Just, Strategy},
+};
+
+/// Kind of a [`metrics`] metric.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    /// [`metrics::Counter`].
+    Counter,
+
+    /// [`metrics::Gauge`].
+    Gauge,
+
+    /// [`metrics::Histogram`].
+    Histogram,
+}
+
+/// Single operation performed via [`metrics`] crate interfaces.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op {
+    /// Registering a metric without recording any value.
+    Register {
+        /// [`Kind`] of the registered metric.
+        kind: Kind,
+
+        /// Name of the registered metric.
+        name: String,
+
+        /// Labels of the registered metric.
+        labels: Vec<(String, String)>,
+    },
+
+    /// Describing a metric.
+    Describe {
+        /// [`Kind`] of the described metric.
+        kind: Kind,
+
+        /// Name of the described metric.
+        name: String,
+
+        /// `help` description of the metric.
+        description: String,
+    },
+
+    /// Recording a value into a metric.
+    Record {
+        /// [`Kind`] of the metric to record the value into.
+        kind: Kind,
+
+        /// Name of the metric to record the value into.
+        name: String,
+
+        /// Labels of the metric to record the value into.
+        labels: Vec<(String, String)>,
+
+        /// Recorded value.
+        ///
+        /// Truncated for [`metrics::Counter`]s, where [`None`] means an
+        /// increment by one.
+        value: Option<f64>,
+    },
+}
+
+impl Op {
+    /// Performs this [`Op`] via [`metrics`] crate interfaces, so is handled
+    /// by the current [`metrics::Recorder`].
+    #[expect( // intentional
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "truncation is intended for arbitrary counter values"
+    )]
+    pub fn perform(&self) {
+        match self {
+            Self::Register { kind, name, labels } => {
+                let labels = to_labels(labels);
+                match kind {
+                    Kind::Counter => {
+                        drop(metrics::counter!(name.clone(), labels));
+                    }
+                    Kind::Gauge => {
+                        drop(metrics::gauge!(name.clone(), labels));
+                    }
+                    Kind::Histogram => {
+                        drop(metrics::histogram!(name.clone(), labels));
+                    }
+                }
+            }
+            Self::Describe { kind, name, description } => match kind {
+                Kind::Counter => {
+                    metrics::describe_counter!(
+                        name.clone(),
+                        description.clone()
+                    );
+                }
+                Kind::Gauge => {
+                    metrics::describe_gauge!(name.clone(), description.clone());
+                }
+                Kind::Histogram => {
+                    metrics::describe_histogram!(
+                        name.clone(),
+                        description.clone()
+                    );
+                }
+            },
+            Self::Record { kind, name, labels, value } => {
+                let labels = to_labels(labels);
+                match (kind, value) {
+                    (Kind::Counter, None) => {
+                        metrics::counter!(name.clone(), labels).increment(1);
+                    }
+                    (Kind::Counter, Some(v)) => {
+                        metrics::counter!(name.clone(), labels)
+                            .absolute(*v as u64);
+                    }
+                    (Kind::Gauge, v) => {
+                        metrics::gauge!(name.clone(), labels)
+                            .set(v.unwrap_or_default());
+                    }
+                    (Kind::Histogram, v) => {
+                        metrics::histogram!(name.clone(), labels)
+                            .record(v.unwrap_or_default());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Converts the provided `labels` pairs into [`metrics::Label`]s.
+fn to_labels(labels: &[(String, String)]) -> Vec<metrics::Label> {
+    labels
+        .iter()
+        .map(|(k, v)| metrics::Label::new(k.clone(), v.clone()))
+        .collect()
+}
+
+/// Generates an arbitrary [`Kind`].
+pub fn kind() -> impl Strategy<Value = Kind> {
+    prop_oneof![Just(Kind::Counter), Just(Kind::Gauge), Just(Kind::Histogram)]
+}
+
+/// Generates an arbitrary metric name, being invalid for [`prometheus`]
+/// sometimes.
+///
+/// Names are drawn from a small alphabet, so the generated [`Op`]s collide
+/// on the same metrics often.
+pub fn metric_name() -> impl Strategy<Value = String> {
+    prop_oneof![
+        8 => "[a-c][a-c_]{0,2}",
+        1 => "[a-c:.\\- ]{0,3}",
+    ]
+}
+
+/// Generates an arbitrary label key, being invalid for [`prometheus`]
+/// sometimes.
+pub fn label_key() -> impl Strategy<Value = String> {
+    prop_oneof![
+        8 => "[x-z][x-z_]{0,2}",
+        1 => "(__)?[x-z:.\\- ]{0,3}",
+    ]
+}
+
+/// Generates an arbitrary label value.
+pub fn label_value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => "[a-z0-9]{0,4}",
+        1 => any::<String>(),
+    ]
+}
+
+/// Generates arbitrary labels of a metric.
+pub fn labels() -> impl Strategy<Value = Vec<(String, String)>> {
+    collection::vec((label_key(), label_value()), 0..=3)
+}
+
+/// Generates an arbitrary value to be recorded into a metric.
+pub fn value() -> impl Strategy<Value = Option<f64>> {
+    option::of(prop_oneof![
+        4 => 0.0..1_000.0_f64,
+        1 => any::<f64>(),
+    ])
+}
+
+/// Generates an arbitrary [`Op`].
+pub fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        1 => (kind(), metric_name(), labels())
+            .prop_map(|(kind, name, labels)| Op::Register {
+                kind,
+                name,
+                labels,
+            }),
+        1 => (kind(), metric_name(), any::<String>())
+            .prop_map(|(kind, name, description)| Op::Describe {
+                kind,
+                name,
+                description,
+            }),
+        4 => (kind(), metric_name(), labels(), value())
+            .prop_map(|(kind, name, labels, value)| Op::Record {
+                kind,
+                name,
+                labels,
+                value,
+            }),
+    ]
+}
+
+/// Generates an arbitrary sequence of [`Op`]s, not longer than the provided
+/// `max` length.
+pub fn ops(max: usize) -> impl Strategy<Value = Vec<Op>> {
+    collection::vec(op(), 0..=max)
+}
+
+/// Validates the provided `report` to be in a valid [`prometheus`] text
+/// format.
+///
+/// # Errors
+///
+/// With the description of the first invalid line of the `report`.
+///
+/// # Example
+///
+/// ```rust
+/// use metrics_prometheus::testing::validate_text;
+///
+/// assert!(validate_text("# TYPE count counter\ncount{kind=\"a\"} 1").is_ok());
+/// assert!(validate_text("count{kind=\"a} 1").is_err());
+/// assert!(validate_text("invalid.name 1").is_err());
+/// ```
+pub fn validate_text(report: &str) -> Result<(), String> {
+    for (n, line) in report.lines().enumerate() {
+        validate_line(line)
+            .map_err(|e| format!("line {}: {e}: {line}", n + 1))?;
+    }
+    Ok(())
+}
+
+/// Validates the provided `line` of a [`prometheus`] text format.
+fn validate_line(line: &str) -> Result<(), String> {
+    if let Some(comment) = line.strip_prefix("# ") {
+        return match comment.split_once(' ') {
+            Some(("HELP", rest)) => {
+                let name = rest.split_once(' ').map_or(rest, |(name, _)| name);
+                validate_name(name)
+            }
+            Some(("TYPE", rest)) => {
+                let (name, kind) =
+                    rest.split_once(' ').ok_or("missing metric type")?;
+                validate_name(name)?;
+                matches!(
+                    kind,
+                    "counter" | "gauge" | "histogram" | "summary" | "untyped",
+                )
+                .then_some(())
+                .ok_or_else(|| format!("invalid metric type `{kind}`"))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    let name_end = line.find(['{', ' ']).ok_or("missing metric value")?;
+    let (name, mut rest) = line.split_at(name_end);
+    validate_name(name)?;
+    if let Some(labels) = rest.strip_prefix('{') {
+        rest = validate_labels(labels)?;
+    }
+    let value = rest.strip_prefix(' ').ok_or("missing metric value")?;
+    let value = value.split_once(' ').map_or(value, |(value, _)| value);
+    match value {
+        "+Inf" | "-Inf" | "NaN" => Ok(()),
+        v => v
+            .parse::<f64>()
+            .map(drop)
+            .map_err(|e| format!("invalid metric value `{v}`: {e}")),
+    }
+}
+
+/// Validates the provided metric or label `name`.
+fn validate_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+    valid.then_some(()).ok_or_else(|| format!("invalid name `{name}`"))
+}
+
+/// Validates the provided `labels` going after the opening `{`, and returns
+/// the rest of the line after the closing `}`.
+fn validate_labels(mut labels: &str) -> Result<&str, String> {
+    loop {
+        if let Some(rest) = labels.strip_prefix('}') {
+            return Ok(rest);
+        }
+        let (name, rest) =
+            labels.split_once("=\"").ok_or("missing label value")?;
+        validate_name(name)?;
+
+        let mut escaped = false;
+        let end = rest
+            .char_indices()
+            .find_map(|(i, c)| {
+                let end = !escaped && c == '"';
+                escaped = !escaped && c == '\\';
+                end.then_some(i)
+            })
+            .ok_or("unterminated label value")?;
+        labels = rest.get(end + 1..).unwrap_or_default();
+        labels = labels.strip_prefix(',').unwrap_or(labels);
+    }
+}
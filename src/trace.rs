@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Optional [`tracing`] instrumentation of the mutations of [`storage`]s,
+//! allowing operators to audit when and where metric families appear.
+//!
+//! All the functions here are no-op, unless the `tracing` feature is enabled.
+//! Events are emitted under the `metrics_prometheus` target.
+//!
+//! [`storage`]: crate::storage
+//! [`tracing`]: https://docs.rs/tracing
+
+#[cfg(any(feature = "ingest", feature = "never-panic"))]
+use std::fmt;
+#[cfg(all(
+    not(feature = "tracing"),
+    any(feature = "ingest", feature = "never-panic"),
+))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "tracing")]
+use tracing::span::EnteredSpan;
+
+/// Emits an event about registering the metric of the provided `kind`,
+/// identified by the provided [`metrics::Key`], with the provided `outcome`.
+#[cfg_attr(
+    not(feature = "tracing"),
+    expect( // intentional
+        clippy::missing_const_for_fn,
+        unused_variables,
+        reason = "no-op without `tracing` feature"
+    )
+)]
+pub(crate) fn registered<T>(
+    kind: &'static str,
+    key: &metrics::Key,
+    outcome: &prometheus::Result<T>,
+) {
+    #[cfg(feature = "tracing")]
+    {
+        let labels = || {
+            key.labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        match outcome {
+            Ok(_) => tracing::debug!(
+                target: "metrics_prometheus",
+                name = key.name(),
+                kind,
+                labels = labels(),
+                outcome = "registered",
+                "metric registered",
+            ),
+            Err(e) => tracing::warn!(
+                target: "metrics_prometheus",
+                name = key.name(),
+                kind,
+                labels = labels(),
+                outcome = "failed",
+                error = %e,
+                "metric registration failed",
+            ),
+        }
+    }
+}
+
+/// Emits an event about registering the external [`prometheus`] metric
+/// identified by the provided `name`, with the provided `outcome`.
+#[cfg_attr(
+    not(feature = "tracing"),
+    expect( // intentional
+        clippy::missing_const_for_fn,
+        unused_variables,
+        reason = "no-op without `tracing` feature"
+    )
+)]
+pub(crate) fn registered_external<T>(
+    name: &str,
+    outcome: &prometheus::Result<T>,
+) {
+    #[cfg(feature = "tracing")]
+    match outcome {
+        Ok(_) => tracing::debug!(
+            target: "metrics_prometheus",
+            name,
+            kind = "external",
+            outcome = "registered",
+            "external metric registered",
+        ),
+        Err(e) => tracing::warn!(
+            target: "metrics_prometheus",
+            name,
+            kind = "external",
+            outcome = "failed",
+            error = %e,
+            "external metric registration failed",
+        ),
+    }
+}
+
+/// Emits an event about describing the metric family of the provided `kind`,
+/// identified by the provided `name`.
+#[cfg_attr(
+    not(feature = "tracing"),
+    expect( // intentional
+        clippy::missing_const_for_fn,
+        unused_variables,
+        reason = "no-op without `tracing` feature"
+    )
+)]
+pub(crate) fn described(kind: &'static str, name: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: "metrics_prometheus",
+        name,
+        kind,
+        outcome = "described",
+        "metric described",
+    );
+}
+
+/// Emits a warning about using the provided deprecated `old` metric name,
+/// routed to the `new` one.
+#[cfg_attr(
+    not(feature = "tracing"),
+    expect( // intentional
+        clippy::missing_const_for_fn,
+        unused_variables,
+        reason = "no-op without `tracing` feature"
+    )
+)]
+pub(crate) fn deprecated(old: &str, new: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        target: "metrics_prometheus",
+        name = old,
+        replacement = new,
+        outcome = "deprecated",
+        "deprecated metric name used",
+    );
+}
+
+/// Logs the provided `error` (or message), which would have been a panic
+/// without the `never-panic` feature, as a last resort.
+///
+/// Emitted as a [`tracing`] error event if the `tracing` feature is enabled,
+/// or printed to the standard error otherwise (at most once per second,
+/// reporting the number of the skipped ones).
+///
+/// [`tracing`]: https://docs.rs/tracing
+#[cfg(feature = "never-panic")]
+pub(crate) fn panic_suppressed(error: &dyn fmt::Display) {
+    #[cfg(feature = "tracing")]
+    tracing::error!(
+        target: "metrics_prometheus",
+        error = %error,
+        outcome = "panic_suppressed",
+        "metrics failure suppressed instead of panicking",
+    );
+    #[cfg(not(feature = "tracing"))]
+    #[expect( // intentional
+        clippy::print_stderr,
+        reason = "last resort logging without `tracing` feature"
+    )]
+    {
+        static THROTTLE: Throttle = Throttle::new();
+
+        if let Some(skipped) = THROTTLE.admit() {
+            eprintln!(
+                "[metrics_prometheus] failure suppressed instead of panicking: \
+                 {error}{skipped}",
+            );
+        }
+    }
+}
+
+/// Logs the provided `error` of ingesting metrics events, which doesn't stop
+/// the ingesting.
+///
+/// Emitted as a [`tracing`] warning event if the `tracing` feature is enabled,
+/// or printed to the standard error otherwise (at most once per second,
+/// reporting the number of the skipped ones).
+///
+/// [`tracing`]: https://docs.rs/tracing
+#[cfg(feature = "ingest")]
+pub(crate) fn ingest_failed(error: &dyn fmt::Display) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        target: "metrics_prometheus",
+        error = %error,
+        outcome = "ingest_failed",
+        "ingesting metrics events failed",
+    );
+    #[cfg(not(feature = "tracing"))]
+    #[expect( // intentional
+        clippy::print_stderr,
+        reason = "logging without `tracing` feature"
+    )]
+    {
+        static THROTTLE: Throttle = Throttle::new();
+
+        if let Some(skipped) = THROTTLE.admit() {
+            eprintln!(
+                "[metrics_prometheus] ingesting failed: {error}{skipped}"
+            );
+        }
+    }
+}
+
+/// Limiter of the last resort logging into the standard error, printing at
+/// most one message per second, so a repeated failure doesn't flood it.
+#[cfg(all(
+    not(feature = "tracing"),
+    any(feature = "ingest", feature = "never-panic"),
+))]
+#[derive(Debug)]
+struct Throttle {
+    /// Second (since [`UNIX_EPOCH`]) of the last printed message.
+    ///
+    /// [`UNIX_EPOCH`]: std::time::UNIX_EPOCH
+    last: AtomicU64,
+
+    /// Number of messages skipped since the last printed one.
+    skipped: AtomicU64,
+}
+
+#[cfg(all(
+    not(feature = "tracing"),
+    any(feature = "ingest", feature = "never-panic"),
+))]
+impl Throttle {
+    /// Creates a new [`Throttle`] admitting the very first message.
+    const fn new() -> Self {
+        Self { last: AtomicU64::new(0), skipped: AtomicU64::new(0) }
+    }
+
+    /// Checks whether a message may be printed now, returning the [`Skipped`]
+    /// messages to be mentioned along, if so.
+    fn admit(&self) -> Option<Skipped> {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |d| d.as_secs());
+        let last = self.last.load(Ordering::Relaxed);
+        if now > last
+            && self
+                .last
+                .compare_exchange(
+                    last,
+                    now,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+        {
+            Some(Skipped(self.skipped.swap(0, Ordering::Relaxed)))
+        } else {
+            _ = self.skipped.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+/// Number of messages skipped by a [`Throttle`], displayed as a suffix of the
+/// next printed message.
+#[cfg(all(
+    not(feature = "tracing"),
+    any(feature = "ingest", feature = "never-panic"),
+))]
+#[derive(Clone, Copy, Debug)]
+struct Skipped(u64);
+
+#[cfg(all(
+    not(feature = "tracing"),
+    any(feature = "ingest", feature = "never-panic"),
+))]
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 > 0 {
+            write!(f, " ({} similar messages skipped)", self.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Guard of a [`tracing`] span, exited once dropped.
+///
+/// [`tracing`]: https://docs.rs/tracing
+#[derive(Debug)]
+#[must_use]
+pub(crate) struct Span {
+    /// Entered [`tracing`] span.
+    #[cfg(feature = "tracing")]
+    _entered: EnteredSpan,
+}
+
+/// Enters a span of freezing the metrics registered so far, either all of
+/// them, or only the selected ones, if `partial`.
+#[cfg_attr(
+    not(feature = "tracing"),
+    expect( // intentional
+        clippy::missing_const_for_fn,
+        unused_variables,
+        reason = "no-op without `tracing` feature"
+    )
+)]
+pub(crate) fn freezing(partial: bool) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::info_span!(
+            target: "metrics_prometheus",
+            "freeze",
+            partial,
+        );
+        let entered = span.entered();
+        tracing::info!(target: "metrics_prometheus", "freezing metrics");
+        Span { _entered: entered }
+    }
+    #[cfg(not(feature = "tracing"))]
+    Span {}
+}
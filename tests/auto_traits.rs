@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Compile-time assertions of auto traits implemented by public types, as
+//! they're commonly stored in global or shared state across async tasks.
+//!
+//! Any type accidentally losing an auto trait fails the compilation.
+
+use metrics_prometheus::{
+    budget, catalog, clock, failure::strategy, metric, recorder, storage,
+    tasks, AnyRecorder, FreezableRecorder, FrozenRecorder, PausableRecorder,
+    RateLimitedRecorder, Recorder,
+};
+use static_assertions::assert_impl_all;
+
+assert_impl_all!(Recorder: Send, Sync, Unpin);
+assert_impl_all!(Recorder<strategy::Panic>: Send, Sync, Unpin);
+assert_impl_all!(FreezableRecorder: Send, Sync, Unpin);
+assert_impl_all!(FrozenRecorder: Send, Sync, Unpin);
+assert_impl_all!(PausableRecorder<Recorder>: Send, Sync, Unpin);
+assert_impl_all!(RateLimitedRecorder<Recorder>: Send, Sync, Unpin);
+assert_impl_all!(AnyRecorder: Send, Sync, Unpin);
+assert_impl_all!(recorder::cached::Renderer: Send, Sync, Unpin);
+assert_impl_all!(recorder::Builder: Send, Sync, Unpin);
+assert_impl_all!(recorder::info::Info: Send, Sync, Unpin);
+assert_impl_all!(recorder::layer::Stack: Send, Sync, Unpin);
+assert_impl_all!(recorder::layer::Optional<Recorder>: Send, Sync, Unpin);
+assert_impl_all!(recorder::pausable::Switch: Send, Sync, Unpin);
+assert_impl_all!(recorder::rate_limited::Limit: Send, Sync, Unpin);
+assert_impl_all!(recorder::state_set::StateSet: Send, Sync, Unpin);
+assert_impl_all!(recorder::typed::CounterVec<2, strategy::NoOp>: Send, Sync, Unpin);
+assert_impl_all!(metrics_prometheus::FreezeReport: Send, Sync, Unpin);
+assert_impl_all!(recorder::PartialRender: Send, Sync, Unpin);
+
+assert_impl_all!(strategy::NoOp: Send, Sync, Unpin);
+assert_impl_all!(strategy::Panic: Send, Sync, Unpin);
+assert_impl_all!(strategy::PanicInDebugNoOpInRelease: Send, Sync, Unpin);
+
+assert_impl_all!(storage::Mutable: Send, Sync, Unpin);
+assert_impl_all!(storage::Immutable: Send, Sync, Unpin);
+assert_impl_all!(storage::Counts: Send, Sync, Unpin);
+assert_impl_all!(storage::Orphans: Send, Sync, Unpin);
+
+assert_impl_all!(metric::Fallible<prometheus::IntCounter>: Send, Sync, Unpin);
+assert_impl_all!(metric::Fallible<prometheus::Gauge>: Send, Sync, Unpin);
+assert_impl_all!(metric::Fallible<prometheus::Histogram>: Send, Sync, Unpin);
+assert_impl_all!(metric::Handle<prometheus::IntCounter>: Send, Sync, Unpin);
+assert_impl_all!(metric::PrometheusIntCounter: Send, Sync, Unpin);
+assert_impl_all!(metric::PrometheusGauge: Send, Sync, Unpin);
+assert_impl_all!(metric::PrometheusHistogram: Send, Sync, Unpin);
+assert_impl_all!(metric::bundle::Schema: Send, Sync, Unpin);
+assert_impl_all!(metric::custom::Shared: Send, Sync, Unpin);
+assert_impl_all!(metric::windowed::Rate: Send, Sync, Unpin);
+assert_impl_all!(metric::extrema::Family: Send, Sync, Unpin);
+assert_impl_all!(metric::batched::Family: Send, Sync, Unpin);
+assert_impl_all!(metric::batched::Histogram: Send, Sync, Unpin);
+assert_impl_all!(metric::extrema::Histogram: Send, Sync, Unpin);
+assert_impl_all!(metric::threshold::Family: Send, Sync, Unpin);
+assert_impl_all!(metric::threshold::Histogram: Send, Sync, Unpin);
+assert_impl_all!(metric::callback::Callbacks: Send, Sync, Unpin);
+assert_impl_all!(metric::annotation::Annotations: Send, Sync, Unpin);
+#[cfg(feature = "origins")]
+assert_impl_all!(metric::origin::Origin: Send, Sync, Unpin);
+#[cfg(feature = "origins")]
+assert_impl_all!(metric::origin::Origins: Send, Sync, Unpin);
+
+assert_impl_all!(catalog::Diff: Send, Sync, Unpin);
+assert_impl_all!(catalog::Family: Send, Sync, Unpin);
+assert_impl_all!(catalog::Change: Send, Sync, Unpin);
+assert_impl_all!(catalog::Entry: Send, Sync, Unpin);
+assert_impl_all!(catalog::Applied: Send, Sync, Unpin);
+assert_impl_all!(catalog::Watcher: Send, Sync, Unpin);
+assert_impl_all!(catalog::Snapshot: Send, Sync, Unpin);
+
+assert_impl_all!(budget::Budget: Send, Sync, Unpin);
+assert_impl_all!(budget::Policy: Send, Sync, Unpin);
+assert_impl_all!(clock::System: Send, Sync, Unpin);
+assert_impl_all!(clock::Mock: Send, Sync, Unpin);
+assert_impl_all!(metrics_prometheus::Matcher: Send, Sync, Unpin);
+
+assert_impl_all!(tasks::Shutdown: Send, Sync, Unpin);
+assert_impl_all!(tasks::Signal: Send, Sync, Unpin);
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checks of the [`budget::Policy::EvictLeastRecentlyUpdated`] evicting the
+//! least recently updated metrics, regardless of their admission order.
+
+#![cfg(feature = "timestamps")]
+
+use std::time::Duration;
+
+use metrics_prometheus::{budget, clock};
+
+#[test]
+fn evicts_least_recently_updated() {
+    let clock = clock::Mock::new();
+    let recorder = metrics_prometheus::Recorder::builder()
+        .with_isolated_registry()
+        .with_clock(clock.clone())
+        .with_metric_budget_policy(budget::Policy::EvictLeastRecentlyUpdated)
+        .with_metric_budget(1, 3)
+        .build();
+
+    let touch = |path: &'static str| {
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("requests", "path" => path).increment(1);
+        });
+        clock.advance(Duration::from_secs(1));
+    };
+    touch("/a");
+    touch("/b");
+    touch("/c");
+    // Updating the oldest metrics makes `/b` the least recently updated one.
+    touch("/a");
+    touch("/c");
+    touch("/d");
+    // Now `/a` is the least recently updated one.
+    touch("/e");
+
+    let report = prometheus::TextEncoder::new()
+        .encode_to_string(&recorder.registry().gather())
+        .unwrap();
+    for (path, present) in
+        [("/a", false), ("/b", false), ("/c", true), ("/d", true), ("/e", true)]
+    {
+        assert_eq!(
+            report.contains(&format!(r#"requests{{path="{path}"}}"#)),
+            present,
+            "unexpected presence of `{path}` in:\n{report}",
+        );
+    }
+}
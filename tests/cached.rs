@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checks of the [`cached::Renderer`] serving the cached text body within its
+//! TTL, and rendering the metrics the same way as the
+//! [`Recorder::try_render_text()`] method does.
+//!
+//! [`cached::Renderer`]: metrics_prometheus::recorder::cached::Renderer
+//! [`Recorder::try_render_text()`]: Recorder::try_render_text
+
+use std::{collections::HashMap, time::Duration};
+
+use metrics_prometheus::{clock, Recorder};
+use prometheus::{core, proto};
+
+/// [`core::Collector`] panicking on each collection.
+struct Buggy(core::Desc);
+
+impl core::Collector for Buggy {
+    fn desc(&self) -> Vec<&core::Desc> {
+        vec![&self.0]
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        panic!("buggy collector")
+    }
+}
+
+#[test]
+fn serves_cached_text_within_ttl() {
+    let clock = clock::Mock::new();
+    let recorder = Recorder::builder()
+        .with_isolated_registry()
+        .with_clock(clock.clone())
+        .build();
+    let renderer = recorder.cached_renderer(Duration::from_secs(10));
+    let counter =
+        metrics::with_local_recorder(&recorder, || metrics::counter!("hits"));
+
+    counter.increment(1);
+    let first = renderer.render().unwrap();
+    assert!(first.contains("hits 1"), "rendered:\n{first}");
+
+    counter.increment(1);
+    clock.advance(Duration::from_secs(9));
+    let cached = renderer.render().unwrap();
+    assert_eq!(cached, first);
+
+    // Clones share the same cache.
+    assert_eq!(renderer.clone().render().unwrap(), first);
+
+    clock.advance(Duration::from_secs(1));
+    let fresh = renderer.render().unwrap();
+    assert!(fresh.contains("hits 2"), "rendered:\n{fresh}");
+
+    counter.increment(1);
+    renderer.invalidate();
+    let invalidated = renderer.render().unwrap();
+    assert!(invalidated.contains("hits 3"), "rendered:\n{invalidated}");
+}
+
+#[test]
+fn survives_panicking_collector() {
+    let clock = clock::Mock::new();
+    let recorder = Recorder::builder()
+        .with_isolated_registry()
+        .with_clock(clock.clone())
+        .build();
+    let renderer = recorder.cached_renderer(Duration::from_secs(10));
+    let counter =
+        metrics::with_local_recorder(&recorder, || metrics::counter!("hits"));
+    counter.increment(1);
+
+    let desc =
+        core::Desc::new("buggy".into(), "help".into(), vec![], HashMap::new())
+            .unwrap();
+    recorder.registry().register(Box::new(Buggy(desc))).unwrap();
+
+    let partial = renderer.render().unwrap_err();
+    assert_eq!(partial.errors.len(), 1, "errors: {:?}", partial.errors);
+    assert!(partial.text.contains("hits 1"), "rendered:\n{}", partial.text);
+
+    // Partial renders are not cached, so the metrics are rendered again even
+    // within the TTL.
+    counter.increment(1);
+    let partial = renderer.render().unwrap_err();
+    assert!(partial.text.contains("hits 2"), "rendered:\n{}", partial.text);
+}
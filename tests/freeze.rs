@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checks of the [`FreezableRecorder`] handing over the in-flight operations
+//! to the frozen metrics without losing or double-registering any of them.
+//!
+//! Longer stress variants of the checks are ignored by default, so run them
+//! with:
+//! ```bash
+//! cargo test --test freeze -- --ignored
+//! ```
+//!
+//! [`FreezableRecorder`]: metrics_prometheus::FreezableRecorder
+
+use std::{sync::Barrier, thread};
+
+use metrics_prometheus::{failure::strategy, FreezableRecorder, Recorder};
+
+/// Number of rounds to repeat each check in, as the transition happens at a
+/// random point of the concurrent recordings.
+const ROUNDS: usize = 300;
+
+/// Number of rounds to repeat each check in by its stress variant.
+const STRESS_ROUNDS: usize = 5000;
+
+/// Number of threads recording metrics concurrently with freezing.
+const THREADS: usize = 8;
+
+/// Number of distinct metrics recorded by each thread.
+const METRICS: usize = 20;
+
+/// Records [`METRICS`] distinct counters twice in each of [`THREADS`]
+/// concurrently with the provided `freeze` operation, returning the
+/// [`prometheus::Registry`] the counters are registered in.
+///
+/// Panics on any double registration in the [`prometheus::Registry`].
+fn record_while(
+    freeze: impl Fn(&FreezableRecorder<strategy::Panic>),
+) -> prometheus::Registry {
+    let registry = prometheus::Registry::new();
+    let recorder = Recorder::builder()
+        .with_registry(&registry)
+        .with_failure_strategy(strategy::Panic)
+        .build_freezable();
+    let barrier = Barrier::new(THREADS + 1);
+
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let (recorder, barrier) = (recorder.clone(), &barrier);
+            _ = s.spawn(move || {
+                _ = barrier.wait();
+                metrics::with_local_recorder(&recorder, || {
+                    for i in 0..METRICS {
+                        let name = format!("count_{t}_{i}");
+                        metrics::counter!(name.clone()).increment(1);
+                        thread::yield_now();
+                        metrics::counter!(name).increment(1);
+                    }
+                });
+            });
+        }
+        _ = barrier.wait();
+        thread::yield_now();
+        freeze(&recorder);
+    });
+    registry
+}
+
+/// Asserts that every counter registered in the provided
+/// [`prometheus::Registry`] has both its recordings.
+fn assert_recorded_twice(registry: &prometheus::Registry) {
+    for family in registry.gather() {
+        for metric in family.get_metric() {
+            assert_eq!(
+                metric.get_counter().get_value(),
+                2.0,
+                "`{}` counter lost its recording",
+                family.get_name(),
+            );
+        }
+    }
+}
+
+#[test]
+fn freeze_loses_no_recordings() {
+    check_freeze_loses_no_recordings(ROUNDS);
+}
+
+#[test]
+#[ignore = "stress variant, run explicitly via `--ignored`"]
+fn freeze_loses_no_recordings_stress() {
+    check_freeze_loses_no_recordings(STRESS_ROUNDS);
+}
+
+/// Checks that [`FreezableRecorder::freeze()`] loses no recordings in the
+/// provided number of `rounds`.
+fn check_freeze_loses_no_recordings(rounds: usize) {
+    for _ in 0..rounds {
+        // Every counter is either registered before the freezing (and so
+        // recorded twice), or not registered at all.
+        assert_recorded_twice(&record_while(FreezableRecorder::freeze));
+    }
+}
+
+#[test]
+fn freeze_names_loses_no_recordings() {
+    check_freeze_names_loses_no_recordings(ROUNDS);
+}
+
+#[test]
+#[ignore = "stress variant, run explicitly via `--ignored`"]
+fn freeze_names_loses_no_recordings_stress() {
+    check_freeze_names_loses_no_recordings(STRESS_ROUNDS);
+}
+
+/// Checks that [`FreezableRecorder::freeze_names()`] loses no recordings in
+/// the provided number of `rounds`.
+fn check_freeze_names_loses_no_recordings(rounds: usize) {
+    let names = (0..THREADS)
+        .flat_map(|t| (0..METRICS).map(move |i| format!("count_{t}_{i}")))
+        .collect::<Vec<_>>();
+    for _ in 0..rounds {
+        let registry = record_while(|recorder| {
+            recorder.freeze_names(names.iter().map(String::as_str));
+        });
+        // Every counter is recorded twice, either in the selectively frozen
+        // metrics, or in the usual ones.
+        let recorded = registry
+            .gather()
+            .iter()
+            .map(|f| f.get_metric().len())
+            .sum::<usize>();
+        assert_eq!(recorded, THREADS * METRICS, "counters are lost");
+        assert_recorded_twice(&registry);
+    }
+}
+
+#[test]
+fn freeze_loses_no_descriptions() {
+    check_freeze_loses_no_descriptions(ROUNDS);
+}
+
+#[test]
+#[ignore = "stress variant, run explicitly via `--ignored`"]
+fn freeze_loses_no_descriptions_stress() {
+    check_freeze_loses_no_descriptions(STRESS_ROUNDS);
+}
+
+/// Checks that [`FreezableRecorder::freeze()`] loses no descriptions in the
+/// provided number of `rounds`.
+fn check_freeze_loses_no_descriptions(rounds: usize) {
+    for _ in 0..rounds {
+        let registry = prometheus::Registry::new();
+        let recorder = Recorder::builder()
+            .with_registry(&registry)
+            .with_failure_strategy(strategy::Panic)
+            .build_freezable();
+        metrics::with_local_recorder(&recorder, || {
+            for t in 0..THREADS {
+                for i in 0..METRICS {
+                    metrics::counter!(format!("count_{t}_{i}")).increment(1);
+                }
+            }
+        });
+        let barrier = Barrier::new(THREADS + 1);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (recorder, barrier) = (recorder.clone(), &barrier);
+                _ = s.spawn(move || {
+                    _ = barrier.wait();
+                    metrics::with_local_recorder(&recorder, || {
+                        for i in 0..METRICS {
+                            metrics::describe_counter!(
+                                format!("count_{t}_{i}"),
+                                format!("Counter {t}_{i}."),
+                            );
+                            thread::yield_now();
+                        }
+                    });
+                });
+            }
+            _ = barrier.wait();
+            thread::yield_now();
+            recorder.freeze();
+        });
+
+        // Every description is applied either to the usual metrics before
+        // draining them, or to the frozen ones.
+        for family in registry.gather() {
+            let name = family.get_name();
+            assert_eq!(
+                family.get_help(),
+                format!("Counter {}.", &name["count_".len()..]),
+                "`{name}` counter lost its description",
+            );
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`loom`] models of concurrent usage of a [`storage::Mutable`], checking its
+//! lock ordering for deadlocks in all the possible interleavings.
+//!
+//! Only the locks of the [`storage`]s are modeled. The ones of
+//! [`prometheus::Registry`] and [`metrics::Registry`] are never held while
+//! acquiring the modeled ones, so don't take part in the lock ordering.
+//!
+//! Run with:
+//! ```bash
+//! RUSTFLAGS="--cfg metrics_prometheus_loom" cargo test --test loom --release
+//! ```
+//!
+//! [`metrics::Registry`]: metrics_util::registry::Registry
+
+#![cfg(metrics_prometheus_loom)]
+
+use loom::thread;
+use metrics_prometheus::{metric::raw::BucketCounts, storage};
+use metrics_util::registry::Storage as _;
+
+#[test]
+fn concurrent_register_and_describe() {
+    loom::model(|| {
+        let storage = storage::Mutable::default();
+        let key = metrics::Key::from_name("loom_register_and_describe");
+
+        let threads = (0..2)
+            .map(|_| {
+                let (storage, key) = (storage.clone(), key.clone());
+                thread::spawn(move || storage.counter(&key))
+            })
+            .collect::<Vec<_>>();
+        storage
+            .describe::<prometheus::IntCounter>(key.name(), "described".into());
+        for t in threads {
+            let counter = t.join().expect("thread shouldn't panic");
+            assert!(counter.0.is_ok(), "registration should succeed");
+        }
+
+        let families = prometheus::default_registry().gather();
+        let family = families
+            .iter()
+            .find(|f| f.get_name() == key.name())
+            .expect("counter should be registered");
+        assert_eq!(family.get_help(), "described");
+
+        storage.unregister_all();
+    });
+}
+
+#[test]
+fn concurrent_raw_and_usual_histograms() {
+    loom::model(|| {
+        let storage = storage::Mutable::default();
+        let key = metrics::Key::from_name("loom_usual_histogram");
+
+        let raw = {
+            let storage = storage.clone();
+            thread::spawn(move || {
+                let counts = BucketCounts {
+                    buckets: vec![(1.0, 1)],
+                    sum: 1.0,
+                    count: 1,
+                };
+                storage
+                    .raw_histogram("loom_raw_histogram", &[])?
+                    .merge(vec![], &counts)
+            })
+        };
+        let usual = {
+            let storage = storage.clone();
+            thread::spawn(move || storage.histogram(&key))
+        };
+        storage.describe::<prometheus::Histogram>(
+            "loom_raw_histogram",
+            "described".into(),
+        );
+
+        assert!(raw.join().expect("thread shouldn't panic").is_ok());
+        assert!(usual.join().expect("thread shouldn't panic").0.is_ok());
+
+        storage.unregister_all();
+    });
+}
+
+#[test]
+fn concurrent_register_and_freeze() {
+    loom::model(|| {
+        let storage = storage::Mutable::default();
+
+        let registering = {
+            let storage = storage.clone();
+            thread::spawn(move || {
+                let counter = storage
+                    .counter(&metrics::Key::from_name("loom_freeze_counter"));
+                let gauge = storage
+                    .gauge(&metrics::Key::from_name("loom_freeze_gauge"));
+                (counter, gauge)
+            })
+        };
+        let frozen = storage::Immutable::from(&storage);
+
+        let (counter, gauge) =
+            registering.join().expect("thread shouldn't panic");
+        assert!(counter.0.is_ok() && gauge.0.is_ok());
+
+        // Metrics registered before freezing are drained into the `frozen`
+        // storage, while the ones registered after stay in the mutable one.
+        frozen.unregister_all();
+        storage.unregister_all();
+    });
+}
+
+#[test]
+fn concurrent_register_and_unregister() {
+    loom::model(|| {
+        let storage = storage::Mutable::default();
+        let key = metrics::Key::from_name("loom_unregister");
+
+        let registering = {
+            let (storage, key) = (storage.clone(), key.clone());
+            thread::spawn(move || storage.counter(&key))
+        };
+        storage.unregister_all();
+
+        let counter = registering.join().expect("thread shouldn't panic");
+        assert!(counter.0.is_ok());
+
+        storage.unregister_all();
+    });
+}
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checks of the `never-panic` feature suppressing the panics on the
+//! recording path, regardless of the configured [`failure::Strategy`].
+//!
+//! [`failure::Strategy`]: metrics_prometheus::failure::Strategy
+
+#![cfg(feature = "never-panic")]
+
+use metrics_prometheus::{
+    failure::{self, strategy},
+    Recorder,
+};
+
+/// [`failure::Strategy`] panicking itself instead of deciding.
+struct Panicking;
+
+impl failure::Strategy for Panicking {
+    fn decide(&self, res: &prometheus::Error) -> failure::Action {
+        panic!("strategy panicked on: {res}")
+    }
+}
+
+/// Records the metrics failing to be registered via a [`Recorder`] with the
+/// provided [`failure::Strategy`], and checks that none of them panics, while
+/// the valid ones are still recorded.
+fn check<S: failure::Strategy + 'static>(strategy: S) {
+    let recorder = Recorder::builder()
+        .with_isolated_registry()
+        .with_failure_strategy(strategy)
+        .build();
+    recorder
+        .registry()
+        .register(Box::new(prometheus::Gauge::new("value", "help").unwrap()))
+        .unwrap();
+
+    metrics::with_local_recorder(&recorder, || {
+        // Invalid metric name.
+        metrics::counter!("queries.count").increment(1);
+
+        // Inconsistent labels.
+        metrics::counter!("count").increment(1);
+        metrics::counter!("count", "whose" => "mine").increment(1);
+
+        // Different types of the same metric.
+        metrics::gauge!("count").increment(1.0);
+        metrics::histogram!("count").record(1.0);
+
+        // Metric registered directly in the `prometheus::Registry`.
+        metrics::gauge!("value").increment(4.5);
+    });
+
+    let report = prometheus::TextEncoder::new()
+        .encode_to_string(&recorder.registry().gather())
+        .unwrap();
+    assert!(report.contains("\ncount 1\n"), "report:\n{report}");
+    assert!(report.contains("\nvalue 0\n"), "report:\n{report}");
+    assert!(!report.contains("queries"), "report:\n{report}");
+}
+
+#[test]
+fn suppresses_panic_strategy() {
+    check(strategy::Panic);
+}
+
+#[test]
+fn suppresses_panicking_strategy() {
+    check(Panicking);
+}
+
+#[test]
+fn suppresses_frozen_panics() {
+    let recorder = Recorder::builder()
+        .with_isolated_registry()
+        .with_failure_strategy(strategy::Panic)
+        .build_freezable();
+    metrics::with_local_recorder(&recorder, || {
+        metrics::counter!("count").increment(1);
+    });
+    recorder.freeze();
+
+    metrics::with_local_recorder(&recorder, || {
+        metrics::counter!("count", "whose" => "mine").increment(1);
+        metrics::counter!("count").increment(1);
+    });
+
+    let report = prometheus::TextEncoder::new()
+        .encode_to_string(&recorder.registry().gather())
+        .unwrap();
+    assert!(report.contains("\ncount 2\n"), "report:\n{report}");
+}
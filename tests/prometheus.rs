@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! End-to-end checks of the metrics exposed by the
+//! `metrics-prometheus-exporter` being scraped by a real Prometheus server,
+//! catching exposition format regressions the checks of rendered strings
+//! miss.
+//!
+//! Prometheus is run in a Docker container via [`testcontainers`] (with its
+//! HTTP API port mapped to the host, and the exporter port exposed to the
+//! container), so a running Docker daemon (or Docker Desktop) is required.
+//!
+//! Run with:
+//! ```bash
+//! RUSTFLAGS="--cfg metrics_prometheus_integration" \
+//!     cargo test --features bin --test prometheus
+//! ```
+
+#![cfg(metrics_prometheus_integration)]
+
+use std::{
+    io::{self, Read as _, Write as _},
+    net::{TcpListener, TcpStream},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use testcontainers::{
+    core::{IntoContainerPort as _, WaitFor},
+    runners::SyncRunner as _,
+    Container, GenericImage, ImageExt as _,
+};
+
+/// Docker image name of the Prometheus server to scrape the exporter with.
+const PROMETHEUS_IMAGE: &str = "prom/prometheus";
+
+/// Docker image tag of the Prometheus server to scrape the exporter with.
+const PROMETHEUS_TAG: &str = "v2.53.0";
+
+/// Port the Prometheus server serves its HTTP API on inside its container.
+const PROMETHEUS_PORT: u16 = 9090;
+
+/// Maximum time to wait for the ingested metrics to be scraped.
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Running `metrics-prometheus-exporter` process, killed on [`Drop`].
+struct Exporter {
+    /// Spawned process of the exporter.
+    process: Child,
+
+    /// Address the exporter ingests metrics events on.
+    ingest_addr: String,
+
+    /// Address the exporter serves `/metrics` HTTP endpoint on.
+    listen_addr: String,
+}
+
+impl Exporter {
+    /// Spawns a new [`Exporter`] on free local ports.
+    fn spawn() -> io::Result<Self> {
+        let ingest_addr = free_addr()?;
+        let listen_addr = free_addr()?;
+        let process =
+            Command::new(env!("CARGO_BIN_EXE_metrics-prometheus-exporter"))
+                .args(["--ingest", &ingest_addr, "--listen", &listen_addr])
+                .stdout(Stdio::null())
+                .spawn()?;
+        wait_until(Duration::from_secs(10), || {
+            TcpStream::connect(&ingest_addr).is_ok()
+        });
+        Ok(Self { process, ingest_addr, listen_addr })
+    }
+
+    /// Ingests the provided metrics events `lines` into this [`Exporter`].
+    fn ingest(&self, lines: &str) -> io::Result<()> {
+        let mut stream = TcpStream::connect(&self.ingest_addr)?;
+        stream.write_all(lines.as_bytes())?;
+        stream.flush()
+    }
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        drop(self.process.kill());
+        drop(self.process.wait());
+    }
+}
+
+/// Prometheus server running in a Docker container, removed on [`Drop`].
+struct Prometheus {
+    /// Docker container running this [`Prometheus`].
+    _container: Container<GenericImage>,
+
+    /// Address this [`Prometheus`] serves its HTTP API on (mapped to the
+    /// host).
+    addr: String,
+}
+
+impl Prometheus {
+    /// Starts a new [`Prometheus`] scraping the exporter listening on the
+    /// provided local `target` address every second.
+    fn start(target: &str) -> io::Result<Self> {
+        let port = target
+            .rsplit_once(':')
+            .and_then(|(_, port)| port.parse::<u16>().ok())
+            .ok_or_else(|| {
+                io::Error::other(format!("no port in `{target}`"))
+            })?;
+        let config = format!(
+            "global:\n  scrape_interval: 1s\n\
+             scrape_configs:\n  - job_name: exporter\n    \
+             static_configs:\n      - targets: \
+             [\"host.testcontainers.internal:{port}\"]\n",
+        );
+
+        let container = GenericImage::new(PROMETHEUS_IMAGE, PROMETHEUS_TAG)
+            .with_exposed_port(PROMETHEUS_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stderr(
+                "Server is ready to receive web requests.",
+            ))
+            .with_copy_to("/etc/prometheus/prometheus.yml", config.into_bytes())
+            .with_exposed_host_port(port)
+            .start()
+            .map_err(io::Error::other)?;
+        let host = container.get_host().map_err(io::Error::other)?;
+        let mapped = container
+            .get_host_port_ipv4(PROMETHEUS_PORT)
+            .map_err(io::Error::other)?;
+        Ok(Self { _container: container, addr: format!("{host}:{mapped}") })
+    }
+
+    /// Requests the provided `path` of the HTTP API of this [`Prometheus`],
+    /// returning the response body.
+    fn get(&self, path: &str) -> io::Result<String> {
+        let mut stream = TcpStream::connect(&self.addr)?;
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             Connection: close\r\n\r\n",
+            self.addr,
+        )?;
+        let mut response = String::new();
+        _ = stream.read_to_string(&mut response)?;
+        Ok(response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_owned())
+            .unwrap_or_default())
+    }
+
+    /// Waits until the provided PromQL `query` returns any series, returning
+    /// the response body.
+    ///
+    /// # Panics
+    ///
+    /// If no series are returned within the [`SCRAPE_TIMEOUT`].
+    fn query(&self, query: &str) -> String {
+        let path = format!("/api/v1/query?query={}", encode(query));
+        let mut body = String::new();
+        let found = wait_until(SCRAPE_TIMEOUT, || {
+            body = self.get(&path).unwrap_or_default();
+            body.contains(r#""result":[{"#)
+        });
+        assert!(found, "`{query}` query returned no series: {body}");
+        body
+    }
+}
+
+/// Returns a free local address to listen on.
+fn free_addr() -> io::Result<String> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.to_string())
+}
+
+/// Polls the provided `condition` until it holds, or the provided `timeout`
+/// expires, returning whether it holds.
+fn wait_until(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+    false
+}
+
+/// Percent-encodes the provided PromQL `query` for using in a URL.
+fn encode(query: &str) -> String {
+    query
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'-' | b'.' => {
+                char::from(b).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[test]
+fn scraped_by_prometheus() -> io::Result<()> {
+    let exporter = Exporter::spawn()?;
+    exporter.ingest(
+        "counter http_requests_total 3 method=GET path=/users\n\
+         gauge queue_length 7 name=jobs\n\
+         histogram request_duration_seconds 0.2 path=/users\n\
+         histogram request_duration_seconds 0.7 path=/users\n",
+    )?;
+    let prometheus = Prometheus::start(&exporter.listen_addr)?;
+
+    let body = prometheus.query("http_requests_total");
+    assert!(body.contains(r#""method":"GET""#), "{body}");
+    assert!(body.contains(r#""path":"/users""#), "{body}");
+    assert!(body.contains(r#""3"]"#), "{body}");
+
+    let body = prometheus.query("queue_length");
+    assert!(body.contains(r#""name":"jobs""#), "{body}");
+    assert!(body.contains(r#""7"]"#), "{body}");
+
+    let body = prometheus.query("request_duration_seconds_count");
+    assert!(body.contains(r#""2"]"#), "{body}");
+    let body =
+        prometheus.query(r#"request_duration_seconds_bucket{le="+Inf"}"#);
+    assert!(body.contains(r#""2"]"#), "{body}");
+
+    let body = prometheus.get("/api/v1/metadata")?;
+    for (name, kind) in [
+        ("http_requests_total", "counter"),
+        ("queue_length", "gauge"),
+        ("request_duration_seconds", "histogram"),
+    ] {
+        assert!(
+            body.contains(&format!(r#""{name}":[{{"type":"{kind}""#)),
+            "`{name}` is not scraped as {kind}: {body}",
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`proptest`]-based checks of recorders robustness on arbitrary metrics
+//! usage scenarios.
+
+#![cfg(feature = "proptest")]
+
+use metrics_prometheus::{catalog, failure::strategy, testing, Recorder};
+use proptest::{prelude::any, prop_assert, prop_assert_eq, proptest};
+
+/// Encodes all the metrics of the provided [`prometheus::Registry`] in a
+/// text format.
+fn report(registry: &prometheus::Registry) -> String {
+    prometheus::TextEncoder::new()
+        .encode_to_string(&registry.gather())
+        .expect("text encoding never fails")
+}
+
+proptest! {
+    #[test]
+    fn recorder_never_panics(ops in testing::ops(32)) {
+        let registry = prometheus::Registry::new();
+        let recorder = Recorder::builder()
+            .with_registry(&registry)
+            .with_failure_strategy(strategy::NoOp)
+            .build();
+
+        metrics::with_local_recorder(&recorder, || {
+            // Performing twice ensures no locks are poisoned.
+            for op in ops.iter().chain(&ops) {
+                op.perform();
+            }
+        });
+
+        let report = report(&registry);
+        let res = testing::validate_text(&report);
+        prop_assert!(res.is_ok(), "{}\n{report}", res.unwrap_err());
+    }
+
+    #[test]
+    fn freezable_recorder_never_panics(
+        before in testing::ops(16),
+        after in testing::ops(16),
+    ) {
+        let registry = prometheus::Registry::new();
+        let recorder = Recorder::builder()
+            .with_registry(&registry)
+            .with_failure_strategy(strategy::NoOp)
+            .build_freezable();
+
+        metrics::with_local_recorder(&recorder, || {
+            for op in &before {
+                op.perform();
+            }
+            recorder.freeze();
+            // Performing twice ensures no locks are poisoned.
+            for op in before.iter().chain(&after).chain(&after) {
+                op.perform();
+            }
+        });
+
+        let report = report(&registry);
+        let res = testing::validate_text(&report);
+        prop_assert!(res.is_ok(), "{}\n{report}", res.unwrap_err());
+    }
+
+    #[test]
+    fn label_values_are_escaped(value in any::<String>()) {
+        let registry = prometheus::Registry::new();
+        let recorder = Recorder::builder()
+            .with_registry(&registry)
+            .with_failure_strategy(strategy::NoOp)
+            .build();
+
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("exotic", "value" => value.clone()).increment(1);
+        });
+
+        let report = report(&registry);
+        let res = testing::validate_text(&report);
+        prop_assert!(res.is_ok(), "{}\n{report}", res.unwrap_err());
+        let escaped = value
+            .replace('\\', r"\\")
+            .replace('"', r#"\""#)
+            .replace('\n', r"\n");
+        let line = format!(r#"exotic{{value="{escaped}"}} 1"#);
+        prop_assert!(report.contains(&line), "{line}\n{report}");
+    }
+
+    #[test]
+    fn normalized_label_values_have_no_control_chars(
+        value in any::<String>(),
+    ) {
+        let registry = prometheus::Registry::new();
+        let recorder = Recorder::builder()
+            .with_registry(&registry)
+            .with_failure_strategy(strategy::NoOp)
+            .with_label_values_normalized(true)
+            .build();
+
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("exotic", "value" => value.clone()).increment(1);
+        });
+
+        let families = registry.gather();
+        let labels = families
+            .iter()
+            .flat_map(|f| f.get_metric())
+            .flat_map(|m| m.get_label())
+            .map(|l| l.get_value().to_owned())
+            .collect::<Vec<_>>();
+        let stripped = value.replace(char::is_control, "");
+        prop_assert_eq!(labels, [stripped]);
+    }
+
+    #[test]
+    fn catalog_snapshot_roundtrips(help in any::<String>()) {
+        let recorder = Recorder::builder()
+            .with_registry(prometheus::Registry::new())
+            .try_with_counter("requests", &["path"], "requests")
+            .expect("valid counter")
+            .build_frozen();
+        let mut snapshot = recorder.catalog();
+        snapshot.families[0].help = help;
+
+        let restored = snapshot.to_string().parse::<catalog::Snapshot>();
+        prop_assert_eq!(restored.ok(), Some(snapshot));
+    }
+}
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checks of the [`striped::Gauge`] not losing or resurrecting its deltas
+//! when being set concurrently with flushing.
+//!
+//! [`striped::Gauge`]: metrics_prometheus::metric::striped::Gauge
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Barrier,
+    },
+    thread,
+    time::Duration,
+};
+
+use metrics::GaugeFn as _;
+use metrics_prometheus::metric::striped;
+
+/// Number of rounds to repeat the check in, as the race happens at a random
+/// point of the concurrent operations.
+const ROUNDS: usize = 500;
+
+/// Underlying gauge being slow to increment, widening the window between
+/// taking the deltas out of the shards and applying them.
+#[derive(Debug, Default)]
+struct Slow(AtomicU64);
+
+impl Slow {
+    /// Returns the current value of this [`Slow`] gauge.
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Acquire))
+    }
+}
+
+impl metrics::GaugeFn for Slow {
+    fn increment(&self, value: f64) {
+        thread::sleep(Duration::from_micros(50));
+        // `Err` is impossible here, as the closure always returns `Some`.
+        _ = self.0.fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+            Some((f64::from_bits(bits) + value).to_bits())
+        });
+    }
+
+    fn decrement(&self, value: f64) {
+        self.increment(-value);
+    }
+
+    fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Release);
+    }
+}
+
+#[test]
+fn set_discards_deltas_flushed_concurrently() {
+    for round in 0..ROUNDS {
+        let value = Arc::new(Slow::default());
+        let gauge = striped::Gauge::new(
+            metrics::Gauge::from_arc(Arc::clone(&value)),
+            1,
+        );
+        let (started, done) = (Barrier::new(2), AtomicBool::new(false));
+
+        thread::scope(|s| {
+            _ = s.spawn(|| {
+                _ = started.wait();
+                while !done.load(Ordering::Acquire) {
+                    gauge.flush();
+                }
+            });
+
+            _ = started.wait();
+            gauge.increment(1.0);
+            // Gives the flushing a chance to take the delta before setting.
+            thread::sleep(Duration::from_micros(10));
+            gauge.set(0.0);
+            done.store(true, Ordering::Release);
+        });
+        gauge.flush();
+
+        assert_eq!(value.get(), 0.0, "stale delta applied in round {round}");
+    }
+}